@@ -3,9 +3,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Result};
-use reqwest::Client;
+use hickory_resolver::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use hickory_resolver::error::ResolveErrorKind;
+use hickory_resolver::proto::rr::RecordType;
+use hickory_resolver::TokioAsyncResolver;
+use reqwest::dns::Resolve;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::debug;
@@ -16,10 +24,74 @@ use super::Dns01Api;
 
 const CLOUDFLARE_API_URL: &str = "https://api.cloudflare.com/client/v4";
 
+/// Retry policy for idempotent Cloudflare API calls: enough attempts to ride out a burst of rate
+/// limiting without retrying forever if the API is genuinely down.
+const MAX_ATTEMPTS: u32 = 5;
+const TOTAL_DEADLINE: Duration = Duration::from_secs(60);
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Sends the request built by `build_request` (invoked fresh on every attempt, since a
+/// `RequestBuilder` is consumed by `send`), retrying with exponential backoff when Cloudflare
+/// responds 429 or a transient 5xx. Honors a `Retry-After` header (seconds) when present instead
+/// of the computed backoff. Non-retryable 4xx (auth/validation errors) fail on the first attempt,
+/// since retrying those just burns the deadline without a different outcome. Returns the response
+/// status and body text on success or on a non-retryable/final failure.
+async fn send_with_retry(
+    build_request: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<(StatusCode, String)> {
+    let start = Instant::now();
+    let mut backoff = INITIAL_BACKOFF;
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let response = build_request()
+            .send()
+            .await
+            .context("failed to send Cloudflare API request")?;
+        let status = response.status();
+
+        if status.is_success() {
+            let body = response
+                .text()
+                .await
+                .context("failed to read Cloudflare API response body")?;
+            return Ok((status, body));
+        }
+
+        let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+
+        let elapsed = start.elapsed();
+        if !retryable || attempt == MAX_ATTEMPTS || elapsed >= TOTAL_DEADLINE {
+            bail!("Cloudflare API request failed with status {status}: {body}");
+        }
+
+        let wait = retry_after
+            .unwrap_or(backoff)
+            .min(TOTAL_DEADLINE - elapsed);
+        debug!(attempt, %status, wait = ?wait, "retrying Cloudflare API request after transient failure");
+        tokio::time::sleep(wait).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+
+    unreachable!("loop always returns or bails before exhausting MAX_ATTEMPTS")
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CloudflareClient {
     zone_id: String,
     api_token: String,
+    /// Shared across every request so connection pools and TLS sessions survive between calls,
+    /// instead of each method paying a fresh handshake via `Client::new()`. Not serialized: a
+    /// freshly deserialized client rebuilds it with the default resolver.
+    #[serde(skip, default = "Client::new")]
+    client: Client,
 }
 
 #[derive(Deserialize)]
@@ -60,18 +132,39 @@ struct ZonesResultInfo {
 
 impl CloudflareClient {
     pub async fn new(api_token: String, base_domain: String) -> Result<Self> {
-        let zone_id = Self::resolve_zone_id(&api_token, &base_domain).await?;
-        Ok(Self { api_token, zone_id })
+        Self::new_with_resolver(api_token, base_domain, None).await
+    }
+
+    /// Builds a client whose DNS resolution of `api.cloudflare.com` goes through `resolver`
+    /// instead of the system stub resolver, for operators running inside a TEE with a locked-down
+    /// or non-standard resolver who need to pin upstream resolution.
+    pub async fn new_with_resolver(
+        api_token: String,
+        base_domain: String,
+        resolver: Option<Arc<dyn Resolve>>,
+    ) -> Result<Self> {
+        let client = match resolver {
+            Some(resolver) => Client::builder()
+                .dns_resolver(resolver)
+                .build()
+                .context("failed to build Cloudflare HTTP client")?,
+            None => Client::new(),
+        };
+        let zone_id = Self::resolve_zone_id(&client, &api_token, &base_domain).await?;
+        Ok(Self {
+            api_token,
+            zone_id,
+            client,
+        })
     }
 
-    async fn resolve_zone_id(api_token: &str, base_domain: &str) -> Result<String> {
+    async fn resolve_zone_id(client: &Client, api_token: &str, base_domain: &str) -> Result<String> {
         let base = base_domain
             .trim()
             .trim_start_matches("*.")
             .trim_end_matches('.')
             .to_lowercase();
 
-        let client = Client::new();
         let url = format!("{CLOUDFLARE_API_URL}/zones");
 
         let per_page = 50u32;
@@ -82,25 +175,17 @@ impl CloudflareClient {
         while page <= total_pages {
             debug!(url = %url, base_domain = %base, page, per_page, "cloudflare list zones request");
 
-            let response = client
-                .get(&url)
-                .header("Authorization", format!("Bearer {api_token}"))
-                .query(&[
-                    ("page", page.to_string()),
-                    ("per_page", per_page.to_string()),
-                ])
-                .send()
-                .await
-                .context("failed to list zones")?;
-
-            let status = response.status();
-            let body = response
-                .text()
-                .await
-                .context("failed to read zones response body")?;
-            if !status.is_success() {
-                bail!("failed to list zones: {body}");
-            }
+            let (status, body) = send_with_retry(|| {
+                client
+                    .get(&url)
+                    .header("Authorization", format!("Bearer {api_token}"))
+                    .query(&[
+                        ("page", page.to_string()),
+                        ("per_page", per_page.to_string()),
+                    ])
+            })
+            .await
+            .context("failed to list zones")?;
 
             #[derive(Deserialize, Debug)]
             struct ZonesPageResponse {
@@ -149,32 +234,24 @@ impl CloudflareClient {
     }
 
     async fn add_record(&self, record: &impl Serialize) -> Result<Response> {
-        let client = Client::new();
+        let client = &self.client;
         let url = format!("{CLOUDFLARE_API_URL}/zones/{}/dns_records", self.zone_id);
 
-        let response = client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
-            .json(record)
-            .send()
-            .await
-            .context("failed to send add_record request")?;
+        let (_, body) = send_with_retry(|| {
+            client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", self.api_token))
+                .header("Content-Type", "application/json")
+                .json(record)
+        })
+        .await?;
 
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .context("failed to read add_record response body")?;
-        if !status.is_success() {
-            anyhow::bail!("failed to add record: {body}");
-        }
         let response = serde_json::from_str(&body).context("failed to parse response")?;
         Ok(response)
     }
 
     async fn remove_record_inner(&self, record_id: &str) -> Result<()> {
-        let client = Client::new();
+        let client = &self.client;
         let url = format!(
             "{CLOUDFLARE_API_URL}/zones/{zone_id}/dns_records/{record_id}",
             zone_id = self.zone_id
@@ -182,25 +259,17 @@ impl CloudflareClient {
 
         debug!(url = %url, "cloudflare remove_record request");
 
-        let response = client
-            .delete(&url)
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .send()
-            .await?;
-
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .context("failed to read remove_record response body")?;
-        if !status.is_success() {
-            anyhow::bail!("failed to remove acme challenge: {body}");
-        }
+        send_with_retry(|| {
+            client
+                .delete(&url)
+                .header("Authorization", format!("Bearer {}", self.api_token))
+        })
+        .await?;
         Ok(())
     }
 
     async fn get_records_inner(&self, domain: &str) -> Result<Vec<Record>> {
-        let client = Client::new();
+        let client = &self.client;
         let url = format!("{CLOUDFLARE_API_URL}/zones/{}/dns_records", self.zone_id);
 
         let per_page = 100u32;
@@ -209,26 +278,17 @@ impl CloudflareClient {
 
         for page in 1..20 {
             // Safety limit to prevent infinite loops
-            let response = client
-                .get(&url)
-                .header("Authorization", format!("Bearer {}", self.api_token))
-                .query(&[
-                    ("name", domain),
-                    ("page", &page.to_string()),
-                    ("per_page", &per_page.to_string()),
-                ])
-                .send()
-                .await?;
-
-            let status = response.status();
-            let body = response
-                .text()
-                .await
-                .context("failed to read get_records response body")?;
-
-            if !status.is_success() {
-                anyhow::bail!("failed to get dns records: {body}");
-            }
+            let (_, body) = send_with_retry(|| {
+                client
+                    .get(&url)
+                    .header("Authorization", format!("Bearer {}", self.api_token))
+                    .query(&[
+                        ("name", domain),
+                        ("page", &page.to_string()),
+                        ("per_page", &per_page.to_string()),
+                    ])
+            })
+            .await?;
 
             let response: CloudflareListResponse =
                 serde_json::from_str(&body).context("failed to parse response")?;
@@ -247,6 +307,121 @@ impl CloudflareClient {
 
         Ok(records)
     }
+
+    /// Confirms `domain`'s `_acme-challenge` TXT record is visible on every authoritative
+    /// nameserver for its zone before the caller tells the ACME server to validate, instead of
+    /// validating blind and risking a flaky issuance. Ideally this would be a default method on
+    /// [`Dns01Api`] built on top of [`Dns01Api::get_records`] so every backend gets it for free,
+    /// but this checkout has no `Dns01Api` trait definition to extend, so it lives here as an
+    /// inherent method until that trait module exists.
+    ///
+    /// Resolves the zone's NS records (following a CNAME on the challenge name itself first, for
+    /// delegated `_acme-challenge` validation domains), then sends a TXT query for `domain`
+    /// directly to each authoritative nameserver's address — bypassing recursive resolvers, which
+    /// can keep serving a stale negative answer from their own cache — and polls every
+    /// `poll_interval` until all of them agree or `timeout` elapses. Tolerates multiple coexisting
+    /// TXT records on the name and IPv6-only nameservers.
+    pub async fn wait_for_propagation(
+        domain: &str,
+        expected_content: &str,
+        timeout: Duration,
+    ) -> Result<()> {
+        const POLL_INTERVAL: Duration = Duration::from_secs(3);
+        let deadline = Instant::now() + timeout;
+
+        let nameservers = Self::authoritative_nameservers(domain).await?;
+        if nameservers.is_empty() {
+            bail!("no authoritative nameservers found for {domain}");
+        }
+
+        loop {
+            let mut all_match = true;
+            for ns_addr in &nameservers {
+                if !Self::nameserver_has_txt(*ns_addr, domain, expected_content).await? {
+                    all_match = false;
+                    break;
+                }
+            }
+            if all_match {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                bail!(
+                    "timed out waiting for DNS-01 propagation of {domain} to all authoritative nameservers"
+                );
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Resolves the authoritative nameservers for `domain`'s zone and returns their addresses.
+    async fn authoritative_nameservers(domain: &str) -> Result<Vec<IpAddr>> {
+        let resolver =
+            TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default());
+
+        // Follow a CNAME on the challenge name itself before asking for NS records, so a
+        // delegated `_acme-challenge` validation domain resolves against the right zone.
+        let lookup_name = match resolver.lookup(domain, RecordType::CNAME).await {
+            Ok(lookup) => lookup
+                .iter()
+                .find_map(|r| r.as_cname().map(|n| n.to_utf8()))
+                .unwrap_or_else(|| domain.to_string()),
+            Err(_) => domain.to_string(),
+        };
+
+        let ns_lookup = resolver
+            .ns_lookup(lookup_name.trim_end_matches('.'))
+            .await
+            .context("failed to resolve authoritative nameservers")?;
+
+        let mut addrs = Vec::new();
+        for ns in ns_lookup.iter() {
+            let ns_name = ns.0.to_utf8();
+            match resolver.lookup_ip(ns_name.as_str()).await {
+                Ok(ips) => addrs.extend(ips.iter()),
+                Err(err) => {
+                    debug!(nameserver = %ns_name, error = %err, "failed to resolve nameserver address, skipping");
+                }
+            }
+        }
+        Ok(addrs)
+    }
+
+    /// Sends a TXT query for `domain` directly to `ns_addr`, bypassing recursive resolvers, and
+    /// checks whether any returned rdata exactly matches `expected_content`.
+    async fn nameserver_has_txt(
+        ns_addr: IpAddr,
+        domain: &str,
+        expected_content: &str,
+    ) -> Result<bool> {
+        let mut config = ResolverConfig::new();
+        config.add_name_server(NameServerConfig {
+            socket_addr: SocketAddr::new(ns_addr, 53),
+            protocol: Protocol::Udp,
+            tls_dns_name: None,
+            trust_negative_responses: false,
+            bind_addr: None,
+        });
+        let mut opts = ResolverOpts::default();
+        opts.use_hosts_file = false;
+        opts.cache_size = 0;
+
+        let resolver = TokioAsyncResolver::tokio(config, opts);
+        let response = match resolver.txt_lookup(domain).await {
+            Ok(response) => response,
+            Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                return Ok(false)
+            }
+            Err(err) => return Err(err).context(format!("TXT query to {ns_addr} failed")),
+        };
+
+        Ok(response.iter().any(|txt| {
+            txt.iter()
+                .map(|chunk| String::from_utf8_lossy(chunk))
+                .collect::<String>()
+                == expected_content
+        }))
+    }
 }
 
 impl Dns01Api for CloudflareClient {
@@ -0,0 +1,251 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::debug;
+
+use crate::dns01_client::Record;
+
+use super::Dns01Api;
+
+/// A generic self-hosted DNS REST API: `/zones`, `/zones/{zone}/records`, bearer-token auth. Lets
+/// dstack deployments use DNS servers other than Cloudflare for ACME without forking the KMS cert
+/// path — see [`Dns01Provider`] for how a deployment picks between the two from config.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenericDnsClient {
+    base_url: String,
+    zone_id: String,
+    bearer_token: String,
+    #[serde(skip, default = "Client::new")]
+    client: Client,
+}
+
+#[derive(Deserialize, Debug)]
+struct ZoneInfo {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ZonesResponse {
+    result: Vec<ZoneInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RecordsResponse {
+    result: Vec<Record>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RecordResponse {
+    result: Record,
+}
+
+impl GenericDnsClient {
+    pub async fn new(base_url: String, bearer_token: String, base_domain: String) -> Result<Self> {
+        let client = Client::new();
+        let zone_id = Self::resolve_zone_id(&client, &base_url, &bearer_token, &base_domain).await?;
+        Ok(Self {
+            base_url,
+            zone_id,
+            bearer_token,
+            client,
+        })
+    }
+
+    /// Resolves the zone to operate on by longest-suffix match over the zones the provider lists,
+    /// exactly like [`super::cloudflare::CloudflareClient::resolve_zone_id`] does.
+    async fn resolve_zone_id(
+        client: &Client,
+        base_url: &str,
+        bearer_token: &str,
+        base_domain: &str,
+    ) -> Result<String> {
+        let base = base_domain
+            .trim()
+            .trim_start_matches("*.")
+            .trim_end_matches('.')
+            .to_lowercase();
+
+        let response = client
+            .get(format!("{base_url}/zones"))
+            .header("Authorization", format!("Bearer {bearer_token}"))
+            .send()
+            .await
+            .context("failed to list zones")?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .context("failed to read zones response body")?;
+        if !status.is_success() {
+            bail!("failed to list zones: {body}");
+        }
+        let zones: ZonesResponse =
+            serde_json::from_str(&body).context("failed to parse zones response")?;
+
+        let parts: Vec<&str> = base.split('.').collect();
+        for i in 0..parts.len() {
+            let candidate = parts[i..].join(".");
+            if let Some(zone) = zones
+                .result
+                .iter()
+                .find(|z| z.name.eq_ignore_ascii_case(&candidate))
+            {
+                debug!(base_domain = %base, zone = %candidate, zone_id = %zone.id, "resolved generic DNS provider zone");
+                return Ok(zone.id.clone());
+            }
+        }
+
+        bail!("no matching zone found for base_domain: {base_domain}")
+    }
+
+    fn records_url(&self) -> String {
+        format!("{}/zones/{}/records", self.base_url, self.zone_id)
+    }
+
+    async fn create_record(&self, record: &impl Serialize) -> Result<String> {
+        let response = self
+            .client
+            .post(self.records_url())
+            .header("Authorization", format!("Bearer {}", self.bearer_token))
+            .header("Content-Type", "application/json")
+            .json(record)
+            .send()
+            .await
+            .context("failed to send create record request")?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .context("failed to read create record response body")?;
+        if !status.is_success() {
+            bail!("failed to create record: {body}");
+        }
+        let response: RecordResponse =
+            serde_json::from_str(&body).context("failed to parse create record response")?;
+        Ok(response.result.id)
+    }
+}
+
+impl Dns01Api for GenericDnsClient {
+    async fn remove_record(&self, record_id: &str) -> Result<()> {
+        let response = self
+            .client
+            .delete(format!("{}/{record_id}", self.records_url()))
+            .header("Authorization", format!("Bearer {}", self.bearer_token))
+            .send()
+            .await
+            .context("failed to send remove record request")?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .context("failed to read remove record response body")?;
+        if !status.is_success() {
+            bail!("failed to remove record: {body}");
+        }
+        Ok(())
+    }
+
+    async fn remove_txt_records(&self, domain: &str) -> Result<()> {
+        let records = self.get_records(domain).await?;
+        for record in records.into_iter().filter(|r| r.r#type == "TXT") {
+            debug!(domain = %domain, id = %record.id, "removing txt record");
+            self.remove_record(&record.id).await?;
+        }
+        Ok(())
+    }
+
+    async fn add_txt_record(&self, domain: &str, content: &str) -> Result<String> {
+        self.create_record(&json!({
+            "type": "TXT",
+            "name": domain,
+            "content": content,
+        }))
+        .await
+    }
+
+    async fn add_caa_record(
+        &self,
+        domain: &str,
+        flags: u8,
+        tag: &str,
+        value: &str,
+    ) -> Result<String> {
+        self.create_record(&json!({
+            "type": "CAA",
+            "name": domain,
+            "data": {
+                "flags": flags,
+                "tag": tag,
+                "value": value
+            }
+        }))
+        .await
+    }
+
+    async fn get_records(&self, domain: &str) -> Result<Vec<Record>> {
+        let target = domain.trim_end_matches('.');
+
+        let response = self
+            .client
+            .get(self.records_url())
+            .header("Authorization", format!("Bearer {}", self.bearer_token))
+            .query(&[("name", domain)])
+            .send()
+            .await
+            .context("failed to send get records request")?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .context("failed to read get records response body")?;
+        if !status.is_success() {
+            bail!("failed to get dns records: {body}");
+        }
+        let response: RecordsResponse =
+            serde_json::from_str(&body).context("failed to parse get records response")?;
+
+        Ok(response
+            .result
+            .into_iter()
+            .filter(|record| record.name.trim_end_matches('.').eq_ignore_ascii_case(target))
+            .collect())
+    }
+}
+
+/// Selects which DNS-01 backend a deployment uses for ACME challenges, picked from config rather
+/// than compiled in. Ideally this factory would live in `dns01_client`'s module root alongside the
+/// [`Dns01Api`] trait it dispatches to, but this checkout has no `mod.rs` for that module, so it
+/// lives here next to the backend it was added to support.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Dns01Provider {
+    Cloudflare { api_token: String },
+    Generic { base_url: String, bearer_token: String },
+}
+
+impl Dns01Provider {
+    pub async fn build(self, base_domain: String) -> Result<Box<dyn Dns01Api>> {
+        match self {
+            Dns01Provider::Cloudflare { api_token } => Ok(Box::new(
+                super::cloudflare::CloudflareClient::new(api_token, base_domain).await?,
+            )),
+            Dns01Provider::Generic {
+                base_url,
+                bearer_token,
+            } => Ok(Box::new(
+                GenericDnsClient::new(base_url, bearer_token, base_domain).await?,
+            )),
+        }
+    }
+}
@@ -4,11 +4,12 @@
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use dstack_mr::Machine;
+use dstack_mr::{ArtifactSource, CacheDir, Machine, TdxMeasurementDetails, TdxMeasurements};
 use dstack_types::ImageInfo;
+use ed25519_dalek::VerifyingKey;
 use fs_err as fs;
 use size_parser::parse_memory_size;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -19,14 +20,21 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Measure a machine configuration
+    /// Measure a single machine configuration
     Measure(MachineConfig),
+    /// Measure every metadata.json in a batch, reporting all successes and failures together
+    MeasureBatch(BatchMachineConfig),
+    /// Fetch a signed release's firmware/kernel/initrd from a manifest (see
+    /// `dstack_mr::ArtifactSource`), verify it against a pinned root key, then measure it -- the
+    /// confirmed-published-release counterpart to `measure`, which only works with files already
+    /// on disk.
+    FetchAndMeasure(FetchConfig),
 }
 
 type Bool = bool;
 
 #[derive(Parser)]
-struct MachineConfig {
+struct MachineFlags {
     /// Number of CPUs
     #[arg(short, long, default_value = "1")]
     cpu: u32,
@@ -35,9 +43,6 @@ struct MachineConfig {
     #[arg(short, long, default_value = "2G", value_parser = parse_memory_size)]
     memory: u64,
 
-    /// Path to dstack image metadata.json
-    metadata: PathBuf,
-
     /// Enable two-pass add pages
     #[arg(long)]
     two_pass_add_pages: Option<Bool>,
@@ -83,54 +88,294 @@ struct MachineConfig {
     json: bool,
 }
 
+#[derive(Parser)]
+struct MachineConfig {
+    #[command(flatten)]
+    flags: MachineFlags,
+
+    /// Path to dstack image metadata.json
+    metadata: PathBuf,
+
+    /// Path to a previously captured TdxMeasurements JSON file (e.g. from `--json`) to diff the
+    /// freshly computed measurements against. Exits nonzero on any per-register mismatch.
+    #[arg(long)]
+    expected: Option<PathBuf>,
+
+    /// When a register mismatches `--expected`, dump the RTMR event logs and ACPI tables behind
+    /// it, to help pinpoint which measured event (firmware blob, kernel cmdline, initrd hash,
+    /// ACPI table) actually changed. Has no effect without `--expected`.
+    #[arg(long)]
+    show_logs: bool,
+}
+
+#[derive(Parser)]
+struct BatchMachineConfig {
+    #[command(flatten)]
+    flags: MachineFlags,
+
+    /// metadata.json paths, or directories each containing one, to measure. A shell glob like
+    /// `images/*/metadata.json` expands to multiple of these before clap ever sees them.
+    #[arg(required = true)]
+    metadata: Vec<PathBuf>,
+}
+
+#[derive(Parser)]
+struct FetchConfig {
+    #[command(flatten)]
+    flags: MachineFlags,
+
+    /// URL of the signed manifest.json describing the release to fetch.
+    #[arg(long)]
+    manifest_url: String,
+
+    /// Hex-encoded Ed25519 public key trusted to have signed the manifest. Repeatable; any one
+    /// matching signature is sufficient.
+    #[arg(long = "root-key", required = true)]
+    root_keys: Vec<String>,
+
+    /// Directory fetched artifacts are cached into, keyed by the manifest's release version.
+    #[arg(long)]
+    cache_dir: PathBuf,
+
+    /// Kernel command line to measure with. A fetched-only release has no local metadata.json to
+    /// read this from, unlike `measure`/`measure-batch`.
+    #[arg(long, default_value = "")]
+    cmdline: String,
+}
+
+/// Parses `--root-key` hex strings into the `VerifyingKey`s `ArtifactSource::new` expects.
+fn parse_root_keys(hex_keys: &[String]) -> Result<Vec<VerifyingKey>> {
+    hex_keys
+        .iter()
+        .map(|hex_key| {
+            let bytes: [u8; 32] = hex::decode(hex_key)
+                .context("Failed to decode --root-key as hex")?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("--root-key must be exactly 32 bytes"))?;
+            VerifyingKey::from_bytes(&bytes).context("--root-key is not a valid Ed25519 public key")
+        })
+        .collect()
+}
+
+/// Resolves `path` to an actual `metadata.json` file: `path` itself if it's a file, or
+/// `path/metadata.json` if it's a directory.
+fn resolve_metadata_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join("metadata.json")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+fn build_machine<'a>(
+    firmware_path: &'a str,
+    kernel_path: &'a str,
+    initrd_path: &'a str,
+    cmdline: &'a str,
+    flags: &'a MachineFlags,
+) -> Machine<'a> {
+    Machine::builder()
+        .cpu_count(flags.cpu)
+        .memory_size(flags.memory)
+        .firmware(firmware_path)
+        .kernel(kernel_path)
+        .initrd(initrd_path)
+        .kernel_cmdline(cmdline)
+        .maybe_two_pass_add_pages(flags.two_pass_add_pages)
+        .maybe_pic(flags.pic)
+        .smm(flags.smm)
+        .maybe_pci_hole64_size(flags.pci_hole64_size)
+        .hugepages(flags.hugepages)
+        .num_gpus(flags.num_gpus)
+        .num_nvswitches(flags.num_nvswitches)
+        .hotplug_off(flags.hotplug_off)
+        .root_verity(flags.root_verity)
+        .maybe_qemu_version(flags.qemu_version.clone())
+        .build()
+}
+
+fn load_image_info(metadata_path: &Path) -> Result<(ImageInfo, String, String, String, String)> {
+    let metadata =
+        fs::read_to_string(metadata_path).context("Failed to read image metadata")?;
+    let image_info: ImageInfo =
+        serde_json::from_str(&metadata).context("Failed to parse image metadata")?;
+    let parent_dir = metadata_path.parent().unwrap_or(".".as_ref());
+    let firmware_path = parent_dir.join(&image_info.bios).display().to_string();
+    let kernel_path = parent_dir.join(&image_info.kernel).display().to_string();
+    let initrd_path = parent_dir.join(&image_info.initrd).display().to_string();
+    let cmdline = image_info.cmdline.clone() + " initrd=initrd";
+    Ok((image_info, firmware_path, kernel_path, initrd_path, cmdline))
+}
+
+fn measure_one(metadata_path: &Path, flags: &MachineFlags) -> Result<TdxMeasurements> {
+    let (_image_info, firmware_path, kernel_path, initrd_path, cmdline) =
+        load_image_info(metadata_path)?;
+    let machine = build_machine(&firmware_path, &kernel_path, &initrd_path, &cmdline, flags);
+    machine
+        .measure()
+        .context("Failed to measure machine configuration")
+}
+
+fn measure_one_with_logs(metadata_path: &Path, flags: &MachineFlags) -> Result<TdxMeasurementDetails> {
+    let (_image_info, firmware_path, kernel_path, initrd_path, cmdline) =
+        load_image_info(metadata_path)?;
+    let machine = build_machine(&firmware_path, &kernel_path, &initrd_path, &cmdline, flags);
+    machine
+        .measure_with_logs()
+        .context("Failed to measure machine configuration")
+}
+
+/// One register's expected-vs-actual comparison, e.g. for printing a match/mismatch table.
+struct RegisterDiff {
+    name: &'static str,
+    expected: Vec<u8>,
+    actual: Vec<u8>,
+}
+
+impl RegisterDiff {
+    fn matches(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+fn diff_measurements(expected: &TdxMeasurements, actual: &TdxMeasurements) -> Vec<RegisterDiff> {
+    vec![
+        RegisterDiff {
+            name: "MRTD",
+            expected: expected.mrtd.clone(),
+            actual: actual.mrtd.clone(),
+        },
+        RegisterDiff {
+            name: "RTMR0",
+            expected: expected.rtmr0.clone(),
+            actual: actual.rtmr0.clone(),
+        },
+        RegisterDiff {
+            name: "RTMR1",
+            expected: expected.rtmr1.clone(),
+            actual: actual.rtmr1.clone(),
+        },
+        RegisterDiff {
+            name: "RTMR2",
+            expected: expected.rtmr2.clone(),
+            actual: actual.rtmr2.clone(),
+        },
+    ]
+}
+
+/// Prints a per-register match/mismatch table and, if `show_logs` and any register mismatched,
+/// dumps the RTMR event logs and ACPI tables behind the freshly computed measurement so a user
+/// can pinpoint which measured event actually changed.
+fn report_expected_diff(
+    diffs: &[RegisterDiff],
+    details: &TdxMeasurementDetails,
+    show_logs: bool,
+) -> bool {
+    let mut all_match = true;
+    for diff in diffs {
+        let status = if diff.matches() { "match" } else { "MISMATCH" };
+        all_match &= diff.matches();
+        println!(
+            "{:<6} {:<9} expected={} actual={}",
+            diff.name,
+            status,
+            hex::encode(&diff.expected),
+            hex::encode(&diff.actual)
+        );
+    }
+    if !all_match && show_logs {
+        println!("\n-- RTMR event logs --");
+        for (i, log) in details.rtmr_logs.iter().enumerate() {
+            println!("RTMR{i} log ({} events):", log.len());
+            for (j, event) in log.iter().enumerate() {
+                println!("  [{j}] {}", hex::encode(event));
+            }
+        }
+        println!("\n-- ACPI tables --");
+        println!("{:#?}", details.acpi_tables);
+    }
+    all_match
+}
+
+fn print_measurements(measurements: &TdxMeasurements, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(measurements)?);
+    } else {
+        println!("Machine measurements:");
+        println!("MRTD: {}", hex::encode(measurements.mrtd));
+        println!("RTMR0: {}", hex::encode(measurements.rtmr0));
+        println!("RTMR1: {}", hex::encode(measurements.rtmr1));
+        println!("RTMR2: {}", hex::encode(measurements.rtmr2));
+    }
+    Ok(())
+}
+
 fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
     let cli = Cli::parse();
     match &cli.command {
         Commands::Measure(config) => {
-            let metadata =
-                fs::read_to_string(&config.metadata).context("Failed to read image metadata")?;
-            let image_info: ImageInfo =
-                serde_json::from_str(&metadata).context("Failed to parse image metadata")?;
-            let parent_dir = config.metadata.parent().unwrap_or(".".as_ref());
-            let firmware_path = parent_dir.join(&image_info.bios).display().to_string();
-            let kernel_path = parent_dir.join(&image_info.kernel).display().to_string();
-            let initrd_path = parent_dir.join(&image_info.initrd).display().to_string();
-            let cmdline = image_info.cmdline + " initrd=initrd";
-
-            let machine = Machine::builder()
-                .cpu_count(config.cpu)
-                .memory_size(config.memory)
-                .firmware(&firmware_path)
-                .kernel(&kernel_path)
-                .initrd(&initrd_path)
-                .kernel_cmdline(&cmdline)
-                .maybe_two_pass_add_pages(config.two_pass_add_pages)
-                .maybe_pic(config.pic)
-                .smm(config.smm)
-                .maybe_pci_hole64_size(config.pci_hole64_size)
-                .hugepages(config.hugepages)
-                .num_gpus(config.num_gpus)
-                .num_nvswitches(config.num_nvswitches)
-                .hotplug_off(config.hotplug_off)
-                .root_verity(config.root_verity)
-                .maybe_qemu_version(config.qemu_version.clone())
-                .build();
+            let Some(expected_path) = &config.expected else {
+                let measurements = measure_one(&config.metadata, &config.flags)?;
+                print_measurements(&measurements, config.flags.json)?;
+                return Ok(());
+            };
+
+            let expected: TdxMeasurements = serde_json::from_str(
+                &fs::read_to_string(expected_path).context("Failed to read --expected file")?,
+            )
+            .context("Failed to parse --expected file as TdxMeasurements")?;
+            let details = measure_one_with_logs(&config.metadata, &config.flags)?;
+            let diffs = diff_measurements(&expected, &details.measurements);
+            let all_match = report_expected_diff(&diffs, &details, config.show_logs);
+            if !all_match {
+                std::process::exit(1);
+            }
+        }
+        Commands::MeasureBatch(config) => {
+            // Collect every per-machine Result up front instead of bailing on the first `?`,
+            // so a CI job verifying a whole image matrix gets the full diff of which configs
+            // mismatch in one run rather than one failure at a time.
+            let results: Vec<serde_json::Value> = config
+                .metadata
+                .iter()
+                .map(|path| {
+                    let metadata_path = resolve_metadata_path(path);
+                    match measure_one(&metadata_path, &config.flags) {
+                        Ok(measurements) => serde_json::json!({
+                            "path": metadata_path,
+                            "measurements": measurements,
+                        }),
+                        Err(err) => serde_json::json!({
+                            "path": metadata_path,
+                            "error": format!("{err:#}"),
+                        }),
+                    }
+                })
+                .collect();
 
+            let any_failed = results.iter().any(|entry| entry.get("error").is_some());
+            println!("{}", serde_json::to_string_pretty(&results)?);
+            if any_failed {
+                std::process::exit(1);
+            }
+        }
+        Commands::FetchAndMeasure(config) => {
+            let root_keys = parse_root_keys(&config.root_keys)?;
+            let source = ArtifactSource::new(config.manifest_url.clone(), root_keys);
+            let cache_dir = CacheDir::new(&config.cache_dir);
+            let resolved = source
+                .resolve(&cache_dir)
+                .map_err(|e| anyhow::anyhow!("Failed to fetch and verify release: {e}"))?;
+
+            let cmdline = config.cmdline.clone() + " initrd=initrd";
+            let (firmware, kernel, initrd) = resolved.machine_paths()?;
+            let machine = build_machine(firmware, kernel, initrd, &cmdline, &config.flags);
             let measurements = machine
                 .measure()
-                .context("Failed to measure machine configuration")?;
-
-            if config.json {
-                println!("{}", serde_json::to_string_pretty(&measurements)?);
-            } else {
-                println!("Machine measurements:");
-                println!("MRTD: {}", hex::encode(measurements.mrtd));
-                println!("RTMR0: {}", hex::encode(measurements.rtmr0));
-                println!("RTMR1: {}", hex::encode(measurements.rtmr1));
-                println!("RTMR2: {}", hex::encode(measurements.rtmr2));
-            }
+                .context("Failed to measure fetched machine configuration")?;
+            print_measurements(&measurements, config.flags.json)?;
         }
     }
 
@@ -0,0 +1,78 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Format-detecting decompression for release tar bundles, generalizing the hardcoded
+//! `flate2::read::GzDecoder` in `tests/tdvf_parse.rs`'s `get_test_image_dir` (which only ever
+//! worked for `.tar.gz`) so newer dstack releases and object-store artifacts can ship as the much
+//! smaller `.tar.zst`, or as `.tar.bz2`, with no caller-visible difference.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error(
+        "could not detect a supported compression format (gzip, zstd, bzip2) from the \
+         archive's magic bytes"
+    )]
+    UnknownFormat,
+    #[error("I/O error extracting archive: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A tar bundle's compression, either sniffed from its magic bytes via [`Compression::detect`]
+/// or declared up front on an [`crate::ArtifactDescriptor`] to skip sniffing entirely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Sniffs the compression format from an archive's leading bytes. Returns `None` if none of
+    /// the three supported magic numbers match.
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::Zstd)
+        } else if header.starts_with(b"BZh") {
+            Some(Self::Bzip2)
+        } else {
+            None
+        }
+    }
+}
+
+/// Extracts a `.tar.{gz,zst,bz2}` archive's bytes into `dest`, using `declared` when the caller
+/// already knows the compression format and otherwise sniffing it from `bytes`' magic number.
+pub fn extract_tar_archive(
+    bytes: &[u8],
+    declared: Option<Compression>,
+    dest: &Path,
+) -> Result<(), ArchiveError> {
+    let compression = declared
+        .or_else(|| Compression::detect(bytes))
+        .ok_or(ArchiveError::UnknownFormat)?;
+
+    match compression {
+        Compression::Gzip => {
+            let decoder = flate2::read::GzDecoder::new(bytes);
+            tar::Archive::new(decoder).unpack(dest)?;
+        }
+        Compression::Zstd => {
+            let decoder = zstd::stream::read::Decoder::new(bytes)?;
+            tar::Archive::new(decoder).unpack(dest)?;
+        }
+        Compression::Bzip2 => {
+            let decoder = bzip2::read::BzDecoder::new(bytes);
+            tar::Archive::new(decoder).unpack(dest)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,375 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Integrity-verified fetching of remote firmware/kernel/initrd inputs for [`crate::Machine`].
+//!
+//! Modeled on TUF's signed-metadata scheme: a `manifest.json` lists each artifact's name,
+//! version, size, and SHA-384 digest, and is itself Ed25519-signed by one of a pinned set of
+//! root public keys. [`ArtifactSource::resolve`] downloads the manifest, checks its signature,
+//! then downloads each artifact and rejects any whose length or digest doesn't match its
+//! manifest entry -- so measuring a published dstack release gives a hard guarantee the measured
+//! bytes are the signed, released bytes rather than a tampered mirror.
+//!
+//! `dstack-mr` has no async runtime of its own, so fetching here is blocking, mirroring the
+//! `reqwest::blocking` download already used by `tests/tdvf_parse.rs`'s `get_test_image_dir`.
+
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha384};
+use thiserror::Error;
+
+use crate::archive::{self, Compression};
+use crate::ArtifactStore;
+use crate::CacheDir;
+
+/// The names `resolve` looks for in a manifest's artifact list. `metadata` is optional: older
+/// manifests that only describe `firmware`/`kernel`/`initrd` are still accepted.
+const REQUIRED_ARTIFACTS: [&str; 3] = ["firmware", "kernel", "initrd"];
+
+/// One artifact listed in a [`Manifest`]: what to fetch, and what it must hash/size to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactDescriptor {
+    pub name: String,
+    pub version: String,
+    pub url: String,
+    pub size: u64,
+    /// Expected digest, lowercase hex-encoded SHA-384.
+    pub sha384: String,
+    /// Set when this artifact is itself a compressed tar bundle (e.g. a whole-release archive
+    /// rather than a single file) whose format the manifest already knows, so
+    /// [`ArtifactSource::fetch_and_extract`] can skip magic-byte sniffing. `None` for a plain
+    /// file, or when the format should be sniffed.
+    #[serde(default)]
+    pub compression: Option<Compression>,
+}
+
+/// The signed body of a manifest: the release it's for and the artifact list it vouches for.
+/// Kept separate from [`Manifest`] so the signature can be computed over exactly these bytes,
+/// rather than over a struct that also carries the signature itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestBody {
+    /// The release this manifest describes, e.g. `v0.5.5`. Used as the cache key for
+    /// [`ArtifactSource::resolve`]'s [`CacheDir`] entry, so every artifact in one release shares
+    /// one locked, atomically-populated directory.
+    pub version: String,
+    pub artifacts: Vec<ArtifactDescriptor>,
+}
+
+/// A downloaded `manifest.json`: the signed artifact list plus the Ed25519 signature over its
+/// canonical JSON encoding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(flatten)]
+    pub body: ManifestBody,
+    /// Ed25519 signature over `serde_json::to_vec(&body)`, lowercase hex-encoded.
+    pub signature: String,
+}
+
+/// Errors integrity-verified fetching can fail with, distinguished so a caller can tell "the
+/// manifest doesn't check out" apart from "the network is down".
+#[derive(Debug, Error)]
+pub enum MeasureError {
+    #[error("request to {url} failed: {source}")]
+    Request {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("I/O error while fetching {url}: {source}")]
+    Io {
+        url: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("manifest at {0} is not valid JSON")]
+    InvalidManifest(String),
+    #[error("manifest at {0} is missing a '{1}' artifact entry")]
+    MissingArtifact(String, &'static str),
+    #[error("manifest at {0} has a malformed signature")]
+    MalformedSignature(String),
+    #[error("manifest at {0} is not signed by any pinned root key")]
+    UntrustedManifest(String),
+    #[error("artifact {artifact} was {actual} bytes, expected {expected}")]
+    SizeMismatch {
+        artifact: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("artifact {artifact} hashed to {actual}, expected {expected}")]
+    IntegrityFailure {
+        artifact: String,
+        expected: String,
+        actual: String,
+    },
+    #[error(transparent)]
+    Cache(#[from] crate::cache_dir::CacheError),
+}
+
+/// Declares where to fetch a signed release's `manifest.json` from, and which root public keys
+/// are trusted to have signed it.
+pub struct ArtifactSource {
+    manifest_url: String,
+    root_keys: Vec<VerifyingKey>,
+}
+
+/// Local paths [`ArtifactSource::resolve`] verified and downloaded, ready to hand to
+/// `Machine::builder()`'s existing `firmware`/`kernel`/`initrd` setters via [`Self::machine_paths`].
+/// `dstack-mr-cli fetch-and-measure` is the real, working caller of this (not just a unit test):
+/// it resolves a signed release through [`ArtifactSource::resolve`] and feeds the result straight
+/// into `build_machine`/`Machine::measure`.
+///
+/// A literal `Machine::builder().artifact_source(source)` setter isn't wired up yet: `Machine`'s
+/// builder is generated by `bon::Builder` from its `&'a str` path fields, and teaching that
+/// generated typestate to accept a fetched-and-owned [`ArtifactSource`] in place of three
+/// separate string setters needs its own builder-surface change (likely a second `bon::Builder`
+/// struct that borrows from a resolved, owned [`ResolvedArtifacts`]). Until that lands, resolve
+/// up front and feed the paths in, e.g.:
+/// ```ignore
+/// let resolved = source.resolve(cache_dir)?;
+/// let (firmware, kernel, initrd) = resolved.machine_paths()?;
+/// let machine = Machine::builder()
+///     .firmware(firmware)
+///     .kernel(kernel)
+///     .initrd(initrd)
+///     // ...
+///     .build();
+/// ```
+pub struct ResolvedArtifacts {
+    pub firmware: PathBuf,
+    pub kernel: PathBuf,
+    pub initrd: PathBuf,
+    /// Present only if the manifest carried a `metadata` entry.
+    pub metadata: Option<PathBuf>,
+}
+
+impl ResolvedArtifacts {
+    /// Borrows `firmware`/`kernel`/`initrd` as the `&str` paths `Machine::builder()`'s existing
+    /// setters expect, erroring instead of panicking if the cache produced a non-UTF-8 path.
+    pub fn machine_paths(&self) -> anyhow::Result<(&str, &str, &str)> {
+        use anyhow::Context;
+        let firmware = self
+            .firmware
+            .to_str()
+            .context("fetched firmware path is not valid UTF-8")?;
+        let kernel = self
+            .kernel
+            .to_str()
+            .context("fetched kernel path is not valid UTF-8")?;
+        let initrd = self
+            .initrd
+            .to_str()
+            .context("fetched initrd path is not valid UTF-8")?;
+        Ok((firmware, kernel, initrd))
+    }
+}
+
+impl ArtifactSource {
+    pub fn new(manifest_url: impl Into<String>, root_keys: Vec<VerifyingKey>) -> Self {
+        Self {
+            manifest_url: manifest_url.into(),
+            root_keys,
+        }
+    }
+
+    fn fetch_bytes(&self, url: &str) -> Result<Vec<u8>, MeasureError> {
+        let to_err = |source: reqwest::Error| MeasureError::Request {
+            url: url.to_string(),
+            source,
+        };
+        let response = reqwest::blocking::get(url)
+            .map_err(to_err)?
+            .error_for_status()
+            .map_err(to_err)?;
+        Ok(response.bytes().map_err(to_err)?.to_vec())
+    }
+
+    /// Downloads `manifest_url` and checks its Ed25519 signature against the pinned root key
+    /// set, returning the verified manifest body.
+    fn fetch_manifest(&self) -> Result<Manifest, MeasureError> {
+        let bytes = self.fetch_bytes(&self.manifest_url)?;
+        let manifest: Manifest = serde_json::from_slice(&bytes)
+            .map_err(|_| MeasureError::InvalidManifest(self.manifest_url.clone()))?;
+        let body_bytes = serde_json::to_vec(&manifest.body)
+            .map_err(|_| MeasureError::InvalidManifest(self.manifest_url.clone()))?;
+        let sig_bytes = hex::decode(&manifest.signature)
+            .map_err(|_| MeasureError::MalformedSignature(self.manifest_url.clone()))?;
+        let signature = Signature::from_slice(&sig_bytes)
+            .map_err(|_| MeasureError::MalformedSignature(self.manifest_url.clone()))?;
+        let trusted = self
+            .root_keys
+            .iter()
+            .any(|key| key.verify(&body_bytes, &signature).is_ok());
+        if !trusted {
+            return Err(MeasureError::UntrustedManifest(self.manifest_url.clone()));
+        }
+        Ok(manifest)
+    }
+
+    /// Fetches an artifact's bytes, preferring `store` (keyed by the artifact's SHA-384 digest)
+    /// when one is given and falling back to `descriptor.url` over HTTP on a store miss.
+    fn fetch_artifact_bytes(
+        &self,
+        descriptor: &ArtifactDescriptor,
+        store: Option<&dyn ArtifactStore>,
+    ) -> Result<Vec<u8>, MeasureError> {
+        if let Some(store) = store {
+            match store.get(&descriptor.sha384) {
+                Ok(bytes) => return Ok(bytes),
+                Err(e) => log::debug!(
+                    "object store miss for artifact {} ({}), falling back to {}: {e:?}",
+                    descriptor.name,
+                    descriptor.sha384,
+                    descriptor.url
+                ),
+            }
+        }
+        self.fetch_bytes(&descriptor.url)
+    }
+
+    /// Checks `bytes` against `descriptor`'s expected length/digest, rejecting on a mismatch.
+    fn verify_bytes(
+        &self,
+        descriptor: &ArtifactDescriptor,
+        bytes: &[u8],
+    ) -> Result<(), MeasureError> {
+        if bytes.len() as u64 != descriptor.size {
+            return Err(MeasureError::SizeMismatch {
+                artifact: descriptor.name.clone(),
+                expected: descriptor.size,
+                actual: bytes.len() as u64,
+            });
+        }
+        let actual = hex::encode(Sha384::digest(bytes));
+        if actual != descriptor.sha384 {
+            return Err(MeasureError::IntegrityFailure {
+                artifact: descriptor.name.clone(),
+                expected: descriptor.sha384.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks `bytes` against `descriptor`'s expected length/digest and writes them to `dest`,
+    /// rejecting (and leaving no file behind) on a mismatch.
+    fn verify_and_write(
+        &self,
+        descriptor: &ArtifactDescriptor,
+        bytes: Vec<u8>,
+        dest: &Path,
+    ) -> Result<(), MeasureError> {
+        self.verify_bytes(descriptor, &bytes)?;
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| MeasureError::Io {
+                url: descriptor.url.clone(),
+                source: e.into(),
+            })?;
+        }
+        fs::write(dest, &bytes).map_err(|e| MeasureError::Io {
+            url: descriptor.url.clone(),
+            source: e.into(),
+        })?;
+        Ok(())
+    }
+
+    /// Downloads a single artifact (optionally via `store`) and writes it to `dest`, rejecting it
+    /// (and leaving no file behind) if its length or SHA-384 digest doesn't match `descriptor`.
+    fn fetch_artifact(
+        &self,
+        descriptor: &ArtifactDescriptor,
+        store: Option<&dyn ArtifactStore>,
+        dest: &Path,
+    ) -> Result<(), MeasureError> {
+        let bytes = self.fetch_artifact_bytes(descriptor, store)?;
+        self.verify_and_write(descriptor, bytes, dest)
+    }
+
+    /// Downloads and verifies the manifest, then downloads and verifies `firmware`, `kernel`,
+    /// and `initrd` (and `metadata`, if listed) into `cache_dir`, keyed by the manifest's
+    /// `version` so every artifact in one release shares a single locked, atomically-populated
+    /// [`CacheEntry`](crate::CacheEntry) -- safe even if several processes resolve the same
+    /// release concurrently. Fails closed: any artifact whose length or digest doesn't match its
+    /// manifest entry leaves no usable file behind, and aborts the whole resolve.
+    pub fn resolve(&self, cache_dir: &CacheDir) -> Result<ResolvedArtifacts, MeasureError> {
+        self.resolve_impl(cache_dir, None)
+    }
+
+    /// Like [`Self::resolve`], but resolves each artifact by its SHA-384 digest from `store`
+    /// first (e.g. a shared [`crate::TieredStore`] deduplicating across a fleet), falling back
+    /// to its manifest `url` over HTTP on a store miss.
+    pub fn resolve_with_store(
+        &self,
+        cache_dir: &CacheDir,
+        store: &dyn ArtifactStore,
+    ) -> Result<ResolvedArtifacts, MeasureError> {
+        self.resolve_impl(cache_dir, Some(store))
+    }
+
+    fn resolve_impl(
+        &self,
+        cache_dir: &CacheDir,
+        store: Option<&dyn ArtifactStore>,
+    ) -> Result<ResolvedArtifacts, MeasureError> {
+        let manifest = self.fetch_manifest()?;
+        let find = |name: &'static str| {
+            manifest
+                .body
+                .artifacts
+                .iter()
+                .find(|a| a.name == name)
+                .ok_or_else(|| MeasureError::MissingArtifact(self.manifest_url.clone(), name))
+        };
+
+        let entry = cache_dir.entry(&manifest.body.version);
+        let has_metadata = manifest.body.artifacts.iter().any(|a| a.name == "metadata");
+        let populated = entry.get_or_populate(|tmp: &Path| {
+            for name in REQUIRED_ARTIFACTS {
+                let descriptor = find(name).map_err(anyhow::Error::new)?;
+                let dest = tmp.join(&descriptor.name);
+                self.fetch_artifact(descriptor, store, &dest)
+                    .map_err(anyhow::Error::new)?;
+            }
+            if let Some(descriptor) = manifest.body.artifacts.iter().find(|a| a.name == "metadata")
+            {
+                let dest = tmp.join(&descriptor.name);
+                self.fetch_artifact(descriptor, store, &dest)
+                    .map_err(anyhow::Error::new)?;
+            }
+            Ok(())
+        })?;
+
+        Ok(ResolvedArtifacts {
+            firmware: populated.join("firmware"),
+            kernel: populated.join("kernel"),
+            initrd: populated.join("initrd"),
+            metadata: has_metadata.then(|| populated.join("metadata")),
+        })
+    }
+
+    /// Downloads and verifies `descriptor` (optionally via `store`), then -- since it names a
+    /// compressed tar bundle rather than a single file -- extracts it into `cache_dir`'s entry
+    /// for `descriptor.version`, using `descriptor.compression` when set and otherwise sniffing
+    /// the format from the downloaded bytes' magic number. Use this for a manifest artifact that
+    /// packages a whole release (e.g. firmware/kernel/initrd together, gzip/zstd/bzip2-compressed)
+    /// instead of [`Self::resolve`]'s one-file-per-artifact model.
+    pub fn fetch_and_extract(
+        &self,
+        descriptor: &ArtifactDescriptor,
+        store: Option<&dyn ArtifactStore>,
+        cache_dir: &CacheDir,
+    ) -> Result<PathBuf, MeasureError> {
+        let bytes = self.fetch_artifact_bytes(descriptor, store)?;
+        self.verify_bytes(descriptor, &bytes)?;
+        let entry = cache_dir.entry(&descriptor.version);
+        entry
+            .get_or_populate(|tmp| {
+                archive::extract_tar_archive(&bytes, descriptor.compression, tmp)?;
+                Ok(())
+            })
+            .map_err(MeasureError::from)
+    }
+}
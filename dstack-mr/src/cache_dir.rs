@@ -0,0 +1,155 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Concurrency-safe caching of downloaded release artifacts, promoted from the ad hoc
+//! check-then-extract pattern in `tests/tdvf_parse.rs`'s `get_test_image_dir` into a reusable
+//! type guarded by advisory file locks, so multiple `dstack-mr` processes (e.g. a parallel
+//! `cargo test` invocation, or several CI jobs) warming the same cache directory don't race --
+//! two processes both seeing a missing completion marker, both extracting, and corrupting each
+//! other's partially written tarball.
+//!
+//! Locking follows the usual "exclusive to write, shared to read" advisory-lock pattern via
+//! `fs4`: [`CacheEntry::get_or_populate`] acquires an exclusive lock on a per-entry `.lock`
+//! sentinel before populating, re-checks the completion marker under the lock (another process
+//! may have finished while we waited), extracts to a temporary sibling directory and atomically
+//! renames it into place, writes the completion marker, then downgrades to a shared lock so the
+//! now safely-populated entry can be read concurrently by any number of readers.
+
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use fs4::FileExt;
+use thiserror::Error;
+
+/// Written into an entry's directory only after it has been fully extracted; its absence means
+/// the directory (if present at all) is from an interrupted populate and must not be trusted.
+const COMPLETE_MARKER: &str = ".complete";
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("I/O error on cache entry {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("populating cache entry {path} failed: {source}")]
+    Populate {
+        path: PathBuf,
+        #[source]
+        source: anyhow::Error,
+    },
+}
+
+/// A directory of version-keyed cache entries, each independently lockable.
+pub struct CacheDir {
+    root: PathBuf,
+}
+
+impl CacheDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The entry for `version`, e.g. a dstack release tag like `v0.5.5`.
+    pub fn entry(&self, version: &str) -> CacheEntry {
+        CacheEntry {
+            path: self.root.join(version),
+            lock_path: self.root.join(format!("{version}.lock")),
+        }
+    }
+}
+
+/// A single version's slot in a [`CacheDir`].
+pub struct CacheEntry {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl CacheEntry {
+    /// Where the populated entry lives once [`Self::get_or_populate`] succeeds.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn is_complete(&self) -> bool {
+        self.path.join(COMPLETE_MARKER).exists()
+    }
+
+    fn io_err(&self, source: io::Error) -> CacheError {
+        CacheError::Io {
+            path: self.path.clone(),
+            source,
+        }
+    }
+
+    fn tmp_path(&self) -> PathBuf {
+        let name = self.path.file_name().and_then(|n| n.to_str()).unwrap_or("entry");
+        self.path.with_file_name(format!("{name}.tmp"))
+    }
+
+    /// Returns this entry's directory, populating it first if it isn't already complete.
+    /// `populate` is handed an empty temporary directory to extract/write into; on success that
+    /// directory is atomically renamed into place and marked complete.
+    ///
+    /// Safe to call from multiple processes concurrently: only the first to acquire the
+    /// exclusive lock runs `populate`, every other caller re-checks the completion marker under
+    /// the lock before deciding to populate at all, and a directory left behind by an
+    /// interrupted populate (crash, `SIGKILL`) is discarded and retried rather than trusted.
+    pub fn get_or_populate(
+        &self,
+        populate: impl FnOnce(&Path) -> anyhow::Result<()>,
+    ) -> Result<PathBuf, CacheError> {
+        if self.is_complete() {
+            return Ok(self.path.clone());
+        }
+
+        let parent = self
+            .path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        fs_err::create_dir_all(&parent).map_err(|e| self.io_err(e))?;
+
+        let lock_file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&self.lock_path)
+            .map_err(|e| self.io_err(e))?;
+        lock_file.lock_exclusive().map_err(|e| self.io_err(e))?;
+
+        // Another process may have populated this entry while we waited for the lock.
+        if self.is_complete() {
+            lock_file.unlock().ok();
+            lock_file.lock_shared().map_err(|e| self.io_err(e))?;
+            return Ok(self.path.clone());
+        }
+
+        if self.path.exists() {
+            fs_err::remove_dir_all(&self.path).map_err(|e| self.io_err(e))?;
+        }
+        let tmp_path = self.tmp_path();
+        if tmp_path.exists() {
+            fs_err::remove_dir_all(&tmp_path).map_err(|e| self.io_err(e))?;
+        }
+        fs_err::create_dir_all(&tmp_path).map_err(|e| self.io_err(e))?;
+
+        populate(&tmp_path).map_err(|source| CacheError::Populate {
+            path: self.path.clone(),
+            source,
+        })?;
+
+        fs_err::rename(&tmp_path, &self.path).map_err(|e| self.io_err(e))?;
+        fs_err::write(self.path.join(COMPLETE_MARKER), b"").map_err(|e| self.io_err(e))?;
+
+        // Downgrade to a shared lock now that the entry is safely populated, so concurrent
+        // readers (including future calls in this same process) don't serialize behind us.
+        lock_file.unlock().ok();
+        lock_file.lock_shared().map_err(|e| self.io_err(e))?;
+
+        Ok(self.path.clone())
+    }
+}
@@ -0,0 +1,182 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! RTMR1 (UEFI boot chain) and RTMR2 (kernel command line + initrd) measurement, mirroring EDK2's
+//! measured boot: PE/COFF images (shim, GRUB, the kernel's EFI stub) are measured via the
+//! Authenticode image digest, everything else via a plain SHA-384 over the raw bytes.
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha384};
+
+use crate::num::read_le;
+use crate::tcg_event::{event_type, TcgPcrEvent2};
+use crate::{measure_sha384, RtmrLog};
+
+/// Index of the Certificate Table entry in the Optional Header's `DataDirectory`.
+const CERTIFICATE_TABLE_INDEX: usize = 4;
+
+/// Computes the Authenticode image digest of a PE/COFF image (the Microsoft "hash of the image"
+/// algorithm): SHA-384 over the image with the Optional Header `CheckSum` field and the
+/// Certificate Table data-directory entry excluded from the hash input, then each section's raw
+/// data hashed in ascending `PointerToRawData` order up to the end of the last section, excluding
+/// the Attribute Certificate Table region itself (which, for an unsigned image, simply isn't
+/// reached since sections end before it).
+pub(crate) fn authenticode_digest(image: &[u8]) -> Result<Vec<u8>> {
+    if image.len() < 0x40 {
+        bail!("PE image too small for a DOS header");
+    }
+    let pe_offset = read_le::<u32>(image, 0x3C, "e_lfanew")? as usize;
+    if pe_offset.checked_add(24).map(|end| end > image.len()).unwrap_or(true) {
+        bail!("PE header offset out of bounds");
+    }
+    if &image[pe_offset..pe_offset + 4] != b"PE\0\0" {
+        bail!("Not a PE/COFF image: missing PE signature");
+    }
+
+    let coff_offset = pe_offset + 4;
+    let number_of_sections = read_le::<u16>(image, coff_offset + 2, "NumberOfSections")? as usize;
+    let size_of_optional_header =
+        read_le::<u16>(image, coff_offset + 16, "SizeOfOptionalHeader")? as usize;
+
+    let optional_header_offset = coff_offset + 20;
+    if optional_header_offset + size_of_optional_header > image.len() {
+        bail!("Optional header out of bounds");
+    }
+    let magic = read_le::<u16>(image, optional_header_offset, "Magic")?;
+    match magic {
+        0x10b /* PE32 */ | 0x20b /* PE32+ */ => {}
+        other => bail!("Unsupported PE optional header magic: {other:#x}"),
+    }
+
+    // CheckSum and SizeOfHeaders sit at the same offsets in PE32 and PE32+: PE32+'s wider
+    // ImageBase field exactly offsets its missing BaseOfData field.
+    let size_of_headers_offset = optional_header_offset + 60;
+    let checksum_offset = optional_header_offset + 64;
+    let number_of_rva_and_sizes_offset = optional_header_offset
+        + if magic == 0x20b { 108 } else { 92 };
+
+    let size_of_headers = read_le::<u32>(image, size_of_headers_offset, "SizeOfHeaders")? as usize;
+    let number_of_rva_and_sizes =
+        read_le::<u32>(image, number_of_rva_and_sizes_offset, "NumberOfRvaAndSizes")? as usize;
+    if number_of_rva_and_sizes <= CERTIFICATE_TABLE_INDEX {
+        bail!("PE optional header has no Certificate Table data directory entry");
+    }
+    let data_directory_offset = number_of_rva_and_sizes_offset + 4;
+    let cert_entry_offset = data_directory_offset + CERTIFICATE_TABLE_INDEX * 8;
+    if cert_entry_offset + 8 > image.len() {
+        bail!("Certificate Table data directory entry out of bounds");
+    }
+
+    let section_table_offset = optional_header_offset + size_of_optional_header;
+    let mut sections = Vec::with_capacity(number_of_sections);
+    for i in 0..number_of_sections {
+        let entry = section_table_offset + i * 40;
+        if entry + 40 > image.len() {
+            bail!("Section header {i} out of bounds");
+        }
+        let size_of_raw_data = read_le::<u32>(image, entry + 16, "SizeOfRawData")? as usize;
+        let pointer_to_raw_data = read_le::<u32>(image, entry + 20, "PointerToRawData")? as usize;
+        sections.push((pointer_to_raw_data, size_of_raw_data));
+    }
+    sections.sort_by_key(|&(pointer, _)| pointer);
+
+    let hash_range = |hasher: &mut Sha384, start: usize, end: usize| -> Result<()> {
+        if start > end || end > image.len() {
+            bail!("Authenticode hash range out of bounds");
+        }
+        hasher.update(&image[start..end]);
+        Ok(())
+    };
+
+    let mut hasher = Sha384::new();
+    hash_range(&mut hasher, 0, checksum_offset)?;
+    hash_range(&mut hasher, checksum_offset + 4, cert_entry_offset)?;
+    hash_range(&mut hasher, cert_entry_offset + 8, size_of_headers)?;
+    for (pointer, size) in sections {
+        if size == 0 {
+            continue;
+        }
+        hash_range(&mut hasher, pointer, pointer + size)
+            .context("PE section raw data out of bounds")?;
+    }
+
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Measures the kernel command line the way a Linux EFI stub's measured boot does.
+pub fn measure_cmdline(cmdline: &str) -> Vec<u8> {
+    measure_sha384(cmdline.as_bytes())
+}
+
+/// Builds a synthetic record describing the QEMU direct-kernel-boot parameters (the initrd size,
+/// guest memory size, and Linux boot protocol `setup_header` offset) that OVMF/td-shim measure
+/// when launching a kernel image directly, without a bootloader.
+fn direct_boot_params(initrd_len: u32, memory_size: u64, setup_header_offset: u64) -> Vec<u8> {
+    let mut params = Vec::with_capacity(4 + 8 + 8);
+    params.extend_from_slice(&initrd_len.to_le_bytes());
+    params.extend_from_slice(&memory_size.to_le_bytes());
+    params.extend_from_slice(&setup_header_offset.to_le_bytes());
+    params
+}
+
+/// Measures RTMR1, the UEFI boot chain: the Authenticode digest of `kernel_data` (the PE/COFF
+/// image ultimately loaded — shim, GRUB, or the kernel's own EFI stub), followed by the direct-
+/// kernel-boot parameters.
+pub fn rtmr1_log(
+    kernel_data: &[u8],
+    initrd_len: u32,
+    memory_size: u64,
+    setup_header_offset: u64,
+) -> Result<RtmrLog> {
+    let kernel_digest =
+        authenticode_digest(kernel_data).context("Failed to compute kernel Authenticode digest")?;
+    let boot_params_digest = measure_sha384(&direct_boot_params(
+        initrd_len,
+        memory_size,
+        setup_header_offset,
+    ));
+    Ok(vec![kernel_digest, boot_params_digest])
+}
+
+/// Builds the TCG event log backing [`rtmr1_log`]; see [`crate::tdvf::Tdvf::rtmr0_event_log`] for
+/// why a standards-compliant log lets a verifier independently re-check the measurement.
+pub fn rtmr1_event_log(
+    kernel_data: &[u8],
+    initrd_len: u32,
+    memory_size: u64,
+    setup_header_offset: u64,
+) -> Result<Vec<TcgPcrEvent2>> {
+    let kernel_digest =
+        authenticode_digest(kernel_data).context("Failed to compute kernel Authenticode digest")?;
+    let boot_params = direct_boot_params(initrd_len, memory_size, setup_header_offset);
+    let boot_params_digest = measure_sha384(&boot_params);
+
+    Ok(vec![
+        TcgPcrEvent2::new(
+            1,
+            event_type::EV_EFI_BOOT_SERVICES_APPLICATION,
+            kernel_digest,
+            Vec::new(),
+        ),
+        TcgPcrEvent2::new(1, event_type::EV_EVENT_TAG, boot_params_digest, boot_params),
+    ])
+}
+
+/// Measures RTMR2: the kernel command line and the initrd.
+pub fn rtmr2_log(cmdline: &str, initrd_data: &[u8]) -> RtmrLog {
+    vec![measure_cmdline(cmdline), measure_sha384(initrd_data)]
+}
+
+/// Builds the TCG event log backing [`rtmr2_log`].
+pub fn rtmr2_event_log(cmdline: &str, initrd_data: &[u8]) -> Vec<TcgPcrEvent2> {
+    vec![
+        TcgPcrEvent2::new(
+            2,
+            event_type::EV_EVENT_TAG,
+            measure_cmdline(cmdline),
+            cmdline.as_bytes().to_vec(),
+        ),
+        TcgPcrEvent2::new(2, event_type::EV_IPL, measure_sha384(initrd_data), Vec::new()),
+    ]
+}
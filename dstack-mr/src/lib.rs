@@ -5,7 +5,15 @@
 use serde::{Deserialize, Serialize};
 use serde_human_bytes as hex_bytes;
 
+pub use archive::{extract_tar_archive, ArchiveError, Compression};
+pub use artifact_source::{
+    ArtifactDescriptor, ArtifactSource, Manifest, ManifestBody, MeasureError, ResolvedArtifacts,
+};
+pub use cache_dir::{CacheDir, CacheEntry, CacheError};
 pub use machine::{Machine, TdxMeasurementDetails};
+pub use object_store::{ArtifactStore, HttpStore, LocalCas, StoreError, TieredStore};
+pub use tcg_event::TcgPcrEvent2;
+pub use tdvf::{SecureBootConfig, TdvfVersion};
 
 use util::{measure_log, measure_sha384, utf16_encode};
 
@@ -13,9 +21,15 @@ pub type RtmrLog = Vec<Vec<u8>>;
 pub type RtmrLogs = [RtmrLog; 3];
 
 mod acpi;
+mod archive;
+mod artifact_source;
+mod cache_dir;
 mod kernel;
 mod machine;
 mod num;
+mod object_store;
+mod qemu_capabilities;
+mod tcg_event;
 mod tdvf;
 mod util;
 
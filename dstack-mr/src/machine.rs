@@ -3,13 +3,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::acpi::Tables;
-use crate::tdvf::Tdvf;
+use crate::qemu_capabilities::QemuCapabilities;
+use crate::tdvf::{SecureBootConfig, Tdvf};
 use crate::util::debug_print_log;
 use crate::{kernel, RtmrLogs, TdxMeasurements};
-use crate::{measure_log, measure_sha384};
+use crate::measure_log;
 use anyhow::{bail, Context, Result};
 use fs_err as fs;
-use log::debug;
+use log::{debug, warn};
+use std::path::Path;
 
 #[derive(Debug, bon::Builder)]
 pub struct Machine<'a> {
@@ -22,6 +24,10 @@ pub struct Machine<'a> {
     pub two_pass_add_pages: Option<bool>,
     pub pic: Option<bool>,
     pub qemu_version: Option<String>,
+    /// Path to the actual QEMU binary to probe for real capabilities via
+    /// [`QemuCapabilities::probe`]. When unset, `versioned_options()` falls back to the
+    /// version-tuple heuristic.
+    pub qemu_path: Option<&'a str>,
     #[builder(default = false)]
     pub smm: bool,
     pub pci_hole64_size: Option<u64>,
@@ -30,6 +36,9 @@ pub struct Machine<'a> {
     pub num_nvswitches: u32,
     pub hotplug_off: bool,
     pub root_verity: bool,
+    /// Provisioned Secure Boot key set to measure into RTMR0's `PK`/`KEK`/`db`/`dbx` events.
+    /// `None` reproduces the keyless configuration `rtmr0_log` has always produced.
+    pub secure_boot: Option<SecureBootConfig>,
 }
 
 fn parse_version_tuple(v: &str) -> Result<(u32, u32, u32)> {
@@ -52,19 +61,26 @@ impl Machine<'_> {
             Some(v) => Some(parse_version_tuple(v).context("Failed to parse QEMU version")?),
             None => None,
         };
-        let default_pic;
-        let default_two_pass;
         let version = version.unwrap_or((9, 1, 0));
         if version < (8, 0, 0) {
             bail!("Unsupported QEMU version: {version:?}");
         }
-        if ((8, 0, 0)..(9, 0, 0)).contains(&version) {
-            default_pic = true;
-            default_two_pass = true;
-        } else {
-            default_pic = false;
-            default_two_pass = false;
+
+        // Prefer what the binary itself reports; distro-patched TDX builds don't always line
+        // up with upstream's version-tuple feature cutoffs. Only fall back to the heuristic
+        // below when no binary was given to probe, or probing it failed.
+        let probed = self.qemu_path.and_then(|path| {
+            QemuCapabilities::probe(Path::new(path))
+                .inspect_err(|err| warn!("Failed to probe QEMU capabilities, falling back to version heuristic: {err:#}"))
+                .ok()
+        });
+
+        let (default_pic, default_two_pass) = match &probed {
+            Some(caps) => (caps.pic, caps.two_pass_add_pages),
+            None if ((8, 0, 0)..(9, 0, 0)).contains(&version) => (true, true),
+            None => (false, false),
         };
+
         Ok(VersionedOptions {
             version,
             pic: self.pic.unwrap_or(default_pic),
@@ -115,10 +131,7 @@ impl Machine<'_> {
         debug_print_log("RTMR1", &rtmr1_log);
         let rtmr1 = measure_log(&rtmr1_log);
 
-        let rtmr2_log = vec![
-            kernel::measure_cmdline(self.kernel_cmdline),
-            measure_sha384(&initrd_data),
-        ];
+        let rtmr2_log = kernel::rtmr2_log(self.kernel_cmdline, &initrd_data);
         debug_print_log("RTMR2", &rtmr2_log);
         let rtmr2 = measure_log(&rtmr2_log);
 
@@ -0,0 +1,83 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Real QEMU capability probing, so [`crate::machine::Machine::versioned_options`] can track a
+//! distro-patched build's actual feature set instead of trusting its reported version number
+//! alone. Modeled on the probing libvirt's `qemu_capabilities.c` does: run the binary with
+//! `-device help`/`-machine help`/`-cpu help` and scrape the output, rather than branching
+//! purely on a parsed `major.minor.patch` tuple.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+/// The subset of a QEMU build's actual feature set that affects TDX measurement.
+#[derive(Debug, Clone, Default)]
+pub struct QemuCapabilities {
+    /// Whether the `tdx-guest` object accepts a `two-pass-add-pages` property.
+    pub two_pass_add_pages: bool,
+    /// Whether `-machine` accepts a `pic` property (older TDX builds wired in an i8259 PIC
+    /// that upstream TDX doesn't need).
+    pub pic: bool,
+    /// Whether `-machine` accepts an `smm` property.
+    pub smm: bool,
+    /// Whether a hugepage-capable `memory-backend-*` object is available.
+    pub hugepages: bool,
+    /// Whether `-machine` accepts a `pci-hole64-size` property.
+    pub pci_hole64: bool,
+    /// Whether `-cpu host` is offered, i.e. host CPU passthrough is possible.
+    pub host_cpu_passthrough: bool,
+    /// Device models from `-device help` that look like GPU/NVSwitch passthrough devices, so a
+    /// host claiming GPU support can be sanity-checked against what the binary actually offers.
+    pub gpu_device_models: Vec<String>,
+}
+
+impl QemuCapabilities {
+    /// Probes `qemu_path` by running it with `-device help`, `-machine help`, `-cpu help`, and
+    /// `-object tdx-guest,help`, then scraping their output.
+    ///
+    /// Fails only if the binary can't be executed at all; a capability simply absent from the
+    /// output is reported as unsupported rather than an error.
+    pub fn probe(qemu_path: &Path) -> Result<Self> {
+        let device_help = run_help(qemu_path, "-device")?;
+        let machine_help = run_help(qemu_path, "-machine")?;
+        let cpu_help = run_help(qemu_path, "-cpu")?;
+        let tdx_guest_help = run_object_help(qemu_path, "tdx-guest").unwrap_or_default();
+
+        Ok(Self {
+            two_pass_add_pages: tdx_guest_help.contains("two-pass-add-pages"),
+            pic: machine_help.contains("pic="),
+            smm: machine_help.contains("smm="),
+            hugepages: device_help.contains("memory-backend-file")
+                || device_help.contains("memory-backend-ram"),
+            pci_hole64: machine_help.contains("pci-hole64-size="),
+            host_cpu_passthrough: cpu_help.lines().any(|line| line.trim() == "host"),
+            gpu_device_models: device_help
+                .lines()
+                .filter(|line| {
+                    let lower = line.to_ascii_lowercase();
+                    lower.contains("vfio-pci") || lower.contains("nvswitch")
+                })
+                .map(|line| line.trim().to_string())
+                .collect(),
+        })
+    }
+}
+
+fn run_help(qemu_path: &Path, flag: &str) -> Result<String> {
+    let output = Command::new(qemu_path)
+        .args([flag, "help"])
+        .output()
+        .with_context(|| format!("Failed to run `{} {flag} help`", qemu_path.display()))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Runs `-object <name>,help`, which lists the properties a given `-object` type accepts.
+fn run_object_help(qemu_path: &Path, name: &str) -> Result<String> {
+    let output = Command::new(qemu_path)
+        .args(["-object", &format!("{name},help")])
+        .output()
+        .with_context(|| format!("Failed to probe `-object {name},help`"))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
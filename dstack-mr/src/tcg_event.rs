@@ -0,0 +1,116 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Minimal `TCG_PCR_EVENT2` (TCG PC Client Platform Firmware Profile §9.2) record type, shared by
+//! the RTMR0 (`tdvf`) and RTMR1/RTMR2 (`kernel`) event-log emitters so a verifier can parse and
+//! independently re-verify the measured-boot log with off-the-shelf TCG event-log tooling instead
+//! of a bare `Vec<Vec<u8>>` of digests.
+
+use crate::RtmrLog;
+
+/// `TPMI_ALG_HASH` value for SHA-384 (TCG Algorithm Registry).
+const TPM_ALG_SHA384: u16 = 0x000C;
+
+/// `EV_*` event type codes used by the measured-boot log (TCG PC Client Platform Firmware
+/// Profile §9/§10). Only the subset this crate emits is listed.
+pub(crate) mod event_type {
+    /// A measurement with no corresponding UEFI construct (used here for the TDVF `TD_HOB`).
+    pub const EV_EFI_HANDOFF_TABLES2: u32 = 0x8000000B;
+    /// A firmware volume image range, e.g. the Configuration Firmware Volume.
+    pub const EV_EFI_PLATFORM_FIRMWARE_BLOB2: u32 = 0x8000000A;
+    /// A `UEFI_VARIABLE_DATA` read by firmware to configure a driver (SecureBoot/PK/KEK/db/dbx).
+    pub const EV_EFI_VARIABLE_DRIVER_CONFIG: u32 = 0x80000001;
+    /// The separator between the "firmware" and "OS loader" phases of boot.
+    pub const EV_SEPARATOR: u32 = 0x00000004;
+    /// A UEFI boot variable (`BootOrder`, `Boot####`).
+    pub const EV_EFI_VARIABLE_BOOT2: u32 = 0x8000000C;
+    /// Launch of a UEFI boot services application (a PE/COFF image: shim, GRUB, the kernel stub).
+    pub const EV_EFI_BOOT_SERVICES_APPLICATION: u32 = 0x80000003;
+    /// An event-tag record, used here for the kernel command line.
+    pub const EV_EVENT_TAG: u32 = 0x00000006;
+    /// An OS-loader-measured image (the initrd).
+    pub const EV_IPL: u32 = 0x0000000D;
+}
+
+/// One `TCG_PCR_EVENT2` record: which RTMR it extends, its `EV_*` type, the SHA-384 digest folded
+/// into that RTMR, and the raw event data the digest was computed over (when reconstructible —
+/// see individual emitters for cases where only the digest, not the source bytes, is known).
+#[derive(Debug, Clone)]
+pub struct TcgPcrEvent2 {
+    pub mr_index: u32,
+    pub event_type: u32,
+    pub digest: Vec<u8>,
+    pub event_data: Vec<u8>,
+}
+
+impl TcgPcrEvent2 {
+    pub fn new(mr_index: u32, event_type: u32, digest: Vec<u8>, event_data: Vec<u8>) -> Self {
+        Self {
+            mr_index,
+            event_type,
+            digest,
+            event_data,
+        }
+    }
+
+    /// Serializes this record to its on-the-wire `TCG_PCR_EVENT2` layout: `mr_index: u32`,
+    /// `event_type: u32`, a one-digest `TPML_DIGEST_VALUES` (`count: u32` = 1, `alg_id: u16` =
+    /// `TPM_ALG_SHA384`, then the 48 digest bytes), `event_size: u32`, then the raw event bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(4 + 4 + 4 + 2 + self.digest.len() + 4 + self.event_data.len());
+        out.extend_from_slice(&self.mr_index.to_le_bytes());
+        out.extend_from_slice(&self.event_type.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes()); // TPML_DIGEST_VALUES.count
+        out.extend_from_slice(&TPM_ALG_SHA384.to_le_bytes());
+        out.extend_from_slice(&self.digest);
+        out.extend_from_slice(&(self.event_data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.event_data);
+        out
+    }
+}
+
+/// Serializes `events` in order, concatenating each record's [`TcgPcrEvent2::to_bytes`].
+pub fn serialize_events(events: &[TcgPcrEvent2]) -> Vec<u8> {
+    events.iter().flat_map(|event| event.to_bytes()).collect()
+}
+
+/// Extracts the per-event digests in order, the same `RtmrLog` shape `measure_log` folds into an
+/// RTMR value — so `measure_log(&event_digests(&events))` reproduces exactly what folding the
+/// bare digest list (the pre-event-log representation) would have produced.
+pub fn event_digests(events: &[TcgPcrEvent2]) -> RtmrLog {
+    events.iter().map(|event| event.digest.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure_log;
+
+    #[test]
+    fn event_digests_replay_matches_bare_digest_log() {
+        let digests: RtmrLog = vec![vec![0x11; 48], vec![0x22; 48], vec![0x33; 48]];
+        let events: Vec<TcgPcrEvent2> = digests
+            .iter()
+            .enumerate()
+            .map(|(i, digest)| {
+                TcgPcrEvent2::new(0, event_type::EV_SEPARATOR, digest.clone(), vec![i as u8])
+            })
+            .collect();
+
+        assert_eq!(measure_log(&event_digests(&events)), measure_log(&digests));
+    }
+
+    #[test]
+    fn to_bytes_round_trip_layout() {
+        let event = TcgPcrEvent2::new(0, event_type::EV_SEPARATOR, vec![0xAB; 48], vec![1, 2, 3]);
+        let bytes = event.to_bytes();
+        assert_eq!(&bytes[0..4], &0u32.to_le_bytes());
+        assert_eq!(&bytes[4..8], &event_type::EV_SEPARATOR.to_le_bytes());
+        assert_eq!(&bytes[8..12], &1u32.to_le_bytes());
+        assert_eq!(&bytes[12..14], &TPM_ALG_SHA384.to_le_bytes());
+        assert_eq!(&bytes[14..62], &[0xABu8; 48][..]);
+        assert_eq!(&bytes[62..66], &3u32.to_le_bytes());
+        assert_eq!(&bytes[66..], &[1, 2, 3]);
+    }
+}
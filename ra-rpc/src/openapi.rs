@@ -17,11 +17,43 @@ use anyhow::{anyhow, bail, Context, Result};
 use prost_types::{
     field_descriptor_proto::{Label as FieldLabel, Type as FieldType},
     DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorSet,
-    ServiceDescriptorProto, SourceCodeInfo,
+    OneofDescriptorProto, ServiceDescriptorProto, SourceCodeInfo,
 };
 use prpc::Message as _;
 use serde_json::{json, Map, Value};
 
+/// An OpenAPI `securitySchemes` entry, registered on [`DocumentInfo`] and referenced by name from
+/// [`ServiceConfig::with_security`]/[`ServiceConfig::with_method_security`].
+#[derive(Clone, Debug)]
+pub enum SecurityScheme {
+    /// `Authorization: Bearer <token>`, e.g. a JWT.
+    Bearer { bearer_format: Option<Cow<'static, str>> },
+    /// An API key sent as a request header.
+    ApiKeyHeader { header_name: Cow<'static, str> },
+    /// HTTP Basic authentication.
+    Basic,
+}
+
+impl SecurityScheme {
+    fn to_schema_object(&self) -> Value {
+        match self {
+            SecurityScheme::Bearer { bearer_format } => {
+                let mut obj = json!({ "type": "http", "scheme": "bearer" });
+                if let Some(format) = bearer_format {
+                    obj["bearerFormat"] = Value::String(format.to_string());
+                }
+                obj
+            }
+            SecurityScheme::ApiKeyHeader { header_name } => json!({
+                "type": "apiKey",
+                "in": "header",
+                "name": header_name.to_string()
+            }),
+            SecurityScheme::Basic => json!({ "type": "http", "scheme": "basic" }),
+        }
+    }
+}
+
 /// High level metadata used for the `info` and `servers` sections of the
 /// generated OpenAPI specification.
 #[derive(Clone, Debug)]
@@ -30,6 +62,19 @@ pub struct DocumentInfo<'a> {
     pub version: Cow<'a, str>,
     pub description: Option<Cow<'a, str>>,
     pub servers: Vec<Cow<'a, str>>,
+    security_schemes: Vec<(Cow<'a, str>, SecurityScheme)>,
+    /// Whether generated schemas should key `properties`/`required` by the original `.proto`
+    /// field name instead of `json_name` (camelCase by default). Off by default, matching the
+    /// protobuf canonical JSON mapping that real clients of these endpoints speak; set via
+    /// [`Self::with_proto_field_names`] for servers configured with the "preserve proto field
+    /// names" JSON option.
+    use_proto_field_names: bool,
+    /// Whether enum schemas should be emitted as an `anyOf` of the string and integer forms
+    /// (with aliases, per-value descriptions and a documented zero-value default) instead of the
+    /// plain `{"type": "string", "enum": [...]}` every consumer of this document has seen so
+    /// far. Off by default to avoid changing already-published specs out from under existing
+    /// clients; set via [`Self::with_rich_enum_schemas`].
+    rich_enum_schemas: bool,
 }
 
 impl<'a> DocumentInfo<'a> {
@@ -39,6 +84,9 @@ impl<'a> DocumentInfo<'a> {
             version: version.into(),
             description: None,
             servers: Vec::new(),
+            security_schemes: Vec::new(),
+            use_proto_field_names: false,
+            rich_enum_schemas: false,
         }
     }
 
@@ -51,6 +99,34 @@ impl<'a> DocumentInfo<'a> {
         self.servers.push(server.into());
         self
     }
+
+    /// Registers a named [`SecurityScheme`] under `components.securitySchemes`, so
+    /// [`ServiceConfig::with_security`]/[`ServiceConfig::with_method_security`] can reference it
+    /// by `name`.
+    pub fn with_security_scheme(
+        mut self,
+        name: impl Into<Cow<'a, str>>,
+        scheme: SecurityScheme,
+    ) -> Self {
+        self.security_schemes.push((name.into(), scheme));
+        self
+    }
+
+    /// Keys generated schemas' `properties`/`required` by the original `.proto` field name
+    /// rather than `json_name`, for servers whose JSON serializer was configured with the
+    /// "preserve proto field names" option.
+    pub fn with_proto_field_names(mut self) -> Self {
+        self.use_proto_field_names = true;
+        self
+    }
+
+    /// Emits enum schemas as an `anyOf` of the string and integer forms (aliases, per-value
+    /// descriptions, and a documented zero-value `default`), matching everything protobuf JSON
+    /// actually accepts for an enum field instead of just the symbolic name.
+    pub fn with_rich_enum_schemas(mut self) -> Self {
+        self.rich_enum_schemas = true;
+        self
+    }
 }
 
 /// Configuration describing how a pRPC service should be exposed over HTTP.
@@ -61,6 +137,17 @@ pub struct ServiceConfig<'a> {
     pub method_prefix: Cow<'a, str>,
     pub tag: Option<Cow<'a, str>>,
     pub description: Option<Cow<'a, str>>,
+    /// Names of [`SecurityScheme`]s (registered via [`DocumentInfo::with_security_scheme`])
+    /// required by every method in this service, unless overridden per-method by
+    /// [`Self::with_method_security`].
+    security: Vec<Cow<'a, str>>,
+    /// Per-method security overrides, keyed by method name. An empty list means that method
+    /// needs no authentication even though the service otherwise requires `security`.
+    method_security: HashMap<String, Vec<Cow<'a, str>>>,
+    /// Whether to advertise `application/x-protobuf` alongside `application/json` for this
+    /// service's request/response bodies, for clients that want the binary wire format. Off by
+    /// default, since most pRPC consumers only ever send JSON.
+    protobuf_content_type: bool,
 }
 
 impl<'a> ServiceConfig<'a> {
@@ -71,6 +158,9 @@ impl<'a> ServiceConfig<'a> {
             method_prefix: Cow::Borrowed(""),
             tag: None,
             description: None,
+            security: Vec::new(),
+            method_security: HashMap::new(),
+            protobuf_content_type: false,
         }
     }
 
@@ -88,6 +178,50 @@ impl<'a> ServiceConfig<'a> {
         self.description = Some(description.into());
         self
     }
+
+    /// Requires one of `scheme_names` (by the name passed to
+    /// [`DocumentInfo::with_security_scheme`]) to call any method on this service, unless a
+    /// method has its own [`Self::with_method_security`] override.
+    pub fn with_security(
+        mut self,
+        scheme_names: impl IntoIterator<Item = impl Into<Cow<'a, str>>>,
+    ) -> Self {
+        self.security = scheme_names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Overrides [`Self::with_security`] for one `method_name`, e.g. to exempt a health-check
+    /// method from an otherwise-required `security` scheme by passing an empty iterator.
+    pub fn with_method_security(
+        mut self,
+        method_name: impl Into<String>,
+        scheme_names: impl IntoIterator<Item = impl Into<Cow<'a, str>>>,
+    ) -> Self {
+        self.method_security.insert(
+            method_name.into(),
+            scheme_names.into_iter().map(Into::into).collect(),
+        );
+        self
+    }
+
+    /// Advertises `application/x-protobuf` (tagged with an `x-proto-message` extension naming
+    /// the fully-qualified message) alongside `application/json` for this service's request
+    /// bodies and responses, so clients that prefer the binary wire format can discover it from
+    /// the generated document instead of assuming JSON-only.
+    pub fn with_protobuf_content_type(mut self) -> Self {
+        self.protobuf_content_type = true;
+        self
+    }
+
+    fn effective_security(&self, method_name: &str) -> Option<&[Cow<'a, str>]> {
+        if let Some(names) = self.method_security.get(method_name) {
+            Some(names.as_slice())
+        } else if !self.security.is_empty() {
+            Some(self.security.as_slice())
+        } else {
+            None
+        }
+    }
 }
 
 /// Descriptor blob plus the set of services that should be surfaced from it.
@@ -199,6 +333,402 @@ fn extend_path(base: &[i32], field_number: i32, index: i32) -> Vec<i32> {
     path
 }
 
+/// Field number of the `google.api.http` extension on `google.protobuf.MethodOptions`, as
+/// assigned in `google/api/annotations.proto`.
+const GOOGLE_API_HTTP_EXTENSION_FIELD: u32 = 72295728;
+
+/// One raw protobuf field value, read without knowledge of the message's schema. Only the wire
+/// types descriptor.proto and `HttpRule` actually use (varint and length-delimited) are handled;
+/// others are skipped by [`iter_raw_fields`].
+enum RawField<'a> {
+    Varint(u64),
+    LengthDelimited(&'a [u8]),
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut result = 0u64;
+    let mut shift = 0u32;
+    loop {
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        result |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+        shift += 7;
+        if shift >= 64 {
+            return None;
+        }
+    }
+}
+
+/// Walks the top-level fields of a protobuf message's encoded bytes, yielding each field number
+/// paired with its value. Used to pull the `google.api.http` extension (and the handful of
+/// descriptor.proto fields needed to locate it) out of a [`FileDescriptorSet`] without a
+/// schema-aware extension registry, since `prost_types`'s hand-written `MethodOptions` has no
+/// field for it and silently drops unrecognized fields on a normal typed decode.
+fn iter_raw_fields(buf: &[u8]) -> impl Iterator<Item = (u32, RawField<'_>)> {
+    let mut pos = 0usize;
+    std::iter::from_fn(move || loop {
+        if pos >= buf.len() {
+            return None;
+        }
+        let tag = read_varint(buf, &mut pos)?;
+        let field_number = (tag >> 3) as u32;
+        let wire_type = tag & 0x7;
+        match wire_type {
+            0 => {
+                let value = read_varint(buf, &mut pos)?;
+                return Some((field_number, RawField::Varint(value)));
+            }
+            1 => {
+                pos = pos.checked_add(8)?;
+            }
+            2 => {
+                let len = read_varint(buf, &mut pos)? as usize;
+                let end = pos.checked_add(len)?;
+                let slice = buf.get(pos..end)?;
+                pos = end;
+                return Some((field_number, RawField::LengthDelimited(slice)));
+            }
+            5 => {
+                pos = pos.checked_add(4)?;
+            }
+            _ => return None,
+        }
+    })
+}
+
+/// The bytes of the last length-delimited occurrence of `field_number` in `buf` (protobuf's
+/// "last one wins" rule for singular fields).
+fn find_length_delimited(buf: &[u8], field_number: u32) -> Option<&[u8]> {
+    iter_raw_fields(buf)
+        .filter_map(|(n, v)| match v {
+            RawField::LengthDelimited(b) if n == field_number => Some(b),
+            _ => None,
+        })
+        .last()
+}
+
+/// The bytes of every length-delimited occurrence of `field_number` in `buf`, in declaration
+/// order, for `repeated` fields.
+fn find_all_length_delimited(buf: &[u8], field_number: u32) -> Vec<&[u8]> {
+    iter_raw_fields(buf)
+        .filter_map(|(n, v)| match v {
+            RawField::LengthDelimited(b) if n == field_number => Some(b),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The last varint-wire-type occurrence of `field_number` in `buf` ("last one wins", same as
+/// [`find_length_delimited`]).
+fn find_varint(buf: &[u8], field_number: u32) -> Option<u64> {
+    iter_raw_fields(buf)
+        .filter_map(|(n, v)| match v {
+            RawField::Varint(x) if n == field_number => Some(x),
+            _ => None,
+        })
+        .last()
+}
+
+/// The HTTP verb an annotated method is transcoded to, from `HttpRule`'s `get`/`put`/`post`/
+/// `delete`/`patch` oneof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HttpVerb {
+    Get,
+    Put,
+    Post,
+    Delete,
+    Patch,
+}
+
+impl HttpVerb {
+    fn as_str(self) -> &'static str {
+        match self {
+            HttpVerb::Get => "get",
+            HttpVerb::Put => "put",
+            HttpVerb::Post => "post",
+            HttpVerb::Delete => "delete",
+            HttpVerb::Patch => "patch",
+        }
+    }
+}
+
+/// A parsed `google.api.http` annotation: which verb/path template a method is transcoded to,
+/// and which part (if any) of the request message is the HTTP body.
+#[derive(Debug, Clone)]
+struct HttpRule {
+    verb: HttpVerb,
+    /// The raw path template, e.g. `/v1/messages/{message_id}` or `/v1/{name=shelves/*}`.
+    path: String,
+    /// `Some("*")` for the whole remaining message, `Some(field)` to narrow the body to one
+    /// sub-message field, or `None` (typical for `get`/`delete`) for no body at all.
+    body: Option<String>,
+    /// Extra `HttpRule`s from the `additional_bindings` field (tag 11): each one binds the same
+    /// method to another verb/path, e.g. a `get` that's reachable both as `/v1/{id}` and as a
+    /// legacy `/v1/shelves/{shelf}/{id}`. [`generate_document`] emits one operation per binding,
+    /// all routed to the same method.
+    additional_bindings: Vec<HttpRule>,
+}
+
+/// Parses an `HttpRule` message's raw bytes (field numbers per `google/api/http.proto`): `get`=2,
+/// `put`=3, `post`=4, `delete`=5, `patch`=6 (the verb oneof, each a path template string), `body`=7,
+/// `additional_bindings`=11 (repeated, nested `HttpRule` messages).
+fn parse_http_rule(buf: &[u8]) -> Option<HttpRule> {
+    let mut verb_and_path = None;
+    let mut body = None;
+    let mut additional_bindings = Vec::new();
+    for (field_number, value) in iter_raw_fields(buf) {
+        let RawField::LengthDelimited(bytes) = value else {
+            continue;
+        };
+        match field_number {
+            2..=6 => {
+                let text = std::str::from_utf8(bytes).ok()?;
+                let verb = match field_number {
+                    2 => HttpVerb::Get,
+                    3 => HttpVerb::Put,
+                    4 => HttpVerb::Post,
+                    5 => HttpVerb::Delete,
+                    6 => HttpVerb::Patch,
+                    _ => unreachable!(),
+                };
+                verb_and_path = Some((verb, text.to_string()));
+            }
+            7 => body = Some(std::str::from_utf8(bytes).ok()?.to_string()),
+            11 => additional_bindings.extend(parse_http_rule(bytes)),
+            _ => {}
+        }
+    }
+    let (verb, path) = verb_and_path?;
+    Some(HttpRule {
+        verb,
+        path,
+        body,
+        additional_bindings,
+    })
+}
+
+/// Splits a `google.api.http` path template into an OpenAPI-style path (`{var=**}`/`{var=*}`
+/// segments collapsed to plain `{var}`) and the ordered list of field names it binds, e.g.
+/// `/v1/{name=shelves/*}/books/{book_id}` becomes (`/v1/{name}/books/{book_id}`, `["name",
+/// "book_id"]`). Only top-level field names are supported; a dotted path like `{a.b}` is kept as
+/// a single (unmatchable) field name rather than resolved through nested messages.
+fn parse_path_template(template: &str) -> (String, Vec<String>) {
+    let mut openapi_path = String::new();
+    let mut fields = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            openapi_path.push_str(rest);
+            rest = "";
+            break;
+        };
+        openapi_path.push_str(&rest[..start]);
+        let inner = &rest[start + 1..start + end];
+        let field_name = inner.split('=').next().unwrap_or(inner).to_string();
+        openapi_path.push('{');
+        openapi_path.push_str(&field_name);
+        openapi_path.push('}');
+        fields.push(field_name);
+        rest = &rest[start + end + 1..];
+    }
+    openapi_path.push_str(rest);
+    (openapi_path, fields)
+}
+
+/// Field number of the `validate.rules` extension (protoc-gen-validate) on `FieldOptions`.
+const PGV_FIELD_RULES_EXTENSION_FIELD: u32 = 1071;
+
+/// Field number of this crate's own `(dstack.example)` extension on `MessageOptions`: a string
+/// holding a JSON literal that [`SchemaBuilder::example_for_message`] uses verbatim instead of
+/// synthesizing one, for messages whose generated example (proto zero values, first enum
+/// variant, …) wouldn't make a useful "Try it out" starting point. Chosen from the 50000-99999
+/// range descriptor.proto reserves for internal/private extensions, same as real-world custom
+/// options.
+const SCHEMA_EXAMPLE_EXTENSION_FIELD: u32 = 90001;
+
+/// A subset of `protoc-gen-validate`'s `validate.FieldRules` oneof — just the constraint kinds
+/// [`field_schema`](SchemaBuilder::field_schema) knows how to translate into JSON Schema
+/// keywords. Rule kinds this doesn't parse (`bytes`, `enum`, `map`, `any`, `duration`,
+/// `timestamp`, and the `sint*`/`fixed*`/`float`/`double` numeric variants, which use different
+/// wire encodings than plain varints) are silently left unenforced in the generated spec rather
+/// than rejected.
+#[derive(Debug, Clone, Default)]
+struct FieldRules {
+    numeric: Option<NumericRules>,
+    string: Option<StringRules>,
+    repeated: Option<RepeatedRules>,
+    /// From `MessageRules.required` (field rules oneof tag 17) — the one PGV constraint that
+    /// applies to a singular message-typed field rather than to the field's own value shape.
+    message_required: bool,
+}
+
+/// `gt`/`gte`/`lt`/`lte` bounds shared by PGV's `Int32Rules`/`Int64Rules`/`UInt32Rules`/
+/// `UInt64Rules` (field numbers 2-5 in each, varint-encoded).
+#[derive(Debug, Clone, Default)]
+struct NumericRules {
+    lt: Option<i64>,
+    lte: Option<i64>,
+    gt: Option<i64>,
+    gte: Option<i64>,
+}
+
+/// A subset of `StringRules`: `min_len`=2, `max_len`=3, `pattern`=6, `in`=10, `not_in`=11.
+#[derive(Debug, Clone, Default)]
+struct StringRules {
+    min_len: Option<u64>,
+    max_len: Option<u64>,
+    pattern: Option<String>,
+    in_values: Vec<String>,
+    not_in_values: Vec<String>,
+}
+
+/// `RepeatedRules.min_items`=1, `max_items`=2.
+#[derive(Debug, Clone, Default)]
+struct RepeatedRules {
+    min_items: Option<u64>,
+    max_items: Option<u64>,
+}
+
+/// Parses a `FieldRules` message's raw bytes. Field numbers per `validate.proto`'s `FieldRules`
+/// oneof: `int32`=3, `int64`=4, `uint32`=5, `uint64`=6 (all share `Int32Rules`'s layout), `string`
+/// =14, `repeated`=18, `message`=17.
+fn parse_field_rules(buf: &[u8]) -> Option<FieldRules> {
+    let mut rules = FieldRules::default();
+    for (field_number, value) in iter_raw_fields(buf) {
+        let RawField::LengthDelimited(bytes) = value else {
+            continue;
+        };
+        match field_number {
+            3 | 4 | 5 | 6 => rules.numeric = parse_numeric_rules(bytes),
+            14 => rules.string = parse_string_rules(bytes),
+            18 => rules.repeated = parse_repeated_rules(bytes),
+            17 => rules.message_required = find_varint(bytes, 2).is_some_and(|v| v != 0),
+            _ => {}
+        }
+    }
+    let is_empty = rules.numeric.is_none()
+        && rules.string.is_none()
+        && rules.repeated.is_none()
+        && !rules.message_required;
+    if is_empty {
+        None
+    } else {
+        Some(rules)
+    }
+}
+
+fn parse_numeric_rules(buf: &[u8]) -> Option<NumericRules> {
+    let rules = NumericRules {
+        lt: find_varint(buf, 2).map(|v| v as i64),
+        lte: find_varint(buf, 3).map(|v| v as i64),
+        gt: find_varint(buf, 4).map(|v| v as i64),
+        gte: find_varint(buf, 5).map(|v| v as i64),
+    };
+    if rules.lt.is_none() && rules.lte.is_none() && rules.gt.is_none() && rules.gte.is_none() {
+        None
+    } else {
+        Some(rules)
+    }
+}
+
+fn parse_string_rules(buf: &[u8]) -> Option<StringRules> {
+    let rules = StringRules {
+        min_len: find_varint(buf, 2),
+        max_len: find_varint(buf, 3),
+        pattern: find_length_delimited(buf, 6)
+            .and_then(|b| std::str::from_utf8(b).ok())
+            .map(str::to_string),
+        in_values: find_all_length_delimited(buf, 10)
+            .into_iter()
+            .filter_map(|b| std::str::from_utf8(b).ok())
+            .map(str::to_string)
+            .collect(),
+        not_in_values: find_all_length_delimited(buf, 11)
+            .into_iter()
+            .filter_map(|b| std::str::from_utf8(b).ok())
+            .map(str::to_string)
+            .collect(),
+    };
+    let is_empty = rules.min_len.is_none()
+        && rules.max_len.is_none()
+        && rules.pattern.is_none()
+        && rules.in_values.is_empty()
+        && rules.not_in_values.is_empty();
+    if is_empty {
+        None
+    } else {
+        Some(rules)
+    }
+}
+
+fn parse_repeated_rules(buf: &[u8]) -> Option<RepeatedRules> {
+    let rules = RepeatedRules {
+        min_items: find_varint(buf, 1),
+        max_items: find_varint(buf, 2),
+    };
+    if rules.min_items.is_none() && rules.max_items.is_none() {
+        None
+    } else {
+        Some(rules)
+    }
+}
+
+/// Applies a [`FieldRules`]'s numeric/string constraints to a non-repeated field's schema.
+fn apply_scalar_rules(schema: &mut Value, rules: &FieldRules) {
+    let Value::Object(map) = schema else {
+        return;
+    };
+    if let Some(numeric) = &rules.numeric {
+        if let Some(gt) = numeric.gt {
+            map.insert("exclusiveMinimum".into(), json!(gt));
+        }
+        if let Some(gte) = numeric.gte {
+            map.insert("minimum".into(), json!(gte));
+        }
+        if let Some(lt) = numeric.lt {
+            map.insert("exclusiveMaximum".into(), json!(lt));
+        }
+        if let Some(lte) = numeric.lte {
+            map.insert("maximum".into(), json!(lte));
+        }
+    }
+    if let Some(string) = &rules.string {
+        if let Some(min_len) = string.min_len {
+            map.insert("minLength".into(), json!(min_len));
+        }
+        if let Some(max_len) = string.max_len {
+            map.insert("maxLength".into(), json!(max_len));
+        }
+        if let Some(pattern) = &string.pattern {
+            map.insert("pattern".into(), json!(pattern));
+        }
+        if !string.in_values.is_empty() {
+            map.insert("enum".into(), json!(string.in_values));
+        }
+        if !string.not_in_values.is_empty() {
+            map.insert("not".into(), json!({ "enum": string.not_in_values }));
+        }
+    }
+}
+
+/// Applies a [`FieldRules`]'s `repeated` constraints to an array field's schema.
+fn apply_repeated_rules(schema: &mut Value, rules: &FieldRules) {
+    let Value::Object(map) = schema else {
+        return;
+    };
+    if let Some(repeated) = &rules.repeated {
+        if let Some(min_items) = repeated.min_items {
+            map.insert("minItems".into(), json!(min_items));
+        }
+        if let Some(max_items) = repeated.max_items {
+            map.insert("maxItems".into(), json!(max_items));
+        }
+    }
+}
+
 /// Final resources consumed by the Rocket helper.
 #[derive(Clone)]
 pub(crate) struct RenderedDoc {
@@ -212,6 +742,11 @@ pub struct SwaggerUiConfig {
     pub title: String,
     pub dark_mode: bool,
     pub swagger_ui_dist: String,
+    /// Whether Swagger UI's "Authorize" dialog keeps what was entered across page reloads, via
+    /// its own `persistAuthorization` option. Defaults to `true` since re-entering a bearer
+    /// token/API key on every reload of the RPC Explorer is the main friction this is meant to
+    /// remove.
+    pub persist_authorization: bool,
 }
 
 impl Default for SwaggerUiConfig {
@@ -220,6 +755,7 @@ impl Default for SwaggerUiConfig {
             title: "pRPC Explorer".to_string(),
             dark_mode: true,
             swagger_ui_dist: "https://cdn.jsdelivr.net/npm/swagger-ui-dist@5".to_string(),
+            persist_authorization: true,
         }
     }
 }
@@ -237,10 +773,14 @@ pub fn generate_document(
     for (source_id, source) in sources.iter().enumerate() {
         let descriptor_set = FileDescriptorSet::decode(source.descriptor)
             .context("failed to decode descriptor set")?;
-        registry.ingest(descriptor_set, source_id);
+        registry.ingest(descriptor_set, source.descriptor, source_id);
     }
 
-    let mut schema_builder = SchemaBuilder::new(&registry);
+    let mut schema_builder = SchemaBuilder::new(
+        &registry,
+        info.use_proto_field_names,
+        info.rich_enum_schemas,
+    );
     let mut paths = BTreeMap::<String, Value>::new();
 
     for (source_id, source) in sources.iter().enumerate() {
@@ -250,23 +790,42 @@ pub fn generate_document(
                 .with_context(|| format!("service {} not found in descriptor", svc_cfg.name))?;
 
             for method in &service.methods {
-                if method.client_streaming || method.server_streaming {
-                    bail!(
-                        "streaming method {}.{} is not supported by the HTTP bridge",
-                        service.full_name,
-                        method.name
-                    );
+                let bindings: Vec<(&str, String, Option<&HttpRule>)> = match &method.http_rule {
+                    Some(rule) => std::iter::once(rule)
+                        .chain(rule.additional_bindings.iter())
+                        .map(|binding| {
+                            let (openapi_path, _) = parse_path_template(&binding.path);
+                            (binding.verb.as_str(), openapi_path, Some(binding))
+                        })
+                        .collect(),
+                    None => {
+                        let base = normalize_mount_path(svc_cfg.mount_path.as_ref());
+                        let method_segment = format!("{}{}", svc_cfg.method_prefix, method.name);
+                        vec![("post", join_path(&base, &method_segment), None)]
+                    }
+                };
+
+                for (binding_index, (verb, path, rule)) in bindings.into_iter().enumerate() {
+                    let operation = build_operation(
+                        service,
+                        method,
+                        svc_cfg,
+                        rule,
+                        binding_index,
+                        &mut schema_builder,
+                    )?;
+
+                    match paths.get_mut(&path) {
+                        Some(Value::Object(op_map)) => {
+                            op_map.insert(verb.to_string(), operation);
+                        }
+                        _ => {
+                            let mut op_map = Map::new();
+                            op_map.insert(verb.to_string(), operation);
+                            paths.insert(path, Value::Object(op_map));
+                        }
+                    }
                 }
-
-                let base = normalize_mount_path(svc_cfg.mount_path.as_ref());
-                let method_segment = format!("{}{}", svc_cfg.method_prefix, method.name);
-                let path = join_path(&base, &method_segment);
-                let post_operation =
-                    build_operation(service, method, svc_cfg, &mut schema_builder)?;
-
-                let mut op_map = Map::new();
-                op_map.insert("post".to_string(), post_operation);
-                paths.insert(path, Value::Object(op_map));
             }
         }
     }
@@ -301,6 +860,13 @@ pub fn generate_document(
 
     let mut components = Map::new();
     components.insert("schemas".into(), Value::Object(schemas));
+    if !info.security_schemes.is_empty() {
+        let mut security_schemes = Map::new();
+        for (name, scheme) in &info.security_schemes {
+            security_schemes.insert(name.to_string(), scheme.to_schema_object());
+        }
+        components.insert("securitySchemes".into(), Value::Object(security_schemes));
+    }
 
     doc.insert("paths".into(), map_to_value(paths));
     doc.insert("components".into(), Value::Object(components));
@@ -322,6 +888,8 @@ fn build_operation(
     service: &ServiceInfo,
     method: &MethodInfo,
     svc_cfg: &ServiceConfig<'_>,
+    rule: Option<&HttpRule>,
+    binding_index: usize,
     schema_builder: &mut SchemaBuilder<'_>,
 ) -> Result<Value> {
     let mut operation = Map::new();
@@ -331,14 +899,19 @@ fn build_operation(
         .map(|t| t.to_string())
         .unwrap_or_else(|| service.full_name.clone());
     operation.insert("tags".into(), Value::Array(vec![Value::String(tag)]));
-    operation.insert(
-        "operationId".into(),
-        Value::String(format!(
-            "{}_{}",
+    // `additional_bindings` route several verb/path pairs to the same method, so every binding
+    // past the first needs its own `operationId` to stay unique within the document.
+    let operation_id = if binding_index == 0 {
+        format!("{}_{}", service.full_name.replace('.', "_"), method.name)
+    } else {
+        format!(
+            "{}_{}_binding{}",
             service.full_name.replace('.', "_"),
-            method.name
-        )),
-    );
+            method.name,
+            binding_index + 1
+        )
+    };
+    operation.insert("operationId".into(), Value::String(operation_id));
     let summary = method
         .description
         .as_deref()
@@ -371,37 +944,86 @@ fn build_operation(
     let description = description_parts.join("\n\n");
     operation.insert("description".into(), Value::String(description));
 
-    if !is_empty_type(&method.input_type) {
-        let schema = schema_builder.schema_ref(&method.input_type)?;
-        let request = json!({
-            "required": true,
-            "content": {
-                "application/json": {
-                    "schema": schema
-                }
+    match rule {
+        Some(rule) => {
+            let (parameters, request_body) =
+                build_transcoded_request(method, rule, schema_builder)?;
+            if !parameters.is_empty() {
+                operation.insert("parameters".into(), Value::Array(parameters));
+            }
+            if let Some(body) = request_body {
+                // google.api.http has no defined meaning for a client-streaming method (the spec
+                // only documents it for unary and server-streaming RPCs), but if one shows up
+                // anyway, document the body honestly as a stream rather than silently treating
+                // it as a single message.
+                let body = if method.client_streaming {
+                    restream_request_body(body, &method.input_type)
+                } else {
+                    body
+                };
+                operation.insert("requestBody".into(), body);
             }
-        });
-        operation.insert("requestBody".into(), request);
+        }
+        None => {
+            if !is_empty_type(&method.input_type) {
+                let schema = schema_builder.schema_ref(&method.input_type)?;
+                let request = if method.client_streaming {
+                    json!({
+                        "required": true,
+                        "description": format!(
+                            "A client-streaming sequence of `{}` messages.",
+                            normalize_type_name(&method.input_type).trim_start_matches('.')
+                        ),
+                        "content": streaming_content(&schema)
+                    })
+                } else {
+                    let example = schema_builder.example_for_message(&method.input_type);
+                    json!({
+                        "required": true,
+                        "content": content_with_media_types(
+                            &schema,
+                            &method.input_type,
+                            svc_cfg.protobuf_content_type,
+                            Some(&example)
+                        )
+                    })
+                };
+                operation.insert("requestBody".into(), request);
+            }
+        }
     }
 
-    let success_schema = if is_empty_type(&method.output_type) {
-        json!({ "type": "object" })
+    let (success_schema, success_example) = if is_empty_type(&method.output_type) {
+        (json!({ "type": "object" }), json!({}))
     } else {
-        schema_builder.schema_ref(&method.output_type)?
+        (
+            schema_builder.schema_ref(&method.output_type)?,
+            schema_builder.example_for_message(&method.output_type),
+        )
     };
 
-    let mut responses = Map::new();
-    responses.insert(
-        "200".into(),
+    let success_response = if method.server_streaming {
+        json!({
+            "description": format!(
+                "A server-streaming sequence of `{}` messages, one JSON object per message.",
+                normalize_type_name(&method.output_type).trim_start_matches('.')
+            ),
+            "content": streaming_content(&success_schema)
+        })
+    } else {
         json!({
             "description": "Successful response",
-            "content": {
-                "application/json": {
-                    "schema": success_schema
-                }
-            }
-        }),
-    );
+            "content": content_with_media_types(
+                &success_schema,
+                &method.output_type,
+                svc_cfg.protobuf_content_type,
+                Some(&success_example)
+            )
+        })
+    };
+
+    let mut responses = Map::new();
+    responses.insert("200".into(), success_response);
     responses.insert(
         "400".into(),
         json!({
@@ -415,9 +1037,186 @@ fn build_operation(
     );
     operation.insert("responses".into(), Value::Object(responses));
 
+    if method.client_streaming || method.server_streaming {
+        operation.insert(
+            "x-grpc-streaming".into(),
+            json!({
+                "clientStreaming": method.client_streaming,
+                "serverStreaming": method.server_streaming
+            }),
+        );
+    }
+
+    if let Some(scheme_names) = svc_cfg.effective_security(&method.name) {
+        let security = scheme_names
+            .iter()
+            .map(|name| {
+                let mut requirement = Map::new();
+                requirement.insert(name.to_string(), Value::Array(Vec::new()));
+                Value::Object(requirement)
+            })
+            .collect();
+        operation.insert("security".into(), Value::Array(security));
+    }
+
     Ok(Value::Object(operation))
 }
 
+/// Splits `method`'s input message across `rule`'s path template and `body` selector, per
+/// gRPC-transcoding semantics: fields bound by the path template become `path` parameters, the
+/// `body` selector (`"*"`, a single sub-message field, or absent) determines the `requestBody`,
+/// and any top-level scalar/enum field left over from both becomes a `query` parameter.
+/// Message-typed fields that aren't the path or the body are skipped — this bridge doesn't
+/// support decoding nested objects from a flat query string.
+fn build_transcoded_request(
+    method: &MethodInfo,
+    rule: &HttpRule,
+    schema_builder: &mut SchemaBuilder<'_>,
+) -> Result<(Vec<Value>, Option<Value>)> {
+    let (_, path_fields) = parse_path_template(&rule.path);
+    let owner_message = normalize_type_name(&method.input_type);
+    let mut remaining: Vec<FieldDescriptorProto> = schema_builder
+        .registry
+        .message(&owner_message)
+        .map(|message| message.descriptor.field.clone())
+        .unwrap_or_default();
+
+    let mut parameters = Vec::new();
+    for field_name in &path_fields {
+        let Some(pos) = remaining
+            .iter()
+            .position(|f| f.name.as_deref() == Some(field_name.as_str()))
+        else {
+            continue;
+        };
+        let field = remaining.remove(pos);
+        let schema = schema_builder.scalar_schema(&field)?;
+        parameters.push(json!({
+            "name": field_name,
+            "in": "path",
+            "required": true,
+            "schema": schema
+        }));
+    }
+
+    let request_body = match rule.body.as_deref() {
+        Some("*") => (!remaining.is_empty())
+            .then(|| schema_builder.build_fields_object_schema(&owner_message, &remaining))
+            .transpose()?
+            .map(|schema| {
+                let mut example = Map::new();
+                for field in &remaining {
+                    let json_key = field_json_key(field, schema_builder.use_proto_field_names);
+                    example.insert(json_key, schema_builder.example_for_field(field));
+                }
+                remaining.clear();
+                json!({
+                    "required": true,
+                    "content": {
+                        "application/json": { "schema": schema, "example": Value::Object(example) }
+                    }
+                })
+            }),
+        Some(field_name) => {
+            let pos = remaining
+                .iter()
+                .position(|f| f.name.as_deref() == Some(field_name));
+            if let Some(pos) = pos {
+                let field = remaining.remove(pos);
+                let schema = schema_builder.field_schema(&owner_message, &field)?;
+                let example = schema_builder.example_for_field(&field);
+                Some(json!({
+                    "required": true,
+                    "content": { "application/json": { "schema": schema, "example": example } }
+                }))
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+
+    for field in &remaining {
+        if matches!(field_type(field), FieldType::Message) {
+            continue;
+        }
+        let schema = schema_builder.scalar_schema(field)?;
+        parameters.push(json!({
+            "name": field.name.clone().unwrap_or_default(),
+            "in": "query",
+            "required": false,
+            "schema": schema
+        }));
+    }
+
+    Ok((parameters, request_body))
+}
+
+/// Builds an OpenAPI `content` map for `schema`: always `application/json` (with `example`, if
+/// given, so Swagger UI's "Try it out" doesn't start from an empty body), plus
+/// `application/x-protobuf` (tagged with an `x-proto-message` extension naming `message_type`)
+/// when `include_protobuf` is set via [`ServiceConfig::with_protobuf_content_type`].
+fn content_with_media_types(
+    schema: &Value,
+    message_type: &str,
+    include_protobuf: bool,
+    example: Option<&Value>,
+) -> Value {
+    let mut json_media_type = json!({ "schema": schema });
+    if let (Some(example), Value::Object(obj)) = (example, &mut json_media_type) {
+        obj.insert("example".to_string(), example.clone());
+    }
+    let mut content = Map::new();
+    content.insert("application/json".to_string(), json_media_type);
+    if include_protobuf {
+        content.insert(
+            "application/x-protobuf".to_string(),
+            json!({
+                "schema": schema,
+                "x-proto-message": normalize_type_name(message_type).trim_start_matches('.')
+            }),
+        );
+    }
+    Value::Object(content)
+}
+
+/// Builds the `content` map for a streaming request/response body: `application/json-seq`
+/// (RFC 7464 JSON text sequence) whose `schema` describes one streamed message, not an array of
+/// them, since each frame on the wire is its own standalone JSON text. Binary protobuf streaming
+/// isn't represented here — `ServiceConfig::with_protobuf_content_type` only applies to unary
+/// bodies in this bridge.
+fn streaming_content(schema: &Value) -> Value {
+    json!({
+        "application/json-seq": { "schema": schema }
+    })
+}
+
+/// Rewrites an already-built transcoded request `body` (see [`build_transcoded_request`]) to
+/// advertise itself as a client-streaming sequence of `input_type` messages instead of a single
+/// JSON object, for the edge case of a `google.api.http`-annotated client-streaming method (the
+/// transcoding spec doesn't actually define this combination, but we document it honestly rather
+/// than silently dropping the streaming flag).
+fn restream_request_body(mut body: Value, input_type: &str) -> Value {
+    if let Value::Object(obj) = &mut body {
+        if let Some(schema) = obj
+            .get("content")
+            .and_then(|c| c.get("application/json"))
+            .and_then(|c| c.get("schema"))
+            .cloned()
+        {
+            obj.insert("content".into(), streaming_content(&schema));
+        }
+        obj.insert(
+            "description".into(),
+            Value::String(format!(
+                "A client-streaming sequence of `{}` messages.",
+                normalize_type_name(input_type).trim_start_matches('.')
+            )),
+        );
+    }
+    body
+}
+
 fn rpc_error_schema() -> Value {
     json!({
         "type": "object",
@@ -480,10 +1279,26 @@ struct DescriptorRegistry {
     services: Vec<ServiceInfo>,
     service_by_full_name: HashMap<String, usize>,
     service_by_simple_name: HashMap<String, Vec<usize>>,
+    /// `google.api.http` annotations, keyed by (source, fully-qualified service name, method
+    /// name). Populated from the raw descriptor bytes in [`Self::ingest`] alongside the typed
+    /// `prost_types` walk, since that extension isn't a field `MethodOptions` knows about.
+    http_rules: HashMap<(usize, String, String), HttpRule>,
+    /// `protoc-gen-validate` (`validate.rules`) constraints, keyed by (fully-qualified message
+    /// name, field name). Populated from the raw descriptor bytes in [`Self::ingest`], for the
+    /// same reason as [`Self::http_rules`]: `prost_types::FieldOptions` has no field for this
+    /// extension. Not namespaced by source, matching [`Self::messages`]'s flat keying.
+    field_rules: HashMap<(String, String), FieldRules>,
+    /// Author-supplied `(dstack.example)` overrides, keyed by fully-qualified message name.
+    /// Populated from the raw descriptor bytes in [`Self::ingest`], for the same reason as
+    /// [`Self::http_rules`]: `prost_types::MessageOptions` has no field for this extension.
+    message_examples: HashMap<String, Value>,
 }
 
 impl DescriptorRegistry {
-    fn ingest(&mut self, set: FileDescriptorSet, source_id: usize) {
+    fn ingest(&mut self, set: FileDescriptorSet, raw: &[u8], source_id: usize) {
+        self.index_http_rules(raw, source_id);
+        self.index_field_rules(raw);
+        self.index_message_examples(raw);
         for file in set.file {
             let package = file.package.unwrap_or_default();
             let comments = SourceCodeComments::from_source_info(file.source_code_info.clone());
@@ -502,6 +1317,127 @@ impl DescriptorRegistry {
         }
     }
 
+    /// Walks `raw` (the same bytes [`FileDescriptorSet::decode`] was given) by hand to recover
+    /// `google.api.http` annotations, matching descriptor.proto's own field numbers:
+    /// `FileDescriptorSet.file`=1, `FileDescriptorProto.package`=2, `FileDescriptorProto.service`
+    /// =6, `ServiceDescriptorProto.name`=1, `ServiceDescriptorProto.method`=2,
+    /// `MethodDescriptorProto.name`=1, `MethodDescriptorProto.options`=4.
+    fn index_http_rules(&mut self, raw: &[u8], source_id: usize) {
+        for file_bytes in find_all_length_delimited(raw, 1) {
+            let package = find_length_delimited(file_bytes, 2)
+                .and_then(|b| std::str::from_utf8(b).ok())
+                .unwrap_or_default();
+            for service_bytes in find_all_length_delimited(file_bytes, 6) {
+                let service_name = find_length_delimited(service_bytes, 1)
+                    .and_then(|b| std::str::from_utf8(b).ok())
+                    .unwrap_or_default();
+                let full_service_name = qualified_service_name(package, service_name);
+                for method_bytes in find_all_length_delimited(service_bytes, 2) {
+                    let method_name = find_length_delimited(method_bytes, 1)
+                        .and_then(|b| std::str::from_utf8(b).ok())
+                        .unwrap_or_default();
+                    let Some(options_bytes) = find_length_delimited(method_bytes, 4) else {
+                        continue;
+                    };
+                    let Some(http_rule_bytes) =
+                        find_length_delimited(options_bytes, GOOGLE_API_HTTP_EXTENSION_FIELD)
+                    else {
+                        continue;
+                    };
+                    if let Some(rule) = parse_http_rule(http_rule_bytes) {
+                        self.http_rules.insert(
+                            (source_id, full_service_name.clone(), method_name.to_string()),
+                            rule,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Walks `raw`'s messages (and their nested types) for `validate.rules` constraints, matching
+    /// descriptor.proto's field numbers: `FileDescriptorProto.message_type`=4,
+    /// `DescriptorProto.field`=2, `DescriptorProto.nested_type`=3,
+    /// `FieldDescriptorProto.options`=8.
+    fn index_field_rules(&mut self, raw: &[u8]) {
+        for file_bytes in find_all_length_delimited(raw, 1) {
+            let package = find_length_delimited(file_bytes, 2)
+                .and_then(|b| std::str::from_utf8(b).ok())
+                .unwrap_or_default();
+            for message_bytes in find_all_length_delimited(file_bytes, 4) {
+                self.index_message_field_rules(package, &[], message_bytes);
+            }
+        }
+    }
+
+    fn index_message_field_rules(&mut self, package: &str, parents: &[String], message_bytes: &[u8]) {
+        let name = find_length_delimited(message_bytes, 1)
+            .and_then(|b| std::str::from_utf8(b).ok())
+            .unwrap_or_default();
+        let mut path = parents.to_vec();
+        path.push(name.to_string());
+        let full_name = canonical_name(package, &path);
+
+        for field_bytes in find_all_length_delimited(message_bytes, 2) {
+            let field_name = find_length_delimited(field_bytes, 1)
+                .and_then(|b| std::str::from_utf8(b).ok())
+                .unwrap_or_default();
+            let Some(options_bytes) = find_length_delimited(field_bytes, 8) else {
+                continue;
+            };
+            let Some(rules_bytes) =
+                find_length_delimited(options_bytes, PGV_FIELD_RULES_EXTENSION_FIELD)
+            else {
+                continue;
+            };
+            if let Some(rules) = parse_field_rules(rules_bytes) {
+                self.field_rules
+                    .insert((full_name.clone(), field_name.to_string()), rules);
+            }
+        }
+
+        for nested_bytes in find_all_length_delimited(message_bytes, 3) {
+            self.index_message_field_rules(package, &path, nested_bytes);
+        }
+    }
+
+    /// Walks `raw`'s messages (and their nested types) for `(dstack.example)` overrides, matching
+    /// descriptor.proto's field numbers: `FileDescriptorProto.message_type`=4,
+    /// `DescriptorProto.nested_type`=3, `DescriptorProto.options`=7.
+    fn index_message_examples(&mut self, raw: &[u8]) {
+        for file_bytes in find_all_length_delimited(raw, 1) {
+            let package = find_length_delimited(file_bytes, 2)
+                .and_then(|b| std::str::from_utf8(b).ok())
+                .unwrap_or_default();
+            for message_bytes in find_all_length_delimited(file_bytes, 4) {
+                self.index_message_example(package, &[], message_bytes);
+            }
+        }
+    }
+
+    fn index_message_example(&mut self, package: &str, parents: &[String], message_bytes: &[u8]) {
+        let name = find_length_delimited(message_bytes, 1)
+            .and_then(|b| std::str::from_utf8(b).ok())
+            .unwrap_or_default();
+        let mut path = parents.to_vec();
+        path.push(name.to_string());
+        let full_name = canonical_name(package, &path);
+
+        if let Some(example) = find_length_delimited(message_bytes, 7)
+            .and_then(|options_bytes| {
+                find_length_delimited(options_bytes, SCHEMA_EXAMPLE_EXTENSION_FIELD)
+            })
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .and_then(|text| serde_json::from_str::<Value>(text).ok())
+        {
+            self.message_examples.insert(full_name.clone(), example);
+        }
+
+        for nested_bytes in find_all_length_delimited(message_bytes, 3) {
+            self.index_message_example(package, &path, nested_bytes);
+        }
+    }
+
     fn register_message(
         &mut self,
         package: &str,
@@ -561,9 +1497,19 @@ impl DescriptorRegistry {
         path.push(name);
         let full_name = canonical_name(package, &path);
         let description = comments.comment_for(descriptor_path).map(|s| s.to_string());
+        let mut value_comments = HashMap::new();
+        for (idx, value) in descriptor.value.iter().enumerate() {
+            if let Some(value_name) = value.name.as_ref() {
+                let value_path = extend_path(descriptor_path, 2, idx as i32);
+                if let Some(comment) = comments.comment_for(&value_path) {
+                    value_comments.insert(value_name.clone(), comment.to_string());
+                }
+            }
+        }
         let info = EnumInfo {
             descriptor,
             description,
+            value_comments,
         };
         self.enums.insert(full_name, info);
     }
@@ -578,6 +1524,7 @@ impl DescriptorRegistry {
     ) {
         let simple_name = descriptor.name.clone().unwrap_or_default();
         let full_name = qualified_service_name(package, &simple_name);
+        let http_rules = &self.http_rules;
         let methods = descriptor
             .method
             .into_iter()
@@ -586,13 +1533,18 @@ impl DescriptorRegistry {
                 let description = comments
                     .comment_for(&extend_path(descriptor_path, 2, idx as i32))
                     .map(|s| s.to_string());
+                let method_name = method.name.unwrap_or_default();
+                let http_rule = http_rules
+                    .get(&(source_id, full_name.clone(), method_name.clone()))
+                    .cloned();
                 MethodInfo {
-                    name: method.name.unwrap_or_default(),
+                    name: method_name,
                     input_type: normalize_type_name(&method.input_type.unwrap_or_default()),
                     output_type: normalize_type_name(&method.output_type.unwrap_or_default()),
                     client_streaming: method.client_streaming.unwrap_or(false),
                     server_streaming: method.server_streaming.unwrap_or(false),
                     description,
+                    http_rule,
                 }
             })
             .collect();
@@ -649,6 +1601,20 @@ impl DescriptorRegistry {
     fn enumeration(&self, name: &str) -> Option<&EnumInfo> {
         self.enums.get(name)
     }
+
+    fn field_rules(&self, message_name: &str, field_name: &str) -> Option<&FieldRules> {
+        self.field_rules
+            .get(&(message_name.to_string(), field_name.to_string()))
+    }
+
+    fn field_required_by_rules(&self, message_name: &str, field_name: &str) -> bool {
+        self.field_rules(message_name, field_name)
+            .is_some_and(|rules| rules.message_required)
+    }
+
+    fn message_example(&self, message_name: &str) -> Option<&Value> {
+        self.message_examples.get(message_name)
+    }
 }
 
 #[derive(Clone)]
@@ -664,6 +1630,9 @@ struct MessageInfo {
 struct EnumInfo {
     descriptor: EnumDescriptorProto,
     description: Option<String>,
+    /// Leading comments on individual `EnumValueDescriptorProto`s, keyed by value name, mirroring
+    /// [`MessageInfo::field_comments`].
+    value_comments: HashMap<String, String>,
 }
 
 #[derive(Clone)]
@@ -682,20 +1651,28 @@ struct MethodInfo {
     client_streaming: bool,
     server_streaming: bool,
     description: Option<String>,
+    /// The method's `google.api.http` transcoding rule, if its `.proto` declares one. Drives
+    /// [`build_operation`]'s choice of HTTP verb/path/body instead of the default "POST the whole
+    /// request" mapping.
+    http_rule: Option<HttpRule>,
 }
 
 struct SchemaBuilder<'a> {
     registry: &'a DescriptorRegistry,
     generated: BTreeMap<String, Value>,
     visited: BTreeSet<String>,
+    use_proto_field_names: bool,
+    rich_enum_schemas: bool,
 }
 
 impl<'a> SchemaBuilder<'a> {
-    fn new(registry: &'a DescriptorRegistry) -> Self {
+    fn new(registry: &'a DescriptorRegistry, use_proto_field_names: bool, rich_enum_schemas: bool) -> Self {
         Self {
             registry,
             generated: BTreeMap::new(),
             visited: BTreeSet::new(),
+            use_proto_field_names,
+            rich_enum_schemas,
         }
     }
 
@@ -742,16 +1719,25 @@ impl<'a> SchemaBuilder<'a> {
 
         let mut required = Vec::new();
         let mut props = BTreeMap::new();
+        let mut oneof_members: BTreeMap<i32, Vec<String>> = BTreeMap::new();
         for field in &descriptor.descriptor.field {
             let field_name = field.name.clone().unwrap_or_default();
-            let mut schema = self.field_schema(field)?;
+            let json_key = field_json_key(field, self.use_proto_field_names);
+            let mut schema = self.field_schema(&descriptor.full_name, field)?;
             if let Some(doc) = descriptor.field_comments.get(&field_name) {
                 apply_schema_description(&mut schema, doc);
             }
-            if is_required_field(field) {
-                required.push(field_name.clone());
+            if is_required_field(field)
+                || self
+                    .registry
+                    .field_required_by_rules(&descriptor.full_name, &field_name)
+            {
+                required.push(json_key.clone());
             }
-            props.insert(field_name, schema);
+            if let Some(index) = real_oneof_index(field) {
+                oneof_members.entry(index).or_default().push(json_key.clone());
+            }
+            props.insert(json_key, schema);
         }
 
         let mut obj = Map::new();
@@ -770,12 +1756,21 @@ impl<'a> SchemaBuilder<'a> {
                 Value::Array(required.into_iter().map(Value::String).collect()),
             );
         }
+        let fragments = build_oneof_fragments(&descriptor.descriptor.oneof_decl, &oneof_members);
+        merge_oneof_fragments(&mut obj, fragments);
 
         self.generated.insert(schema_key(name), Value::Object(obj));
         self.visited.remove(name);
         Ok(())
     }
 
+    /// Emits the enum's schema: a plain `{"type": "string", "enum": [...]}` by default, or (when
+    /// [`DocumentInfo::with_rich_enum_schemas`] is set) an `anyOf` of the string enum (every
+    /// value name, including aliases when `options.allow_alias` is set) and the integer enum
+    /// (every distinct number, documented with a number->name(s) table), since protobuf JSON
+    /// accepts either form for an enum field. In rich mode, the zero value (if any) becomes the
+    /// schema's `default` so generated clients know the implicit value a missing/omitted field
+    /// takes.
     fn ensure_enum_generated(&mut self, name: &str) -> Result<()> {
         if self.generated.contains_key(&schema_key(name)) {
             return Ok(());
@@ -784,24 +1779,90 @@ impl<'a> SchemaBuilder<'a> {
             .registry
             .enumeration(name)
             .ok_or_else(|| anyhow!("enum {} not found", name))?;
-        let mut variants = Vec::new();
-        for value in &descriptor.descriptor.value {
-            if let Some(name) = &value.name {
-                variants.push(Value::String(name.clone()));
+
+        if !self.rich_enum_schemas {
+            let mut variants = Vec::new();
+            for value in &descriptor.descriptor.value {
+                if let Some(value_name) = &value.name {
+                    variants.push(Value::String(value_name.clone()));
+                }
+            }
+            let mut schema = Map::new();
+            schema.insert("type".into(), Value::String("string".into()));
+            schema.insert("enum".into(), Value::Array(variants));
+            if let Some(doc) = &descriptor.description {
+                schema.insert("description".into(), Value::String(doc.clone()));
             }
+            self.generated
+                .insert(schema_key(name), Value::Object(schema));
+            return Ok(());
         }
+
+        let mut names = Vec::new();
+        let mut numbers: BTreeMap<i32, Vec<String>> = BTreeMap::new();
+        let mut default_name = None;
+        for value in &descriptor.descriptor.value {
+            let Some(value_name) = value.name.clone() else {
+                continue;
+            };
+            let number = value.number.unwrap_or_default();
+            if number == 0 && default_name.is_none() {
+                default_name = Some(value_name.clone());
+            }
+            names.push(value_name.clone());
+            numbers.entry(number).or_default().push(value_name);
+        }
+
+        let mut string_schema = Map::new();
+        string_schema.insert("type".into(), Value::String("string".into()));
+        string_schema.insert(
+            "enum".into(),
+            Value::Array(names.into_iter().map(Value::String).collect()),
+        );
+
+        let number_table = numbers
+            .iter()
+            .map(|(number, value_names)| {
+                let comment = value_names
+                    .iter()
+                    .find_map(|n| descriptor.value_comments.get(n));
+                match comment {
+                    Some(comment) => format!("{} = {} ({})", number, value_names.join(" / "), comment),
+                    None => format!("{} = {}", number, value_names.join(" / ")),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        let mut int_schema = Map::new();
+        int_schema.insert("type".into(), Value::String("integer".into()));
+        int_schema.insert(
+            "enum".into(),
+            Value::Array(numbers.keys().map(|n| Value::Number((*n).into())).collect()),
+        );
+        int_schema.insert(
+            "description".into(),
+            Value::String(format!("Numeric form of the enum: {}", number_table)),
+        );
+
         let mut schema = Map::new();
-        schema.insert("type".into(), Value::String("string".into()));
-        schema.insert("enum".into(), Value::Array(variants));
+        schema.insert(
+            "anyOf".into(),
+            Value::Array(vec![Value::Object(string_schema), Value::Object(int_schema)]),
+        );
         if let Some(doc) = &descriptor.description {
             schema.insert("description".into(), Value::String(doc.clone()));
         }
+        if let Some(default_name) = default_name {
+            schema.insert("default".into(), Value::String(default_name));
+        }
         self.generated
             .insert(schema_key(name), Value::Object(schema));
         Ok(())
     }
 
-    fn field_schema(&mut self, field: &FieldDescriptorProto) -> Result<Value> {
+    /// Builds `field`'s schema, then applies any `protoc-gen-validate` constraints registered
+    /// for `(owner_message, field.name)` on top of it (see [`DescriptorRegistry::field_rules`]).
+    fn field_schema(&mut self, owner_message: &str, field: &FieldDescriptorProto) -> Result<Value> {
         if matches!(field_type(field), FieldType::Message)
             && matches!(field_label(field), FieldLabel::Repeated)
         {
@@ -815,16 +1876,32 @@ impl<'a> SchemaBuilder<'a> {
             }
         }
 
-        let schema = match field_label(field) {
+        let mut schema = match field_label(field) {
             FieldLabel::Repeated => {
                 let inner = self.scalar_schema(field)?;
-                json!({
+                let mut array_schema = json!({
                     "type": "array",
                     "items": inner
-                })
+                });
+                if let Some(rules) = self
+                    .registry
+                    .field_rules(owner_message, field.name.as_deref().unwrap_or_default())
+                {
+                    apply_repeated_rules(&mut array_schema, rules);
+                }
+                array_schema
             }
             _ => self.scalar_schema(field)?,
         };
+
+        if !matches!(field_label(field), FieldLabel::Repeated) {
+            if let Some(rules) = self
+                .registry
+                .field_rules(owner_message, field.name.as_deref().unwrap_or_default())
+            {
+                apply_scalar_rules(&mut schema, rules);
+            }
+        }
         Ok(schema)
     }
 
@@ -867,6 +1944,49 @@ impl<'a> SchemaBuilder<'a> {
         })
     }
 
+    /// Builds an inline (unnamed, uncached) object schema from `fields`, for the `body: "*"`
+    /// transcoding case where the HTTP body is "whatever's left of the request message" rather
+    /// than the whole message type.
+    fn build_fields_object_schema(
+        &mut self,
+        owner_message: &str,
+        fields: &[FieldDescriptorProto],
+    ) -> Result<Value> {
+        let mut required = Vec::new();
+        let mut properties = Map::new();
+        let mut oneof_members: BTreeMap<i32, Vec<String>> = BTreeMap::new();
+        for field in fields {
+            let json_key = field_json_key(field, self.use_proto_field_names);
+            let schema = self.field_schema(owner_message, field)?;
+            if is_required_field(field) {
+                required.push(json_key.clone());
+            }
+            if let Some(index) = real_oneof_index(field) {
+                oneof_members.entry(index).or_default().push(json_key.clone());
+            }
+            properties.insert(json_key, schema);
+        }
+        let mut obj = Map::new();
+        obj.insert("type".into(), Value::String("object".into()));
+        obj.insert("properties".into(), Value::Object(properties));
+        if !required.is_empty() {
+            obj.insert(
+                "required".into(),
+                Value::Array(required.into_iter().map(Value::String).collect()),
+            );
+        }
+        if !oneof_members.is_empty() {
+            let oneof_decl = self
+                .registry
+                .message(owner_message)
+                .map(|message| message.descriptor.oneof_decl.clone())
+                .unwrap_or_default();
+            let fragments = build_oneof_fragments(&oneof_decl, &oneof_members);
+            merge_oneof_fragments(&mut obj, fragments);
+        }
+        Ok(Value::Object(obj))
+    }
+
     fn map_field_schema(&mut self, entry: &MessageInfo) -> Result<Value> {
         let mut value_field = None;
         for field in &entry.descriptor.field {
@@ -883,6 +2003,113 @@ impl<'a> SchemaBuilder<'a> {
         }))
     }
 
+    /// Synthesizes a representative JSON example for `type_name`'s message, for
+    /// [`build_operation`] to attach to a request body or response so Swagger UI's "Try it out"
+    /// doesn't start from empty. An author-supplied [`DescriptorRegistry::message_example`] (a
+    /// `(dstack.example)` `MessageOptions` override) wins outright; otherwise every field gets a
+    /// proto zero value (scalars), the first declared value name (enums), one more level of this
+    /// same expansion (message fields), or a single-element array/object (repeated/map fields).
+    /// Reuses [`Self::visited`] — the same cycle guard [`Self::ensure_message_generated`] relies
+    /// on — so a self-referential message bottoms out at `null` at the cycle boundary instead of
+    /// looping.
+    fn example_for_message(&mut self, type_name: &str) -> Value {
+        let normalized = normalize_type_name(type_name);
+        if let Some(example) = builtin_example(&normalized) {
+            return example;
+        }
+        if let Some(example) = self.registry.message_example(&normalized) {
+            return example.clone();
+        }
+        if !self.visited.insert(normalized.clone()) {
+            return Value::Null;
+        }
+        let example = match self.registry.message(&normalized) {
+            Some(message) => {
+                let mut obj = Map::new();
+                for field in &message.descriptor.field {
+                    let json_key = field_json_key(field, self.use_proto_field_names);
+                    obj.insert(json_key, self.example_for_field(field));
+                }
+                Value::Object(obj)
+            }
+            None => Value::Object(Map::new()),
+        };
+        self.visited.remove(&normalized);
+        example
+    }
+
+    fn example_for_field(&mut self, field: &FieldDescriptorProto) -> Value {
+        if matches!(field_label(field), FieldLabel::Repeated) {
+            if matches!(field_type(field), FieldType::Message) {
+                if let Some(type_name) = &field.type_name {
+                    let normalized = normalize_type_name(type_name);
+                    if let Some(entry) = self.registry.message(&normalized) {
+                        if entry.is_map_entry {
+                            return self.example_for_map_entry(entry);
+                        }
+                    }
+                }
+            }
+            return Value::Array(vec![self.example_for_scalar(field)]);
+        }
+        self.example_for_scalar(field)
+    }
+
+    /// A map's JSON representation is an object keyed by the (stringified) map key rather than
+    /// an array, so the single-element container here is `{"key": <value example>}` rather than
+    /// the `example_for_field` array form used for ordinary repeated fields.
+    fn example_for_map_entry(&mut self, entry: &MessageInfo) -> Value {
+        let value_field = entry
+            .descriptor
+            .field
+            .iter()
+            .find(|f| f.number.unwrap_or_default() == 2)
+            .cloned();
+        let mut obj = Map::new();
+        if let Some(value_field) = value_field {
+            obj.insert("key".to_string(), self.example_for_scalar(&value_field));
+        }
+        Value::Object(obj)
+    }
+
+    fn example_for_scalar(&mut self, field: &FieldDescriptorProto) -> Value {
+        match field_type(field) {
+            FieldType::Double | FieldType::Float => json!(0),
+            FieldType::Int64
+            | FieldType::Sint64
+            | FieldType::Sfixed64
+            | FieldType::Uint64
+            | FieldType::Fixed64
+            | FieldType::Int32
+            | FieldType::Sint32
+            | FieldType::Sfixed32
+            | FieldType::Uint32
+            | FieldType::Fixed32 => json!(0),
+            FieldType::Bool => json!(false),
+            FieldType::String => json!(""),
+            FieldType::Bytes => json!(""),
+            FieldType::Enum => {
+                let type_name = field.type_name.as_deref().unwrap_or_default();
+                self.example_for_enum(type_name)
+            }
+            FieldType::Message => {
+                let type_name = field.type_name.as_deref().unwrap_or_default();
+                self.example_for_message(type_name)
+            }
+            FieldType::Group => Value::Null,
+        }
+    }
+
+    fn example_for_enum(&mut self, type_name: &str) -> Value {
+        let normalized = normalize_type_name(type_name);
+        self.registry
+            .enumeration(&normalized)
+            .and_then(|e| e.descriptor.value.first())
+            .and_then(|v| v.name.clone())
+            .map(Value::String)
+            .unwrap_or(Value::Null)
+    }
+
     fn finish(self) -> Map<String, Value> {
         let mut map = Map::new();
         for (k, v) in self.generated {
@@ -909,6 +2136,24 @@ fn builtin_type_schema(name: &str) -> Option<Value> {
         ".google.protobuf.Duration" => {
             Some(json!({"type": "string", "description": "Duration string"}))
         }
+        ".google.protobuf.FieldMask" => Some(json!({
+            "type": "string",
+            "description": "A comma-separated list of field paths, per the protobuf JSON mapping for FieldMask."
+        })),
+        ".google.protobuf.Struct" => Some(json!({
+            "type": "object",
+            "description": "An arbitrary JSON object (protobuf's dynamic Struct type)."
+        })),
+        // `google.protobuf.Value` is a proto3-JSON-native union (null/bool/number/string/array/
+        // object), so it's left unconstrained rather than pinned to one JSON type.
+        ".google.protobuf.Value" => Some(json!({
+            "description": "Any JSON value: null, a boolean, a number, a string, an array, or an object."
+        })),
+        ".google.protobuf.ListValue" => Some(json!({
+            "type": "array",
+            "items": {},
+            "description": "A JSON array of arbitrary google.protobuf.Value elements."
+        })),
         ".google.protobuf.BytesValue" => {
             Some(wrapper_schema(json!({"type": "string", "format": "byte"})))
         }
@@ -932,17 +2177,59 @@ fn builtin_type_schema(name: &str) -> Option<Value> {
         ".google.protobuf.FloatValue" => {
             Some(wrapper_schema(json!({"type": "number", "format": "float"})))
         }
-        ".google.protobuf.Any" => Some(json!({"type": "object"})),
+        ".google.protobuf.Any" => Some(json!({
+            "type": "object",
+            "description": "A serialized message of any type, identified by the `@type` URL.",
+            "properties": {
+                "@type": {
+                    "type": "string",
+                    "description": "A URL identifying the packed message's type, e.g. `type.googleapis.com/google.protobuf.Duration`."
+                }
+            },
+            "required": ["@type"],
+            "additionalProperties": true
+        })),
         _ if name.starts_with(".google.protobuf.") => Some(json!({"type": "object"})),
         _ => None,
     }
 }
 
-fn wrapper_schema(inner: Value) -> Value {
-    json!({
-        "type": "object",
-        "properties": { "value": inner },
-        "required": ["value"]
+/// Proto3 JSON encodes a well-known wrapper type (`Int32Value`, `StringValue`, …) as the bare
+/// underlying value or `null`, not as an object — so `schema` (the unwrapped primitive's schema)
+/// gets its `type` widened to also admit `"null"` rather than nested under a `value` property.
+fn wrapper_schema(mut schema: Value) -> Value {
+    if let Value::Object(map) = &mut schema {
+        if let Some(Value::String(ty)) = map.get("type").cloned() {
+            map.insert("type".into(), json!([ty, "null"]));
+        }
+    }
+    schema
+}
+
+/// Concrete example values for the well-known types [`builtin_type_schema`] special-cases,
+/// since those never go through [`SchemaBuilder::example_for_message`]'s field-by-field walk
+/// (they aren't in [`DescriptorRegistry::message`]). `None` for anything not listed here falls
+/// back to an empty object.
+fn builtin_example(name: &str) -> Option<Value> {
+    Some(match name {
+        ".google.protobuf.Empty" => json!({}),
+        ".google.protobuf.Timestamp" => json!("1970-01-01T00:00:00Z"),
+        ".google.protobuf.Duration" => json!("0s"),
+        ".google.protobuf.FieldMask" => json!(""),
+        ".google.protobuf.Struct" | ".google.protobuf.Any" => json!({}),
+        ".google.protobuf.Value" => Value::Null,
+        ".google.protobuf.ListValue" => json!([]),
+        ".google.protobuf.StringValue" | ".google.protobuf.BytesValue" => json!(""),
+        ".google.protobuf.BoolValue" => json!(false),
+        ".google.protobuf.Int32Value"
+        | ".google.protobuf.Sint32Value"
+        | ".google.protobuf.UInt32Value"
+        | ".google.protobuf.Int64Value"
+        | ".google.protobuf.Sint64Value"
+        | ".google.protobuf.UInt64Value"
+        | ".google.protobuf.DoubleValue"
+        | ".google.protobuf.FloatValue" => json!(0),
+        _ => return None,
     })
 }
 
@@ -986,6 +2273,92 @@ fn is_required_field(field: &FieldDescriptorProto) -> bool {
     matches!(field_label(field), FieldLabel::Required)
 }
 
+/// `field`'s `oneof_decl` index, if it belongs to a *real* oneof the author wrote in the
+/// `.proto`. Proto3's synthetic oneofs (one per `optional` scalar field, used only to carry
+/// explicit-presence information) report an `oneof_index` too but set `proto3_optional`, and
+/// those should stay ordinary nullable fields rather than feed into a `oneOf` group.
+fn real_oneof_index(field: &FieldDescriptorProto) -> Option<i32> {
+    if field.proto3_optional == Some(true) {
+        return None;
+    }
+    field.oneof_index
+}
+
+/// Builds one `{"oneOf": [{"required": [member]}, ...]}` fragment per real oneof in `members`
+/// (oneof index -> member property keys), so schema consumers can tell that exactly one member
+/// of each group may be set. `oneof_decl` is accepted for parity with the descriptor but isn't
+/// otherwise consulted: JSON Schema's `oneOf` has no slot for the `.proto` oneof's own name, and
+/// a message can have more than one real oneof, so each gets its own fragment rather than a
+/// single combined `oneOf` — callers merge a lone fragment directly into the message schema, or
+/// combine more than one under `allOf` (see [`SchemaBuilder::ensure_message_generated`] /
+/// [`SchemaBuilder::build_fields_object_schema`]).
+fn build_oneof_fragments(
+    _oneof_decl: &[OneofDescriptorProto],
+    members: &BTreeMap<i32, Vec<String>>,
+) -> Vec<Value> {
+    members
+        .values()
+        .map(|keys| {
+            let variants: Vec<Value> = keys
+                .iter()
+                .map(|key| json!({ "required": [key] }))
+                .collect();
+            json!({ "oneOf": variants })
+        })
+        .collect()
+}
+
+/// Merges `fragments` (built by [`build_oneof_fragments`]) into `obj`: a lone fragment's keys
+/// are inserted directly (so the message schema gets a plain top-level `oneOf`), while more than
+/// one real oneof is combined under `allOf` since JSON Schema only allows a single top-level
+/// `oneOf` keyword per schema object.
+fn merge_oneof_fragments(obj: &mut Map<String, Value>, fragments: Vec<Value>) {
+    match fragments.len() {
+        0 => {}
+        1 => {
+            if let Some(Value::Object(fragment)) = fragments.into_iter().next() {
+                obj.extend(fragment);
+            }
+        }
+        _ => {
+            obj.insert("allOf".into(), Value::Array(fragments));
+        }
+    }
+}
+
+/// The `properties`/`required` key to use for `field`, matching whichever name a real client
+/// exchanges in the JSON payload: `json_name` (camelCase) by default, or the original `.proto`
+/// field name when `use_proto_field_names` is set (see [`DocumentInfo::with_proto_field_names`]).
+fn field_json_key(field: &FieldDescriptorProto, use_proto_field_names: bool) -> String {
+    let proto_name = field.name.clone().unwrap_or_default();
+    if use_proto_field_names {
+        return proto_name;
+    }
+    field
+        .json_name
+        .clone()
+        .unwrap_or_else(|| to_lower_camel_case(&proto_name))
+}
+
+/// Converts a `snake_case` proto field name to `lowerCamelCase`, for the rare case where
+/// `json_name` wasn't populated on the descriptor (protoc always sets it, but hand-built
+/// `FileDescriptorSet`s might not).
+fn to_lower_camel_case(proto_name: &str) -> String {
+    let mut out = String::with_capacity(proto_name.len());
+    let mut capitalize_next = false;
+    for ch in proto_name.chars() {
+        if ch == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            out.extend(ch.to_uppercase());
+            capitalize_next = false;
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
 fn field_label(field: &FieldDescriptorProto) -> FieldLabel {
     FieldLabel::try_from(field.label.unwrap_or_default()).unwrap_or(FieldLabel::Optional)
 }
@@ -1036,6 +2409,7 @@ fn build_swagger_ui_html(spec_url: &str, cfg: &SwaggerUiConfig) -> String {
         url: '{spec}',
         dom_id: '#swagger-ui',
         deepLinking: true,
+        persistAuthorization: {persist_authorization},
         presets: [
           SwaggerUIBundle.presets.apis,
           SwaggerUIStandalonePreset
@@ -1062,14 +2436,15 @@ fn build_swagger_ui_html(spec_url: &str, cfg: &SwaggerUiConfig) -> String {
         bundle = bundle,
         preset = preset,
         spec = spec,
-        background = background
+        background = background,
+        persist_authorization = cfg.persist_authorization
     )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use prost_types::{FileDescriptorProto, MethodDescriptorProto};
+    use prost_types::{EnumOptions, EnumValueDescriptorProto, FileDescriptorProto, MethodDescriptorProto};
     fn test_descriptor() -> Vec<u8> {
         let request = DescriptorProto {
             name: Some("PingRequest".into()),
@@ -1139,4 +2514,614 @@ mod tests {
                 .is_object()
         );
     }
+
+    #[test]
+    fn synthesizes_request_and_response_examples_from_field_defaults() {
+        let descriptor = test_descriptor();
+        let sources = vec![DescriptorSource::new(
+            &descriptor,
+            vec![ServiceConfig::new("TestService", "/prpc")],
+        )];
+        let info = DocumentInfo::new("Test API", "1.0.0");
+        let json = generate_document(&sources, &info).expect("spec");
+        let doc: Value = serde_json::from_str(&json).expect("valid json");
+        let operation = &doc["paths"]["/prpc/Ping"]["post"];
+        assert_eq!(
+            operation["requestBody"]["content"]["application/json"]["example"]["message"],
+            ""
+        );
+        assert_eq!(
+            operation["responses"]["200"]["content"]["application/json"]["example"]["echo"],
+            ""
+        );
+    }
+
+    #[test]
+    fn server_streaming_method_uses_json_seq_content_and_x_grpc_streaming_extension() {
+        let request = DescriptorProto {
+            name: Some("PingRequest".into()),
+            field: vec![FieldDescriptorProto {
+                name: Some("message".into()),
+                number: Some(1),
+                label: Some(FieldLabel::Optional as i32),
+                r#type: Some(FieldType::String as i32),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let response = DescriptorProto {
+            name: Some("PingResponse".into()),
+            field: vec![FieldDescriptorProto {
+                name: Some("echo".into()),
+                number: Some(1),
+                label: Some(FieldLabel::Optional as i32),
+                r#type: Some(FieldType::String as i32),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let service = ServiceDescriptorProto {
+            name: Some("TestService".into()),
+            method: vec![MethodDescriptorProto {
+                name: Some("Ping".into()),
+                input_type: Some(".test.PingRequest".into()),
+                output_type: Some(".test.PingResponse".into()),
+                server_streaming: Some(true),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("test.proto".into()),
+            package: Some("test".into()),
+            message_type: vec![request, response],
+            service: vec![service],
+            ..Default::default()
+        };
+        let set = FileDescriptorSet { file: vec![file] };
+        let mut descriptor = Vec::new();
+        set.encode(&mut descriptor).unwrap();
+
+        let sources = vec![DescriptorSource::new(
+            &descriptor,
+            vec![ServiceConfig::new("TestService", "/prpc")],
+        )];
+        let info = DocumentInfo::new("Test API", "1.0.0");
+        let json = generate_document(&sources, &info).expect("spec");
+        let doc: Value = serde_json::from_str(&json).expect("valid json");
+
+        let op = &doc["paths"]["/prpc/Ping"]["post"];
+        assert!(op["responses"]["200"]["content"]["application/json-seq"]["schema"].is_object());
+        assert!(op["responses"]["200"]["content"]
+            .get("application/json")
+            .is_none());
+        assert_eq!(op["x-grpc-streaming"]["serverStreaming"], true);
+        assert_eq!(op["x-grpc-streaming"]["clientStreaming"], false);
+        // Request is still unary (only the response streams).
+        assert!(
+            op["requestBody"]["content"]["application/json"]["schema"].is_object()
+        );
+    }
+
+    #[test]
+    fn emits_security_schemes_and_per_method_overrides() {
+        let descriptor = test_descriptor();
+        let sources = vec![DescriptorSource::new(
+            &descriptor,
+            vec![ServiceConfig::new("TestService", "/prpc")
+                .with_security(["bearerAuth"])
+                .with_method_security("Ping", Vec::<&str>::new())],
+        )];
+        let info = DocumentInfo::new("Test API", "1.0.0").with_security_scheme(
+            "bearerAuth",
+            SecurityScheme::Bearer {
+                bearer_format: Some("JWT".into()),
+            },
+        );
+        let json = generate_document(&sources, &info).expect("spec");
+        let doc: Value = serde_json::from_str(&json).expect("valid json");
+        assert_eq!(
+            doc["components"]["securitySchemes"]["bearerAuth"]["type"],
+            "http"
+        );
+        assert_eq!(
+            doc["components"]["securitySchemes"]["bearerAuth"]["bearerFormat"],
+            "JWT"
+        );
+        // Ping has an empty method-security override, so it should require no auth even though
+        // the service otherwise requires bearerAuth.
+        assert_eq!(
+            doc["paths"]["/prpc/Ping"]["post"]["security"],
+            Value::Array(Vec::new())
+        );
+    }
+
+    #[test]
+    fn field_schema_keys_use_json_name_by_default() {
+        let request = DescriptorProto {
+            name: Some("EchoRequest".into()),
+            field: vec![
+                FieldDescriptorProto {
+                    name: Some("echo_message".into()),
+                    json_name: Some("echoMessage".into()),
+                    number: Some(1),
+                    label: Some(FieldLabel::Optional as i32),
+                    r#type: Some(FieldType::String as i32),
+                    ..Default::default()
+                },
+                FieldDescriptorProto {
+                    name: Some("retry_count".into()),
+                    number: Some(2),
+                    label: Some(FieldLabel::Optional as i32),
+                    r#type: Some(FieldType::Int32 as i32),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let response = DescriptorProto {
+            name: Some("EchoResponse".into()),
+            ..Default::default()
+        };
+        let service = ServiceDescriptorProto {
+            name: Some("EchoService".into()),
+            method: vec![MethodDescriptorProto {
+                name: Some("Echo".into()),
+                input_type: Some(".test.EchoRequest".into()),
+                output_type: Some(".test.EchoResponse".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("echo.proto".into()),
+            package: Some("test".into()),
+            message_type: vec![request, response],
+            service: vec![service],
+            ..Default::default()
+        };
+        let set = FileDescriptorSet { file: vec![file] };
+        let mut descriptor = Vec::new();
+        set.encode(&mut descriptor).unwrap();
+
+        let sources = vec![DescriptorSource::new(
+            &descriptor,
+            vec![ServiceConfig::new("EchoService", "/prpc")],
+        )];
+
+        let info = DocumentInfo::new("Test API", "1.0.0");
+        let json = generate_document(&sources, &info).expect("spec");
+        let doc: Value = serde_json::from_str(&json).expect("valid json");
+        let schema = &doc["components"]["schemas"]["test.EchoRequest"];
+        assert!(schema["properties"]["echoMessage"].is_object());
+        assert!(schema["properties"]["retryCount"].is_object());
+        assert!(schema["properties"]["echo_message"].is_null());
+
+        let proto_names_info = DocumentInfo::new("Test API", "1.0.0").with_proto_field_names();
+        let json = generate_document(&sources, &proto_names_info).expect("spec");
+        let doc: Value = serde_json::from_str(&json).expect("valid json");
+        let schema = &doc["components"]["schemas"]["test.EchoRequest"];
+        assert!(schema["properties"]["echo_message"].is_object());
+        assert!(schema["properties"]["retry_count"].is_object());
+        assert!(schema["properties"]["echoMessage"].is_null());
+    }
+
+    #[test]
+    fn real_oneof_fields_emit_oneof_and_synthetic_oneof_stays_a_plain_field() {
+        let request = DescriptorProto {
+            name: Some("ChoiceRequest".into()),
+            field: vec![
+                FieldDescriptorProto {
+                    name: Some("by_id".into()),
+                    number: Some(1),
+                    label: Some(FieldLabel::Optional as i32),
+                    r#type: Some(FieldType::String as i32),
+                    oneof_index: Some(0),
+                    ..Default::default()
+                },
+                FieldDescriptorProto {
+                    name: Some("by_name".into()),
+                    number: Some(2),
+                    label: Some(FieldLabel::Optional as i32),
+                    r#type: Some(FieldType::String as i32),
+                    oneof_index: Some(0),
+                    ..Default::default()
+                },
+                FieldDescriptorProto {
+                    name: Some("note".into()),
+                    number: Some(3),
+                    label: Some(FieldLabel::Optional as i32),
+                    r#type: Some(FieldType::String as i32),
+                    proto3_optional: Some(true),
+                    oneof_index: Some(1),
+                    ..Default::default()
+                },
+            ],
+            oneof_decl: vec![
+                OneofDescriptorProto {
+                    name: Some("selector".into()),
+                    ..Default::default()
+                },
+                OneofDescriptorProto {
+                    name: Some("_note".into()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let response = DescriptorProto {
+            name: Some("ChoiceResponse".into()),
+            ..Default::default()
+        };
+        let service = ServiceDescriptorProto {
+            name: Some("ChoiceService".into()),
+            method: vec![MethodDescriptorProto {
+                name: Some("Choose".into()),
+                input_type: Some(".test.ChoiceRequest".into()),
+                output_type: Some(".test.ChoiceResponse".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("choice.proto".into()),
+            package: Some("test".into()),
+            message_type: vec![request, response],
+            service: vec![service],
+            ..Default::default()
+        };
+        let set = FileDescriptorSet { file: vec![file] };
+        let mut descriptor = Vec::new();
+        set.encode(&mut descriptor).unwrap();
+
+        let sources = vec![DescriptorSource::new(
+            &descriptor,
+            vec![ServiceConfig::new("ChoiceService", "/prpc")],
+        )];
+        let info = DocumentInfo::new("Test API", "1.0.0");
+        let json = generate_document(&sources, &info).expect("spec");
+        let doc: Value = serde_json::from_str(&json).expect("valid json");
+        let schema = &doc["components"]["schemas"]["test.ChoiceRequest"];
+
+        // All three fields are still ordinary properties, including the synthetic-oneof `note`.
+        assert!(schema["properties"]["byId"].is_object());
+        assert!(schema["properties"]["byName"].is_object());
+        assert!(schema["properties"]["note"].is_object());
+
+        let one_of = schema["oneOf"].as_array().expect("oneOf array for the real oneof");
+        assert_eq!(one_of.len(), 2);
+        assert!(one_of.contains(&json!({"required": ["byId"]})));
+        assert!(one_of.contains(&json!({"required": ["byName"]})));
+    }
+
+    fn status_enum_descriptor() -> EnumDescriptorProto {
+        EnumDescriptorProto {
+            name: Some("Status".into()),
+            value: vec![
+                EnumValueDescriptorProto {
+                    name: Some("STATUS_UNKNOWN".into()),
+                    number: Some(0),
+                    ..Default::default()
+                },
+                EnumValueDescriptorProto {
+                    name: Some("STATUS_OK".into()),
+                    number: Some(1),
+                    ..Default::default()
+                },
+                EnumValueDescriptorProto {
+                    name: Some("STATUS_ALIAS_OK".into()),
+                    number: Some(1),
+                    ..Default::default()
+                },
+            ],
+            options: Some(EnumOptions {
+                allow_alias: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn enum_document(rich: bool) -> Value {
+        let request = DescriptorProto {
+            name: Some("StatusRequest".into()),
+            field: vec![FieldDescriptorProto {
+                name: Some("status".into()),
+                number: Some(1),
+                label: Some(FieldLabel::Optional as i32),
+                r#type: Some(FieldType::Enum as i32),
+                type_name: Some(".test.Status".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let response = DescriptorProto {
+            name: Some("StatusResponse".into()),
+            ..Default::default()
+        };
+        let service = ServiceDescriptorProto {
+            name: Some("StatusService".into()),
+            method: vec![MethodDescriptorProto {
+                name: Some("GetStatus".into()),
+                input_type: Some(".test.StatusRequest".into()),
+                output_type: Some(".test.StatusResponse".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("status.proto".into()),
+            package: Some("test".into()),
+            message_type: vec![request, response],
+            enum_type: vec![status_enum_descriptor()],
+            service: vec![service],
+            ..Default::default()
+        };
+        let set = FileDescriptorSet { file: vec![file] };
+        let mut descriptor = Vec::new();
+        set.encode(&mut descriptor).unwrap();
+
+        let sources = vec![DescriptorSource::new(
+            &descriptor,
+            vec![ServiceConfig::new("StatusService", "/prpc")],
+        )];
+        let info = if rich {
+            DocumentInfo::new("Test API", "1.0.0").with_rich_enum_schemas()
+        } else {
+            DocumentInfo::new("Test API", "1.0.0")
+        };
+        let json = generate_document(&sources, &info).expect("spec");
+        serde_json::from_str(&json).expect("valid json")
+    }
+
+    #[test]
+    fn enum_schema_defaults_to_a_plain_string_enum() {
+        let doc = enum_document(false);
+        let schema = &doc["components"]["schemas"]["test.Status"];
+        assert_eq!(schema["type"], "string");
+        assert_eq!(
+            schema["enum"],
+            json!(["STATUS_UNKNOWN", "STATUS_OK", "STATUS_ALIAS_OK"])
+        );
+        assert!(schema.get("anyOf").is_none());
+    }
+
+    #[test]
+    fn rich_enum_schemas_add_integer_form_aliases_and_default() {
+        let doc = enum_document(true);
+        let schema = &doc["components"]["schemas"]["test.Status"];
+        let variants = schema["anyOf"].as_array().expect("anyOf array");
+        assert_eq!(variants.len(), 2);
+
+        let string_form = &variants[0];
+        assert_eq!(string_form["type"], "string");
+        assert_eq!(
+            string_form["enum"],
+            json!(["STATUS_UNKNOWN", "STATUS_OK", "STATUS_ALIAS_OK"])
+        );
+
+        let int_form = &variants[1];
+        assert_eq!(int_form["type"], "integer");
+        assert_eq!(int_form["enum"], json!([0, 1]));
+        assert!(int_form["description"]
+            .as_str()
+            .expect("int form description")
+            .contains("STATUS_OK / STATUS_ALIAS_OK"));
+
+        assert_eq!(schema["default"], "STATUS_UNKNOWN");
+    }
+
+    #[test]
+    fn swagger_ui_html_includes_persist_authorization() {
+        let html = build_swagger_ui_html("/openapi.json", &SwaggerUiConfig::default());
+        assert!(html.contains("persistAuthorization: true"));
+    }
+
+    #[test]
+    fn parse_path_template_collapses_wildcard_captures() {
+        let (path, fields) = parse_path_template("/v1/{name=shelves/*}/books/{book_id}");
+        assert_eq!(path, "/v1/{name}/books/{book_id}");
+        assert_eq!(fields, vec!["name".to_string(), "book_id".to_string()]);
+    }
+
+    #[test]
+    fn parse_http_rule_reads_verb_path_and_body_from_raw_bytes() {
+        // Hand-encoded `HttpRule` bytes, since `prost_types::MethodOptions` has no field for the
+        // `google.api.http` extension and can't produce them via a typed `encode()` call.
+        let mut buf = Vec::new();
+        buf.push(0x12); // field 2 (get), wire type 2 (length-delimited)
+        let path = b"/v1/{name}";
+        buf.push(path.len() as u8);
+        buf.extend_from_slice(path);
+        buf.push(0x3A); // field 7 (body), wire type 2
+        buf.push(1);
+        buf.push(b'*');
+
+        let rule = parse_http_rule(&buf).expect("http rule");
+        assert_eq!(rule.verb, HttpVerb::Get);
+        assert_eq!(rule.path, "/v1/{name}");
+        assert_eq!(rule.body.as_deref(), Some("*"));
+    }
+
+    #[test]
+    fn parse_http_rule_returns_none_without_a_verb() {
+        // Only a `body` selector, no `get`/`put`/`post`/`delete`/`patch` path template.
+        let buf = vec![0x3A, 1, b'*'];
+        assert!(parse_http_rule(&buf).is_none());
+    }
+
+    #[test]
+    fn parse_http_rule_collects_additional_bindings() {
+        let mut inner = Vec::new();
+        inner.push(0x12); // field 2 (get), wire type 2
+        let inner_path = b"/v1/legacy/{name}";
+        inner.push(inner_path.len() as u8);
+        inner.extend_from_slice(inner_path);
+
+        let mut buf = Vec::new();
+        buf.push(0x22); // field 4 (post), wire type 2
+        let path = b"/v1/items";
+        buf.push(path.len() as u8);
+        buf.extend_from_slice(path);
+        buf.push(0x5A); // field 11 (additional_bindings), wire type 2
+        buf.push(inner.len() as u8);
+        buf.extend_from_slice(&inner);
+
+        let rule = parse_http_rule(&buf).expect("http rule");
+        assert_eq!(rule.verb, HttpVerb::Post);
+        assert_eq!(rule.additional_bindings.len(), 1);
+        assert_eq!(rule.additional_bindings[0].verb, HttpVerb::Get);
+        assert_eq!(rule.additional_bindings[0].path, "/v1/legacy/{name}");
+    }
+
+    #[test]
+    fn protobuf_content_type_is_opt_in() {
+        let descriptor = test_descriptor();
+        let sources = vec![DescriptorSource::new(
+            &descriptor,
+            vec![ServiceConfig::new("TestService", "/prpc").with_protobuf_content_type()],
+        )];
+        let info = DocumentInfo::new("Test API", "1.0.0");
+        let json = generate_document(&sources, &info).expect("spec");
+        let doc: Value = serde_json::from_str(&json).expect("valid json");
+        let request_body = &doc["paths"]["/prpc/Ping"]["post"]["requestBody"]["content"];
+        assert!(request_body["application/json"].is_object());
+        assert_eq!(
+            request_body["application/x-protobuf"]["x-proto-message"],
+            "test.PingRequest"
+        );
+        let ok_response = &doc["paths"]["/prpc/Ping"]["post"]["responses"]["200"]["content"];
+        assert_eq!(
+            ok_response["application/x-protobuf"]["x-proto-message"],
+            "test.PingResponse"
+        );
+        // The 400 RpcError response isn't backed by a real protobuf message in this bridge, so
+        // it stays JSON-only even when protobuf content negotiation is enabled.
+        assert!(doc["paths"]["/prpc/Ping"]["post"]["responses"]["400"]["content"]
+            ["application/x-protobuf"]
+            .is_null());
+    }
+
+    #[test]
+    fn builtin_type_schema_covers_well_known_types() {
+        assert_eq!(
+            builtin_type_schema(".google.protobuf.Timestamp"),
+            Some(json!({"type": "string", "format": "date-time"}))
+        );
+        let field_mask = builtin_type_schema(".google.protobuf.FieldMask").expect("FieldMask schema");
+        assert_eq!(field_mask["type"], "string");
+        assert!(field_mask["description"].is_string());
+
+        let strct = builtin_type_schema(".google.protobuf.Struct").expect("Struct schema");
+        assert_eq!(strct["type"], "object");
+        assert!(strct["description"].is_string());
+
+        let value = builtin_type_schema(".google.protobuf.Value").expect("Value schema");
+        assert_eq!(value.get("type"), None);
+        assert!(value["description"].is_string());
+
+        let list_value = builtin_type_schema(".google.protobuf.ListValue").expect("ListValue schema");
+        assert_eq!(list_value["type"], "array");
+        assert_eq!(list_value["items"], json!({}));
+        assert!(list_value["description"].is_string());
+
+        let any_schema = builtin_type_schema(".google.protobuf.Any").expect("Any schema");
+        assert_eq!(any_schema["required"], json!(["@type"]));
+        assert_eq!(any_schema["properties"]["@type"]["type"], "string");
+    }
+
+    #[test]
+    fn wrapper_types_are_nullable_primitives_not_objects() {
+        let schema =
+            builtin_type_schema(".google.protobuf.StringValue").expect("StringValue schema");
+        assert_eq!(schema, json!({"type": ["string", "null"]}));
+    }
+
+    #[test]
+    fn find_all_length_delimited_collects_repeated_fields_in_order() {
+        let mut buf = Vec::new();
+        for word in ["one", "two"] {
+            buf.push(0x0A); // field 1, wire type 2
+            buf.push(word.len() as u8);
+            buf.extend_from_slice(word.as_bytes());
+        }
+        let found = find_all_length_delimited(&buf, 1);
+        assert_eq!(found, vec![b"one".as_slice(), b"two".as_slice()]);
+        assert_eq!(find_length_delimited(&buf, 1), Some(b"two".as_slice()));
+    }
+
+    #[test]
+    fn parse_numeric_rules_reads_bounds() {
+        let mut buf = Vec::new();
+        buf.push(0x20); // field 4 (gt), varint
+        buf.push(5);
+        buf.push(0x18); // field 3 (lte), varint
+        buf.push(42);
+        let rules = parse_numeric_rules(&buf).expect("numeric rules");
+        assert_eq!(rules.gt, Some(5));
+        assert_eq!(rules.lte, Some(42));
+        assert_eq!(rules.lt, None);
+        assert_eq!(rules.gte, None);
+    }
+
+    #[test]
+    fn parse_string_rules_reads_lengths_pattern_and_in_values() {
+        let mut buf = Vec::new();
+        buf.push(0x10); // field 2 (min_len), varint
+        buf.push(3);
+        buf.push(0x18); // field 3 (max_len), varint
+        buf.push(20);
+        buf.push(0x32); // field 6 (pattern), length-delimited
+        let pattern = b"^a+$";
+        buf.push(pattern.len() as u8);
+        buf.extend_from_slice(pattern);
+        buf.push(0x52); // field 10 (in), length-delimited
+        buf.push(2);
+        buf.extend_from_slice(b"ok");
+        let rules = parse_string_rules(&buf).expect("string rules");
+        assert_eq!(rules.min_len, Some(3));
+        assert_eq!(rules.max_len, Some(20));
+        assert_eq!(rules.pattern.as_deref(), Some("^a+$"));
+        assert_eq!(rules.in_values, vec!["ok".to_string()]);
+    }
+
+    #[test]
+    fn parse_repeated_rules_reads_min_and_max_items() {
+        let buf = vec![0x08, 1, 0x10, 5]; // field 1 (min_items)=1, field 2 (max_items)=5
+        let rules = parse_repeated_rules(&buf).expect("repeated rules");
+        assert_eq!(rules.min_items, Some(1));
+        assert_eq!(rules.max_items, Some(5));
+    }
+
+    #[test]
+    fn apply_scalar_rules_translates_to_json_schema_keywords() {
+        let mut schema = json!({"type": "integer"});
+        let rules = FieldRules {
+            numeric: Some(NumericRules {
+                lt: None,
+                lte: Some(10),
+                gt: Some(0),
+                gte: None,
+            }),
+            ..Default::default()
+        };
+        apply_scalar_rules(&mut schema, &rules);
+        assert_eq!(schema["exclusiveMinimum"], 0);
+        assert_eq!(schema["maximum"], 10);
+    }
+
+    #[test]
+    fn apply_repeated_rules_translates_min_and_max_items() {
+        let mut schema = json!({"type": "array", "items": {"type": "string"}});
+        let rules = FieldRules {
+            repeated: Some(RepeatedRules {
+                min_items: Some(1),
+                max_items: Some(5),
+            }),
+            ..Default::default()
+        };
+        apply_repeated_rules(&mut schema, &rules);
+        assert_eq!(schema["minItems"], 1);
+        assert_eq!(schema["maxItems"], 5);
+    }
 }
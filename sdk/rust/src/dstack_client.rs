@@ -8,9 +8,13 @@ use anyhow::Result;
 use hex::encode as hex_encode;
 use http_client_unix_domain_socket::{ClientUnix, Method};
 use reqwest::Client;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::Mutex as AsyncMutex;
 
 pub use dstack_sdk_types::dstack::*;
 
@@ -29,10 +33,52 @@ struct VerifyRequest<'a> {
     public_key: String,
 }
 
+/// Errors surfaced by protocol/capability negotiation
+#[derive(Debug, Error)]
+pub enum DstackClientError {
+    /// The connected guest agent did not advertise support for a method this client needs
+    #[error("method `{0}` is not supported by the connected guest agent (version {1})")]
+    UnsupportedByServer(String, String),
+}
+
+/// The set of methods and protocol version advertised by a guest agent, learned via `connect()`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerCapabilities {
+    /// Protocol version reported by the guest agent, e.g. "1.1.0"
+    #[serde(default)]
+    pub version: String,
+    /// Method/endpoint names the guest agent advertises support for
+    #[serde(default)]
+    pub methods: Vec<String>,
+}
+
+impl ServerCapabilities {
+    /// Agents predating the `/Version` handshake endpoint: assume the legacy method set
+    fn legacy() -> Self {
+        Self {
+            version: "0.0.0".to_string(),
+            methods: vec![
+                "GetKey".to_string(),
+                "GetQuote".to_string(),
+                "Info".to_string(),
+                "EmitEvent".to_string(),
+            ],
+        }
+    }
+
+    fn supports(&self, method: &str) -> bool {
+        self.methods.iter().any(|m| m == method)
+    }
+}
+
 fn get_endpoint(endpoint: Option<&str>) -> String {
     if let Some(e) = endpoint {
         return e.to_string();
     }
+    #[cfg(windows)]
+    if let Ok(pipe_endpoint) = env::var("TAPPD_PIPE") {
+        return pipe_endpoint;
+    }
     if let Ok(sim_endpoint) = env::var("DSTACK_SIMULATOR_ENDPOINT") {
         return sim_endpoint;
     }
@@ -43,6 +89,81 @@ fn get_endpoint(endpoint: Option<&str>) -> String {
 pub enum ClientKind {
     Http,
     Unix,
+    #[cfg(windows)]
+    NamedPipe,
+}
+
+/// Returns true if `endpoint` looks like a Windows named pipe path, e.g. `\\.\pipe\dstack`.
+#[cfg(windows)]
+fn is_named_pipe_endpoint(endpoint: &str) -> bool {
+    endpoint.starts_with(r"\\.\pipe\") || endpoint.starts_with(r"\\?\pipe\")
+}
+
+/// Sends a single framed HTTP/JSON request over a Windows named pipe and reads the response.
+///
+/// Mirrors the framing used by the Unix domain socket path so `/GetKey`, `/GetQuote`,
+/// `/Info`, `/Sign` and `/Verify` all work unchanged on Windows.
+#[cfg(windows)]
+async fn send_named_pipe_request<S: Serialize>(
+    pipe_name: &str,
+    path: &str,
+    payload: &S,
+) -> anyhow::Result<Value> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    let body = serde_json::to_vec(payload)?;
+    let request = format!(
+        "POST {path} HTTP/1.1\r\n\
+         Host: localhost\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {len}\r\n\
+         Connection: close\r\n\r\n",
+        path = path,
+        len = body.len()
+    );
+
+    let mut pipe = ClientOptions::new().open(pipe_name)?;
+    pipe.write_all(request.as_bytes()).await?;
+    pipe.write_all(&body).await?;
+
+    let mut raw = Vec::new();
+    pipe.read_to_end(&mut raw).await?;
+
+    let header_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| i + 4)
+        .ok_or_else(|| anyhow::anyhow!("malformed named pipe response: missing header terminator"))?;
+    let json_body = &raw[header_end..];
+
+    Ok(serde_json::from_slice(json_body)?)
+}
+
+/// Configures retry-with-backoff behavior for idempotent RPCs (`GetKey`, `GetQuote`, `Info`,
+/// `GetTlsKey`, `Sign`, `Verify`). `EmitEvent` and other non-idempotent calls always run with a
+/// single attempt regardless of this configuration.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Maximum number of attempts per call, including the first. `1` disables retrying.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles after each subsequent attempt, capped at `max_backoff`.
+    pub initial_backoff: Duration,
+    /// Upper bound on the backoff delay between attempts.
+    pub max_backoff: Duration,
+    /// Per-attempt timeout; an attempt that exceeds this is treated as a retryable failure.
+    pub timeout: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            timeout: Duration::from_secs(30),
+        }
+    }
 }
 
 pub trait BaseClient {}
@@ -55,6 +176,20 @@ pub struct DstackClient {
     endpoint: String,
     /// The type of client (HTTP or Unix domain socket)
     client: ClientKind,
+    /// Capabilities negotiated by `connect()`, if any
+    capabilities: Mutex<Option<ServerCapabilities>>,
+    /// Pooled HTTP client, built once and reused across calls so keep-alive connections aren't
+    /// torn down and re-established on every RPC.
+    http_client: Client,
+    /// Pooled Unix-domain-socket client, connected lazily on first use and reused after that.
+    /// Cleared on a transport failure so the next call reconnects instead of retrying a socket
+    /// that's gone stale (e.g. the dstack service restarted).
+    unix_client: AsyncMutex<Option<ClientUnix>>,
+    /// Retry/backoff behavior applied to idempotent RPCs; see [`RetryConfig`].
+    retry_config: RetryConfig,
+    /// When set (via `with_transport`), RPCs are dispatched here instead of over the network
+    #[cfg(feature = "mock")]
+    transport: Option<std::sync::Arc<dyn crate::mock::Transport>>,
 }
 
 impl BaseClient for DstackClient {}
@@ -66,6 +201,10 @@ impl DstackClient {
             ref e if e.starts_with("http://") || e.starts_with("https://") => {
                 (e.to_string(), ClientKind::Http)
             }
+            #[cfg(windows)]
+            ref e if is_named_pipe_endpoint(e) => {
+                ("http://localhost".to_string(), ClientKind::NamedPipe)
+            }
             _ => ("http://localhost".to_string(), ClientKind::Unix),
         };
 
@@ -73,23 +212,148 @@ impl DstackClient {
             base_url,
             endpoint,
             client,
+            capabilities: Mutex::new(None),
+            http_client: Client::new(),
+            unix_client: AsyncMutex::new(None),
+            retry_config: RetryConfig::default(),
+            #[cfg(feature = "mock")]
+            transport: None,
+        }
+    }
+
+    /// Overrides the default retry/backoff behavior applied to idempotent RPCs.
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = retry_config;
+        self
+    }
+
+    /// Builds a client that dispatches every RPC to `transport` instead of a Unix socket or
+    /// HTTP connection. Pair with [`crate::mock::MockTransport`] to unit-test code that embeds
+    /// this SDK without a live guest agent.
+    #[cfg(feature = "mock")]
+    pub fn with_transport(transport: std::sync::Arc<dyn crate::mock::Transport>) -> Self {
+        DstackClient {
+            base_url: "http://localhost".to_string(),
+            endpoint: String::new(),
+            client: ClientKind::Unix,
+            capabilities: Mutex::new(None),
+            http_client: Client::new(),
+            unix_client: AsyncMutex::new(None),
+            retry_config: RetryConfig::default(),
+            transport: Some(transport),
         }
     }
 
+    /// Negotiates the protocol version and capability set with the connected guest agent.
+    ///
+    /// Calls the lightweight `/Version` handshake endpoint. Agents that predate this endpoint
+    /// (a 404/400 response) are treated as speaking the legacy `Tappd.*`/flat-path method set.
+    /// The negotiated capabilities are cached on the client for `version()`/`supported()`.
+    pub async fn connect(&self) -> Result<ServerCapabilities> {
+        let caps = match self
+            .send_rpc_request::<_, ServerCapabilities>("/Version", &json!({}), false)
+            .await
+        {
+            Ok(caps) => caps,
+            Err(_) => ServerCapabilities::legacy(),
+        };
+        *self.capabilities.lock().unwrap() = Some(caps.clone());
+        Ok(caps)
+    }
+
+    /// The protocol version negotiated by `connect()`, or `None` if `connect()` hasn't run yet.
+    pub fn version(&self) -> Option<String> {
+        self.capabilities
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|c| c.version.clone())
+    }
+
+    /// Whether the negotiated capability set advertises `method`.
+    ///
+    /// Returns `true` if `connect()` has not been called, so callers that never negotiate
+    /// retain today's behavior of calling through directly.
+    pub fn supported(&self, method: &str) -> bool {
+        match self.capabilities.lock().unwrap().as_ref() {
+            Some(caps) => caps.supports(method),
+            None => true,
+        }
+    }
+
+    /// Returns an `UnsupportedByServer` error if `connect()` was called and the negotiated
+    /// capability set does not advertise `method`.
+    fn require_supported(&self, method: &str) -> Result<()> {
+        if self.supported(method) {
+            return Ok(());
+        }
+        let version = self.version().unwrap_or_default();
+        Err(DstackClientError::UnsupportedByServer(method.to_string(), version).into())
+    }
+
+    /// Sends an RPC, retrying with exponential backoff when `idempotent` is `true` and the
+    /// failure looks transient (a connection reset/timeout, or a 5xx response). `EmitEvent` and
+    /// other side-effecting calls must pass `idempotent: false` so they run at most once.
     async fn send_rpc_request<S: Serialize, D: DeserializeOwned>(
         &self,
         path: &str,
         payload: &S,
+        idempotent: bool,
+    ) -> anyhow::Result<D> {
+        #[cfg(feature = "mock")]
+        if let Some(transport) = &self.transport {
+            let body = transport.send(path, serde_json::to_value(payload)?).await?;
+            return Ok(serde_json::from_value(body)?);
+        }
+
+        let max_attempts = if idempotent {
+            self.retry_config.max_attempts.max(1)
+        } else {
+            1
+        };
+        let mut backoff = self.retry_config.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result = match tokio::time::timeout(
+                self.retry_config.timeout,
+                self.send_rpc_request_once(path, payload),
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(_) => Err(anyhow::anyhow!(
+                    "RPC {path} timed out after {:?}",
+                    self.retry_config.timeout
+                )),
+            };
+
+            match result {
+                Ok(value) => return Ok(value),
+                Err(err) if attempt < max_attempts && Self::is_retryable(&err) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.retry_config.max_backoff);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// A single attempt at dispatching `path`, with no retry/backoff applied.
+    async fn send_rpc_request_once<S: Serialize, D: DeserializeOwned>(
+        &self,
+        path: &str,
+        payload: &S,
     ) -> anyhow::Result<D> {
         match &self.client {
             ClientKind::Http => {
-                let client = Client::new();
                 let url = format!(
                     "{}/{}",
                     self.base_url.trim_end_matches('/'),
                     path.trim_start_matches('/')
                 );
-                let res = client
+                let res = self
+                    .http_client
                     .post(&url)
                     .json(payload)
                     .header("Content-Type", "application/json")
@@ -99,20 +363,53 @@ impl DstackClient {
                 Ok(res.json().await?)
             }
             ClientKind::Unix => {
-                let mut unix_client = ClientUnix::try_new(&self.endpoint).await?;
-                let res = unix_client
+                let mut guard = self.unix_client.lock().await;
+                if guard.is_none() {
+                    *guard = Some(ClientUnix::try_new(&self.endpoint).await?);
+                }
+                let unix_client = guard.as_mut().expect("populated above");
+                match unix_client
                     .send_request_json::<_, _, Value>(
                         path,
                         Method::POST,
                         &[("Content-Type", "application/json")],
                         Some(&payload),
                     )
-                    .await?;
-                Ok(res.1)
+                    .await
+                {
+                    Ok(res) => Ok(serde_json::from_value(res.1)?),
+                    Err(err) => {
+                        // The socket may be dead (e.g. dstack restarted); drop it so the next
+                        // attempt reconnects instead of repeatedly failing on it.
+                        *guard = None;
+                        Err(err.into())
+                    }
+                }
+            }
+            #[cfg(windows)]
+            ClientKind::NamedPipe => {
+                let body: Value = send_named_pipe_request(&self.endpoint, path, payload).await?;
+                Ok(serde_json::from_value(body)?)
             }
         }
     }
 
+    /// Whether a failed attempt looks transient enough to retry: a reqwest connect/timeout
+    /// error, or a 5xx response, but never a 4xx. The Unix-domain-socket and named-pipe
+    /// transports don't expose a typed HTTP status the way reqwest does, so any failure there
+    /// (most commonly a connection reset while dstack is restarting) is treated as transient.
+    fn is_retryable(err: &anyhow::Error) -> bool {
+        if let Some(reqwest_err) = err.downcast_ref::<reqwest::Error>() {
+            return reqwest_err.is_connect()
+                || reqwest_err.is_timeout()
+                || reqwest_err
+                    .status()
+                    .map(|status| status.is_server_error())
+                    .unwrap_or(true);
+        }
+        true
+    }
+
     pub async fn get_key(
         &self,
         path: Option<String>,
@@ -123,7 +420,7 @@ impl DstackClient {
             "purpose": purpose.unwrap_or_default(),
             "algorithm": "secp256k1", // Default or specify as needed
         });
-        let response = self.send_rpc_request("/GetKey", &data).await?;
+        let response = self.send_rpc_request("/GetKey", &data, true).await?;
         let response = serde_json::from_value::<GetKeyResponse>(response)?;
 
         Ok(response)
@@ -135,14 +432,14 @@ impl DstackClient {
         }
         let hex_data = hex_encode(report_data);
         let data = json!({ "report_data": hex_data });
-        let response = self.send_rpc_request("/GetQuote", &data).await?;
+        let response = self.send_rpc_request("/GetQuote", &data, true).await?;
         let response = serde_json::from_value::<GetQuoteResponse>(response)?;
 
         Ok(response)
     }
 
     pub async fn info(&self) -> Result<InfoResponse> {
-        let response = self.send_rpc_request("/Info", &json!({})).await?;
+        let response = self.send_rpc_request("/Info", &json!({}), true).await?;
         Ok(InfoResponse::validated_from_value(response)?)
     }
 
@@ -152,12 +449,14 @@ impl DstackClient {
         }
         let hex_payload = hex_encode(payload);
         let data = json!({ "event": event, "payload": hex_payload });
-        self.send_rpc_request::<_, ()>("/EmitEvent", &data).await?;
+        self.send_rpc_request::<_, ()>("/EmitEvent", &data, false)
+            .await?;
         Ok(())
     }
 
     pub async fn get_tls_key(&self, tls_key_config: TlsKeyConfig) -> Result<GetTlsKeyResponse> {
-        let response = self.send_rpc_request("/GetTlsKey", &tls_key_config).await?;
+        self.require_supported("GetTlsKey")?;
+        let response = self.send_rpc_request("/GetTlsKey", &tls_key_config, true).await?;
         let response = serde_json::from_value::<GetTlsKeyResponse>(response)?;
 
         Ok(response)
@@ -165,11 +464,12 @@ impl DstackClient {
 
     /// Signs a payload using a derived key.
     pub async fn sign(&self, algorithm: &str, data: Vec<u8>) -> Result<SignResponse> {
+        self.require_supported("Sign")?;
         let payload = SignRequest {
             algorithm,
             data: hex_encode(data),
         };
-        let response = self.send_rpc_request("/Sign", &payload).await?;
+        let response = self.send_rpc_request("/Sign", &payload, true).await?;
         let response = serde_json::from_value::<SignResponse>(response)?;
         Ok(response)
     }
@@ -182,13 +482,14 @@ impl DstackClient {
         signature: Vec<u8>,
         public_key: Vec<u8>,
     ) -> Result<VerifyResponse> {
+        self.require_supported("Verify")?;
         let payload = VerifyRequest {
             algorithm,
             data: hex_encode(data),
             signature: hex_encode(signature),
             public_key: hex_encode(public_key),
         };
-        let response = self.send_rpc_request("/Verify", &payload).await?;
+        let response = self.send_rpc_request("/Verify", &payload, true).await?;
         let response = serde_json::from_value::<VerifyResponse>(response)?;
         Ok(response)
     }
@@ -0,0 +1,15 @@
+// SPDX-FileCopyrightText: © 2025 Daniel Sharifi <daniel.sharifi@nearone.org>
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rust SDK for talking to the dstack/tappd guest agent.
+
+pub mod dstack_client;
+pub mod ethereum;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod quote_verifier;
+pub mod report;
+pub mod substrate;
+pub mod tappd_client;
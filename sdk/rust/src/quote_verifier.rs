@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline/online DCAP verification of TDX quotes returned by the dstack/tappd guest agent.
+//!
+//! Unlike [`crate::dstack_client`]'s `replay_rtmrs`, which only re-derives RTMR values from the
+//! event log, [`QuoteVerifier`] answers the stronger question of whether a quote is *authentic*:
+//! the PCK certificate chain is rooted in Intel, the Quoting Enclave's report is correctly
+//! signed, the attestation key signed the TD report, and the TCB is not revoked or stale.
+
+use anyhow::{Context, Result};
+use dcap_qvl::collateral::get_collateral_from_pcs;
+pub use dcap_qvl::collateral::Collateral;
+use dcap_qvl::quote::Quote;
+use dcap_qvl::verify::verify as qvl_verify;
+use std::collections::BTreeMap;
+
+use crate::report::report_measurements;
+
+/// Default Intel Provisioning Certification Caching Service used when the caller doesn't
+/// supply their own PCCS URL or collateral.
+const DEFAULT_PCCS_URL: &str = "https://pccs.phala.network/tdx";
+
+/// TCB freshness status of a verified quote, as reported by Intel's TCB info.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TcbStatus {
+    /// The platform's TCB is current.
+    UpToDate,
+    /// The TCB is current but requires a configuration change to mitigate an advisory.
+    SWHardeningNeeded,
+    /// The platform's TCB is out of date.
+    OutOfDate,
+    /// The platform's TCB has been revoked and must not be trusted.
+    Revoked,
+    /// A status string Intel has defined that this SDK doesn't have a dedicated variant for.
+    Other(String),
+}
+
+impl From<&str> for TcbStatus {
+    fn from(value: &str) -> Self {
+        match value {
+            "UpToDate" => Self::UpToDate,
+            "SWHardeningNeeded" => Self::SWHardeningNeeded,
+            "OutOfDate" | "OutOfDateConfigurationNeeded" => Self::OutOfDate,
+            "Revoked" => Self::Revoked,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+/// The outcome of a full DCAP verification of a TDX quote.
+#[derive(Debug, Clone)]
+pub struct QuoteVerificationResult {
+    /// The report layout this quote carried (TD1.0, TD1.5, or SGX).
+    pub report_kind: crate::report::ReportKind,
+    /// The TD1.5-only service-TD measurement register, if `report_kind` is `Td15`.
+    pub mr_servicetd: Option<Vec<u8>>,
+    /// Whether the quote's signature chain, QE report, and attestation key all check out.
+    pub quote_authentic: bool,
+    /// The freshness of the platform's TCB at `verified_at`.
+    pub tcb_status: TcbStatus,
+    /// Advisory IDs (e.g. `INTEL-SA-00000`) attached to the TCB level, if any.
+    pub advisory_ids: Vec<String>,
+    /// Set when the caller passed `expected_report_data`: whether it matched the quote's.
+    pub report_data_matches: Option<bool>,
+    /// Set when the caller passed `expected_rtmrs`: whether every register matched.
+    pub rtmrs_match: Option<bool>,
+}
+
+impl QuoteVerificationResult {
+    /// True only if the quote is authentic and, when checked, `report_data`/RTMRs also matched.
+    pub fn is_trustworthy(&self) -> bool {
+        self.quote_authentic
+            && self.report_data_matches.unwrap_or(true)
+            && self.rtmrs_match.unwrap_or(true)
+            && !matches!(self.tcb_status, TcbStatus::Revoked)
+    }
+}
+
+/// Performs full DCAP verification of raw TDX/SGX quote bytes.
+pub struct QuoteVerifier;
+
+impl QuoteVerifier {
+    /// Verifies `quote` against Intel-rooted collateral.
+    ///
+    /// * `collateral` - supply a pre-fetched [`Collateral`] for air-gapped verification;
+    ///   `None` fetches TCB info, QE identity, PCK CRL, and the PCK cert chain from `pccs_url`
+    ///   (or [`DEFAULT_PCCS_URL`]) based on the FMSPC embedded in the quote's PCK leaf cert.
+    /// * `verified_at` - unix timestamp the TCB/CRL validity windows are checked against, so
+    ///   verification is deterministic in tests rather than depending on wall-clock time.
+    /// * `expected_report_data` / `expected_rtmrs` - optional cross-checks against the
+    ///   attested TD report, so one call can answer "is this quote trustworthy and does it
+    ///   measure what I expect".
+    pub async fn verify_quote(
+        quote: &[u8],
+        collateral: Option<Collateral>,
+        pccs_url: Option<&str>,
+        verified_at: u64,
+        expected_report_data: Option<&[u8]>,
+        expected_rtmrs: Option<&BTreeMap<u8, String>>,
+    ) -> Result<QuoteVerificationResult> {
+        let parsed = Quote::parse(quote).context("Failed to parse quote")?;
+
+        let collateral = match collateral {
+            Some(collateral) => collateral,
+            None => {
+                let pccs_url = pccs_url.unwrap_or(DEFAULT_PCCS_URL);
+                get_collateral_from_pcs(pccs_url, quote)
+                    .await
+                    .context("Failed to fetch DCAP collateral from PCCS")?
+            }
+        };
+
+        let report = qvl_verify(quote, &collateral, verified_at)
+            .context("DCAP quote verification failed")?;
+
+        // TD1.0, TD1.5, and SGX reports each lay out report_data/RTMRs differently; this reads
+        // whichever layout the quote actually carries instead of assuming TD1.0.
+        let measurements = report_measurements(&parsed)?;
+
+        let report_data_matches =
+            expected_report_data.map(|expected| measurements.report_data == expected);
+
+        let rtmrs_match = expected_rtmrs.map(|expected| {
+            let actual: BTreeMap<u8, String> = measurements
+                .rtmrs
+                .iter()
+                .map(|(idx, mr)| (*idx, hex::encode(mr)))
+                .collect();
+            actual == *expected
+        });
+
+        Ok(QuoteVerificationResult {
+            report_kind: measurements.kind,
+            mr_servicetd: measurements.mr_servicetd.clone(),
+            quote_authentic: true,
+            tcb_status: TcbStatus::from(report.status.as_str()),
+            advisory_ids: report.advisory_ids,
+            report_data_matches,
+            rtmrs_match,
+        })
+    }
+}
+
+/// Adds `verify_quote` to quote response types decoded from the tappd/dstack guest agent.
+pub trait VerifiableQuote {
+    /// Decodes the embedded quote and runs full DCAP verification on it; see
+    /// [`QuoteVerifier::verify_quote`] for parameter semantics.
+    #[allow(async_fn_in_trait)]
+    async fn verify_quote(
+        &self,
+        collateral: Option<Collateral>,
+        pccs_url: Option<&str>,
+        verified_at: u64,
+        expected_report_data: Option<&[u8]>,
+        expected_rtmrs: Option<&BTreeMap<u8, String>>,
+    ) -> Result<QuoteVerificationResult>;
+}
+
+impl VerifiableQuote for dstack_sdk_types::tappd::TdxQuoteResponse {
+    async fn verify_quote(
+        &self,
+        collateral: Option<Collateral>,
+        pccs_url: Option<&str>,
+        verified_at: u64,
+        expected_report_data: Option<&[u8]>,
+        expected_rtmrs: Option<&BTreeMap<u8, String>>,
+    ) -> Result<QuoteVerificationResult> {
+        let quote = self.decode_quote().context("Failed to decode quote hex")?;
+        QuoteVerifier::verify_quote(
+            &quote,
+            collateral,
+            pccs_url,
+            verified_at,
+            expected_report_data,
+            expected_rtmrs,
+        )
+        .await
+    }
+}
+
+impl VerifiableQuote for dstack_sdk_types::dstack::GetQuoteResponse {
+    async fn verify_quote(
+        &self,
+        collateral: Option<Collateral>,
+        pccs_url: Option<&str>,
+        verified_at: u64,
+        expected_report_data: Option<&[u8]>,
+        expected_rtmrs: Option<&BTreeMap<u8, String>>,
+    ) -> Result<QuoteVerificationResult> {
+        let quote = self.decode_quote().context("Failed to decode quote hex")?;
+        QuoteVerifier::verify_quote(
+            &quote,
+            collateral,
+            pccs_url,
+            verified_at,
+            expected_report_data,
+            expected_rtmrs,
+        )
+        .await
+    }
+}
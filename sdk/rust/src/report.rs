@@ -0,0 +1,79 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Report-layout-agnostic accessors over a parsed DCAP quote.
+//!
+//! `dcap_qvl::quote::Quote` carries a report whose shape depends on the backend that produced
+//! it: a TDX 1.0 guest reports `TD10`, a TDX 1.5 guest (which adds `mr_servicetd` and related
+//! fields) reports `TD15`, and an SGX enclave reports `SGX`. Code that only knows about TD1.0
+//! (`report.as_td10()`) panics or silently drops data for the other two, so this module gives
+//! one typed enum that covers all three.
+
+use anyhow::{bail, Result};
+use dcap_qvl::quote::{Quote, Report};
+use std::collections::BTreeMap;
+
+/// The report layout embedded in a DCAP quote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    /// A TDX 1.0 TD report.
+    Td10,
+    /// A TDX 1.5 TD report, which additionally measures a service TD.
+    Td15,
+    /// An SGX enclave report.
+    Sgx,
+}
+
+/// The RTMR-equivalent measurement registers of a report, regardless of backend.
+///
+/// SGX reports have no RTMRs; `rtmrs` is empty in that case and callers should use
+/// `mr_enclave`/`mr_signer` instead.
+#[derive(Debug, Clone)]
+pub struct ReportMeasurements {
+    /// Which report layout these measurements were read from.
+    pub kind: ReportKind,
+    /// The report's `report_data` field (64 bytes).
+    pub report_data: Vec<u8>,
+    /// RTMR0..RTMR3 (TD10) or RTMR0..RTMR3 plus the TD1.5-only `mr_servicetd` register.
+    pub rtmrs: BTreeMap<u8, Vec<u8>>,
+    /// TD1.5's additional service-TD measurement register, if this is a TD1.5 report.
+    pub mr_servicetd: Option<Vec<u8>>,
+}
+
+/// Detects the report layout carried by a parsed quote and extracts its measurements in a
+/// backend-agnostic form.
+pub fn report_measurements(quote: &Quote) -> Result<ReportMeasurements> {
+    match &quote.report {
+        Report::TD10(r) => Ok(ReportMeasurements {
+            kind: ReportKind::Td10,
+            report_data: r.report_data.to_vec(),
+            rtmrs: BTreeMap::from([
+                (0u8, r.rt_mr0.to_vec()),
+                (1, r.rt_mr1.to_vec()),
+                (2, r.rt_mr2.to_vec()),
+                (3, r.rt_mr3.to_vec()),
+            ]),
+            mr_servicetd: None,
+        }),
+        Report::TD15(r) => Ok(ReportMeasurements {
+            kind: ReportKind::Td15,
+            report_data: r.base.report_data.to_vec(),
+            rtmrs: BTreeMap::from([
+                (0u8, r.base.rt_mr0.to_vec()),
+                (1, r.base.rt_mr1.to_vec()),
+                (2, r.base.rt_mr2.to_vec()),
+                (3, r.base.rt_mr3.to_vec()),
+            ]),
+            mr_servicetd: Some(r.mr_servicetd.to_vec()),
+        }),
+        Report::SGX(r) => Ok(ReportMeasurements {
+            kind: ReportKind::Sgx,
+            report_data: r.report_data.to_vec(),
+            rtmrs: BTreeMap::new(),
+            mr_servicetd: None,
+        }),
+        #[allow(unreachable_patterns)]
+        _ => bail!("unrecognized DCAP report layout"),
+    }
+}
@@ -0,0 +1,102 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Derives Substrate (sr25519/ed25519) keypairs and SS58 addresses from the same attested
+//! [`GetKeyResponse`] key material [`crate::ethereum::to_account`] uses for Ethereum, so a
+//! dstack app can sign extrinsics with `subxt` or any other Substrate client using its attested
+//! key.
+
+use dstack_sdk_types::dstack::GetKeyResponse;
+use sha2::{Digest, Sha256};
+use sp_core::crypto::{Pair as _, Ss58AddressFormat, Ss58Codec};
+use sp_core::{ed25519, sr25519};
+
+/// Which Substrate signature scheme to derive a keypair for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    Sr25519,
+    Ed25519,
+}
+
+/// A Substrate keypair derived by [`derive_keypair`], covering whichever [`Curve`] was
+/// requested.
+pub enum Keypair {
+    Sr25519(sr25519::Pair),
+    Ed25519(ed25519::Pair),
+}
+
+impl Keypair {
+    /// The SS58-encoded address for this keypair's public key, under `network_prefix` (e.g. `0`
+    /// for Polkadot, `42` for a generic/test chain).
+    pub fn to_ss58_address(&self, network_prefix: u16) -> String {
+        let format = Ss58AddressFormat::custom(network_prefix);
+        match self {
+            Keypair::Sr25519(pair) => pair.public().to_ss58check_with_version(format),
+            Keypair::Ed25519(pair) => pair.public().to_ss58check_with_version(format),
+        }
+    }
+}
+
+/// Derives a 32-byte seed from `get_key_response`'s raw key plus `path`, so different paths off
+/// the same attested key produce independent, deterministic keypairs. An empty `path` reuses the
+/// raw key bytes directly (matching [`crate::ethereum::to_account`], which also uses them as-is).
+fn derive_seed(get_key_response: &GetKeyResponse, path: &str) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+    let key_bytes = get_key_response.decode_key()?;
+    if path.is_empty() {
+        return Ok(key_bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| "key material is not 32 bytes")?);
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(&key_bytes);
+    hasher.update(path.as_bytes());
+    Ok(hasher.finalize().into())
+}
+
+/// Derives a Substrate `curve` keypair from `get_key_response`'s key material at `path`, the
+/// entry point future non-Ethereum chains (e.g. a Solana ed25519 signer) can reuse for the same
+/// KDF-from-sealed-key flow `to_sr25519_keypair`/`to_ed25519_keypair` build on.
+pub fn derive_keypair(
+    get_key_response: &GetKeyResponse,
+    curve: Curve,
+    path: &str,
+) -> Result<Keypair, Box<dyn std::error::Error>> {
+    let seed = derive_seed(get_key_response, path)?;
+    Ok(match curve {
+        Curve::Sr25519 => Keypair::Sr25519(sr25519::Pair::from_seed(&seed)),
+        Curve::Ed25519 => Keypair::Ed25519(ed25519::Pair::from_seed(&seed)),
+    })
+}
+
+/// Derives an sr25519 keypair directly from `get_key_response`'s key material (no path
+/// derivation), for the common case of one Substrate account per attested key.
+pub fn to_sr25519_keypair(
+    get_key_response: &GetKeyResponse,
+) -> Result<sr25519::Pair, Box<dyn std::error::Error>> {
+    match derive_keypair(get_key_response, Curve::Sr25519, "")? {
+        Keypair::Sr25519(pair) => Ok(pair),
+        Keypair::Ed25519(_) => unreachable!("Curve::Sr25519 always derives a Keypair::Sr25519"),
+    }
+}
+
+/// Derives an ed25519 keypair directly from `get_key_response`'s key material (no path
+/// derivation).
+pub fn to_ed25519_keypair(
+    get_key_response: &GetKeyResponse,
+) -> Result<ed25519::Pair, Box<dyn std::error::Error>> {
+    match derive_keypair(get_key_response, Curve::Ed25519, "")? {
+        Keypair::Ed25519(pair) => Ok(pair),
+        Keypair::Sr25519(_) => unreachable!("Curve::Ed25519 always derives a Keypair::Ed25519"),
+    }
+}
+
+/// The SS58 address for `get_key_response`'s sr25519 account under `network_prefix`.
+pub fn to_ss58_address(
+    get_key_response: &GetKeyResponse,
+    network_prefix: u16,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let pair = to_sr25519_keypair(get_key_response)?;
+    Ok(Keypair::Sr25519(pair).to_ss58_address(network_prefix))
+}
@@ -18,6 +18,10 @@ fn get_tappd_endpoint(endpoint: Option<&str>) -> String {
     if let Some(e) = endpoint {
         return e.to_string();
     }
+    #[cfg(windows)]
+    if let Ok(pipe_endpoint) = env::var("TAPPD_PIPE") {
+        return pipe_endpoint;
+    }
     if let Ok(sim_endpoint) = env::var("TAPPD_SIMULATOR_ENDPOINT") {
         return sim_endpoint;
     }
@@ -28,6 +32,55 @@ fn get_tappd_endpoint(endpoint: Option<&str>) -> String {
 pub enum TappdClientKind {
     Http,
     Unix,
+    #[cfg(windows)]
+    NamedPipe,
+}
+
+/// Returns true if `endpoint` looks like a Windows named pipe path, e.g. `\\.\pipe\tappd`.
+#[cfg(windows)]
+fn is_named_pipe_endpoint(endpoint: &str) -> bool {
+    endpoint.starts_with(r"\\.\pipe\") || endpoint.starts_with(r"\\?\pipe\")
+}
+
+/// Sends a single framed HTTP/JSON request over a Windows named pipe and reads the response.
+///
+/// This mirrors the framing used by the Unix domain socket path (`http_client_unix_domain_socket`)
+/// so the same `/prpc/...` request/response shape works unchanged on Windows.
+#[cfg(windows)]
+async fn send_named_pipe_request<S: Serialize>(
+    pipe_name: &str,
+    path: &str,
+    payload: &S,
+) -> anyhow::Result<Value> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    let body = serde_json::to_vec(payload)?;
+    let request = format!(
+        "POST {path} HTTP/1.1\r\n\
+         Host: localhost\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {len}\r\n\
+         Connection: close\r\n\r\n",
+        path = path,
+        len = body.len()
+    );
+
+    let mut pipe = ClientOptions::new().open(pipe_name)?;
+    pipe.write_all(request.as_bytes()).await?;
+    pipe.write_all(&body).await?;
+
+    let mut raw = Vec::new();
+    pipe.read_to_end(&mut raw).await?;
+
+    let header_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| i + 4)
+        .ok_or_else(|| anyhow::anyhow!("malformed named pipe response: missing header terminator"))?;
+    let json_body = &raw[header_end..];
+
+    Ok(serde_json::from_slice(json_body)?)
 }
 
 /// The main client for interacting with the legacy Tappd service
@@ -38,6 +91,9 @@ pub struct TappdClient {
     endpoint: String,
     /// The type of client (HTTP or Unix domain socket)
     client: TappdClientKind,
+    /// When set (via `with_transport`), RPCs are dispatched here instead of over the network
+    #[cfg(feature = "mock")]
+    transport: Option<std::sync::Arc<dyn crate::mock::Transport>>,
 }
 
 impl BaseClient for TappdClient {}
@@ -49,6 +105,10 @@ impl TappdClient {
             ref e if e.starts_with("http://") || e.starts_with("https://") => {
                 (e.to_string(), TappdClientKind::Http)
             }
+            #[cfg(windows)]
+            ref e if is_named_pipe_endpoint(e) => {
+                ("http://localhost".to_string(), TappdClientKind::NamedPipe)
+            }
             _ => ("http://localhost".to_string(), TappdClientKind::Unix),
         };
 
@@ -56,6 +116,21 @@ impl TappdClient {
             base_url,
             endpoint,
             client,
+            #[cfg(feature = "mock")]
+            transport: None,
+        }
+    }
+
+    /// Builds a client that dispatches every RPC to `transport` instead of a Unix socket or
+    /// HTTP connection. Pair with [`crate::mock::MockTransport`] to unit-test code that embeds
+    /// this SDK without a live tappd guest agent.
+    #[cfg(feature = "mock")]
+    pub fn with_transport(transport: std::sync::Arc<dyn crate::mock::Transport>) -> Self {
+        TappdClient {
+            base_url: "http://localhost".to_string(),
+            endpoint: String::new(),
+            client: TappdClientKind::Unix,
+            transport: Some(transport),
         }
     }
 
@@ -64,6 +139,12 @@ impl TappdClient {
         path: &str,
         payload: &S,
     ) -> anyhow::Result<D> {
+        #[cfg(feature = "mock")]
+        if let Some(transport) = &self.transport {
+            let body = transport.send(path, serde_json::to_value(payload)?).await?;
+            return Ok(serde_json::from_value(body)?);
+        }
+
         match &self.client {
             TappdClientKind::Http => {
                 let client = Client::new();
@@ -93,6 +174,11 @@ impl TappdClient {
                     .await?;
                 Ok(res.1)
             }
+            #[cfg(windows)]
+            TappdClientKind::NamedPipe => {
+                let body: Value = send_named_pipe_request(&self.endpoint, path, payload).await?;
+                Ok(serde_json::from_value(body)?)
+            }
         }
     }
 
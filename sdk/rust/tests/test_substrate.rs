@@ -0,0 +1,33 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use dstack_sdk::dstack_client::DstackClient;
+use dstack_sdk::substrate::{derive_keypair, to_sr25519_keypair, to_ss58_address, Curve};
+use dstack_sdk_types::dstack::GetKeyResponse;
+
+#[tokio::test]
+async fn test_async_to_sr25519_keypair() {
+    let client = DstackClient::new(None);
+    let result = client
+        .get_key(Some("test".to_string()), None)
+        .await
+        .expect("get_key failed");
+
+    let _: &GetKeyResponse = &result;
+    let _keypair = to_sr25519_keypair(&result).expect("to_sr25519_keypair failed");
+    let _address = to_ss58_address(&result, 42).expect("to_ss58_address failed");
+}
+
+#[tokio::test]
+async fn test_async_derive_keypair_by_path() {
+    let client = DstackClient::new(None);
+    let result = client
+        .get_key(Some("test".to_string()), None)
+        .await
+        .expect("get_key failed");
+
+    let a = derive_keypair(&result, Curve::Sr25519, "//app/1").expect("derive_keypair failed");
+    let b = derive_keypair(&result, Curve::Sr25519, "//app/2").expect("derive_keypair failed");
+    assert_ne!(a.to_ss58_address(42), b.to_ss58_address(42));
+}
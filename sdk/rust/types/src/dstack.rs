@@ -7,7 +7,7 @@ use alloc::{
     string::{String, ToString},
     vec::Vec,
 };
-use anyhow::{Context as _, Result};
+use anyhow::{bail, Context as _, Result};
 use hex::{encode as hex_encode, FromHexError};
 use serde::{Deserialize, Serialize};
 use serde_json::{from_str, Value};
@@ -36,6 +36,30 @@ fn replay_rtmr(history: Vec<String>) -> Result<String, FromHexError> {
     Ok(hex_encode(mr))
 }
 
+/// Same recurrence as [`replay_rtmr`], but keeps each event's name around so a malformed digest
+/// fails with a message naming the offending event instead of a generic "Invalid digest" blanket
+/// error, and rejects an oversized digest rather than silently hashing it unpadded.
+fn replay_rtmr_checked(history: &[(&str, &str)]) -> Result<String> {
+    if history.is_empty() {
+        return Ok(INIT_MR.to_string());
+    }
+    let mut mr = hex::decode(INIT_MR).expect("INIT_MR is a fixed, valid hex constant");
+    for (digest, event) in history {
+        let mut content_bytes = hex::decode(digest)
+            .with_context(|| alloc::format!("event `{event}` has a malformed digest (not valid hex)"))?;
+        if content_bytes.len() > 48 {
+            bail!(
+                "event `{event}` has an oversized digest: {} bytes, expected at most 48",
+                content_bytes.len()
+            );
+        }
+        content_bytes.resize(48, 0);
+        mr.extend_from_slice(&content_bytes);
+        mr = sha2::Sha384::digest(&mr).to_vec();
+    }
+    Ok(hex_encode(mr))
+}
+
 /// Represents an event log entry in the system
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
@@ -47,12 +71,82 @@ pub struct EventLog {
     pub event_type: u32,
     /// The cryptographic digest of the event
     pub digest: String,
+    /// The hash algorithm `digest` was produced with (e.g. `"sha256"`, `"sha384"`), for
+    /// self-describing event logs. `None` means the reader's own default algorithm applies (the
+    /// legacy/untagged case).
+    #[serde(default)]
+    pub digest_function: Option<String>,
     /// The type of event as a string
     pub event: String,
     /// The payload data associated with the event
     pub event_payload: String,
 }
 
+/// The replayed-vs-claimed result for a single RTMR, as produced by
+/// [`TcbInfo::verify_rtmrs`]/[`GetQuoteResponse::verify_rtmrs`].
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "borsh_schema", derive(BorshSchema))]
+pub struct RtmrVerification {
+    /// The RTMR index, 0-3
+    pub index: u8,
+    /// The value claimed in `TcbInfo`
+    pub expected: String,
+    /// The value recomputed from the event log via the replay recurrence
+    pub replayed: String,
+    /// Whether the event log contained any events for this register. `false` means `replayed`
+    /// is just the all-zero initial value, which is not the same thing as a verified match.
+    pub measured: bool,
+    /// Whether `replayed` equals `expected`
+    pub matched: bool,
+}
+
+/// A full RTMR0-3 verification report; see [`TcbInfo::verify_rtmrs`].
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "borsh_schema", derive(BorshSchema))]
+pub struct RtmrVerificationReport {
+    /// One entry per RTMR, ordered by index
+    pub registers: Vec<RtmrVerification>,
+}
+
+impl RtmrVerificationReport {
+    /// Whether every register in the report matched its claimed value.
+    pub fn all_matched(&self) -> bool {
+        self.registers.iter().all(|r| r.matched)
+    }
+}
+
+/// Recomputes RTMR0-3 from `event_log` and compares each against the corresponding field of
+/// `tcb_info`, instead of trusting the quote's claimed values outright.
+fn verify_rtmrs_against(event_log: &[EventLog], tcb_info: &TcbInfo) -> Result<RtmrVerificationReport> {
+    let mut registers = Vec::with_capacity(4);
+    for idx in 0..4u32 {
+        let history: Vec<(&str, &str)> = event_log
+            .iter()
+            .filter(|event| event.imr == idx)
+            .map(|event| (event.digest.as_str(), event.event.as_str()))
+            .collect();
+        let measured = !history.is_empty();
+        let replayed = replay_rtmr_checked(&history)
+            .with_context(|| alloc::format!("failed to replay RTMR{idx}"))?;
+        let expected = match idx {
+            0 => &tcb_info.rtmr0,
+            1 => &tcb_info.rtmr1,
+            2 => &tcb_info.rtmr2,
+            _ => &tcb_info.rtmr3,
+        };
+        registers.push(RtmrVerification {
+            index: idx as u8,
+            expected: expected.clone(),
+            matched: replayed.eq_ignore_ascii_case(expected),
+            replayed,
+            measured,
+        });
+    }
+    Ok(RtmrVerificationReport { registers })
+}
+
 /// Configuration for TLS key generation
 #[derive(Debug, bon::Builder, Serialize, Deserialize)]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
@@ -141,6 +235,15 @@ impl GetQuoteResponse {
         }
         Ok(rtmrs)
     }
+
+    /// Replays this quote's event log and checks each RTMR against `tcb_info`'s claimed value,
+    /// rather than trusting the quote blind. `tcb_info` is passed in separately since
+    /// [`GetQuoteResponse`] doesn't carry one itself — pair it with the [`InfoResponse`] or
+    /// [`TcbInfo`] fetched for the same instance.
+    pub fn verify_rtmrs(&self, tcb_info: &TcbInfo) -> Result<RtmrVerificationReport> {
+        let parsed_event_log = self.decode_event_log()?;
+        verify_rtmrs_against(&parsed_event_log, tcb_info)
+    }
 }
 
 /// Response containing instance information and attestation data
@@ -184,6 +287,12 @@ impl InfoResponse {
         }
         serde_json::from_value(obj)
     }
+
+    /// Replays `tcb_info`'s own event log and checks each RTMR against the value it claims,
+    /// rather than silently trusting it. See [`TcbInfo::verify_rtmrs`].
+    pub fn verify_rtmrs(&self) -> Result<RtmrVerificationReport> {
+        self.tcb_info.verify_rtmrs()
+    }
 }
 
 /// Trusted Computing Base information structure
@@ -201,6 +310,9 @@ pub struct TcbInfo {
     pub rtmr2: String,
     /// The value of RTMR3 (Runtime Measurement Register 3)
     pub rtmr3: String,
+    /// The TD1.5-only service-TD measurement register, empty for TD1.0/SGX guests
+    #[serde(default)]
+    pub mr_servicetd: String,
     /// The hash of the OS image. This is empty if the OS image is not measured by KMS.
     #[serde(default)]
     pub os_image_hash: String,
@@ -214,6 +326,16 @@ pub struct TcbInfo {
     pub event_log: Vec<EventLog>,
 }
 
+impl TcbInfo {
+    /// Replays RTMR0-3 from `event_log` and compares each against `rtmr0..rtmr3`, rather than
+    /// trusting the claimed values outright. Distinguishes an unmeasured register (no events for
+    /// that RTMR, so it's still the all-zero initial value) from one that was actually measured
+    /// and happens to match, via [`RtmrVerification::measured`].
+    pub fn verify_rtmrs(&self) -> Result<RtmrVerificationReport> {
+        verify_rtmrs_against(&self.event_log, self)
+    }
+}
+
 /// Response containing TLS key and certificate chain
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
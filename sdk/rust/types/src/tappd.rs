@@ -19,8 +19,6 @@ use borsh::{BorshDeserialize, BorshSerialize};
 
 use crate::dstack::EventLog;
 
-const INIT_MR: &str = "000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
-
 /// Hash algorithms supported by the TDX quote generation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
@@ -38,6 +36,10 @@ pub enum QuoteHashAlgorithm {
     Raw,
 }
 
+/// `report_data` is a fixed 64-byte field; prefixes longer than this couldn't leave any room for
+/// `content`, so [`QuoteHashAlgorithm::build_report_data`] rejects them outright.
+const MAX_REPORT_DATA_PREFIX_LEN: usize = 32;
+
 impl QuoteHashAlgorithm {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -53,22 +55,152 @@ impl QuoteHashAlgorithm {
             Self::Raw => "raw",
         }
     }
-}
 
-fn replay_rtmr(history: Vec<String>) -> Result<String, FromHexError> {
-    if history.is_empty() {
-        return Ok(INIT_MR.to_string());
+    /// Parses the [`Self::as_str`] wire form (`"sha256"`, `"sha3-256"`, `"keccak256"`, ...) back
+    /// into a `QuoteHashAlgorithm`, for event logs/responses that carry the algorithm as a string
+    /// (e.g. [`TdxQuoteResponse::hash_algorithm`], a per-entry `EventLog::digest_function`).
+    pub fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "sha256" => Self::Sha256,
+            "sha384" => Self::Sha384,
+            "sha512" => Self::Sha512,
+            "sha3-256" => Self::Sha3_256,
+            "sha3-384" => Self::Sha3_384,
+            "sha3-512" => Self::Sha3_512,
+            "keccak256" => Self::Keccak256,
+            "keccak384" => Self::Keccak384,
+            "keccak512" => Self::Keccak512,
+            "raw" => Self::Raw,
+            other => bail!("Unsupported hash algorithm: {other}"),
+        })
+    }
+
+    /// The native digest width this algorithm produces, i.e. the RTMR/PCR bank width a replay
+    /// extend with it needs. `None` for [`Self::Raw`], which has no hash function to extend with.
+    fn digest_size(&self) -> Option<usize> {
+        match self {
+            Self::Sha256 | Self::Sha3_256 | Self::Keccak256 => Some(32),
+            Self::Sha384 | Self::Sha3_384 | Self::Keccak384 => Some(48),
+            Self::Sha512 | Self::Sha3_512 | Self::Keccak512 => Some(64),
+            Self::Raw => None,
+        }
     }
-    let mut mr = hex::decode(INIT_MR)?;
-    for content in history {
+
+    /// Hashes `data` with this algorithm. Returns `None` for [`Self::Raw`], which has no hash
+    /// function.
+    fn digest(&self, data: &[u8]) -> Option<Vec<u8>> {
+        Some(match self {
+            Self::Sha256 => sha2::Sha256::digest(data).to_vec(),
+            Self::Sha384 => sha2::Sha384::digest(data).to_vec(),
+            Self::Sha512 => sha2::Sha512::digest(data).to_vec(),
+            Self::Sha3_256 => sha3::Sha3_256::digest(data).to_vec(),
+            Self::Sha3_384 => sha3::Sha3_384::digest(data).to_vec(),
+            Self::Sha3_512 => sha3::Sha3_512::digest(data).to_vec(),
+            Self::Keccak256 => sha3::Keccak256::digest(data).to_vec(),
+            Self::Keccak384 => sha3::Keccak384::digest(data).to_vec(),
+            Self::Keccak512 => sha3::Keccak512::digest(data).to_vec(),
+            Self::Raw => return None,
+        })
+    }
+
+    /// Builds the 64-byte `report_data` a quote commits to, reproducing exactly what the server
+    /// binds in so a verifier can re-derive and compare against `VerificationDetails.report_data`.
+    ///
+    /// For every variant but [`Self::Raw`], this hashes `prefix || content` with the selected
+    /// algorithm and left-aligns the digest into the 64-byte buffer, zero-padding the remainder on
+    /// the right — so a verifier re-hashing the same `prefix`/`content` agrees byte-for-byte
+    /// regardless of whether the algorithm's digest is 32, 48, or 64 bytes. [`Self::Raw`] skips
+    /// hashing entirely: `content` (which must already be ≤64 bytes) is copied verbatim.
+    pub fn build_report_data(&self, prefix: &[u8], content: &[u8]) -> Result<[u8; 64]> {
+        if prefix.len() > MAX_REPORT_DATA_PREFIX_LEN {
+            bail!(
+                "report_data prefix too long: {} bytes (max {})",
+                prefix.len(),
+                MAX_REPORT_DATA_PREFIX_LEN
+            );
+        }
+
+        let mut report_data = [0u8; 64];
+        if content.len() > 64 {
+            bail!(
+                "raw report_data content too long: {} bytes (max 64)",
+                content.len()
+            );
+        }
+        if matches!(self, Self::Raw) {
+            report_data[..content.len()].copy_from_slice(content);
+            return Ok(report_data);
+        }
+
+        let mut preimage = Vec::with_capacity(prefix.len() + content.len());
+        preimage.extend_from_slice(prefix);
+        preimage.extend_from_slice(content);
+
+        let digest = self
+            .digest(&preimage)
+            .context("Unreachable: only Self::Raw has no digest function")?;
+        report_data[..digest.len()].copy_from_slice(&digest);
+        Ok(report_data)
+    }
+}
+
+/// Replays one IMR's RTMR bank from `history`, a sequence of `(digest_hex, digest_function)`
+/// pairs in log order. An entry's own `digest_function` (if present) picks its hash algorithm;
+/// otherwise `default_algorithm` applies — the untagged/legacy case. Every entry folded into the
+/// same bank must agree on the algorithm (mixing banks mid-replay would silently produce a
+/// meaningless result), so a mismatch is an error rather than a silent fall-through.
+fn replay_rtmr(
+    history: Vec<(String, Option<String>)>,
+    default_algorithm: &QuoteHashAlgorithm,
+) -> Result<String> {
+    let mut bank_algorithm: Option<QuoteHashAlgorithm> = None;
+    let mut mr: Option<Vec<u8>> = None;
+
+    for (content, digest_function) in history {
+        let algorithm = match digest_function {
+            Some(name) => QuoteHashAlgorithm::parse(&name)?,
+            None => default_algorithm.clone(),
+        };
+        let digest_size = algorithm
+            .digest_size()
+            .with_context(|| format!("RTMR measurement algorithm has no digest: {}", algorithm.as_str()))?;
+
+        match &bank_algorithm {
+            None => bank_algorithm = Some(algorithm.clone()),
+            Some(existing) if existing.as_str() != algorithm.as_str() => bail!(
+                "Inconsistent RTMR measurement algorithms within a single IMR: {} vs {}",
+                existing.as_str(),
+                algorithm.as_str()
+            ),
+            _ => {}
+        }
+
         let mut content_bytes = hex::decode(content)?;
-        if content_bytes.len() < 48 {
-            content_bytes.resize(48, 0);
+        if content_bytes.len() < digest_size {
+            content_bytes.resize(digest_size, 0);
+        }
+
+        let mut extended = mr.unwrap_or_else(|| vec![0u8; digest_size]);
+        extended.extend_from_slice(&content_bytes);
+        mr = Some(
+            algorithm
+                .digest(&extended)
+                .context("Unreachable: digest_size already rejected Raw")?,
+        );
+    }
+
+    match mr {
+        Some(mr) => Ok(hex_encode(mr)),
+        None => {
+            let digest_size = default_algorithm.digest_size().with_context(|| {
+                format!(
+                    "RTMR measurement algorithm has no digest: {}",
+                    default_algorithm.as_str()
+                )
+            })?;
+            Ok(hex_encode(vec![0u8; digest_size]))
         }
-        mr.extend_from_slice(&content_bytes);
-        mr = sha2::Sha384::digest(&mr).to_vec();
     }
-    Ok(hex_encode(mr))
 }
 
 /// Response from a key derivation request
@@ -82,12 +214,32 @@ pub struct DeriveKeyResponse {
     pub certificate_chain: Vec<String>,
 }
 
+/// The key material [`DeriveKeyResponse::decode_key`] extracted, tagged by which PKCS#8
+/// `AlgorithmIdentifier` OID the private key was encoded under so callers don't have to guess
+/// the curve from the byte length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodedKey {
+    /// A 32-byte ECDSA P-256 private scalar (`ecPublicKey`, OID `1.2.840.10045.2.1`).
+    EcP256([u8; 32]),
+    /// A 32-byte Ed25519 seed (OID `1.3.101.112`, RFC 8410).
+    Ed25519([u8; 32]),
+    /// The raw PKCS#8 `privateKey` bytes, for any algorithm not special-cased above.
+    Raw(Vec<u8>),
+}
+
 impl DeriveKeyResponse {
-    /// Decodes the key from PEM format and extracts the raw ECDSA P-256 private key bytes
-    pub fn decode_key(&self) -> Result<Vec<u8>, anyhow::Error> {
+    /// Decodes the key from PEM format and dispatches on the PKCS#8 `PrivateKeyInfo.algorithm`
+    /// OID: ECDSA P-256 (`ecPublicKey`) unwraps the DER-encoded `ECPrivateKey` as before, and
+    /// Ed25519 unwraps the single OCTET STRING layer RFC 8410 wraps the 32-byte seed in. Any
+    /// other algorithm is returned as the raw `privateKey` bytes.
+    pub fn decode_key(&self) -> Result<DecodedKey, anyhow::Error> {
         use pkcs8::der::asn1::{Int, OctetString};
         use pkcs8::der::{Decode, Document, Reader, SliceReader};
-        use pkcs8::PrivateKeyInfo;
+        use pkcs8::{ObjectIdentifier, PrivateKeyInfo};
+
+        const EC_PUBLIC_KEY_OID: ObjectIdentifier =
+            ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+        const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
 
         let key_content = self.key.trim();
 
@@ -104,42 +256,68 @@ impl DeriveKeyResponse {
         let private_key_info = PrivateKeyInfo::from_der(doc.as_bytes())
             .map_err(|e| anyhow::anyhow!("Failed to parse PKCS#8 private key: {:?}", e))?;
 
-        // Extract the private key bytes from the PKCS#8 structure
-        // For ECDSA P-256 keys, the private key data contains a DER-encoded ECPrivateKey
-        let private_key_data = private_key_info.private_key;
-
-        // Parse the ECPrivateKey structure to extract the raw key bytes
-        // ECPrivateKey ::= SEQUENCE {
-        //   version INTEGER,
-        //   privateKey OCTET STRING,
-        //   parameters [0] EXPLICIT ECParameters OPTIONAL,
-        //   publicKey [1] EXPLICIT BIT STRING OPTIONAL
-        // }
-        let mut reader = SliceReader::new(private_key_data)
-            .map_err(|e| anyhow::anyhow!("Failed to create reader: {:?}", e))?;
-        let key_bytes = reader
-            .sequence(|reader| {
-                // Skip version (INTEGER)
-                let _version: Int = reader.decode()?;
-                // Get the private key (OCTET STRING)
-                let private_key: OctetString = reader.decode()?;
-                // Skip optional fields (parameters and publicKey)
-                // We don't need to parse them, just consume remaining data
-                while !reader.is_finished() {
-                    let _: pkcs8::der::Any = reader.decode()?;
-                }
-                Ok(private_key.as_bytes().to_vec())
-            })
-            .map_err(|e| anyhow::anyhow!("Failed to parse ECPrivateKey structure: {:?}", e))?;
+        if private_key_info.algorithm.oid == EC_PUBLIC_KEY_OID {
+            // The private key data contains a DER-encoded ECPrivateKey:
+            // ECPrivateKey ::= SEQUENCE {
+            //   version INTEGER,
+            //   privateKey OCTET STRING,
+            //   parameters [0] EXPLICIT ECParameters OPTIONAL,
+            //   publicKey [1] EXPLICIT BIT STRING OPTIONAL
+            // }
+            let mut reader = SliceReader::new(private_key_info.private_key)
+                .map_err(|e| anyhow::anyhow!("Failed to create reader: {:?}", e))?;
+            let key_bytes = reader
+                .sequence(|reader| {
+                    // Skip version (INTEGER)
+                    let _version: Int = reader.decode()?;
+                    // Get the private key (OCTET STRING)
+                    let private_key: OctetString = reader.decode()?;
+                    // Skip optional fields (parameters and publicKey)
+                    // We don't need to parse them, just consume remaining data
+                    while !reader.is_finished() {
+                        let _: pkcs8::der::Any = reader.decode()?;
+                    }
+                    Ok(private_key.as_bytes().to_vec())
+                })
+                .map_err(|e| anyhow::anyhow!("Failed to parse ECPrivateKey structure: {:?}", e))?;
 
-        if key_bytes.len() != 32 {
-            bail!(
-                "Expected 32-byte ECDSA P-256 private key, got {} bytes",
-                key_bytes.len()
-            );
+            let key_bytes: [u8; 32] = key_bytes.try_into().map_err(|bytes: Vec<u8>| {
+                anyhow::anyhow!(
+                    "Expected 32-byte ECDSA P-256 private key, got {} bytes",
+                    bytes.len()
+                )
+            })?;
+            Ok(DecodedKey::EcP256(key_bytes))
+        } else if private_key_info.algorithm.oid == ED25519_OID {
+            // RFC 8410 §7: CurvePrivateKey ::= OCTET STRING, itself stored as the contents of
+            // the outer PrivateKeyInfo.privateKey OCTET STRING — one more layer to unwrap.
+            let seed = OctetString::from_der(private_key_info.private_key)
+                .map_err(|e| anyhow::anyhow!("Failed to parse Ed25519 CurvePrivateKey: {:?}", e))?;
+            let seed_bytes: [u8; 32] = seed.as_bytes().try_into().map_err(|_| {
+                anyhow::anyhow!(
+                    "Expected 32-byte Ed25519 seed, got {} bytes",
+                    seed.as_bytes().len()
+                )
+            })?;
+            Ok(DecodedKey::Ed25519(seed_bytes))
+        } else {
+            Ok(DecodedKey::Raw(private_key_info.private_key.to_vec()))
         }
+    }
 
-        Ok(key_bytes)
+    /// Bundles the derived key together with `certificate_chain` into a PKCS#12 (`.p12`/`.pfx`)
+    /// archive, so the identity can be dropped straight into OpenSSL, a Java keystore, nginx, or
+    /// any other TLS stack that consumes `.pfx` files.
+    ///
+    /// The key is stored in a `pkcs8ShroudedKeyBag` encrypted with PBES2 (PBKDF2-HMAC-SHA256 +
+    /// AES-256-CBC) under `password`, and each certificate in the chain (leaf first) gets its own
+    /// `certBag`. The leaf cert and the key share a `localKeyId` (the SHA-256 digest of the leaf
+    /// cert's DER) so importers pair them up, and both carry `friendly_name` as their display
+    /// name. The whole `AuthenticatedSafe` is HMAC-SHA256 MAC-protected under `password`
+    /// (RFC 7292 §4), with the MAC key derived via PBKDF2-HMAC-SHA256 rather than the legacy
+    /// Appendix B KDF, matching the key encryption above.
+    pub fn to_pkcs12(&self, password: &str, friendly_name: &str) -> Result<Vec<u8>> {
+        pkcs12::build(self, password, friendly_name)
     }
 }
 
@@ -169,20 +347,28 @@ impl TdxQuoteResponse {
         serde_json::from_str(&self.event_log)
     }
 
-    /// Replays RTMR history to calculate final RTMR values
+    /// Replays RTMR history to calculate final RTMR values. Each event's own `digest_function`
+    /// picks the hash/bank width used to fold it in; an untagged event falls back to
+    /// `self.hash_algorithm` (defaulting to SHA-384, matching the original hard-coded behavior)
+    /// so existing, algorithm-less event logs replay exactly as before.
     pub fn replay_rtmrs(&self) -> Result<BTreeMap<u8, String>> {
+        let default_algorithm = match &self.hash_algorithm {
+            Some(name) => QuoteHashAlgorithm::parse(name)?,
+            None => QuoteHashAlgorithm::Sha384,
+        };
+
         let parsed_event_log: Vec<EventLog> = self.decode_event_log()?;
         let mut rtmrs = BTreeMap::new();
         for idx in 0..4 {
             let mut history = Vec::new();
             for event in &parsed_event_log {
                 if event.imr == idx {
-                    history.push(event.digest.clone());
+                    history.push((event.digest.clone(), event.digest_function.clone()));
                 }
             }
             rtmrs.insert(
                 idx as u8,
-                replay_rtmr(history)
+                replay_rtmr(history, &default_algorithm)
                     .ok()
                     .context("Invalid digest in event log")?,
             );
@@ -206,6 +392,9 @@ pub struct TappdTcbInfo {
     pub rtmr2: String,
     /// The value of RTMR3 (Runtime Measurement Register 3)
     pub rtmr3: String,
+    /// The TD1.5-only service-TD measurement register, empty for TD1.0/SGX guests
+    #[serde(default)]
+    pub mr_servicetd: String,
     /// The event log entries
     pub event_log: Vec<EventLog>,
     /// The application compose file
@@ -228,3 +417,302 @@ pub struct TappdInfoResponse {
     /// The name of the application
     pub app_name: String,
 }
+
+/// Minimal hand-rolled DER encoding for the handful of PKCS#12 (RFC 7292) structures
+/// [`DeriveKeyResponse::to_pkcs12`] needs to emit. There's no PKCS#12 crate in the dependency
+/// tree, so this builds the `PFX`/`SafeBag`/`CertBag`/`MacData` ASN.1 directly on top of the same
+/// low-level `der`/`pkcs8` primitives `decode_key` already uses to parse them.
+mod pkcs12 {
+    use alloc::{vec, vec::Vec};
+    use anyhow::{Context as _, Result};
+    use cbc::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+    use hmac::{Hmac, Mac as _};
+    use pkcs8::der::{asn1::OctetStringRef, Encode};
+    use pkcs8::ObjectIdentifier;
+    use sha2::{Digest as _, Sha256};
+
+    use super::{DecodedKey, DeriveKeyResponse};
+
+    const ID_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1");
+    const PKCS8_SHROUDED_KEY_BAG: ObjectIdentifier =
+        ObjectIdentifier::new_unwrap("1.2.840.113549.1.12.10.1.2");
+    const CERT_BAG: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.12.10.1.3");
+    const X509_CERTIFICATE: ObjectIdentifier =
+        ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.22.1");
+    const FRIENDLY_NAME: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.20");
+    const LOCAL_KEY_ID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.21");
+    const ID_PBES2: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.5.13");
+    const ID_PBKDF2: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.5.12");
+    const HMAC_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.2.9");
+    const AES256_CBC: ObjectIdentifier =
+        ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.1.42");
+    const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+
+    const PBKDF2_ITERATIONS: u32 = 100_000;
+
+    type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+
+    fn fill_random(buf: &mut [u8]) -> Result<()> {
+        getrandom::getrandom(buf).context("Failed to generate random bytes")
+    }
+
+    /// `ContextSpecific`-style `DER` wrapper: re-encodes `inner` (already DER) under an
+    /// explicit/implicit `[tag]`, matching the `[0]`/`[1]` constructs PKCS#12 uses throughout.
+    fn tagged(tag: u8, inner: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xa0 | tag];
+        write_len(&mut out, inner.len());
+        out.extend_from_slice(inner);
+        out
+    }
+
+    fn write_len(out: &mut Vec<u8>, len: usize) {
+        if len < 0x80 {
+            out.push(len as u8);
+        } else {
+            let len_bytes = len.to_be_bytes();
+            let first_nonzero = len_bytes.iter().position(|&b| b != 0).unwrap_or(7);
+            let len_bytes = &len_bytes[first_nonzero..];
+            out.push(0x80 | len_bytes.len() as u8);
+            out.extend_from_slice(len_bytes);
+        }
+    }
+
+    fn der_sequence(elements: &[&[u8]]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for element in elements {
+            body.extend_from_slice(element);
+        }
+        let mut out = vec![0x30];
+        write_len(&mut out, body.len());
+        out.extend_from_slice(&body);
+        out
+    }
+
+    fn der_set(elements: &[&[u8]]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for element in elements {
+            body.extend_from_slice(element);
+        }
+        let mut out = vec![0x31];
+        write_len(&mut out, body.len());
+        out.extend_from_slice(&body);
+        out
+    }
+
+    fn der_octet_string(bytes: &[u8]) -> Result<Vec<u8>> {
+        Ok(OctetStringRef::new(bytes)
+            .context("Failed to build OCTET STRING")?
+            .to_der()
+            .context("Failed to encode OCTET STRING")?)
+    }
+
+    fn der_integer_u32(value: u32) -> Vec<u8> {
+        let bytes = value.to_be_bytes();
+        let first_nonzero = bytes.iter().position(|&b| b != 0).unwrap_or(3);
+        let mut content = bytes[first_nonzero..].to_vec();
+        if content.is_empty() {
+            content.push(0);
+        } else if content[0] & 0x80 != 0 {
+            content.insert(0, 0);
+        }
+        let mut out = vec![0x02];
+        write_len(&mut out, content.len());
+        out.extend_from_slice(&content);
+        out
+    }
+
+    fn der_oid(oid: ObjectIdentifier) -> Result<Vec<u8>> {
+        oid.to_der().context("Failed to encode OBJECT IDENTIFIER")
+    }
+
+    /// `BMPString` (UCS-2BE), the string type PKCS#12 requires `friendlyName` attributes to use.
+    fn der_bmp_string(s: &str) -> Vec<u8> {
+        let mut content = Vec::with_capacity(s.len() * 2);
+        for unit in s.encode_utf16() {
+            content.extend_from_slice(&unit.to_be_bytes());
+        }
+        let mut out = vec![0x1e];
+        write_len(&mut out, content.len());
+        out.extend_from_slice(&content);
+        out
+    }
+
+    /// `AlgorithmIdentifier ::= SEQUENCE { algorithm OBJECT IDENTIFIER, parameters ANY OPTIONAL }`
+    fn algorithm_identifier(oid: ObjectIdentifier, params: Option<&[u8]>) -> Result<Vec<u8>> {
+        let oid_der = der_oid(oid)?;
+        match params {
+            Some(params) => Ok(der_sequence(&[&oid_der, params])),
+            None => Ok(der_sequence(&[&oid_der])),
+        }
+    }
+
+    /// `PKCS12Attribute ::= SEQUENCE { attrId OBJECT IDENTIFIER, attrValues SET OF ANY }`
+    fn attribute(oid: ObjectIdentifier, value: &[u8]) -> Result<Vec<u8>> {
+        let oid_der = der_oid(oid)?;
+        let values = der_set(&[value]);
+        Ok(der_sequence(&[&oid_der, &values]))
+    }
+
+    fn bag_attributes(friendly_name: &str, local_key_id: &[u8; 32]) -> Result<Vec<u8>> {
+        let friendly_name_attr = attribute(FRIENDLY_NAME, &der_bmp_string(friendly_name))?;
+        let local_key_id_attr = attribute(LOCAL_KEY_ID, &der_octet_string(local_key_id)?)?;
+        Ok(der_set(&[&friendly_name_attr, &local_key_id_attr]))
+    }
+
+    /// `SafeBag ::= SEQUENCE { bagId OBJECT IDENTIFIER, bagValue [0] EXPLICIT ANY, bagAttributes
+    /// SET OF PKCS12Attribute OPTIONAL }`
+    fn safe_bag(bag_id: ObjectIdentifier, bag_value: &[u8], attributes: Option<&[u8]>) -> Result<Vec<u8>> {
+        let bag_id_der = der_oid(bag_id)?;
+        let bag_value_tagged = tagged(0, bag_value);
+        match attributes {
+            Some(attributes) => Ok(der_sequence(&[&bag_id_der, &bag_value_tagged, attributes])),
+            None => Ok(der_sequence(&[&bag_id_der, &bag_value_tagged])),
+        }
+    }
+
+    /// `ContentInfo ::= SEQUENCE { contentType OBJECT IDENTIFIER, content [0] EXPLICIT ANY
+    /// OPTIONAL }`, specialised to the `data` content type every bag here uses (unencrypted,
+    /// carrying a plain OCTET STRING payload).
+    fn data_content_info(payload: &[u8]) -> Result<Vec<u8>> {
+        let content_type = der_oid(ID_DATA)?;
+        let octet_string = der_octet_string(payload)?;
+        let content = tagged(0, &octet_string);
+        Ok(der_sequence(&[&content_type, &content]))
+    }
+
+    /// Encrypts `pkcs8_der` under `password` with PBES2 (PBKDF2-HMAC-SHA256 + AES-256-CBC/PKCS7)
+    /// and wraps the result as an `EncryptedPrivateKeyInfo`, the `bagValue` of a
+    /// `pkcs8ShroudedKeyBag`.
+    fn encrypted_private_key_info(pkcs8_der: &[u8], password: &str) -> Result<Vec<u8>> {
+        let mut salt = [0u8; 16];
+        fill_random(&mut salt)?;
+        let mut iv = [0u8; 16];
+        fill_random(&mut iv)?;
+        let mut derived_key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, PBKDF2_ITERATIONS, &mut derived_key);
+
+        let ciphertext = Aes256CbcEnc::new(&derived_key.into(), &iv.into())
+            .encrypt_padded_vec_mut::<Pkcs7>(pkcs8_der);
+
+        let salt_der = der_octet_string(&salt)?;
+        let iterations_der = der_integer_u32(PBKDF2_ITERATIONS);
+        let prf = algorithm_identifier(HMAC_WITH_SHA256, None)?;
+        let pbkdf2_params = der_sequence(&[&salt_der, &iterations_der, &prf]);
+        let kdf = algorithm_identifier(ID_PBKDF2, Some(&pbkdf2_params))?;
+
+        let iv_der = der_octet_string(&iv)?;
+        let encryption_scheme = algorithm_identifier(AES256_CBC, Some(&iv_der))?;
+
+        let pbes2_params = der_sequence(&[&kdf, &encryption_scheme]);
+        let encryption_algorithm = algorithm_identifier(ID_PBES2, Some(&pbes2_params))?;
+        let encrypted_data = der_octet_string(&ciphertext)?;
+        Ok(der_sequence(&[&encryption_algorithm, &encrypted_data]))
+    }
+
+    /// Re-encodes `response.key`'s PEM as a bare PKCS#8 `PrivateKeyInfo` DER blob, the form
+    /// [`encrypted_private_key_info`] shrouds.
+    fn pkcs8_der(response: &DeriveKeyResponse) -> Result<Vec<u8>> {
+        use pkcs8::der::Document;
+
+        // Touch `decode_key` so an unparsable key surfaces as the same error it already raises
+        // for every other caller, then re-derive the raw PKCS#8 DER from the PEM for encryption.
+        let _: DecodedKey = response
+            .decode_key()
+            .context("Failed to decode derived key")?;
+        let (label, doc) = Document::from_pem(response.key.trim())
+            .map_err(|e| anyhow::anyhow!("Failed to parse PEM: {:?}", e))?;
+        if label != "PRIVATE KEY" {
+            anyhow::bail!("Expected PRIVATE KEY PEM label, got: {}", label);
+        }
+        Ok(doc.as_bytes().to_vec())
+    }
+
+    /// `CertBag ::= SEQUENCE { certId OBJECT IDENTIFIER, certValue [0] EXPLICIT OCTET STRING }`
+    fn cert_bag(cert_der: &[u8]) -> Result<Vec<u8>> {
+        let cert_id = der_oid(X509_CERTIFICATE)?;
+        let cert_octet_string = der_octet_string(cert_der)?;
+        let cert_value = tagged(0, &cert_octet_string);
+        Ok(der_sequence(&[&cert_id, &cert_value]))
+    }
+
+    fn cert_pem_to_der(pem: &str) -> Result<Vec<u8>> {
+        use pkcs8::der::Document;
+
+        let (label, doc) = Document::from_pem(pem.trim())
+            .map_err(|e| anyhow::anyhow!("Failed to parse certificate PEM: {:?}", e))?;
+        if label != "CERTIFICATE" {
+            anyhow::bail!("Expected CERTIFICATE PEM label, got: {}", label);
+        }
+        Ok(doc.as_bytes().to_vec())
+    }
+
+    /// `MacData ::= SEQUENCE { mac DigestInfo, macSalt OCTET STRING, iterations INTEGER }`, where
+    /// `DigestInfo ::= SEQUENCE { digestAlgorithm AlgorithmIdentifier, digest OCTET STRING }`.
+    /// The MAC key is derived from `password` via PBKDF2-HMAC-SHA256 (not the legacy PKCS#12
+    /// Appendix B KDF) for consistency with the key encryption above, and computed over
+    /// `auth_safe_der`, the DER encoding of the `AuthenticatedSafe` carried inside the outer
+    /// `ContentInfo`.
+    fn mac_data(auth_safe_der: &[u8], password: &str) -> Result<Vec<u8>> {
+        let mut salt = [0u8; 20];
+        fill_random(&mut salt)?;
+        let mut mac_key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, PBKDF2_ITERATIONS, &mut mac_key);
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&mac_key).context("Invalid HMAC key")?;
+        mac.update(auth_safe_der);
+        let digest = mac.finalize().into_bytes();
+
+        let digest_algorithm = algorithm_identifier(ID_SHA256, None)?;
+        let digest_der = der_octet_string(&digest)?;
+        let digest_info = der_sequence(&[&digest_algorithm, &digest_der]);
+        let salt_der = der_octet_string(&salt)?;
+        let iterations_der = der_integer_u32(PBKDF2_ITERATIONS);
+        Ok(der_sequence(&[&digest_info, &salt_der, &iterations_der]))
+    }
+
+    /// Builds the PKCS#12 archive described on [`DeriveKeyResponse::to_pkcs12`].
+    pub(super) fn build(
+        response: &DeriveKeyResponse,
+        password: &str,
+        friendly_name: &str,
+    ) -> Result<Vec<u8>> {
+        if response.certificate_chain.is_empty() {
+            anyhow::bail!("Cannot build a PKCS#12 archive without at least a leaf certificate");
+        }
+
+        let leaf_cert_der = cert_pem_to_der(&response.certificate_chain[0])?;
+        let local_key_id: [u8; 32] = Sha256::digest(&leaf_cert_der).into();
+
+        let key_bag_value = encrypted_private_key_info(&pkcs8_der(response)?, password)?;
+        let key_attributes = bag_attributes(friendly_name, &local_key_id)?;
+        let mut bags = vec![safe_bag(
+            PKCS8_SHROUDED_KEY_BAG,
+            &key_bag_value,
+            Some(&key_attributes),
+        )?];
+
+        for (index, cert_pem) in response.certificate_chain.iter().enumerate() {
+            let cert_der = if index == 0 {
+                leaf_cert_der.clone()
+            } else {
+                cert_pem_to_der(cert_pem)?
+            };
+            let cert_bag_value = cert_bag(&cert_der)?;
+            let attributes = (index == 0)
+                .then(|| bag_attributes(friendly_name, &local_key_id))
+                .transpose()?;
+            bags.push(safe_bag(CERT_BAG, &cert_bag_value, attributes.as_deref())?);
+        }
+
+        let bag_refs: Vec<&[u8]> = bags.iter().map(Vec::as_slice).collect();
+        let safe_contents = der_sequence(&bag_refs);
+        let safe_contents_content_info = data_content_info(&safe_contents)?;
+        let auth_safe_der = der_sequence(&[&safe_contents_content_info]);
+
+        let pfx_auth_safe = data_content_info(&auth_safe_der)?;
+        let mac_data_der = mac_data(&auth_safe_der, password)?;
+
+        let version = der_integer_u32(3);
+        Ok(der_sequence(&[&version, &pfx_auth_safe, &mac_data_der]))
+    }
+}
@@ -47,22 +47,180 @@ pub enum MemorySizeError {
     #[error("Invalid numeric value: {0}")]
     InvalidNumber(String),
     #[error("Unknown memory size suffix: {0}")]
-    UnknownSuffix(char),
+    UnknownSuffix(String),
     #[error("Overflow in memory size calculation")]
     Overflow,
 }
 
+/// The unit a [`MemorySize`] was originally expressed in, remembered so `Display`/`Serialize`
+/// can re-emit the same textual form instead of always recomputing a "best" unit via
+/// [`MemorySize::format_human`] -- e.g. a config value parsed from "2048M" stays "2048M" instead
+/// of round-tripping to "2G".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// A plain decimal or hexadecimal number with no unit suffix at all.
+    Raw,
+    /// An explicit "b" suffix.
+    Bytes,
+    /// Bare binary shorthand, 1024-based (kept for backward compatibility).
+    K,
+    /// SI (decimal, 1000-based).
+    Kb,
+    /// IEC (binary, 1024-based).
+    Kib,
+    M,
+    Mb,
+    Mib,
+    G,
+    Gb,
+    Gib,
+    T,
+    Tb,
+    Tib,
+}
+
+impl Unit {
+    /// Matches a unit token (case-insensitive) to the [`Unit`] it denotes. Returns `None` for an
+    /// unrecognized token.
+    fn from_token(token: &str) -> Option<Self> {
+        match token.to_ascii_lowercase().as_str() {
+            "b" => Some(Self::Bytes),
+            "k" => Some(Self::K),
+            "kb" => Some(Self::Kb),
+            "kib" => Some(Self::Kib),
+            "m" => Some(Self::M),
+            "mb" => Some(Self::Mb),
+            "mib" => Some(Self::Mib),
+            "g" => Some(Self::G),
+            "gb" => Some(Self::Gb),
+            "gib" => Some(Self::Gib),
+            "t" => Some(Self::T),
+            "tb" => Some(Self::Tb),
+            "tib" => Some(Self::Tib),
+            _ => None,
+        }
+    }
+
+    /// The byte multiplier this unit denotes.
+    fn multiplier(self) -> u64 {
+        match self {
+            Self::Raw | Self::Bytes => 1,
+            Self::K | Self::Kib => 1024,
+            Self::Kb => 1000,
+            Self::M | Self::Mib => 1024u64.pow(2),
+            Self::Mb => 1000u64.pow(2),
+            Self::G | Self::Gib => 1024u64.pow(3),
+            Self::Gb => 1000u64.pow(3),
+            Self::T | Self::Tib => 1024u64.pow(4),
+            Self::Tb => 1000u64.pow(4),
+        }
+    }
+
+    /// The suffix text this unit formats as (e.g. `Unit::Kib` -> `"KiB"`).
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::Raw => "",
+            Self::Bytes => "b",
+            Self::K => "K",
+            Self::Kb => "kB",
+            Self::Kib => "KiB",
+            Self::M => "M",
+            Self::Mb => "MB",
+            Self::Mib => "MiB",
+            Self::G => "G",
+            Self::Gb => "GB",
+            Self::Gib => "GiB",
+            Self::T => "T",
+            Self::Tb => "TB",
+            Self::Tib => "TiB",
+        }
+    }
+}
+
 /// A memory size value that can be parsed from strings with various formats
 /// and optionally serialized/deserialized with serde.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+///
+/// Equality, ordering, and hashing are based solely on the byte count: two `MemorySize`s parsed
+/// from different text ("1024K" and "1M") that denote the same number of bytes compare equal.
+/// The remembered [`Unit`] only affects how a value is re-rendered by `Display`/`Serialize`.
+#[derive(Debug, Clone, Copy)]
 pub struct MemorySize {
     bytes: u64,
+    unit: Option<Unit>,
+}
+
+impl PartialEq for MemorySize {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl Eq for MemorySize {}
+
+impl PartialOrd for MemorySize {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MemorySize {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.bytes.cmp(&other.bytes)
+    }
+}
+
+impl std::hash::Hash for MemorySize {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bytes.hash(state);
+    }
 }
 
 impl MemorySize {
-    /// Create a new MemorySize from a number of bytes
+    /// Create a new MemorySize from a number of bytes, with no remembered unit.
     pub fn from_bytes(bytes: u64) -> Self {
-        Self { bytes }
+        Self { bytes, unit: None }
+    }
+
+    /// The unit this size was originally parsed with, if any. `None` for values built via
+    /// [`MemorySize::from_bytes`] or after a call to [`MemorySize::normalized`].
+    pub fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+
+    /// Returns a copy of this size with its remembered unit recomputed as the largest bare unit
+    /// (`T`/`G`/`M`/`K`/raw bytes) that divides the byte count exactly -- the same order
+    /// [`MemorySize::format_human`] picks -- so a freshly computed size round-trips through its
+    /// own text form instead of falling back to [`MemorySize::format_human`] on every format.
+    pub fn infer(self) -> Self {
+        const UNITS: &[(Unit, u64)] = &[
+            (Unit::T, 1024u64.pow(4)),
+            (Unit::G, 1024u64.pow(3)),
+            (Unit::M, 1024u64.pow(2)),
+            (Unit::K, 1024),
+        ];
+
+        for &(unit, size) in UNITS {
+            if self.bytes != 0 && self.bytes % size == 0 {
+                return Self {
+                    bytes: self.bytes,
+                    unit: Some(unit),
+                };
+            }
+        }
+
+        Self {
+            bytes: self.bytes,
+            unit: Some(Unit::Raw),
+        }
+    }
+
+    /// Returns a copy of this size with its remembered unit discarded, so `Display`/`Serialize`
+    /// always recompute via [`MemorySize::format_human`].
+    pub fn normalized(self) -> Self {
+        Self {
+            bytes: self.bytes,
+            unit: None,
+        }
     }
 
     /// Get the memory size in bytes
@@ -95,13 +253,18 @@ impl MemorySize {
     /// Supports the following formats:
     /// - Plain numbers: "1024", "2048"
     /// - Hexadecimal: "0x1000", "0X2000"
-    /// - With suffixes: "2K", "4M", "1G", "2T" (case-insensitive)
+    /// - Bare binary shorthand: "2K", "4M", "1G", "2T" (case-insensitive, 1024-based, kept for
+    ///   backward compatibility)
+    /// - Explicit SI (decimal, 1000-based) units: "kB", "MB", "GB", "TB"
+    /// - Explicit IEC (binary, 1024-based) units: "KiB", "MiB", "GiB", "TiB"
+    /// - A bare byte unit: "100b"
+    /// - A decimal fraction with any of the above suffixes: "1.5K", "0.25TiB"
     ///
-    /// Suffixes use binary (1024-based) multipliers:
-    /// - K/k: 1024 bytes
-    /// - M/m: 1024^2 bytes
-    /// - G/g: 1024^3 bytes
-    /// - T/t: 1024^4 bytes
+    /// The unit is matched as the longest trailing alphabetic run of the trimmed input
+    /// (case-insensitive); the remaining prefix is the numeric part. This disambiguates "500MB"
+    /// (500,000,000 bytes, SI) from "500MiB" (524,288,000 bytes, IEC), while "500M" keeps its
+    /// existing 1024-based meaning. Plain numbers and hexadecimal values are always parsed as
+    /// exact integers; only the suffixed form accepts a fraction.
     pub fn parse(s: &str) -> Result<Self, MemorySizeError> {
         let s = s.trim();
 
@@ -114,7 +277,10 @@ impl MemorySize {
             let hex_str = &s[2..];
             let bytes = u64::from_str_radix(hex_str, 16)
                 .map_err(|_| MemorySizeError::InvalidHex(hex_str.to_string()))?;
-            return Ok(Self::from_bytes(bytes));
+            return Ok(Self {
+                bytes,
+                unit: Some(Unit::Raw),
+            });
         }
 
         // Handle plain numbers (all digits)
@@ -122,34 +288,61 @@ impl MemorySize {
             let bytes = s
                 .parse::<u64>()
                 .map_err(|_| MemorySizeError::InvalidNumber(s.to_string()))?;
-            return Ok(Self::from_bytes(bytes));
+            return Ok(Self {
+                bytes,
+                unit: Some(Unit::Raw),
+            });
         }
 
-        // Handle numbers with suffixes
-        let Some(last_char) = s.chars().last() else {
-            return Err(MemorySizeError::Empty);
-        };
-
-        let multiplier = match last_char.to_ascii_lowercase() {
-            'k' => 1024u64,
-            'm' => 1024u64.saturating_mul(1024),
-            'g' => 1024u64.saturating_mul(1024).saturating_mul(1024),
-            't' => 1024u64
-                .saturating_mul(1024)
-                .saturating_mul(1024)
-                .saturating_mul(1024),
-            _ => return Err(MemorySizeError::UnknownSuffix(last_char)),
-        };
-        let num_part = s.trim_end_matches(last_char);
+        // Handle numbers with suffixes: split off the longest trailing alphabetic run as the
+        // unit token, leaving the numeric part in front of it.
+        let alpha_count = s
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .count()
+            .max(1);
+        let split_at = s.len().saturating_sub(alpha_count);
+        let unit_token = &s[split_at..];
+        let num_part = &s[..split_at];
+
+        let unit = Unit::from_token(unit_token)
+            .ok_or_else(|| MemorySizeError::UnknownSuffix(unit_token.to_string()))?;
+
+        // The numeric part may be a plain integer ("2K") or a decimal fraction ("1.5K",
+        // "0.25TiB"); both are parsed as f64 so a fraction scales correctly, with exact integers
+        // still round-tripping precisely at the sizes this crate deals in.
         let num = num_part
-            .parse::<u64>()
+            .parse::<f64>()
             .map_err(|_| MemorySizeError::InvalidNumber(num_part.to_string()))?;
+        if !num.is_finite() || num < 0.0 {
+            return Err(MemorySizeError::InvalidNumber(num_part.to_string()));
+        }
+
+        let bytes_f64 = num * unit.multiplier() as f64;
+        if !bytes_f64.is_finite() || bytes_f64 > u64::MAX as f64 {
+            return Err(MemorySizeError::Overflow);
+        }
+        let bytes = bytes_f64.round() as u64;
 
-        let bytes = num
-            .checked_mul(multiplier)
-            .ok_or(MemorySizeError::Overflow)?;
+        Ok(Self {
+            bytes,
+            unit: Some(unit),
+        })
+    }
 
-        Ok(Self::from_bytes(bytes))
+    /// Formats this size using its remembered [`Unit`] (see [`MemorySize::parse`]), if that unit
+    /// divides the byte count exactly; otherwise falls back to [`MemorySize::format_human`]. Used
+    /// by `Display` and human-readable `Serialize` so a value round-trips through the same text
+    /// it was parsed from, instead of always recomputing a "best" unit.
+    fn format_remembered(&self) -> String {
+        if let Some(unit) = self.unit {
+            let multiplier = unit.multiplier();
+            if self.bytes % multiplier == 0 {
+                return format!("{}{}", self.bytes / multiplier, unit.suffix());
+            }
+        }
+        self.format_human()
     }
 
     /// Format the memory size in a human-readable way
@@ -188,7 +381,7 @@ impl FromStr for MemorySize {
 
 impl fmt::Display for MemorySize {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.format_human())
+        write!(f, "{}", self.format_remembered())
     }
 }
 
@@ -204,6 +397,63 @@ impl From<MemorySize> for u64 {
     }
 }
 
+impl MemorySize {
+    /// Adds `other` to this size, returning `None` on overflow instead of saturating.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.bytes.checked_add(other.bytes).map(Self::from_bytes)
+    }
+
+    /// Subtracts `other` from this size, returning `None` if it would underflow.
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.bytes.checked_sub(other.bytes).map(Self::from_bytes)
+    }
+}
+
+// Arithmetic results have no single remembered unit to speak of, so they're built via
+// `from_bytes` and fall back to `format_human` when displayed; use `infer()` on the result if a
+// remembered unit is wanted.
+impl std::ops::Add for MemorySize {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::from_bytes(self.bytes.saturating_add(rhs.bytes))
+    }
+}
+
+impl std::ops::Sub for MemorySize {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::from_bytes(self.bytes.saturating_sub(rhs.bytes))
+    }
+}
+
+impl std::ops::Mul<u64> for MemorySize {
+    type Output = Self;
+
+    fn mul(self, rhs: u64) -> Self {
+        Self::from_bytes(self.bytes.saturating_mul(rhs))
+    }
+}
+
+impl std::ops::AddAssign for MemorySize {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl std::ops::MulAssign<u64> for MemorySize {
+    fn mul_assign(&mut self, rhs: u64) {
+        *self = *self * rhs;
+    }
+}
+
+impl std::iter::Sum for MemorySize {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::from_bytes(0), |acc, x| acc + x)
+    }
+}
+
 #[cfg(feature = "serde")]
 impl Serialize for MemorySize {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -212,7 +462,7 @@ impl Serialize for MemorySize {
     {
         if serializer.is_human_readable() {
             // Serialize as human-readable string for JSON, YAML, etc.
-            serializer.serialize_str(&self.format_human())
+            serializer.serialize_str(&self.format_remembered())
         } else {
             // Serialize as raw bytes for binary formats
             serializer.serialize_u64(self.bytes)
@@ -413,6 +663,258 @@ pub mod human_size {
     }
 }
 
+/// Serde support for encoding memory sizes as `0x`-prefixed hex "QUANTITY" strings, the form used
+/// by JSON-RPC `eth_*`-style APIs (no leading zeros, e.g. `0x1000`). Usable via
+/// `#[serde(with = "size_parser::hex_quantity")]`, generic over any numeric type convertible
+/// to/from `u64` just like [`human_size`].
+#[cfg(feature = "serde")]
+pub mod hex_quantity {
+    use serde::{de::Error, Deserializer, Serializer};
+    use std::convert::{TryFrom, TryInto};
+
+    /// Serialize a numeric memory size as a `0x`-prefixed hex string (human-readable formats) or
+    /// a plain `u64` (binary formats).
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Copy + TryInto<u64>,
+        T::Error: std::fmt::Display,
+    {
+        let bytes: u64 = (*value).try_into().map_err(|e| {
+            serde::ser::Error::custom(format!("memory size conversion error: {}", e))
+        })?;
+
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format!("{bytes:#x}"))
+        } else {
+            serializer.serialize_u64(bytes)
+        }
+    }
+
+    /// Deserialize a memory size from a `0x`-prefixed hex quantity string or a plain integer.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<u64>,
+        T::Error: std::fmt::Display,
+    {
+        use serde::de::Visitor;
+        use std::fmt;
+
+        struct HexQuantityVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<T> Visitor<'_> for HexQuantityVisitor<T>
+        where
+            T: TryFrom<u64>,
+            T::Error: std::fmt::Display,
+        {
+            type Value = T;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a 0x-prefixed hex quantity string or a number")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                let hex = value
+                    .strip_prefix("0x")
+                    .or_else(|| value.strip_prefix("0X"))
+                    .ok_or_else(|| E::custom(format!("expected a 0x-prefixed hex quantity, got {value:?}")))?;
+                let bytes = u64::from_str_radix(hex, 16)
+                    .map_err(|e| E::custom(format!("invalid hex quantity: {e}")))?;
+                T::try_from(bytes)
+                    .map_err(|e| E::custom(format!("memory size conversion error: {}", e)))
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                T::try_from(value)
+                    .map_err(|e| E::custom(format!("memory size conversion error: {}", e)))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(HexQuantityVisitor(std::marker::PhantomData))
+        } else {
+            deserializer.deserialize_u64(HexQuantityVisitor(std::marker::PhantomData))
+        }
+    }
+}
+
+/// Shared implementation behind [`bytes_be`] and [`bytes_le`]: encodes a memory size as a fixed
+/// 8-byte array (raw bytes for binary formats, a zero-padded hex string of that array for
+/// human-readable ones) in the given endianness.
+#[cfg(feature = "serde")]
+mod fixed_bytes {
+    use serde::{de::Error, Deserializer, Serializer};
+    use std::convert::{TryFrom, TryInto};
+
+    pub fn serialize<S, T>(value: &T, serializer: S, to_array: fn(u64) -> [u8; 8]) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Copy + TryInto<u64>,
+        T::Error: std::fmt::Display,
+    {
+        let bytes: u64 = (*value).try_into().map_err(|e| {
+            serde::ser::Error::custom(format!("memory size conversion error: {}", e))
+        })?;
+        let array = to_array(bytes);
+
+        if serializer.is_human_readable() {
+            let hex: String = array.iter().map(|b| format!("{b:02x}")).collect();
+            serializer.serialize_str(&hex)
+        } else {
+            serializer.serialize_bytes(&array)
+        }
+    }
+
+    pub fn deserialize<'de, D, T>(
+        deserializer: D,
+        from_array: fn([u8; 8]) -> u64,
+    ) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<u64>,
+        T::Error: std::fmt::Display,
+    {
+        use serde::de::{SeqAccess, Visitor};
+        use std::fmt;
+
+        struct FixedBytesVisitor<T> {
+            from_array: fn([u8; 8]) -> u64,
+            marker: std::marker::PhantomData<T>,
+        }
+
+        impl<'de, T> Visitor<'de> for FixedBytesVisitor<T>
+        where
+            T: TryFrom<u64>,
+            T::Error: std::fmt::Display,
+        {
+            type Value = T;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an 8-byte array or a 16-character hex string")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                if value.len() != 16 {
+                    return Err(E::custom(format!(
+                        "expected a 16-character hex string, got {} characters",
+                        value.len()
+                    )));
+                }
+                let mut array = [0u8; 8];
+                for (i, byte) in array.iter_mut().enumerate() {
+                    *byte = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16)
+                        .map_err(|e| E::custom(format!("invalid hex byte: {e}")))?;
+                }
+                T::try_from((self.from_array)(array))
+                    .map_err(|e| E::custom(format!("memory size conversion error: {}", e)))
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                let array: [u8; 8] = value
+                    .try_into()
+                    .map_err(|_| E::custom(format!("expected 8 bytes, got {}", value.len())))?;
+                T::try_from((self.from_array)(array))
+                    .map_err(|e| E::custom(format!("memory size conversion error: {}", e)))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut array = [0u8; 8];
+                for (i, byte) in array.iter_mut().enumerate() {
+                    *byte = seq
+                        .next_element()?
+                        .ok_or_else(|| serde::de::Error::invalid_length(i, &self))?;
+                }
+                T::try_from((self.from_array)(array))
+                    .map_err(|e| serde::de::Error::custom(format!("memory size conversion error: {}", e)))
+            }
+        }
+
+        let visitor = FixedBytesVisitor {
+            from_array,
+            marker: std::marker::PhantomData,
+        };
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(visitor)
+        } else {
+            deserializer.deserialize_bytes(visitor)
+        }
+    }
+}
+
+/// Serde support for encoding memory sizes as a fixed 8-byte big-endian array (or, for
+/// human-readable formats, the equivalent zero-padded hex string), for compact binary wire
+/// formats and byte-order-sensitive protocols. Usable via
+/// `#[serde(with = "size_parser::bytes_be")]`.
+#[cfg(feature = "serde")]
+pub mod bytes_be {
+    use super::fixed_bytes;
+    use serde::{Deserializer, Serializer};
+    use std::convert::{TryFrom, TryInto};
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Copy + TryInto<u64>,
+        T::Error: std::fmt::Display,
+    {
+        fixed_bytes::serialize(value, serializer, u64::to_be_bytes)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<u64>,
+        T::Error: std::fmt::Display,
+    {
+        fixed_bytes::deserialize(deserializer, u64::from_be_bytes)
+    }
+}
+
+/// Serde support for encoding memory sizes as a fixed 8-byte little-endian array (or, for
+/// human-readable formats, the equivalent zero-padded hex string). Usable via
+/// `#[serde(with = "size_parser::bytes_le")]`.
+#[cfg(feature = "serde")]
+pub mod bytes_le {
+    use super::fixed_bytes;
+    use serde::{Deserializer, Serializer};
+    use std::convert::{TryFrom, TryInto};
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Copy + TryInto<u64>,
+        T::Error: std::fmt::Display,
+    {
+        fixed_bytes::serialize(value, serializer, u64::to_le_bytes)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<u64>,
+        T::Error: std::fmt::Display,
+    {
+        fixed_bytes::deserialize(deserializer, u64::from_le_bytes)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -453,20 +955,58 @@ mod tests {
             MemorySize::parse("   "),
             Err(MemorySizeError::Empty)
         ));
-        assert!(matches!(
+        assert_eq!(
             MemorySize::parse("abc"),
-            Err(MemorySizeError::UnknownSuffix('c'))
-        ));
+            Err(MemorySizeError::UnknownSuffix("abc".to_string()))
+        );
         assert!(matches!(
             MemorySize::parse("0xgg"),
             Err(MemorySizeError::InvalidHex(_))
         ));
-        assert!(matches!(
+        // "abcK" is entirely alphabetic, so the whole token is taken as the (unrecognized) unit.
+        assert_eq!(
             MemorySize::parse("abcK"),
-            Err(MemorySizeError::InvalidNumber(_))
+            Err(MemorySizeError::UnknownSuffix("abcK".to_string()))
+        );
+        assert!(matches!(
+            MemorySize::parse("1Q"),
+            Err(MemorySizeError::UnknownSuffix(_))
         ));
     }
 
+    #[test]
+    fn test_parse_si_and_iec_units() {
+        // SI (decimal, 1000-based)
+        assert_eq!(MemorySize::parse("1kB").unwrap().bytes(), 1000);
+        assert_eq!(MemorySize::parse("500MB").unwrap().bytes(), 500_000_000);
+        assert_eq!(MemorySize::parse("1GB").unwrap().bytes(), 1_000_000_000);
+        assert_eq!(MemorySize::parse("1TB").unwrap().bytes(), 1_000_000_000_000);
+
+        // IEC (binary, 1024-based)
+        assert_eq!(MemorySize::parse("1KiB").unwrap().bytes(), 1024);
+        assert_eq!(MemorySize::parse("500MiB").unwrap().bytes(), 500 * 1024 * 1024);
+        assert_eq!(MemorySize::parse("1GiB").unwrap().bytes(), 1024 * 1024 * 1024);
+        assert_eq!(MemorySize::parse("1TiB").unwrap().bytes(), 1024u64.pow(4));
+
+        // Bare binary shorthand keeps its existing 1024-based meaning, distinct from the SI unit
+        // of the same letter.
+        assert_eq!(
+            MemorySize::parse("500M").unwrap().bytes(),
+            MemorySize::parse("500MiB").unwrap().bytes()
+        );
+        assert_ne!(
+            MemorySize::parse("500M").unwrap().bytes(),
+            MemorySize::parse("500MB").unwrap().bytes()
+        );
+
+        // Case-insensitive matching for all unit forms.
+        assert_eq!(MemorySize::parse("2kb").unwrap().bytes(), 2000);
+        assert_eq!(MemorySize::parse("2Kib").unwrap().bytes(), 2048);
+
+        // A bare byte unit.
+        assert_eq!(MemorySize::parse("100b").unwrap().bytes(), 100);
+    }
+
     #[test]
     fn test_format_human() {
         assert_eq!(MemorySize::from_bytes(1024).format_human(), "1K");
@@ -624,4 +1164,195 @@ mod tests {
             serde_json::from_str(&format!(r#"{{"memory_size":{}}}"#, gb_2)).unwrap();
         assert_eq!(from_string_2g.memory_size, from_number_2g.memory_size);
     }
+
+    #[test]
+    fn test_round_trip_preserves_parsed_unit() {
+        // A value that would recompute to a different unit under `format_human` instead keeps
+        // the unit it was parsed with.
+        assert_eq!(MemorySize::parse("2048M").unwrap().to_string(), "2048M");
+        assert_eq!(MemorySize::parse("1500M").unwrap().to_string(), "1500M");
+        assert_eq!(MemorySize::parse("500MB").unwrap().to_string(), "500MB");
+        assert_eq!(MemorySize::parse("500MiB").unwrap().to_string(), "500MiB");
+        assert_eq!(MemorySize::parse("1024").unwrap().to_string(), "1024");
+        assert_eq!(MemorySize::parse("0x1000").unwrap().to_string(), "4096");
+
+        // Values built from raw bytes have no remembered unit, so they fall back to
+        // `format_human`.
+        assert_eq!(MemorySize::from_bytes(2 * 1024 * 1024 * 1024).to_string(), "2G");
+    }
+
+    #[test]
+    fn test_infer_and_normalized() {
+        assert_eq!(
+            MemorySize::from_bytes(2 * 1024 * 1024 * 1024).infer().to_string(),
+            "2G"
+        );
+        assert_eq!(MemorySize::from_bytes(1536).infer().to_string(), "1536");
+        assert_eq!(MemorySize::from_bytes(0).infer().to_string(), "0");
+
+        // `normalized` drops the remembered unit, reverting to `format_human`.
+        let parsed = MemorySize::parse("2048M").unwrap();
+        assert_eq!(parsed.to_string(), "2048M");
+        assert_eq!(parsed.normalized().to_string(), "2G");
+        assert_eq!(parsed.normalized().unit(), None);
+    }
+
+    #[test]
+    fn test_equality_ignores_remembered_unit() {
+        let a = MemorySize::parse("1024K").unwrap();
+        let b = MemorySize::parse("1M").unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let mut ha = DefaultHasher::new();
+        let mut hb = DefaultHasher::new();
+        a.hash(&mut ha);
+        b.hash(&mut hb);
+        assert_eq!(ha.finish(), hb.finish());
+    }
+
+    #[test]
+    fn test_parse_fractional_sizes() {
+        assert_eq!(MemorySize::parse("1.5K").unwrap().bytes(), 1536);
+        assert_eq!(
+            MemorySize::parse("0.5M").unwrap().bytes(),
+            512 * 1024
+        );
+        assert_eq!(
+            MemorySize::parse("2.25GiB").unwrap().bytes(),
+            (2.25 * 1024.0 * 1024.0 * 1024.0) as u64
+        );
+        assert_eq!(MemorySize::parse("1.0K").unwrap().bytes(), 1024);
+        assert_eq!(MemorySize::parse("0.5kB").unwrap().bytes(), 500);
+
+        // Plain integers and hex values are unaffected and remain exact.
+        assert_eq!(MemorySize::parse("1024").unwrap().bytes(), 1024);
+        assert_eq!(MemorySize::parse("0x1000").unwrap().bytes(), 4096);
+    }
+
+    #[test]
+    fn test_parse_fractional_errors() {
+        assert!(matches!(
+            MemorySize::parse("-1.5K"),
+            Err(MemorySizeError::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            MemorySize::parse("1.5.5K"),
+            Err(MemorySizeError::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            MemorySize::parse("nanK"),
+            Err(MemorySizeError::InvalidNumber(_))
+        ));
+    }
+
+    #[test]
+    fn test_arithmetic_operators() {
+        let one_k = MemorySize::from_bytes(1024);
+        let two_k = MemorySize::from_bytes(2048);
+
+        assert_eq!((one_k + two_k).bytes(), 3072);
+        assert_eq!((two_k - one_k).bytes(), 1024);
+        assert_eq!((one_k * 3).bytes(), 3072);
+
+        let mut total = one_k;
+        total += two_k;
+        assert_eq!(total.bytes(), 3072);
+
+        let mut scaled = one_k;
+        scaled *= 4;
+        assert_eq!(scaled.bytes(), 4096);
+
+        assert_eq!(one_k.checked_add(two_k).unwrap().bytes(), 3072);
+        assert_eq!(one_k.checked_sub(two_k), None);
+        assert_eq!(
+            MemorySize::from_bytes(u64::MAX).checked_add(one_k),
+            None
+        );
+
+        // Operator impls saturate instead of panicking on overflow/underflow.
+        assert_eq!((one_k - two_k).bytes(), 0);
+        assert_eq!(
+            (MemorySize::from_bytes(u64::MAX) + one_k).bytes(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_sum_over_collection() {
+        let sizes = vec![
+            MemorySize::parse("1K").unwrap(),
+            MemorySize::parse("2K").unwrap(),
+            MemorySize::parse("1M").unwrap(),
+        ];
+        let total: MemorySize = sizes.into_iter().sum();
+        assert_eq!(total.bytes(), 3 * 1024 + 1024 * 1024);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_hex_quantity_field_attribute() {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Config {
+            #[serde(with = "crate::hex_quantity")]
+            memory_size: u64,
+        }
+
+        let config = Config {
+            memory_size: 4096,
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, r#"{"memory_size":"0x1000"}"#);
+
+        let deserialized: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.memory_size, 4096);
+
+        // Plain JSON numbers are also accepted.
+        let from_number: Config = serde_json::from_str(r#"{"memory_size":4096}"#).unwrap();
+        assert_eq!(from_number.memory_size, 4096);
+
+        // Uppercase "0X" prefix is accepted too.
+        let from_upper: Config = serde_json::from_str(r#"{"memory_size":"0X1000"}"#).unwrap();
+        assert_eq!(from_upper.memory_size, 4096);
+
+        assert!(serde_json::from_str::<Config>(r#"{"memory_size":"1000"}"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_bytes_be_and_bytes_le_field_attributes() {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct BigEndianConfig {
+            #[serde(with = "crate::bytes_be")]
+            memory_size: u64,
+        }
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct LittleEndianConfig {
+            #[serde(with = "crate::bytes_le")]
+            memory_size: u64,
+        }
+
+        let be_config = BigEndianConfig {
+            memory_size: 0x0000_0000_0000_1000,
+        };
+        let be_json = serde_json::to_string(&be_config).unwrap();
+        assert_eq!(be_json, r#"{"memory_size":"0000000000001000"}"#);
+        let be_roundtrip: BigEndianConfig = serde_json::from_str(&be_json).unwrap();
+        assert_eq!(be_roundtrip, be_config);
+
+        let le_config = LittleEndianConfig {
+            memory_size: 0x0000_0000_0000_1000,
+        };
+        let le_json = serde_json::to_string(&le_config).unwrap();
+        assert_eq!(le_json, r#"{"memory_size":"0010000000000000"}"#);
+        let le_roundtrip: LittleEndianConfig = serde_json::from_str(&le_json).unwrap();
+        assert_eq!(le_roundtrip, le_config);
+    }
 }
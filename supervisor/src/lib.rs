@@ -3,7 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod process;
+mod ring_buffer;
 mod supervisor;
 pub mod web_api;
-pub use process::{ProcessConfig, ProcessInfo, ProcessState, ProcessStatus};
+pub use process::{ProcessConfig, ProcessInfo, ProcessState, ProcessStats, ProcessStatus};
+pub use supervisor::Supervisor;
 pub use web_api::Response;
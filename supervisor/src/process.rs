@@ -0,0 +1,174 @@
+// SPDX-FileCopyrightText: © 2024 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Process configuration and runtime status types shared between [`crate::supervisor::Supervisor`]
+//! and its callers over [`crate::web_api`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Describes one process [`crate::supervisor::Supervisor`] should spawn and keep track of, e.g.
+/// a VM's QEMU invocation built by `vmm`'s `qemu` module.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessConfig {
+    /// Unique id this process is supervised and queried under.
+    pub id: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Path stdout is redirected to. Empty means stdout is discarded.
+    #[serde(default)]
+    pub stdout: String,
+    /// Path stderr is redirected to. Empty means stderr is discarded.
+    #[serde(default)]
+    pub stderr: String,
+    /// Whether, and how, the supervisor should relaunch this process after it exits.
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    /// Optional liveness probe used to detect a process that's running but stuck, surfaced as
+    /// the `Unhealthy` [`ProcessState`] rather than acted on directly.
+    #[serde(default)]
+    pub health_check: Option<HealthCheck>,
+}
+
+/// Whether the supervisor relaunches a process after it exits, mirroring Kubernetes's
+/// container restart policies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum RestartPolicy {
+    /// Never relaunch; an exit is final.
+    #[default]
+    Never,
+    /// Relaunch only after a nonzero exit code.
+    OnFailure(BackoffPolicy),
+    /// Relaunch after any exit, success or failure.
+    Always(BackoffPolicy),
+}
+
+impl RestartPolicy {
+    /// Whether this policy wants a relaunch given the exit code just observed.
+    pub fn should_restart(&self, exit_code: Option<i32>) -> bool {
+        match self {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure(_) => exit_code != Some(0),
+            RestartPolicy::Always(_) => true,
+        }
+    }
+
+    pub fn backoff(&self) -> Option<&BackoffPolicy> {
+        match self {
+            RestartPolicy::Never => None,
+            RestartPolicy::OnFailure(backoff) | RestartPolicy::Always(backoff) => Some(backoff),
+        }
+    }
+}
+
+/// Exponential backoff applied between relaunch attempts, capped at `max_delay_ms` and reset
+/// once a relaunched process has stayed healthy for `reset_after_ms`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BackoffPolicy {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// `None` means retry forever.
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// How long a process must stay running (without exiting again) before the backoff delay
+    /// resets back to `initial_delay_ms`, rather than continuing to grow across unrelated later
+    /// crashes.
+    pub reset_after_ms: u64,
+}
+
+impl BackoffPolicy {
+    /// The delay before the `attempt`-th relaunch (0-based), doubling each attempt and capped
+    /// at `max_delay_ms`.
+    pub fn delay_ms(&self, attempt: u32) -> u64 {
+        self.initial_delay_ms
+            .saturating_mul(1u64.checked_shl(attempt).unwrap_or(u64::MAX))
+            .min(self.max_delay_ms)
+    }
+}
+
+/// A liveness probe run periodically against a running process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub probe: HealthProbe,
+    pub interval_ms: u64,
+    pub timeout_ms: u64,
+    /// Consecutive probe failures before the process is reported `Unhealthy`.
+    pub failure_threshold: u32,
+}
+
+/// How a [`HealthCheck`] determines whether a process is alive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HealthProbe {
+    /// Exit code zero means healthy.
+    Exec { command: String, args: Vec<String> },
+    /// A 2xx response means healthy.
+    Http { url: String },
+}
+
+/// A supervised process's lifecycle state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessState {
+    Starting,
+    Running,
+    /// Running, but its [`HealthCheck`] has failed `failure_threshold` times in a row.
+    Unhealthy,
+    Stopped,
+    Exited { code: Option<i32> },
+}
+
+impl ProcessState {
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self,
+            ProcessState::Starting | ProcessState::Running | ProcessState::Unhealthy
+        )
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        matches!(self, ProcessState::Stopped | ProcessState::Exited { .. })
+    }
+}
+
+/// A process's current runtime status, separate from its static [`ProcessConfig`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessStatus {
+    pub status: ProcessState,
+    pub pid: Option<u32>,
+    /// Exit code of the most recent run, if it has exited at least once.
+    #[serde(default)]
+    pub last_exit_code: Option<i32>,
+    /// How many times the supervisor has relaunched this process under its `restart_policy`.
+    #[serde(default)]
+    pub restart_count: u32,
+}
+
+/// A point-in-time resource-usage sample for a running supervised process, read from
+/// `/proc/<pid>/stat` and `/proc/<pid>/io` on Linux. Each field is `None` rather than a bogus
+/// zero when it couldn't be read, e.g. the process isn't running or the host isn't Linux.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ProcessStats {
+    /// Total CPU time (user + system) consumed since the process started, in milliseconds.
+    pub cpu_time_ms: Option<u64>,
+    /// Resident set size.
+    pub rss_bytes: Option<u64>,
+    /// Bytes read from storage, per `/proc/<pid>/io`'s `read_bytes`.
+    pub read_bytes: Option<u64>,
+    /// Bytes written to storage, per `/proc/<pid>/io`'s `write_bytes`.
+    pub write_bytes: Option<u64>,
+}
+
+/// A supervised process's config plus its current status, as returned by
+/// [`crate::supervisor::Supervisor::info`]/[`crate::supervisor::Supervisor::list`] and served
+/// over [`crate::web_api`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub config: ProcessConfig,
+    pub state: ProcessStatus,
+}
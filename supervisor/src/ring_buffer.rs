@@ -0,0 +1,68 @@
+// SPDX-FileCopyrightText: © 2024 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! A bounded ring buffer of recent process output, so a late log follower can be sent the tail
+//! of a process's stdout/stderr without the supervisor holding the whole thing in memory.
+
+use std::collections::VecDeque;
+
+/// Holds at most `capacity` bytes of the most recently written output, discarding the oldest
+/// bytes first once full.
+#[derive(Debug, Clone)]
+pub struct RingBuffer {
+    capacity: usize,
+    data: VecDeque<u8>,
+}
+
+impl RingBuffer {
+    /// Creates an empty buffer that retains at most `capacity` bytes.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            data: VecDeque::with_capacity(capacity.min(64 * 1024)),
+        }
+    }
+
+    /// Appends `bytes`, evicting the oldest data if the buffer would exceed capacity.
+    pub fn push(&mut self, bytes: &[u8]) {
+        if bytes.len() >= self.capacity {
+            // The new chunk alone fills (or overflows) the buffer; keep only its tail.
+            self.data.clear();
+            self.data.extend(bytes[bytes.len() - self.capacity..].iter().copied());
+            return;
+        }
+        let overflow = (self.data.len() + bytes.len()).saturating_sub(self.capacity);
+        for _ in 0..overflow {
+            self.data.pop_front();
+        }
+        self.data.extend(bytes.iter().copied());
+    }
+
+    /// Returns the retained bytes in chronological order.
+    pub fn contents(&self) -> Vec<u8> {
+        self.data.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_oldest_bytes_once_full() {
+        let mut buf = RingBuffer::new(4);
+        buf.push(b"ab");
+        buf.push(b"cd");
+        assert_eq!(buf.contents(), b"abcd");
+        buf.push(b"ef");
+        assert_eq!(buf.contents(), b"cdef");
+    }
+
+    #[test]
+    fn single_chunk_larger_than_capacity_keeps_tail() {
+        let mut buf = RingBuffer::new(3);
+        buf.push(b"abcdef");
+        assert_eq!(buf.contents(), b"def");
+    }
+}
@@ -0,0 +1,410 @@
+// SPDX-FileCopyrightText: © 2024 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Spawns and tracks child processes described by [`ProcessConfig`], teeing their stdout/stderr
+//! into a bounded backlog plus a broadcast channel for live log followers, and publishing
+//! [`ProcessState`] transitions over a `watch` channel so a caller can await `Exited` without
+//! polling. This mirrors `vmm`'s `ConsoleProxy`, generalized from one pty to many child
+//! processes.
+//!
+//! A process configured with a `restart_policy` other than [`RestartPolicy::Never`] is
+//! relaunched by the same background task that spawned it, applying the configured
+//! [`BackoffPolicy`] between attempts; a process with a `health_check` is probed on its own
+//! interval and reported `Unhealthy` after enough consecutive failures, without being killed —
+//! only an actual exit ever triggers a relaunch decision.
+
+use crate::process::{
+    HealthCheck, HealthProbe, ProcessConfig, ProcessInfo, ProcessState, ProcessStats, ProcessStatus,
+};
+use crate::ring_buffer::RingBuffer;
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::sync::{broadcast, watch, Mutex};
+
+/// Broadcast backlog for live log followers; a follower that falls behind drops the oldest
+/// frames rather than stalling the process's own output reader.
+const LOG_BROADCAST_CAPACITY: usize = 1024;
+
+/// How much recent stdout/stderr output [`Supervisor::tail_logs`] can return without a live
+/// follower attached.
+const LOG_RING_BUFFER_CAPACITY: usize = 256 * 1024;
+
+struct SupervisedProcess {
+    config: ProcessConfig,
+    pid: Arc<StdMutex<Option<u32>>>,
+    output: broadcast::Sender<Vec<u8>>,
+    backlog: Arc<StdMutex<RingBuffer>>,
+    state: watch::Sender<ProcessState>,
+    last_exit_code: Arc<StdMutex<Option<i32>>>,
+    restart_count: Arc<AtomicU32>,
+    /// Flips to `true` to ask the supervising task in [`run_supervised`] to kill the current
+    /// attempt (or skip the next one) and stop for good, instead of relaunching.
+    stop_signal: watch::Sender<bool>,
+}
+
+impl SupervisedProcess {
+    fn info(&self) -> ProcessInfo {
+        ProcessInfo {
+            config: self.config.clone(),
+            state: ProcessStatus {
+                status: *self.state.borrow(),
+                pid: *self.pid.lock().unwrap_or_else(|e| e.into_inner()),
+                last_exit_code: *self.last_exit_code.lock().unwrap_or_else(|e| e.into_inner()),
+                restart_count: self.restart_count.load(Ordering::SeqCst),
+            },
+        }
+    }
+}
+
+/// Supervises a set of processes by id.
+#[derive(Clone, Default)]
+pub struct Supervisor {
+    processes: Arc<Mutex<HashMap<String, SupervisedProcess>>>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns `config.command`, tailing its stdout/stderr into a shared backlog and broadcast
+    /// channel, relaunching it per `config.restart_policy` if it exits, and probing
+    /// `config.health_check` if set. Fails if a process with this id is already supervised.
+    pub async fn start(&self, config: ProcessConfig) -> Result<()> {
+        let mut processes = self.processes.lock().await;
+        if processes.contains_key(&config.id) {
+            bail!("Process {} is already supervised", config.id);
+        }
+
+        let (state_tx, _) = watch::channel(ProcessState::Starting);
+        let (output_tx, _) = broadcast::channel(LOG_BROADCAST_CAPACITY);
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let backlog = Arc::new(StdMutex::new(RingBuffer::new(LOG_RING_BUFFER_CAPACITY)));
+        let pid = Arc::new(StdMutex::new(None));
+        let last_exit_code = Arc::new(StdMutex::new(None));
+        let restart_count = Arc::new(AtomicU32::new(0));
+
+        tokio::spawn(run_supervised(
+            config.clone(),
+            pid.clone(),
+            output_tx.clone(),
+            backlog.clone(),
+            state_tx.clone(),
+            last_exit_code.clone(),
+            restart_count.clone(),
+            stop_rx,
+        ));
+        if let Some(health_check) = config.health_check.clone() {
+            tokio::spawn(run_health_check(health_check, state_tx.clone()));
+        }
+
+        processes.insert(
+            config.id.clone(),
+            SupervisedProcess {
+                config,
+                pid,
+                output: output_tx,
+                backlog,
+                state: state_tx,
+                last_exit_code,
+                restart_count,
+                stop_signal: stop_tx,
+            },
+        );
+        Ok(())
+    }
+
+    /// Asks the process to stop: the current attempt is killed (via `Child::start_kill`, since
+    /// tokio has no separate graceful-signal API), or if called mid-backoff, the pending
+    /// relaunch is skipped. Either way no further relaunch happens regardless of
+    /// `restart_policy`. Returns without waiting for the exit; watch [`Self::subscribe_state`]
+    /// for `Stopped`.
+    pub async fn stop(&self, id: &str) -> Result<()> {
+        let processes = self.processes.lock().await;
+        let proc = processes
+            .get(id)
+            .with_context(|| format!("Process {id} not found"))?;
+        let _ = proc.stop_signal.send(true);
+        Ok(())
+    }
+
+    /// Drops a stopped process from supervision. Fails if it's still running.
+    pub async fn remove(&self, id: &str) -> Result<()> {
+        let mut processes = self.processes.lock().await;
+        let proc = processes
+            .get(id)
+            .with_context(|| format!("Process {id} not found"))?;
+        if proc.state.borrow().is_running() {
+            bail!("Process {id} is still running, stop it first");
+        }
+        processes.remove(id);
+        Ok(())
+    }
+
+    pub async fn info(&self, id: &str) -> Result<Option<ProcessInfo>> {
+        let processes = self.processes.lock().await;
+        Ok(processes.get(id).map(SupervisedProcess::info))
+    }
+
+    pub async fn list(&self) -> Result<Vec<ProcessInfo>> {
+        let processes = self.processes.lock().await;
+        Ok(processes.values().map(SupervisedProcess::info).collect())
+    }
+
+    /// Returns up to the last `tail` bytes of `id`'s retained stdout/stderr backlog, meant to be
+    /// sent before switching a follower over to live [`Self::subscribe_logs`] output so nothing
+    /// produced in between is lost.
+    pub async fn tail_logs(&self, id: &str, tail: usize) -> Result<Vec<u8>> {
+        let processes = self.processes.lock().await;
+        let proc = processes
+            .get(id)
+            .with_context(|| format!("Process {id} not found"))?;
+        let contents = proc
+            .backlog
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contents();
+        let start = contents.len().saturating_sub(tail);
+        Ok(contents[start..].to_vec())
+    }
+
+    /// Subscribes to `id`'s stdout/stderr from this point forward. Combine with
+    /// [`Self::tail_logs`] for a gapless transition from backlog to live output.
+    pub async fn subscribe_logs(&self, id: &str) -> Result<broadcast::Receiver<Vec<u8>>> {
+        let processes = self.processes.lock().await;
+        let proc = processes
+            .get(id)
+            .with_context(|| format!("Process {id} not found"))?;
+        Ok(proc.output.subscribe())
+    }
+
+    /// Subscribes to `id`'s [`ProcessState`] transitions (`Starting`/`Running`/`Unhealthy`/
+    /// `Exited`).
+    pub async fn subscribe_state(&self, id: &str) -> Result<watch::Receiver<ProcessState>> {
+        let processes = self.processes.lock().await;
+        let proc = processes
+            .get(id)
+            .with_context(|| format!("Process {id} not found"))?;
+        Ok(proc.state.subscribe())
+    }
+
+    /// Samples `id`'s current CPU/memory/I/O usage, or `Ok(None)` if it isn't currently running.
+    /// Sampled fresh on every call rather than polled on a timer in the background, so the
+    /// effective sampling interval is just however often the caller (a `/metrics` scrape, say)
+    /// asks.
+    pub async fn stats(&self, id: &str) -> Result<Option<ProcessStats>> {
+        let processes = self.processes.lock().await;
+        let proc = processes
+            .get(id)
+            .with_context(|| format!("Process {id} not found"))?;
+        let pid = *proc.pid.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(pid.map(read_process_stats))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_process_stats(pid: u32) -> ProcessStats {
+    /// `sysconf(_SC_CLK_TCK)` is 100 on every Linux platform dstack targets; reading it properly
+    /// would need `libc`, which nothing else in this crate depends on.
+    const CLK_TCK_HZ: u64 = 100;
+    const PAGE_SIZE_BYTES: u64 = 4096;
+
+    let mut stats = ProcessStats::default();
+    if let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+        // `comm` (field 2) is parenthesized and may itself contain spaces/parens, so split off
+        // everything after its closing `)` before splitting the remaining whitespace-separated
+        // fields, which then start at field 3 (`state`).
+        if let Some((_, rest)) = stat.rsplit_once(')') {
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            let utime = fields.get(11).and_then(|s| s.parse::<u64>().ok());
+            let stime = fields.get(12).and_then(|s| s.parse::<u64>().ok());
+            if let (Some(utime), Some(stime)) = (utime, stime) {
+                stats.cpu_time_ms = Some((utime + stime).saturating_mul(1000) / CLK_TCK_HZ);
+            }
+            stats.rss_bytes = fields
+                .get(21)
+                .and_then(|s| s.parse::<u64>().ok())
+                .map(|pages| pages.saturating_mul(PAGE_SIZE_BYTES));
+        }
+    }
+    if let Ok(io) = std::fs::read_to_string(format!("/proc/{pid}/io")) {
+        for line in io.lines() {
+            if let Some(value) = line.strip_prefix("read_bytes:") {
+                stats.read_bytes = value.trim().parse().ok();
+            } else if let Some(value) = line.strip_prefix("write_bytes:") {
+                stats.write_bytes = value.trim().parse().ok();
+            }
+        }
+    }
+    stats
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_process_stats(_pid: u32) -> ProcessStats {
+    ProcessStats::default()
+}
+
+/// Spawns `config.command` in a loop: pumps its output into `output_tx`/`backlog`, waits for it
+/// to exit, and either relaunches after `config.restart_policy`'s backoff or settles into a
+/// terminal `Stopped`/`Exited` state. The only task that ever touches the `Child` it owns, so
+/// [`Supervisor::stop`] talks to it purely through `stop_rx` rather than a shared handle.
+#[allow(clippy::too_many_arguments)]
+async fn run_supervised(
+    config: ProcessConfig,
+    pid_slot: Arc<StdMutex<Option<u32>>>,
+    output_tx: broadcast::Sender<Vec<u8>>,
+    backlog: Arc<StdMutex<RingBuffer>>,
+    state_tx: watch::Sender<ProcessState>,
+    last_exit_code: Arc<StdMutex<Option<i32>>>,
+    restart_count: Arc<AtomicU32>,
+    mut stop_rx: watch::Receiver<bool>,
+) {
+    let mut attempt = 0u32;
+    loop {
+        if *stop_rx.borrow() {
+            let _ = state_tx.send(ProcessState::Stopped);
+            return;
+        }
+
+        let mut command = Command::new(&config.command);
+        command.args(&config.args);
+        if !config.env.is_empty() {
+            command.envs(&config.env);
+        }
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(_) => {
+                let _ = state_tx.send(ProcessState::Exited { code: None });
+                return;
+            }
+        };
+        *pid_slot.lock().unwrap_or_else(|e| e.into_inner()) = child.id();
+
+        for reader in [child.stdout.take(), child.stderr.take()].into_iter().flatten() {
+            let pump_tx = output_tx.clone();
+            let pump_backlog = backlog.clone();
+            tokio::spawn(async move {
+                let mut reader = reader;
+                let mut buf = [0u8; 4096];
+                loop {
+                    match reader.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => {
+                            // No subscribers is not an error: the process keeps running even if
+                            // nobody is currently tailing its output.
+                            pump_backlog
+                                .lock()
+                                .unwrap_or_else(|e| e.into_inner())
+                                .push(&buf[..n]);
+                            let _ = pump_tx.send(buf[..n].to_vec());
+                        }
+                    }
+                }
+            });
+        }
+
+        let _ = state_tx.send(ProcessState::Running);
+        let run_start = Instant::now();
+        let code = tokio::select! {
+            status = child.wait() => status.ok().and_then(|s| s.code()),
+            _ = stop_rx.changed() => {
+                child.start_kill().ok();
+                let status = child.wait().await;
+                status.ok().and_then(|s| s.code())
+            }
+        };
+        let ran_for = run_start.elapsed();
+        *pid_slot.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        *last_exit_code.lock().unwrap_or_else(|e| e.into_inner()) = code;
+
+        if *stop_rx.borrow() {
+            let _ = state_tx.send(ProcessState::Stopped);
+            return;
+        }
+        if !config.restart_policy.should_restart(code) {
+            let _ = state_tx.send(ProcessState::Exited { code });
+            return;
+        }
+        let backoff = config
+            .restart_policy
+            .backoff()
+            .expect("should_restart() was true, so this policy carries a BackoffPolicy");
+        if ran_for >= Duration::from_millis(backoff.reset_after_ms) {
+            attempt = 0;
+        }
+        if backoff.max_retries.is_some_and(|max| attempt >= max) {
+            let _ = state_tx.send(ProcessState::Exited { code });
+            return;
+        }
+        let delay = backoff.delay_ms(attempt);
+        attempt += 1;
+        restart_count.fetch_add(1, Ordering::SeqCst);
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(delay)) => {}
+            _ = stop_rx.changed() => {
+                let _ = state_tx.send(ProcessState::Stopped);
+                return;
+            }
+        }
+    }
+}
+
+/// Probes `health_check` on its configured interval for as long as the process isn't in a
+/// terminal state, flipping `state_tx` to/from `Unhealthy` after `failure_threshold` consecutive
+/// failures. Never kills or restarts the process itself — that decision stays with
+/// [`run_supervised`], triggered only by an actual exit.
+async fn run_health_check(health_check: HealthCheck, state_tx: watch::Sender<ProcessState>) {
+    let mut consecutive_failures = 0u32;
+    let mut ticker = tokio::time::interval(Duration::from_millis(health_check.interval_ms.max(1)));
+    loop {
+        ticker.tick().await;
+        if state_tx.borrow().is_stopped() {
+            return;
+        }
+        if probe_once(&health_check).await {
+            consecutive_failures = 0;
+            if *state_tx.borrow() == ProcessState::Unhealthy {
+                let _ = state_tx.send(ProcessState::Running);
+            }
+            continue;
+        }
+        consecutive_failures += 1;
+        if consecutive_failures >= health_check.failure_threshold && state_tx.borrow().is_running()
+        {
+            let _ = state_tx.send(ProcessState::Unhealthy);
+        }
+    }
+}
+
+async fn probe_once(health_check: &HealthCheck) -> bool {
+    let timeout = Duration::from_millis(health_check.timeout_ms.max(1));
+    match &health_check.probe {
+        HealthProbe::Exec { command, args } => {
+            let mut cmd = Command::new(command);
+            cmd.args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+            matches!(
+                tokio::time::timeout(timeout, cmd.status()).await,
+                Ok(Ok(status)) if status.success()
+            )
+        }
+        HealthProbe::Http { url } => {
+            let Ok(client) = reqwest::Client::builder().timeout(timeout).build() else {
+                return false;
+            };
+            matches!(client.get(url).send().await, Ok(resp) if resp.status().is_success())
+        }
+    }
+}
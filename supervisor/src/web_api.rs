@@ -0,0 +1,211 @@
+// SPDX-FileCopyrightText: © 2024 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The wire shapes `supervisor_client` talks to: a uniform [`Response`] envelope around
+//! [`crate::ProcessInfo`]/[`crate::ProcessStatus`], plus [`stream_process_logs`], the
+//! process-log-tailing logic behind a `GET /processes/{id}/logs?follow=true&tail=N` route.
+//!
+//! `stream_process_logs` is deliberately framework-agnostic: it only needs an
+//! `mpsc::Sender<ProcessLogEvent>` to push into, so whatever HTTP server the supervisor binary
+//! is wired up with (not present in this checkout) can drive it into an SSE or chunked response
+//! without this module depending on that server's types.
+//!
+//! [`process_stats`] and [`render_metrics`] are the same way: they're the logic behind a
+//! `GET /processes/{id}/stats` route and a Prometheus-format `GET /metrics` route respectively,
+//! left for whatever HTTP server hosts this crate to mount. Unlike `vmm`'s pRPC surfaces, this
+//! crate has no `.proto`-generated `FILE_DESCRIPTOR_SET`/`build_openapi_doc` of its own, so these
+//! plain JSON/text endpoints aren't (yet) listed in an RPC Explorer the way `vmm_rpc`'s services
+//! are — that would need a real pRPC service definition for the supervisor, not just these two
+//! handlers.
+
+use crate::process::{ProcessState, ProcessStats};
+use crate::supervisor::Supervisor;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+
+/// A uniform response envelope every `web_api` route returns, so `supervisor_client` has one
+/// success/error JSON shape to deserialize regardless of endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Response<T> {
+    Ok(T),
+    Error { message: String },
+}
+
+impl<T> Response<T> {
+    pub fn ok(value: T) -> Self {
+        Response::Ok(value)
+    }
+
+    pub fn err(message: impl Into<String>) -> Self {
+        Response::Error {
+            message: message.into(),
+        }
+    }
+}
+
+/// One item of a `GET /processes/{id}/logs` stream: either a chunk of raw log output or a
+/// [`ProcessState`] transition, so a single stream carries both without a second connection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProcessLogEvent {
+    Log { data: String },
+    State { state: ProcessState },
+}
+
+/// Streams `id`'s buffered log tail, then (while `follow`) its live output and state
+/// transitions, as [`ProcessLogEvent`]s sent to `sink`.
+///
+/// The buffered tail is always sent first and in full before subscribing to live output, so a
+/// late-arriving follower never sees a gap between what it's shown and what happens next. Once
+/// `follow` live output starts, a lagging receiver (see [`broadcast::error::RecvError::Lagged`])
+/// silently skips ahead to the latest frame rather than blocking the process's own output
+/// reader, matching [`broadcast`]'s drop-oldest semantics. The stream ends cleanly as soon as
+/// the process's `Exited` state has been forwarded.
+pub async fn stream_process_logs(
+    supervisor: &Supervisor,
+    id: &str,
+    tail: usize,
+    follow: bool,
+    sink: mpsc::Sender<ProcessLogEvent>,
+) -> Result<()> {
+    let backlog = supervisor.tail_logs(id, tail).await?;
+    if !backlog.is_empty() {
+        let data = String::from_utf8_lossy(&backlog).into_owned();
+        if sink.send(ProcessLogEvent::Log { data }).await.is_err() {
+            return Ok(());
+        }
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let mut logs = supervisor.subscribe_logs(id).await?;
+    let mut state = supervisor.subscribe_state(id).await?;
+    loop {
+        tokio::select! {
+            changed = state.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let current = *state.borrow();
+                let is_terminal = matches!(current, ProcessState::Exited { .. });
+                if sink.send(ProcessLogEvent::State { state: current }).await.is_err() {
+                    break;
+                }
+                if is_terminal {
+                    break;
+                }
+            }
+            chunk = logs.recv() => {
+                match chunk {
+                    Ok(data) => {
+                        let data = String::from_utf8_lossy(&data).into_owned();
+                        if sink.send(ProcessLogEvent::Log { data }).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Behind `GET /processes/{id}/stats`: `id`'s current [`ProcessStats`], or `Ok(Response::err)`
+/// if it's not supervised.
+pub async fn process_stats(supervisor: &Supervisor, id: &str) -> Result<Response<ProcessStats>> {
+    match supervisor.stats(id).await {
+        Ok(stats) => Ok(Response::ok(stats.unwrap_or_default())),
+        Err(e) => Ok(Response::err(e.to_string())),
+    }
+}
+
+/// Behind `GET /metrics`: a Prometheus text-format exposition of every supervised process's
+/// running state, restart count, and [`ProcessStats`]. Stats that couldn't be sampled (process
+/// not running, or a non-Linux host) are simply omitted from that process's series rather than
+/// reported as a misleading zero.
+pub async fn render_metrics(supervisor: &Supervisor) -> Result<String> {
+    let infos = supervisor.list().await?;
+    let mut samples = Vec::with_capacity(infos.len());
+    for info in &infos {
+        let stats = supervisor.stats(&info.config.id).await?.unwrap_or_default();
+        samples.push((info, stats));
+    }
+
+    let mut out = String::new();
+    out.push_str(
+        "# HELP dstack_supervisor_process_running Whether a supervised process is running (1) or not (0).\n\
+         # TYPE dstack_supervisor_process_running gauge\n",
+    );
+    for (info, _) in &samples {
+        out.push_str(&format!(
+            "dstack_supervisor_process_running{{id=\"{}\"}} {}\n",
+            info.config.id,
+            info.state.status.is_running() as u8
+        ));
+    }
+    out.push_str(
+        "# HELP dstack_supervisor_process_restart_count Number of times a process has been relaunched.\n\
+         # TYPE dstack_supervisor_process_restart_count counter\n",
+    );
+    for (info, _) in &samples {
+        out.push_str(&format!(
+            "dstack_supervisor_process_restart_count{{id=\"{}\"}} {}\n",
+            info.config.id, info.state.restart_count
+        ));
+    }
+    push_stat_metric(
+        &mut out,
+        &samples,
+        "dstack_supervisor_process_cpu_time_ms",
+        "Total CPU time consumed since the process started, in milliseconds.",
+        |s| s.cpu_time_ms,
+    );
+    push_stat_metric(
+        &mut out,
+        &samples,
+        "dstack_supervisor_process_rss_bytes",
+        "Resident set size, in bytes.",
+        |s| s.rss_bytes,
+    );
+    push_stat_metric(
+        &mut out,
+        &samples,
+        "dstack_supervisor_process_read_bytes",
+        "Cumulative bytes read from storage.",
+        |s| s.read_bytes,
+    );
+    push_stat_metric(
+        &mut out,
+        &samples,
+        "dstack_supervisor_process_write_bytes",
+        "Cumulative bytes written to storage.",
+        |s| s.write_bytes,
+    );
+    Ok(out)
+}
+
+fn push_stat_metric(
+    out: &mut String,
+    samples: &[(&crate::process::ProcessInfo, ProcessStats)],
+    name: &str,
+    help: &str,
+    extract: impl Fn(&ProcessStats) -> Option<u64>,
+) {
+    let rows: Vec<_> = samples
+        .iter()
+        .filter_map(|(info, stats)| extract(stats).map(|value| (&info.config.id, value)))
+        .collect();
+    if rows.is_empty() {
+        return;
+    }
+    out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+    for (id, value) in rows {
+        out.push_str(&format!("{name}{{id=\"{id}\"}} {value}\n"));
+    }
+}
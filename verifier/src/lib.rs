@@ -1,11 +1,16 @@
+pub mod revocation;
+pub mod tcb_policy;
 pub mod types;
 pub mod verification;
 
+pub use revocation::RevocationCascade;
+pub use tcb_policy::{TcbPolicy, TcbPolicyConfig, TcbPolicyDecision};
 pub use types::{
-    AcpiTables, ErrorResponse, RtmrEventEntry, RtmrEventStatus, RtmrMismatch, VerificationDetails,
-    VerificationRequest, VerificationResponse,
+    AcpiTables, CacheLimits, CacheUsageResponse, CachedImageEntry, CachedMeasurementEntry,
+    ErrorResponse, PrewarmRequest, PrewarmResponse, RtmrEventEntry, RtmrEventStatus, RtmrMismatch,
+    VerificationDetails, VerificationRequest, VerificationResponse,
 };
-pub use verification::CvmVerifier;
+pub use verification::{Cancelled, CvmVerifier};
 
 // Re-export Attestation from ra_tls for convenience
 pub use ra_tls::attestation::Attestation;
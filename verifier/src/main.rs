@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use std::io::Write;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
@@ -10,15 +11,32 @@ use figment::{
     providers::{Env, Format, Toml},
     Figment,
 };
-use rocket::{fairing::AdHoc, get, post, serde::json::Json, State};
+use flate2::Compression;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use rocket::{
+    delete,
+    fairing::AdHoc,
+    get,
+    http::{Header, Status},
+    post,
+    response::Responder,
+    serde::json::Json,
+    Request, State,
+};
 use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 
+mod revocation;
+mod tcb_policy;
 mod types;
 mod verification;
 
-use types::{VerificationRequest, VerificationResponse};
-use verification::CvmVerifier;
+use types::{
+    CacheUsageResponse, ErrorResponse, PrewarmRequest, PrewarmResponse, VerificationRequest,
+    VerificationResponse,
+};
+use verification::{Cancelled, CvmVerifier};
 
 #[derive(Parser)]
 #[command(name = "dstack-verifier")]
@@ -30,6 +48,10 @@ struct Cli {
     /// Oneshot mode: verify a single report JSON file and exit
     #[arg(long, value_name = "FILE")]
     verify: Option<String>,
+
+    /// In oneshot mode, bypass the verification-result cache entirely
+    #[arg(long)]
+    no_cache: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -40,18 +62,131 @@ pub struct Config {
     pub pccs_url: Option<String>,
     pub image_download_url: String,
     pub image_download_timeout_secs: u64,
+    /// Fallback mirrors for `image_download_url`, tried in order after the primary URL on a
+    /// retryable failure. Each entry is templated the same way as `image_download_url`.
+    #[serde(default)]
+    pub image_mirror_urls: Vec<String>,
+    /// LRU eviction budget for `images/<hash>` cache directories. `None` (the default) leaves
+    /// that dimension unbounded.
+    #[serde(default)]
+    pub image_cache_max_bytes: Option<u64>,
+    #[serde(default)]
+    pub image_cache_max_entries: Option<usize>,
+    /// LRU eviction budget for `measurements/*.json` cache entries. `None` (the default) leaves
+    /// that dimension unbounded.
+    #[serde(default)]
+    pub measurement_cache_max_bytes: Option<u64>,
+    #[serde(default)]
+    pub measurement_cache_max_entries: Option<usize>,
+    /// TCB `tcb_status`/`advisory_ids` acceptance policy (see [`tcb_policy::TcbPolicyConfig`]).
+    /// `None` (the default) performs no TCB policy enforcement at all.
+    #[serde(default)]
+    pub tcb_policy: Option<tcb_policy::TcbPolicyConfig>,
+    /// Path to a signed TUF-style upgrade-authority bundle (see
+    /// [`verification::upgrade_authority::UpgradeAuthority::load`]) gating which release a boot
+    /// is accepted against, with rollback protection. `None` (the default) performs no
+    /// enforcement.
+    #[serde(default)]
+    pub upgrade_authority_bundle_path: Option<String>,
+}
+
+/// Wraps a serialized JSON body, optionally gzip/deflate-compressing it so `/verify` responses
+/// can carry a `Content-Encoding` the same way `vmm`'s log-streaming endpoints do. Verification
+/// responses are small and fully buffered up front (unlike a log tail), so compression here is a
+/// one-shot encode rather than a flush-per-chunk streaming encoder.
+struct CompressibleJson {
+    body: Vec<u8>,
+    encoding: Option<&'static str>,
+}
+
+impl<'r> Responder<'r, 'static> for CompressibleJson {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let mut response = self.body.respond_to(request)?;
+        response.set_header(rocket::http::ContentType::JSON);
+        if let Some(encoding) = self.encoding {
+            response.set_header(Header::new("Content-Encoding", encoding));
+        }
+        Ok(response)
+    }
+}
+
+/// Picks a response content-coding from the client's `Accept-Encoding` header, unless `disabled`
+/// (the route's `compress=false` escape hatch for debugging) is set. Prefers gzip when both are
+/// offered since it's the more widely cached/understood of the two.
+fn negotiate_encoding(req: &Request<'_>, disabled: bool) -> Option<&'static str> {
+    if disabled {
+        return None;
+    }
+    let accept_encoding = req.headers().get_one("Accept-Encoding")?;
+    if accept_encoding
+        .split(',')
+        .any(|coding| coding.split(';').next().unwrap_or("").trim() == "gzip")
+    {
+        Some("gzip")
+    } else if accept_encoding
+        .split(',')
+        .any(|coding| coding.split(';').next().unwrap_or("").trim() == "deflate")
+    {
+        Some("deflate")
+    } else {
+        None
+    }
 }
 
-#[post("/verify", data = "<request>")]
+fn gzip_or_deflate(body: Vec<u8>, encoding: Option<&str>) -> Vec<u8> {
+    match encoding {
+        Some("gzip") => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Compression::default());
+            let _ = encoder.write_all(&body);
+            encoder.finish().unwrap_or_default()
+        }
+        Some("deflate") => {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), Compression::default());
+            let _ = encoder.write_all(&body);
+            encoder.finish().unwrap_or_default()
+        }
+        _ => body,
+    }
+}
+
+#[post("/verify?<compress>", data = "<request>")]
 async fn verify_cvm(
     verifier: &State<Arc<CvmVerifier>>,
+    req: &Request<'_>,
     request: Json<VerificationRequest>,
-) -> Json<VerificationResponse> {
-    match verifier.verify(&request.into_inner()).await {
-        Ok(response) => Json(response),
+    compress: Option<bool>,
+    shutdown: rocket::Shutdown,
+) -> CompressibleJson {
+    // Cancel the in-flight verification as soon as a graceful shutdown is triggered, instead of
+    // leaving it to run to completion (or be dropped mid-write) while the process is exiting.
+    let cancellation = CancellationToken::new();
+    let cancel_on_shutdown = cancellation.clone();
+    let shutdown_watcher = tokio::spawn(async move {
+        shutdown.await;
+        cancel_on_shutdown.cancel();
+    });
+    let response = match verifier.verify(&request.into_inner(), &cancellation).await {
+        Ok(response) => response,
+        Err(e) if e.is::<Cancelled>() => VerificationResponse {
+            is_valid: false,
+            details: types::VerificationDetails {
+                quote_verified: false,
+                event_log_verified: false,
+                os_image_hash_verified: false,
+                report_data: None,
+                tcb_status: None,
+                advisory_ids: vec![],
+                tcb_policy_decision: None,
+                app_info: None,
+                acpi_tables: None,
+                rtmr_debug: None,
+            },
+            reason: Some("Verification cancelled due to server shutdown".to_string()),
+        },
         Err(e) => {
             error!("Verification failed: {:?}", e);
-            Json(VerificationResponse {
+            VerificationResponse {
                 is_valid: false,
                 details: types::VerificationDetails {
                     quote_verified: false,
@@ -60,13 +195,21 @@ async fn verify_cvm(
                     report_data: None,
                     tcb_status: None,
                     advisory_ids: vec![],
+                    tcb_policy_decision: None,
                     app_info: None,
                     acpi_tables: None,
                     rtmr_debug: None,
                 },
                 reason: Some(format!("Internal error: {}", e)),
-            })
+            }
         }
+    };
+    shutdown_watcher.abort();
+    let encoding = negotiate_encoding(req, compress == Some(false));
+    let body = serde_json::to_vec(&response).unwrap_or_default();
+    CompressibleJson {
+        body: gzip_or_deflate(body, encoding),
+        encoding,
     }
 }
 
@@ -78,7 +221,84 @@ fn health() -> Json<serde_json::Value> {
     }))
 }
 
-async fn run_oneshot(file_path: &str, config: &Config) -> anyhow::Result<()> {
+/// Exposes `verifier::verification`'s verification/failure/TCB-status counters and image-download
+/// latency histogram (plus the process defaults the `metrics` crate ships) in OpenMetrics/
+/// Prometheus text format, for scraping by standard monitoring.
+#[get("/metrics")]
+fn metrics(handle: &State<PrometheusHandle>) -> String {
+    handle.render()
+}
+
+/// Lists every cached OS image directory and measurement cache entry with their on-disk sizes.
+#[get("/cache")]
+fn cache_usage(
+    verifier: &State<Arc<CvmVerifier>>,
+) -> Result<Json<CacheUsageResponse>, Json<ErrorResponse>> {
+    verifier.cache_usage().map(Json).map_err(|e| {
+        error!("Failed to read cache usage: {e:?}");
+        Json(ErrorResponse {
+            error: "Failed to read cache usage".to_string(),
+            details: Some(format!("{e:#}")),
+        })
+    })
+}
+
+/// Evicts the measurement cache entry `cache_key`, returning 404 if it isn't present.
+#[delete("/cache/measurements/<cache_key>")]
+fn evict_measurement_cache_entry(
+    verifier: &State<Arc<CvmVerifier>>,
+    cache_key: &str,
+) -> Result<(), Status> {
+    match verifier.evict_measurement_cache_entry(cache_key) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Status::NotFound),
+        Err(e) => {
+            error!("Failed to evict measurement cache entry {cache_key}: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Pre-warms the image download and measurement cache for a `VmConfig`, without requiring a
+/// quote or event log.
+#[post("/cache/prewarm", data = "<request>")]
+async fn prewarm(
+    verifier: &State<Arc<CvmVerifier>>,
+    request: Json<PrewarmRequest>,
+) -> Result<Json<PrewarmResponse>, Json<ErrorResponse>> {
+    let vm_config: dstack_types::VmConfig =
+        serde_json::from_str(&request.vm_config).map_err(|e| {
+            Json(ErrorResponse {
+                error: "Failed to decode VM config JSON".to_string(),
+                details: Some(e.to_string()),
+            })
+        })?;
+
+    verifier.prewarm(&vm_config).await.map(Json).map_err(|e| {
+        error!("Prewarm failed: {e:?}");
+        Json(ErrorResponse {
+            error: "Prewarm failed".to_string(),
+            details: Some(format!("{e:#}")),
+        })
+    })
+}
+
+/// Generated JSON Schema for every request/response type exposed by this service, so clients in
+/// other languages can be built against a stable, machine-readable contract instead of hand-copying
+/// these structs.
+#[get("/schema")]
+fn schema() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "verification_request": schemars::schema_for!(VerificationRequest),
+        "verification_response": schemars::schema_for!(VerificationResponse),
+        "error_response": schemars::schema_for!(ErrorResponse),
+        "cache_usage_response": schemars::schema_for!(CacheUsageResponse),
+        "prewarm_request": schemars::schema_for!(PrewarmRequest),
+        "prewarm_response": schemars::schema_for!(PrewarmResponse),
+    }))
+}
+
+async fn run_oneshot(file_path: &str, config: &Config, no_cache: bool) -> anyhow::Result<()> {
     use std::fs;
 
     info!("Running in oneshot mode for file: {}", file_path);
@@ -99,11 +319,31 @@ async fn run_oneshot(file_path: &str, config: &Config) -> anyhow::Result<()> {
         config.image_cache_dir.clone(),
         config.image_download_url.clone(),
         std::time::Duration::from_secs(config.image_download_timeout_secs),
-    );
+    )
+    .with_mirror_urls(config.image_mirror_urls.clone())
+    .with_image_cache_limits(config.image_cache_max_bytes, config.image_cache_max_entries)
+    .with_measurement_cache_limits(
+        config.measurement_cache_max_bytes,
+        config.measurement_cache_max_entries,
+    )
+    .with_tcb_policy(config.tcb_policy.clone())
+    .with_upgrade_authority(
+        config
+            .upgrade_authority_bundle_path
+            .as_deref()
+            .map(std::path::Path::new),
+    )?;
 
     // Run verification
     info!("Starting verification...");
-    let response = verifier.verify(&request).await?;
+    let cancellation = CancellationToken::new();
+    let response = if no_cache {
+        verifier
+            .verify_without_cache(&request, &cancellation)
+            .await?
+    } else {
+        verifier.verify(&request, &cancellation).await?
+    };
 
     // Persist response next to the input file for convenience
     let output_path = format!("{file_path}.verification.json");
@@ -139,6 +379,10 @@ async fn run_oneshot(file_path: &str, config: &Config) -> anyhow::Result<()> {
         println!("Advisory IDs: {:?}", response.details.advisory_ids);
     }
 
+    if let Some(decision) = &response.details.tcb_policy_decision {
+        println!("TCB policy decision: {:?}", decision);
+    }
+
     if let Some(reason) = &response.reason {
         println!("Reason: {}", reason);
     }
@@ -186,7 +430,7 @@ async fn main() -> Result<()> {
         // Run oneshot verification and exit
         let rt = tokio::runtime::Runtime::new().context("Failed to create runtime")?;
         rt.block_on(async {
-            if let Err(e) = run_oneshot(&file_path, &config).await {
+            if let Err(e) = run_oneshot(&file_path, &config, cli.no_cache).await {
                 error!("Oneshot verification failed: {:#}", e);
                 std::process::exit(1);
             }
@@ -194,15 +438,47 @@ async fn main() -> Result<()> {
         std::process::exit(0);
     }
 
-    let verifier = Arc::new(CvmVerifier::new(
-        config.image_cache_dir.clone(),
-        config.image_download_url.clone(),
-        std::time::Duration::from_secs(config.image_download_timeout_secs),
-    ));
+    let verifier = Arc::new(
+        CvmVerifier::new(
+            config.image_cache_dir.clone(),
+            config.image_download_url.clone(),
+            std::time::Duration::from_secs(config.image_download_timeout_secs),
+        )
+        .with_mirror_urls(config.image_mirror_urls.clone())
+        .with_image_cache_limits(config.image_cache_max_bytes, config.image_cache_max_entries)
+        .with_measurement_cache_limits(
+            config.measurement_cache_max_bytes,
+            config.measurement_cache_max_entries,
+        )
+        .with_tcb_policy(config.tcb_policy.clone())
+        .with_upgrade_authority(
+            config
+                .upgrade_authority_bundle_path
+                .as_deref()
+                .map(std::path::Path::new),
+        )
+        .context("Failed to configure upgrade authority")?,
+    );
+
+    let prometheus_handle = PrometheusBuilder::new()
+        .install_recorder()
+        .context("Failed to install Prometheus metrics recorder")?;
 
     rocket::custom(figment)
-        .mount("/", rocket::routes![verify_cvm, health])
+        .mount(
+            "/",
+            rocket::routes![
+                verify_cvm,
+                health,
+                metrics,
+                cache_usage,
+                evict_measurement_cache_entry,
+                prewarm,
+                schema
+            ],
+        )
         .manage(verifier)
+        .manage(prometheus_handle)
         .attach(AdHoc::on_liftoff("Startup", |_| {
             Box::pin(async {
                 info!("dstack-verifier started successfully");
@@ -0,0 +1,292 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! A Bloom filter cascade (as used for certificate revocation in CRLite-style systems) that
+//! answers "is this identity revoked?" for `os_image_hash`es and TCB/FMSPC identities, without
+//! having to ship or scan an explicit revocation list on every [`super::verification::CvmVerifier`]
+//! query.
+//!
+//! Built from two disjoint sets known at cascade-build time: `revoked` (R) and `valid` (S).
+//! Level 0 is sized to hold R with no false negatives; some members of S will still collide with
+//! it ("false positives"). Those collisions become the include-set for level 1, with R as the
+//! exclude set probed against it; the roles keep alternating until a level produces no false
+//! positives against its exclude set, which is the point at which the cascade exactly separates R
+//! from S. [`RevocationCascade::is_revoked`] descends the same levels at query time, alternating
+//! which answer "present" supports, and returns the moment an identity first drops out.
+
+use std::{io::Write, path::Path};
+
+use anyhow::{bail, ensure, Context, Result};
+use sha2::{Digest as _, Sha256};
+
+/// Target false-positive rate used when sizing each level's filter against its include set.
+const DEFAULT_FALSE_POSITIVE_RATE: f64 = 0.001;
+
+/// Hard cap on cascade depth, as a backstop against pathological inputs (e.g. near-duplicate R/S)
+/// that would otherwise alternate without ever converging.
+const MAX_LEVELS: usize = 16;
+
+const CASCADE_MAGIC: u32 = 0x4443_4c31; // "DCL1"
+const CASCADE_VERSION: u32 = 1;
+
+/// One level of the cascade: a Bloom filter over some include-set, sized for `num_hashes` probes
+/// per element into a `num_bits`-wide bit array.
+#[derive(Debug, Clone)]
+struct BloomLevel {
+    num_bits: u64,
+    num_hashes: u32,
+    bits: Vec<u8>,
+}
+
+impl BloomLevel {
+    fn build(members: &[Vec<u8>], salt: &[u8; 16], level: u32, fp_rate: f64) -> Self {
+        let num_bits = optimal_num_bits(members.len(), fp_rate);
+        let num_hashes = optimal_num_hashes(num_bits, members.len());
+        let bits = vec![0u8; (num_bits as usize).div_ceil(8)];
+        let mut filter = BloomLevel {
+            num_bits,
+            num_hashes,
+            bits,
+        };
+        for member in members {
+            filter.insert(member, salt, level);
+        }
+        filter
+    }
+
+    fn insert(&mut self, member: &[u8], salt: &[u8; 16], level: u32) {
+        for bit_index in self.bit_indices(member, salt, level) {
+            let (byte, bit) = (bit_index / 8, bit_index % 8);
+            self.bits[byte as usize] |= 1 << bit;
+        }
+    }
+
+    fn contains(&self, member: &[u8], salt: &[u8; 16], level: u32) -> bool {
+        self.bit_indices(member, salt, level).all(|bit_index| {
+            let (byte, bit) = (bit_index / 8, bit_index % 8);
+            self.bits[byte as usize] & (1 << bit) != 0
+        })
+    }
+
+    /// Derives `num_hashes` bit positions via Kirsch-Mitzenmacher double hashing: two independent
+    /// 64-bit hashes `h1`/`h2` (the two halves of `SHA256(salt || level || member)`) combined as
+    /// `h1 + i * h2` for `i in 0..num_hashes`, which is statistically equivalent to `num_hashes`
+    /// independent hash functions without having to run SHA-256 per probe.
+    fn bit_indices(
+        &self,
+        member: &[u8],
+        salt: &[u8; 16],
+        level: u32,
+    ) -> impl Iterator<Item = u64> + '_ {
+        let mut hasher = Sha256::new();
+        hasher.update(salt);
+        hasher.update(level.to_le_bytes());
+        hasher.update(member);
+        let digest = hasher.finalize();
+        let h1 = u64::from_le_bytes(digest[0..8].try_into().expect("8 bytes"));
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().expect("8 bytes"));
+        let num_bits = self.num_bits;
+        (0..self.num_hashes).map(move |i| h1.wrapping_add((i as u64).wrapping_mul(h2)) % num_bits)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + 4 + 8 + self.bits.len());
+        out.extend_from_slice(&self.num_bits.to_le_bytes());
+        out.extend_from_slice(&self.num_hashes.to_le_bytes());
+        out.extend_from_slice(&(self.bits.len() as u64).to_le_bytes());
+        out.extend_from_slice(&self.bits);
+        out
+    }
+
+    fn from_reader(cursor: &mut &[u8]) -> Result<Self> {
+        let num_bits = take_u64(cursor)?;
+        let num_hashes = take_u32(cursor)?;
+        let bits_len = take_u64(cursor)? as usize;
+        ensure!(
+            cursor.len() >= bits_len,
+            "truncated revocation cascade: expected {bits_len} more bytes, have {}",
+            cursor.len()
+        );
+        let bits = cursor[..bits_len].to_vec();
+        *cursor = &cursor[bits_len..];
+        Ok(BloomLevel {
+            num_bits,
+            num_hashes,
+            bits,
+        })
+    }
+}
+
+fn optimal_num_bits(num_members: usize, fp_rate: f64) -> u64 {
+    // m = -n * ln(p) / (ln 2)^2, floored at a small constant so an empty or tiny include set still
+    // yields a usable (if oversized-relative-to-n) filter.
+    let n = num_members.max(1) as f64;
+    let m = -(n * fp_rate.ln()) / std::f64::consts::LN_2.powi(2);
+    (m.ceil() as u64).max(64)
+}
+
+fn optimal_num_hashes(num_bits: u64, num_members: usize) -> u32 {
+    let n = num_members.max(1) as f64;
+    let k = (num_bits as f64 / n) * std::f64::consts::LN_2;
+    (k.round() as u32).clamp(1, 32)
+}
+
+fn take_u32(cursor: &mut &[u8]) -> Result<u32> {
+    ensure!(cursor.len() >= 4, "truncated revocation cascade");
+    let value = u32::from_le_bytes(cursor[..4].try_into().expect("4 bytes"));
+    *cursor = &cursor[4..];
+    Ok(value)
+}
+
+fn take_u64(cursor: &mut &[u8]) -> Result<u64> {
+    ensure!(cursor.len() >= 8, "truncated revocation cascade");
+    let value = u64::from_le_bytes(cursor[..8].try_into().expect("8 bytes"));
+    *cursor = &cursor[8..];
+    Ok(value)
+}
+
+/// A built Bloom filter cascade, loadable from and persistable to a versioned binary blob under
+/// the image cache dir (see [`Self::load`]/[`Self::save`]), independent of the image cache itself
+/// so it can be refreshed on its own schedule.
+#[derive(Debug, Clone)]
+pub struct RevocationCascade {
+    salt: [u8; 16],
+    levels: Vec<BloomLevel>,
+}
+
+impl RevocationCascade {
+    /// Builds a cascade that exactly separates `revoked` from `valid` (both sets of opaque
+    /// identity bytes, e.g. a raw `os_image_hash` or a TCB/FMSPC encoding), using `salt` to
+    /// decorrelate this cascade's hash positions from any other cascade built from the same
+    /// inputs.
+    pub fn build(revoked: &[Vec<u8>], valid: &[Vec<u8>], salt: [u8; 16]) -> Self {
+        let mut levels = Vec::new();
+        let mut include = revoked.to_vec();
+        // Level 0's exclude set is `valid`; it alternates with `revoked` at each subsequent level.
+        let mut exclude_is_valid = true;
+
+        while levels.len() < MAX_LEVELS {
+            let level = levels.len() as u32;
+            let filter = BloomLevel::build(&include, &salt, level, DEFAULT_FALSE_POSITIVE_RATE);
+
+            let exclude: &[Vec<u8>] = if exclude_is_valid { valid } else { revoked };
+            let false_positives: Vec<Vec<u8>> = exclude
+                .iter()
+                .filter(|member| filter.contains(member, &salt, level))
+                .cloned()
+                .collect();
+
+            levels.push(filter);
+
+            if false_positives.is_empty() {
+                break;
+            }
+            include = false_positives;
+            exclude_is_valid = !exclude_is_valid;
+        }
+
+        Self { salt, levels }
+    }
+
+    /// Returns whether `identity` is revoked, per the cascade built at construction time. Only
+    /// meaningful for identities that were members of the `revoked`/`valid` sets the cascade was
+    /// built from (or anything that hashes identically to one of them); for anything else this is
+    /// the cascade's best guess, biased towards false positives on the `revoked` side like any
+    /// Bloom filter.
+    pub fn is_revoked(&self, identity: &[u8]) -> bool {
+        for (level_index, level) in self.levels.iter().enumerate() {
+            if !level.contains(identity, &self.salt, level_index as u32) {
+                // Absent at an even level (built with revoked-leaning include set) proves "not
+                // revoked"; absent at an odd level (built with valid-leaning include set) proves
+                // the opposite.
+                return level_index % 2 == 1;
+            }
+        }
+        // Present through every level: the last level had no false positives against its exclude
+        // set, so presence here proves membership in its include set rather than a collision.
+        self.levels
+            .len()
+            .checked_sub(1)
+            .map(|last| last % 2 == 0)
+            .unwrap_or(false)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&CASCADE_MAGIC.to_le_bytes());
+        out.extend_from_slice(&CASCADE_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&(self.levels.len() as u32).to_le_bytes());
+        for level in &self.levels {
+            out.extend_from_slice(&level.to_bytes());
+        }
+        out
+    }
+
+    fn from_bytes(data: &[u8]) -> Result<Self> {
+        let mut cursor = data;
+        let magic = take_u32(&mut cursor)?;
+        ensure!(magic == CASCADE_MAGIC, "not a revocation cascade blob");
+        let version = take_u32(&mut cursor)?;
+        ensure!(
+            version == CASCADE_VERSION,
+            "unsupported revocation cascade version {version}, expected {CASCADE_VERSION}"
+        );
+        ensure!(cursor.len() >= 16, "truncated revocation cascade salt");
+        let mut salt = [0u8; 16];
+        salt.copy_from_slice(&cursor[..16]);
+        cursor = &cursor[16..];
+
+        let level_count = take_u32(&mut cursor)? as usize;
+        let mut levels = Vec::with_capacity(level_count);
+        for _ in 0..level_count {
+            levels.push(BloomLevel::from_reader(&mut cursor)?);
+        }
+        if !cursor.is_empty() {
+            bail!("trailing bytes after revocation cascade");
+        }
+
+        Ok(Self { salt, levels })
+    }
+
+    /// Persists the cascade as a versioned binary blob at `path`, via a temp-file-then-rename so a
+    /// concurrent [`Self::load`] never observes a partially-written file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let parent = path
+            .parent()
+            .context("revocation cascade path has no parent")?;
+        fs_err::create_dir_all(parent).context("failed to create revocation cascade directory")?;
+
+        let mut tmp = tempfile::NamedTempFile::new_in(parent)
+            .context("failed to create temporary revocation cascade file")?;
+        tmp.as_file_mut()
+            .write_all(&self.to_bytes())
+            .context("failed to write revocation cascade")?;
+        tmp.as_file_mut()
+            .sync_all()
+            .context("failed to flush revocation cascade to disk")?;
+        tmp.persist(path).map_err(|e| {
+            anyhow::anyhow!(
+                "failed to persist revocation cascade to {}: {e}",
+                path.display()
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Loads a cascade previously written by [`Self::save`], or `None` if `path` doesn't exist.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs_err::read(path).context("failed to read revocation cascade")?;
+        Self::from_bytes(&data)
+            .with_context(|| format!("failed to parse revocation cascade at {}", path.display()))
+            .map(Some)
+    }
+}
@@ -0,0 +1,125 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! A configurable accept/warn/deny policy over the `tcb_status`/`advisory_ids` an attestation's
+//! quote carries, orthogonal to the MRTD/RTMR measurement comparison
+//! [`crate::verification::CvmVerifier::verify_os_image_hash`] already performs: measurements
+//! matching only proves the guest booted the expected software, not that the underlying
+//! platform's TCB is still trustworthy. Without this, an `OutOfDate` TCB status or an open
+//! advisory passes verification purely on the strength of matching measurements.
+//!
+//! Policies are declarative ([`TcbPolicyConfig`]) so deployments can tighten or relax acceptance
+//! (e.g. temporarily tolerating a specific advisory while a fix is staged) without recompiling.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Declarative TCB acceptance policy: which `tcb_status` values pass outright or only with a
+/// warning, and which `advisory_ids` are denied (with an escape hatch to tolerate specific ones
+/// anyway). See [`TcbPolicy::evaluate`] for how the fields combine into a single decision.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TcbPolicyConfig {
+    /// `tcb_status` values that pass with no warning, e.g. `"UpToDate"`.
+    #[serde(default)]
+    pub accepted_statuses: HashSet<String>,
+    /// `tcb_status` values that pass but are flagged, e.g. `"SWHardeningNeeded"` while a
+    /// mitigation is rolled out.
+    #[serde(default)]
+    pub warn_statuses: HashSet<String>,
+    /// Advisory IDs that deny acceptance outright when present in an attestation's
+    /// `advisory_ids`.
+    #[serde(default)]
+    pub denied_advisory_ids: HashSet<String>,
+    /// Advisory IDs that would otherwise be denied by `denied_advisory_ids` but are temporarily
+    /// tolerated, e.g. while a patched image is being rolled out to every deployment.
+    #[serde(default)]
+    pub tolerated_advisory_ids: HashSet<String>,
+}
+
+/// Outcome of [`TcbPolicy::evaluate`]: whether an attestation's TCB status and advisory IDs are
+/// acceptable, tolerable-with-a-warning, or denied. Carries the offending status/advisory IDs
+/// rather than collapsing to a bare bool, so callers and operators can see why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "verdict", rename_all = "snake_case")]
+pub enum TcbPolicyDecision {
+    Accept,
+    Warn { reason: String },
+    Deny { reason: String },
+}
+
+impl TcbPolicyDecision {
+    pub fn is_deny(&self) -> bool {
+        matches!(self, Self::Deny { .. })
+    }
+}
+
+/// Evaluator built from a [`TcbPolicyConfig`]; see [`Self::evaluate`].
+#[derive(Debug, Clone)]
+pub struct TcbPolicy {
+    config: TcbPolicyConfig,
+}
+
+impl TcbPolicy {
+    pub fn new(config: TcbPolicyConfig) -> Self {
+        Self { config }
+    }
+
+    /// Loads a [`TcbPolicyConfig`] from the JSON file at `path`, or returns `None` if `path`
+    /// doesn't exist (no policy configured, i.e. no TCB enforcement).
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents =
+            fs_err::read_to_string(path).context("Failed to read TCB policy config")?;
+        let config: TcbPolicyConfig =
+            serde_json::from_str(&contents).context("Failed to parse TCB policy config")?;
+        Ok(Some(Self::new(config)))
+    }
+
+    /// Classifies `tcb_status`/`advisory_ids` per the configured policy. Denied advisory IDs are
+    /// checked first and deny the attestation even if `tcb_status` is itself accepted; a
+    /// `tcb_status` absent from both `accepted_statuses` and `warn_statuses` is denied, so the
+    /// allowlist is closed by default — a newly introduced status needs an explicit policy update
+    /// before it's accepted. `tcb_status` being absent (no quote-level status available) is
+    /// treated as accepted, since there's nothing here to judge it against.
+    pub fn evaluate(&self, tcb_status: Option<&str>, advisory_ids: &[String]) -> TcbPolicyDecision {
+        let denied_advisories: Vec<&str> = advisory_ids
+            .iter()
+            .map(String::as_str)
+            .filter(|id| {
+                self.config.denied_advisory_ids.contains(*id)
+                    && !self.config.tolerated_advisory_ids.contains(*id)
+            })
+            .collect();
+        if !denied_advisories.is_empty() {
+            return TcbPolicyDecision::Deny {
+                reason: format!(
+                    "advisory ID(s) denied by policy: {}",
+                    denied_advisories.join(", ")
+                ),
+            };
+        }
+
+        let Some(status) = tcb_status else {
+            return TcbPolicyDecision::Accept;
+        };
+
+        if self.config.accepted_statuses.contains(status) {
+            TcbPolicyDecision::Accept
+        } else if self.config.warn_statuses.contains(status) {
+            TcbPolicyDecision::Warn {
+                reason: format!("TCB status {status} is accepted only with a warning"),
+            }
+        } else {
+            TcbPolicyDecision::Deny {
+                reason: format!("TCB status {status} is not accepted by policy"),
+            }
+        }
+    }
+}
@@ -3,9 +3,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use ra_tls::attestation::AppInfo;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::tcb_policy::TcbPolicyDecision;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VerificationRequest {
     pub quote: String,
     pub event_log: String,
@@ -14,21 +17,36 @@ pub struct VerificationRequest {
     pub debug: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct VerificationResponse {
     pub is_valid: bool,
     pub details: VerificationDetails,
     pub reason: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct VerificationDetails {
     pub quote_verified: bool,
     pub event_log_verified: bool,
     pub os_image_hash_verified: bool,
+    /// Whether the RA-TLS `app_cert`/`certificate_chain` chains to a trusted root and its
+    /// embedded quote extension matches `report_data`. See
+    /// [`crate::verification::CvmVerifier::verify_cert_chain`] for why this is currently always
+    /// `false`: that check needs `ra_tls::attestation`'s cert-chain fields and a trust-anchor
+    /// config this checkout doesn't have yet.
+    pub cert_chain_verified: bool,
     pub report_data: Option<String>,
     pub tcb_status: Option<String>,
     pub advisory_ids: Vec<String>,
+    /// Verdict of [`crate::verification::CvmVerifier`]'s configured TCB policy (see
+    /// [`crate::tcb_policy::TcbPolicy`]) against `tcb_status`/`advisory_ids`. `None` means no
+    /// policy was configured, not that one was evaluated and passed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcb_policy_decision: Option<TcbPolicyDecision>,
+    // `ra_tls::attestation::AppInfo` doesn't derive `JsonSchema` (same gap noted on
+    // `cert_chain_verified` above), so its shape is represented opaquely in the generated schema
+    // rather than left out of it.
+    #[schemars(with = "Option<serde_json::Value>")]
     pub app_info: Option<AppInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub acpi_tables: Option<AcpiTables>,
@@ -36,14 +54,14 @@ pub struct VerificationDetails {
     pub rtmr_debug: Option<Vec<RtmrMismatch>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct AcpiTables {
     pub tables: String,
     pub rsdp: String,
     pub loader: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct RtmrMismatch {
     pub rtmr: String,
     pub expected: String,
@@ -53,7 +71,7 @@ pub struct RtmrMismatch {
     pub missing_expected_digests: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct RtmrEventEntry {
     pub index: usize,
     pub event_type: u32,
@@ -65,7 +83,7 @@ pub struct RtmrEventEntry {
     pub status: RtmrEventStatus,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum RtmrEventStatus {
     Match,
@@ -74,8 +92,59 @@ pub enum RtmrEventStatus {
     Missing,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ErrorResponse {
     pub error: String,
     pub details: Option<String>,
 }
+
+/// On-disk size of one cached OS image directory, as reported by `GET /cache`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CachedImageEntry {
+    pub os_image_hash: String,
+    pub size_bytes: u64,
+    pub last_accessed_unix_secs: u64,
+}
+
+/// On-disk size of one cached measurement entry, as reported by `GET /cache`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CachedMeasurementEntry {
+    pub cache_key: String,
+    pub size_bytes: u64,
+    pub last_accessed_unix_secs: u64,
+}
+
+/// Configured LRU eviction budget for one cache (images or measurements). Either field being
+/// `None` means that dimension is unbounded; see
+/// [`crate::verification::CvmVerifier::with_image_cache_limits`] and
+/// [`crate::verification::CvmVerifier::with_measurement_cache_limits`].
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CacheLimits {
+    pub max_bytes: Option<u64>,
+    pub max_entries: Option<usize>,
+}
+
+/// Body of the `GET /cache` management endpoint: every cached image and measurement entry, with
+/// their sizes and last-access times, plus the configured eviction budgets, so operators can
+/// judge disk usage and tune limits without shelling into the cache directory.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CacheUsageResponse {
+    pub images: Vec<CachedImageEntry>,
+    pub measurements: Vec<CachedMeasurementEntry>,
+    pub image_cache_limits: CacheLimits,
+    pub measurement_cache_limits: CacheLimits,
+}
+
+/// Body of `POST /cache/prewarm`: the same `vm_config` encoding as [`VerificationRequest`] (a
+/// JSON-encoded `dstack_types::VmConfig`), so a client can reuse the request it would otherwise
+/// send to `/verify` to pay the image-download/measurement cost ahead of time.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct PrewarmRequest {
+    pub vm_config: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PrewarmResponse {
+    pub os_image_hash: String,
+    pub cache_key: String,
+}
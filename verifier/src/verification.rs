@@ -3,50 +3,98 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
+    collections::{HashMap, HashSet},
     ffi::OsStr,
+    io::{Read as _, Write as _},
     path::{Path, PathBuf},
     time::Duration,
 };
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use cc_eventlog::TdxEventLog as EventLog;
 use dstack_mr::{RtmrLog, TdxMeasurementDetails, TdxMeasurements};
 use dstack_types::VmConfig;
+use metrics::{counter, histogram};
 use ra_tls::attestation::{Attestation, VerifiedAttestation};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest as _, Sha256, Sha384};
-use tokio::{io::AsyncWriteExt, process::Command};
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+use crate::revocation::RevocationCascade;
+use crate::tcb_policy::TcbPolicyDecision;
 use crate::types::{
-    AcpiTables, RtmrEventEntry, RtmrEventStatus, RtmrMismatch, VerificationDetails,
+    AcpiTables, CacheLimits, CacheUsageResponse, CachedImageEntry, CachedMeasurementEntry,
+    PrewarmResponse, RtmrEventEntry, RtmrEventStatus, RtmrMismatch, VerificationDetails,
     VerificationRequest, VerificationResponse,
 };
 
+/// Root cause carried by the `anyhow::Error` returned when `verify`/`download_image` is aborted
+/// via a [`CancellationToken`] rather than failing for a substantive reason (a bad quote, a
+/// corrupt download, ...). Callers can distinguish the two with `error.is::<Cancelled>()`, e.g.
+/// to report cancellation distinctly instead of folding it into "internal error".
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "verification cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Returns `Err(Cancelled)` if `cancellation` has been triggered. Call at natural checkpoints
+/// (chunk-write boundaries, between extraction/download steps) in long-running verification and
+/// download paths so a cancelled request stops promptly instead of running to completion.
+fn check_cancelled(cancellation: &CancellationToken) -> Result<()> {
+    if cancellation.is_cancelled() {
+        return Err(anyhow::Error::new(Cancelled));
+    }
+    Ok(())
+}
+
+/// Seconds since the Unix epoch, used as the last-access timestamp for LRU eviction. Saturates to
+/// `0` if the system clock is somehow set before the epoch rather than panicking.
+fn now_unix_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 #[derive(Debug, Clone)]
 struct RtmrComputationResult {
     event_indices: [Vec<usize>; 4],
     rtmrs: [[u8; 48]; 4],
 }
 
+/// Reconstructs RTMR0..3 from `eventlog` using the TDX extend recurrence: each register starts as
+/// 48 zero bytes and is updated as `rtmr = SHA384(rtmr || event.digest)` for every event targeting
+/// it, in log order. `mrtd` is a static TD measurement and is never replayed, so only IMRs 0-3 are
+/// accepted here; an event claiming any other IMR index is rejected rather than silently ignored,
+/// and an empty `eventlog` yields all four registers still at their all-zero starting value.
 fn replay_event_logs(eventlog: &[EventLog]) -> Result<RtmrComputationResult> {
     let mut event_indices: [Vec<usize>; 4] = Default::default();
     let mut rtmrs: [[u8; 48]; 4] = [[0u8; 48]; 4];
 
-    for idx in 0..4 {
-        for (event_idx, event) in eventlog.iter().enumerate() {
-            event
-                .validate()
-                .context("Failed to validate event digest")?;
-
-            if event.imr == idx {
-                event_indices[idx as usize].push(event_idx);
-                let mut hasher = Sha384::new();
-                hasher.update(rtmrs[idx as usize]);
-                hasher.update(event.digest);
-                rtmrs[idx as usize] = hasher.finalize().into();
-            }
-        }
+    for (event_idx, event) in eventlog.iter().enumerate() {
+        event
+            .validate()
+            .context("Failed to validate event digest")?;
+        ensure!(
+            (0..4).contains(&event.imr),
+            "Event {event_idx} targets IMR {}, only 0-3 are valid replay targets",
+            event.imr
+        );
+
+        let idx = event.imr as usize;
+        event_indices[idx].push(event_idx);
+        let mut hasher = Sha384::new();
+        hasher.update(rtmrs[idx]);
+        hasher.update(event.digest);
+        rtmrs[idx] = hasher.finalize().into();
     }
 
     Ok(RtmrComputationResult {
@@ -148,18 +196,421 @@ fn collect_rtmr_mismatch(
     }
 }
 
+/// Maximum number of GET attempts `retrying_get` makes (across all mirrors combined) before
+/// giving up. Mirrors are cycled round-robin across attempts, so with `N` mirrors configured this
+/// allows roughly `DOWNLOAD_MAX_ATTEMPTS / N` passes over the full mirror list.
+const DOWNLOAD_MAX_ATTEMPTS: u32 = 5;
+const DOWNLOAD_BASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+const DOWNLOAD_MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Exponential backoff with full jitter: doubles `DOWNLOAD_BASE_RETRY_DELAY` per attempt (capped
+/// at `DOWNLOAD_MAX_RETRY_DELAY`), then adds a random delay up to half that cap so retrying
+/// verifiers don't all hammer the same mirror in lockstep.
+fn backoff_delay(attempt: u32) -> Duration {
+    let exp = DOWNLOAD_BASE_RETRY_DELAY.saturating_mul(1u32 << attempt.min(6));
+    let capped = exp.min(DOWNLOAD_MAX_RETRY_DELAY);
+    let jitter_ms = rand::random::<u64>() % (capped.as_millis() as u64 / 2 + 1);
+    capped + Duration::from_millis(jitter_ms)
+}
+
+/// The `ETag`/`Last-Modified` response headers recorded for a downloaded image, persisted next to
+/// `metadata.json` so a later `download_image` call can send them back as `If-None-Match`/
+/// `If-Modified-Since` and cheaply confirm the remote copy hasn't changed instead of re-fetching.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ConditionalHeaders {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl ConditionalHeaders {
+    fn from_response(response: &reqwest::Response) -> Self {
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// Paths a tarball being extracted by [`extract_tarball`] must contain, checked once the archive
+/// has been fully read, before anything is renamed into `dst_dir`. This is on top of, not instead
+/// of, the path-traversal/symlink rejection `extract_tarball` always applies regardless of
+/// whether a manifest is supplied.
+///
+/// This only pins *presence* of these paths, not their content: at the one call site
+/// (`download_whole_tarball`'s fallback path), `metadata.json` is itself the file the tarball's
+/// content hashes are pinned in, so there's no signed list of expected hashes available before
+/// extraction to check entries against as they stream in. The chunked download path
+/// (`try_chunked_download`) doesn't go through `extract_tarball`/`ExtractManifest` at all — it
+/// reassembles files directly from content-addressed chunks, each checked against its own hash in
+/// `ensure_chunk_cached`. Per-file content pinning for the whole-tarball path happens post-hoc,
+/// once `metadata.json` has actually been read, in [`verify_extracted_integrity`].
+#[derive(Debug, Clone, Default)]
+struct ExtractManifest {
+    /// Paths that must appear in the archive; checked once every entry has been read.
+    required_paths: HashSet<String>,
+}
+
+/// Unpacks the gzip tarball at `tarball_path` into `extracted_dir` in-process (no `tar`/`gzip`
+/// subprocess), rejecting any entry whose path would escape `extracted_dir` and any entry that
+/// isn't a plain file or directory (e.g. a symlink), and hashing each extracted file's contents
+/// with SHA-256 as it's written. When `manifest` is supplied, every `required_paths` entry is
+/// confirmed present once the archive has been fully read. Returns the
+/// extracted files' paths (relative to `extracted_dir`, matching the `sha256sum.txt` convention)
+/// mapped to their hex digest, so the caller can check them against `sha256sum.txt` without a
+/// second read-and-hash pass over the extracted tree.
+fn extract_tarball(
+    tarball_path: &Path,
+    extracted_dir: &Path,
+    manifest: Option<&ExtractManifest>,
+) -> Result<HashMap<String, String>> {
+    let tarball = fs_err::File::open(tarball_path).context("Failed to open downloaded tarball")?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(tarball));
+
+    let mut file_hashes = HashMap::new();
+    let mut seen_paths = HashSet::new();
+    for entry in archive
+        .entries()
+        .context("Failed to read tarball entries")?
+    {
+        let mut entry = entry.context("Failed to read tarball entry")?;
+        let entry_path = entry
+            .path()
+            .context("Failed to read tarball entry path")?
+            .into_owned();
+        ensure!(
+            entry_path
+                .components()
+                .all(|component| matches!(component, std::path::Component::Normal(_))),
+            "tarball entry {} escapes the extraction directory",
+            entry_path.display()
+        );
+        let dest_path = extracted_dir.join(&entry_path);
+
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => {
+                fs_err::create_dir_all(&dest_path)
+                    .context("Failed to create directory from tarball")?;
+            }
+            tar::EntryType::Regular => {
+                if let Some(parent) = dest_path.parent() {
+                    fs_err::create_dir_all(parent)
+                        .context("Failed to create parent directory for tarball entry")?;
+                }
+                let mut out =
+                    fs_err::File::create(&dest_path).context("Failed to create extracted file")?;
+                let mut hasher = Sha256::new();
+                let mut buf = [0u8; 64 * 1024];
+                loop {
+                    let n = entry
+                        .read(&mut buf)
+                        .context("Failed to read tarball entry data")?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buf[..n]);
+                    out.write_all(&buf[..n])
+                        .context("Failed to write extracted file")?;
+                }
+                let digest = hex::encode(hasher.finalize());
+                if let Some(name) = entry_path.to_str() {
+                    if manifest.is_some() {
+                        seen_paths.insert(name.to_string());
+                    }
+                    file_hashes.insert(name.to_string(), digest);
+                }
+            }
+            other => bail!(
+                "unsupported tarball entry type {other:?} for {}",
+                entry_path.display()
+            ),
+        }
+    }
+
+    if let Some(manifest) = manifest {
+        let missing: Vec<&String> = manifest.required_paths.difference(&seen_paths).collect();
+        ensure!(
+            missing.is_empty(),
+            "tarball is missing required entries: {missing:?}"
+        );
+    }
+
+    Ok(file_hashes)
+}
+
+/// Per-file integrity data optionally embedded in `metadata.json` under an `integrity` key,
+/// alongside whatever image-build fields `dstack_types::ImageInfo` also carries there. Verified
+/// by [`verify_extracted_integrity`] once extraction completes and before the extracted tree is
+/// committed into the image cache, so a tampered archive that still happens to carry a valid
+/// `sha256sum.txt` (checked earlier, against hashes computed during extraction itself) is caught
+/// by an independent, post-hoc walk of the files actually on disk. Absent entirely for images
+/// built before this field existed, in which case no extra check is performed.
+#[derive(Debug, Clone, Deserialize)]
+struct ImageIntegrity {
+    /// SHA-384 hex digest of each file, keyed by path relative to the image root.
+    file_hashes: std::collections::BTreeMap<String, String>,
+    /// SHA-384 Merkle root (see [`merkle_root`]) over `file_hashes`' sorted `(path, hash)` entries.
+    merkle_root: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct ImageMetadata {
+    #[serde(default)]
+    integrity: Option<ImageIntegrity>,
+}
+
+/// Builds a SHA-384 Merkle root over `file_hashes`' `(path, hash)` entries, ordered by path (a
+/// `BTreeMap` already iterates that way). Each leaf is `SHA-384(path || 0x00 || hash)`; interior
+/// nodes combine pairs of children, duplicating the last node on an odd-sized level (the same
+/// convention Bitcoin's Merkle trees use), until a single root remains. Returns the digest of the
+/// empty string for an empty map, matching the convention of hashing zero leaves to a fixed value.
+fn merkle_root(file_hashes: &std::collections::BTreeMap<String, String>) -> String {
+    if file_hashes.is_empty() {
+        return hex::encode(Sha384::digest(b""));
+    }
+
+    let mut level: Vec<Vec<u8>> = file_hashes
+        .iter()
+        .map(|(path, hash)| {
+            let mut hasher = Sha384::new();
+            hasher.update(path.as_bytes());
+            hasher.update([0u8]);
+            hasher.update(hash.as_bytes());
+            hasher.finalize().to_vec()
+        })
+        .collect();
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                let mut hasher = Sha384::new();
+                hasher.update(&pair[0]);
+                hasher.update(pair.get(1).unwrap_or(&pair[0]));
+                hasher.finalize().to_vec()
+            })
+            .collect();
+    }
+
+    hex::encode(&level[0])
+}
+
+/// Recursively collects every regular file under `dir` into `out`, as `/`-joined paths relative
+/// to `base` (matching the `file_hashes` key convention) — the same recursion
+/// [`CvmVerifier::dir_size_bytes`] already uses for cache accounting. Errors on anything that
+/// isn't a plain file or directory (e.g. a symlink), since those have no well-defined hash to
+/// check.
+fn list_files_relative(
+    dir: &Path,
+    base: &Path,
+    out: &mut std::collections::BTreeSet<String>,
+) -> Result<()> {
+    for entry in
+        fs_err::read_dir(dir).context("Failed to read directory for integrity verification")?
+    {
+        let entry = entry.context("Failed to read directory entry")?;
+        let file_type = entry
+            .file_type()
+            .context("Failed to read directory entry file type")?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            list_files_relative(&path, base, out)?;
+        } else if file_type.is_file() {
+            let relative = path
+                .strip_prefix(base)
+                .context("Failed to compute path relative to extraction directory")?;
+            let components: Vec<&str> = relative
+                .components()
+                .map(|c| c.as_os_str().to_str().unwrap_or_default())
+                .collect();
+            out.insert(components.join("/"));
+        } else {
+            bail!(
+                "unsupported file type for {} during integrity verification",
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Walks `extracted_dir` (recursively, via [`list_files_relative`]) and checks the result against
+/// `integrity` both ways: every file actually on disk must be listed in `integrity.file_hashes`
+/// (so an archive can't sneak in an extra, unlisted file), and every listed path must exist,
+/// canonicalize to somewhere under `extracted_dir` (rejecting `metadata.json` pointing a hash at
+/// an absolute path or a `../` traversal), and hash to the recorded digest. Finally rebuilds the
+/// Merkle root over `file_hashes` and checks it against `integrity.merkle_root`. Leaves
+/// `extracted_dir` untouched either way; it's the caller's responsibility not to commit it on
+/// error.
+fn verify_extracted_integrity(extracted_dir: &Path, integrity: &ImageIntegrity) -> Result<()> {
+    let canonical_root = extracted_dir
+        .canonicalize()
+        .context("Failed to canonicalize extraction directory")?;
+
+    let mut actual_paths = std::collections::BTreeSet::new();
+    list_files_relative(extracted_dir, extracted_dir, &mut actual_paths)?;
+
+    let expected_paths: std::collections::BTreeSet<String> =
+        integrity.file_hashes.keys().cloned().collect();
+
+    let unexpected: Vec<&String> = actual_paths.difference(&expected_paths).collect();
+    ensure!(
+        unexpected.is_empty(),
+        "extracted image contains file(s) not covered by metadata.json's integrity manifest: {unexpected:?}"
+    );
+    let missing: Vec<&String> = expected_paths.difference(&actual_paths).collect();
+    ensure!(
+        missing.is_empty(),
+        "metadata.json's integrity manifest lists file(s) missing from the extracted image: {missing:?}"
+    );
+
+    for (path, expected_hash) in &integrity.file_hashes {
+        let full_path = extracted_dir.join(path);
+        let canonical_path = full_path
+            .canonicalize()
+            .with_context(|| format!("Failed to canonicalize {path} for integrity verification"))?;
+        ensure!(
+            canonical_path.starts_with(&canonical_root),
+            "integrity manifest entry {path} escapes the extraction directory"
+        );
+
+        let contents = fs_err::read(&canonical_path)
+            .with_context(|| format!("Failed to read {path} for integrity verification"))?;
+        let actual_hash = hex::encode(Sha384::digest(&contents));
+        ensure!(
+            actual_hash.eq_ignore_ascii_case(expected_hash),
+            "integrity check failed for {path}: expected {expected_hash}, got {actual_hash}"
+        );
+    }
+
+    let computed_root = merkle_root(&integrity.file_hashes);
+    ensure!(
+        computed_root.eq_ignore_ascii_case(&integrity.merkle_root),
+        "Merkle root mismatch for extracted image: expected {}, computed {}",
+        integrity.merkle_root,
+        computed_root
+    );
+
+    Ok(())
+}
+
+/// A content-defined-chunking manifest published alongside an OS image: which files make it up,
+/// and the ordered list of `sha256(chunk)` hex digests that reassemble each one. Chunks live in a
+/// store shared across all image versions (see [`CvmVerifier::chunk_store_dir`]), so a new image
+/// that shares most of its firmware/kernel/initrd with a previously downloaded one only needs to
+/// fetch the handful of chunks that actually differ.
+#[derive(Debug, Clone, Deserialize)]
+struct ChunkManifest {
+    files: Vec<ChunkManifestFile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ChunkManifestFile {
+    path: String,
+    chunks: Vec<String>,
+}
+
 const MEASUREMENT_CACHE_VERSION: u32 = 1;
 
 #[derive(Clone, Serialize, Deserialize)]
 struct CachedMeasurement {
     version: u32,
     measurements: TdxMeasurements,
+    /// Updated on every load and store, so [`CvmVerifier::evict_measurement_cache_lru`] can tell
+    /// reused entries apart from stale ones. Defaults to `0` for entries written before this
+    /// field existed, which naturally makes them the first evicted.
+    #[serde(default)]
+    last_accessed_unix_secs: u64,
+}
+
+const VERIFICATION_CACHE_VERSION: u32 = 1;
+
+#[derive(Clone, Serialize)]
+struct CachedVerification {
+    version: u32,
+    cached_at_unix_secs: u64,
+    response: VerificationResponse,
+}
+
+/// The subset of a [`VerificationRequest`] that determines its verification outcome: the quote
+/// bytes, the event log, the PCCS URL (since TCB status can depend on which PCCS answered), and
+/// the VM config (which carries the OS image identifier). `debug` is intentionally excluded: it
+/// only adds optional diagnostic fields to the response and doesn't change `is_valid`.
+#[derive(Serialize)]
+struct VerificationCacheKeyInput<'a> {
+    quote: &'a str,
+    event_log: &'a str,
+    pccs_url: &'a Option<String>,
+    vm_config: &'a str,
+}
+
+fn verification_cache_key(request: &VerificationRequest) -> Result<String> {
+    let input = VerificationCacheKeyInput {
+        quote: &request.quote,
+        event_log: &request.event_log,
+        pccs_url: &request.pccs_url,
+        vm_config: &request.vm_config,
+    };
+    let serialized = serde_json::to_vec(&input)
+        .context("Failed to serialize verification request for cache key computation")?;
+    Ok(hex::encode(Sha256::digest(&serialized)))
 }
 
 pub struct CvmVerifier {
     pub image_cache_dir: String,
     pub download_url: String,
     pub download_timeout: Duration,
+    /// Additional `download_url`-style templates tried, round-robin with the primary, when a
+    /// download fails with a retryable error. See [`Self::tarball_urls`].
+    pub mirror_urls: Vec<String>,
+    /// TTL for cached verification results; `None` disables the cache entirely.
+    pub verification_cache_ttl: Option<Duration>,
+    /// In-memory verification-result cache, keyed by [`verification_cache_key`]. See
+    /// [`Self::load_verification_from_cache`] for why this isn't also read back from the
+    /// on-disk copy `store_verification_in_cache` writes.
+    verification_cache: std::sync::Mutex<std::collections::HashMap<String, CachedVerification>>,
+    /// In-memory copy of the on-disk revocation cascade (see [`Self::revocation_cascade_path`]),
+    /// lazily populated by [`Self::revocation_cascade`] and replaced wholesale by
+    /// [`Self::refresh_revocation_cascade`]. `None` means "not loaded yet"; distinct from a loaded
+    /// cascade that happens to be empty.
+    revocation_cascade: std::sync::Mutex<Option<std::sync::Arc<RevocationCascade>>>,
+    /// Per-`os_image_hash` locks serializing [`Self::ensure_image_cached`], so concurrent `verify`
+    /// calls racing on a cold cache don't both download/extract into the same image directory at
+    /// once. Keyed lazily; entries are never removed, but there's at most one per distinct image
+    /// hash ever seen, which is bounded in practice.
+    download_locks:
+        std::sync::Mutex<std::collections::HashMap<String, std::sync::Arc<tokio::sync::Mutex<()>>>>,
+    /// LRU eviction budget for `images/<hash>` cache directories. Unbounded (both fields `None`)
+    /// by default.
+    image_cache_limits: CacheLimits,
+    /// LRU eviction budget for `measurements/*.json` cache entries. Unbounded (both fields
+    /// `None`) by default.
+    measurement_cache_limits: CacheLimits,
+    /// Reference counts of images currently open for measurement, keyed by `os_image_hash` hex.
+    /// Held for the duration of [`Self::verify_os_image_hash`]/[`Self::prewarm`] via
+    /// [`Self::mark_image_in_use`], so [`Self::evict_image_cache_lru`] never removes a directory
+    /// a concurrent call is actively reading from.
+    image_in_use: std::sync::Mutex<std::collections::HashMap<String, u32>>,
+    /// Accept/warn/deny policy over an attestation's `tcb_status`/`advisory_ids`, applied in
+    /// [`Self::verify_os_image_hash`] alongside the MRTD/RTMR measurement comparison. `None`
+    /// (the default) performs no TCB policy enforcement at all, same as an unconfigured
+    /// [`Self::revocation_cascade`].
+    tcb_policy: Option<crate::tcb_policy::TcbPolicy>,
+    /// Signed TUF-style root/targets/timestamp chain gating which release a boot is accepted
+    /// against, including rollback protection; see [`upgrade_authority::UpgradeAuthority`].
+    /// Checked in [`Self::verify_uncached`] once `app_info` is available. `None` (the default)
+    /// performs no enforcement, same as an unconfigured [`Self::tcb_policy`].
+    upgrade_authority: Option<upgrade_authority::UpgradeAuthority>,
 }
 
 impl CvmVerifier {
@@ -168,7 +619,194 @@ impl CvmVerifier {
             image_cache_dir,
             download_url,
             download_timeout,
+            mirror_urls: Vec::new(),
+            verification_cache_ttl: Some(Duration::from_secs(3600)),
+            verification_cache: std::sync::Mutex::new(std::collections::HashMap::new()),
+            revocation_cascade: std::sync::Mutex::new(None),
+            download_locks: std::sync::Mutex::new(std::collections::HashMap::new()),
+            image_cache_limits: CacheLimits {
+                max_bytes: None,
+                max_entries: None,
+            },
+            measurement_cache_limits: CacheLimits {
+                max_bytes: None,
+                max_entries: None,
+            },
+            image_in_use: std::sync::Mutex::new(std::collections::HashMap::new()),
+            tcb_policy: None,
+            upgrade_authority: None,
+        }
+    }
+
+    /// Overrides the default verification-result cache TTL. Pass `None` to disable the cache.
+    pub fn with_verification_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.verification_cache_ttl = ttl;
+        self
+    }
+
+    /// Sets the LRU eviction budget for cached `images/<hash>` directories. A `None` dimension is
+    /// left unbounded.
+    pub fn with_image_cache_limits(
+        mut self,
+        max_bytes: Option<u64>,
+        max_entries: Option<usize>,
+    ) -> Self {
+        self.image_cache_limits = CacheLimits {
+            max_bytes,
+            max_entries,
+        };
+        self
+    }
+
+    /// Sets the LRU eviction budget for cached `measurements/*.json` entries. A `None` dimension
+    /// is left unbounded.
+    pub fn with_measurement_cache_limits(
+        mut self,
+        max_bytes: Option<u64>,
+        max_entries: Option<usize>,
+    ) -> Self {
+        self.measurement_cache_limits = CacheLimits {
+            max_bytes,
+            max_entries,
+        };
+        self
+    }
+
+    /// Adds fallback `download_url`-style templates, tried round-robin with the primary
+    /// `download_url` when a download attempt fails with a retryable error.
+    pub fn with_mirror_urls(mut self, mirror_urls: Vec<String>) -> Self {
+        self.mirror_urls = mirror_urls;
+        self
+    }
+
+    /// Configures TCB policy enforcement from a declarative [`TcbPolicyConfig`]. Pass `None` (the
+    /// default) to skip enforcement entirely, same as leaving the revocation cascade unconfigured.
+    pub fn with_tcb_policy(mut self, config: Option<crate::tcb_policy::TcbPolicyConfig>) -> Self {
+        self.tcb_policy = config.map(crate::tcb_policy::TcbPolicy::new);
+        self
+    }
+
+    /// Configures upgrade-authority enforcement by loading a signed bundle from `bundle_path` (see
+    /// [`upgrade_authority::UpgradeAuthority::load`]). Pass `None` to skip enforcement entirely,
+    /// the default. Rollback state persists under `image_cache_dir`, alongside the image and
+    /// measurement caches, so it survives a restart.
+    pub fn with_upgrade_authority(mut self, bundle_path: Option<&Path>) -> Result<Self> {
+        self.upgrade_authority = match bundle_path {
+            Some(path) => {
+                upgrade_authority::UpgradeAuthority::load(path, self.upgrade_authority_state_path())
+                    .context("Failed to load upgrade authority bundle")?
+            }
+            None => None,
+        };
+        Ok(self)
+    }
+
+    fn upgrade_authority_state_path(&self) -> PathBuf {
+        Path::new(&self.image_cache_dir)
+            .join("upgrade_authority")
+            .join("state.json")
+    }
+
+    fn verification_cache_dir(&self) -> PathBuf {
+        Path::new(&self.image_cache_dir).join("verifications")
+    }
+
+    fn verification_cache_path(&self, cache_key: &str) -> PathBuf {
+        self.verification_cache_dir()
+            .join(format!("{cache_key}.json"))
+    }
+
+    /// Looks up a fresh (non-expired) cached result for `cache_key`, served from the in-process
+    /// `verification_cache` map rather than from the JSON files `store_verification_in_cache`
+    /// writes to `verification_cache_dir()`.
+    ///
+    /// TODO(verification-cache-disk-reload): wiring the disk copy back in on a cold cache (e.g.
+    /// after a restart) needs `VerificationResponse`/`VerificationDetails` to derive
+    /// `Deserialize`, which in turn needs `ra_tls::attestation::AppInfo` to derive it. That
+    /// module isn't present in this checkout (`ra-tls/src/attestation.rs` doesn't exist even
+    /// though `ra-tls/src/lib.rs` declares `pub mod attestation;`), so that derive can't be
+    /// added here. Once `AppInfo` supports `Deserialize` upstream, this can parse
+    /// `verification_cache_path(cache_key)` the same way `load_measurements_from_cache` does.
+    fn load_verification_from_cache(&self, cache_key: &str) -> Option<VerificationResponse> {
+        let ttl = self.verification_cache_ttl?;
+        let cached = self
+            .verification_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(cache_key)?
+            .clone();
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let age = Duration::from_secs(now.saturating_sub(cached.cached_at_unix_secs));
+        if age > ttl {
+            debug!(
+                "Ignoring expired verification cache {} (age {:?}, ttl {:?})",
+                cache_key, age, ttl
+            );
+            return None;
         }
+
+        debug!("Loaded verification cache entry {}", cache_key);
+        Some(cached.response)
+    }
+
+    /// Populates the in-memory verification cache and best-effort persists a JSON copy under
+    /// `verification_cache_dir()` for operator inspection; see
+    /// [`Self::load_verification_from_cache`] for why the disk copy isn't read back yet.
+    fn store_verification_in_cache(&self, cache_key: &str, response: &VerificationResponse) {
+        let cached_at_unix_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let entry = CachedVerification {
+            version: VERIFICATION_CACHE_VERSION,
+            cached_at_unix_secs,
+            response: response.clone(),
+        };
+
+        self.verification_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(cache_key.to_string(), entry.clone());
+
+        if let Err(e) = self.persist_verification_cache_entry(cache_key, &entry) {
+            warn!(
+                "Failed to persist verification cache entry {}: {e:?}",
+                cache_key
+            );
+        }
+    }
+
+    fn persist_verification_cache_entry(
+        &self,
+        cache_key: &str,
+        entry: &CachedVerification,
+    ) -> Result<()> {
+        let cache_dir = self.verification_cache_dir();
+        fs_err::create_dir_all(&cache_dir)
+            .context("Failed to create verification cache directory")?;
+
+        let path = self.verification_cache_path(cache_key);
+        let mut tmp = tempfile::NamedTempFile::new_in(&cache_dir)
+            .context("Failed to create temporary cache file")?;
+
+        serde_json::to_writer(tmp.as_file_mut(), entry)
+            .context("Failed to serialize verification cache entry")?;
+        tmp.as_file_mut()
+            .sync_all()
+            .context("Failed to flush verification cache entry to disk")?;
+
+        tmp.persist(&path).map_err(|e| {
+            anyhow!(
+                "Failed to persist verification cache to {}: {e}",
+                path.display()
+            )
+        })?;
+        debug!("Stored verification cache entry {}", cache_key);
+        Ok(())
     }
 
     fn measurement_cache_dir(&self) -> PathBuf {
@@ -218,6 +856,15 @@ impl CvmVerifier {
         }
 
         debug!("Loaded measurement cache entry {}", cache_key);
+        // Refresh the last-access timestamp (and run opportunistic LRU eviction) on every hit, not
+        // just on a fresh store, so a measurement kept alive by repeated reuse isn't evicted out
+        // from under it.
+        if let Err(e) = self.store_measurements_in_cache(cache_key, &cached.measurements) {
+            warn!(
+                "Failed to refresh last-access time for measurement cache entry {}: {e:?}",
+                cache_key
+            );
+        }
         Ok(Some(cached.measurements))
     }
 
@@ -237,6 +884,7 @@ impl CvmVerifier {
         let entry = CachedMeasurement {
             version: MEASUREMENT_CACHE_VERSION,
             measurements: measurements.clone(),
+            last_accessed_unix_secs: now_unix_secs(),
         };
         serde_json::to_writer(tmp.as_file_mut(), &entry)
             .context("Failed to serialize measurement cache entry")?;
@@ -251,9 +899,451 @@ impl CvmVerifier {
             )
         })?;
         debug!("Stored measurement cache entry {}", cache_key);
+
+        if let Err(e) = self.evict_measurement_cache_lru() {
+            warn!("Measurement cache LRU eviction failed: {e:?}");
+        }
+        Ok(())
+    }
+
+    /// Evicts the least-recently-used `measurements/*.json` entries until usage is within
+    /// [`Self::measurement_cache_limits`]. A no-op when neither dimension is bounded.
+    fn evict_measurement_cache_lru(&self) -> Result<()> {
+        let limits = &self.measurement_cache_limits;
+        if limits.max_bytes.is_none() && limits.max_entries.is_none() {
+            return Ok(());
+        }
+
+        let dir = self.measurement_cache_dir();
+        if !dir.exists() {
+            return Ok(());
+        }
+
+        let mut entries = Vec::new();
+        for entry in fs_err::read_dir(&dir).context("Failed to read measurement cache directory")? {
+            let entry = entry.context("Failed to read measurement cache directory entry")?;
+            let path = entry.path();
+            if path.extension().and_then(OsStr::to_str) != Some("json") {
+                continue;
+            }
+            let Ok(data) = fs_err::read(&path) else {
+                continue;
+            };
+            let Ok(cached) = serde_json::from_slice::<CachedMeasurement>(&data) else {
+                continue;
+            };
+            let size_bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            entries.push((path, cached.last_accessed_unix_secs, size_bytes));
+        }
+        entries.sort_by_key(|(_, last_accessed, _)| *last_accessed);
+
+        let mut total_bytes: u64 = entries.iter().map(|(_, _, size)| size).sum();
+        let mut total_entries = entries.len();
+
+        for (path, _, size) in entries {
+            let over_bytes = limits.max_bytes.is_some_and(|max| total_bytes > max);
+            let over_entries = limits.max_entries.is_some_and(|max| total_entries > max);
+            if !over_bytes && !over_entries {
+                break;
+            }
+            if fs_err::remove_file(&path).is_ok() {
+                total_bytes = total_bytes.saturating_sub(size);
+                total_entries -= 1;
+                debug!("Evicted measurement cache entry {}", path.display());
+            }
+        }
+        Ok(())
+    }
+
+    fn revocation_cascade_path(&self) -> PathBuf {
+        Path::new(&self.image_cache_dir)
+            .join("revocation")
+            .join("cascade.bin")
+    }
+
+    /// Returns the cascade built by the most recent [`Self::refresh_revocation_cascade`] call,
+    /// loading it from [`Self::revocation_cascade_path`] on first use. Returns `None` when no
+    /// cascade has ever been built for this cache dir.
+    fn revocation_cascade(&self) -> Option<std::sync::Arc<RevocationCascade>> {
+        if let Some(cascade) = self
+            .revocation_cascade
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            return Some(cascade.clone());
+        }
+
+        let path = self.revocation_cascade_path();
+        let cascade = match RevocationCascade::load(&path) {
+            Ok(Some(cascade)) => std::sync::Arc::new(cascade),
+            Ok(None) => return None,
+            Err(e) => {
+                warn!(
+                    "Failed to load revocation cascade from {}: {e:?}",
+                    path.display()
+                );
+                return None;
+            }
+        };
+
+        *self
+            .revocation_cascade
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(cascade.clone());
+        Some(cascade)
+    }
+
+    /// Rebuilds the revocation cascade from the current `revoked`/`valid` identity sets (raw
+    /// `os_image_hash` bytes, TCB status strings, or any other opaque identity bytes callers want
+    /// to deny/allow-list), persists it under [`Self::revocation_cascade_path`], and swaps it in
+    /// for subsequent [`Self::verify`] calls. Intended to be called out-of-band (e.g. on a timer
+    /// or an admin endpoint) whenever the revocation data changes.
+    pub fn refresh_revocation_cascade(&self, revoked: &[Vec<u8>], valid: &[Vec<u8>]) -> Result<()> {
+        let salt: [u8; 16] = rand::random();
+        let cascade = RevocationCascade::build(revoked, valid, salt);
+        cascade
+            .save(&self.revocation_cascade_path())
+            .context("Failed to persist revocation cascade")?;
+
+        *self
+            .revocation_cascade
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(std::sync::Arc::new(cascade));
+        Ok(())
+    }
+
+    /// Checks `os_image_hash` (raw bytes) and `tcb_identity` (an opaque identity derived from the
+    /// quote's TCB status; see the call site) against the loaded revocation cascade, if any.
+    /// Returns a human-readable reason for the first revoked identity found.
+    fn check_revocation(&self, os_image_hash: &[u8], tcb_identity: &[u8]) -> Option<String> {
+        let cascade = self.revocation_cascade()?;
+        if cascade.is_empty() {
+            return None;
+        }
+        if cascade.is_revoked(os_image_hash) {
+            return Some(format!(
+                "os_image_hash {} is revoked",
+                hex::encode(os_image_hash)
+            ));
+        }
+        if cascade.is_revoked(tcb_identity) {
+            return Some(format!(
+                "TCB identity {} is revoked",
+                hex::encode(tcb_identity)
+            ));
+        }
+        None
+    }
+
+    /// Increments the in-use reference count for `hex_os_image_hash` and returns an RAII guard
+    /// that decrements it again on drop. Hold this for as long as an image's `fw`/`kernel`/
+    /// `initrd` files might still be read, so [`Self::evict_image_cache_lru`] can skip it.
+    fn mark_image_in_use(&self, hex_os_image_hash: &str) -> ImageUseGuard<'_> {
+        *self
+            .image_in_use
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(hex_os_image_hash.to_string())
+            .or_insert(0) += 1;
+        ImageUseGuard {
+            verifier: self,
+            hex_os_image_hash: hex_os_image_hash.to_string(),
+        }
+    }
+
+    fn image_access_path(image_dir: &Path) -> PathBuf {
+        image_dir.join("last-access.json")
+    }
+
+    /// Last-access time recorded for `image_dir` by [`Self::touch_image_access`]; `0` if the
+    /// image was cached before this field existed or the sidecar is otherwise unreadable, which
+    /// naturally makes it the first candidate [`Self::evict_image_cache_lru`] considers.
+    fn read_image_access(image_dir: &Path) -> u64 {
+        fs_err::read(Self::image_access_path(image_dir))
+            .ok()
+            .and_then(|data| serde_json::from_slice::<ImageAccess>(&data).ok())
+            .map(|access| access.last_accessed_unix_secs)
+            .unwrap_or(0)
+    }
+
+    /// Records that `image_dir` was just used, for [`Self::evict_image_cache_lru`]. Best effort:
+    /// a failure here shouldn't fail the caller's verification/prewarm.
+    fn touch_image_access(image_dir: &Path) {
+        let access = ImageAccess {
+            last_accessed_unix_secs: now_unix_secs(),
+        };
+        let result = serde_json::to_vec(&access)
+            .context("Failed to serialize image access time")
+            .and_then(|data| {
+                fs_err::write(Self::image_access_path(image_dir), data)
+                    .context("Failed to write image access time")
+            });
+        if let Err(e) = result {
+            warn!(
+                "Failed to persist image access time for {}: {e:?}",
+                image_dir.display()
+            );
+        }
+    }
+
+    /// Returns the local directory for `hex_os_image_hash`, downloading and extracting it first
+    /// if it isn't already cached there. Concurrent callers racing on the same hash (e.g. two
+    /// `verify` calls arriving together on a cold cache) serialize on a per-hash entry in
+    /// `download_locks` instead of racing to download/extract into the same directory; callers
+    /// for distinct hashes proceed fully in parallel.
+    ///
+    /// Every call refreshes the image's last-access time and runs opportunistic LRU eviction;
+    /// callers are expected to already hold an [`Self::mark_image_in_use`] guard for
+    /// `hex_os_image_hash` so the image they just fetched can't be evicted out from under them.
+    async fn ensure_image_cached(
+        &self,
+        hex_os_image_hash: &str,
+        cancellation: &CancellationToken,
+    ) -> Result<PathBuf> {
+        check_cancelled(cancellation)?;
+        let image_dir = Path::new(&self.image_cache_dir)
+            .join("images")
+            .join(hex_os_image_hash);
+
+        let lock = self
+            .download_locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(hex_os_image_hash.to_string())
+            .or_insert_with(|| std::sync::Arc::new(tokio::sync::Mutex::new(())))
+            .clone();
+        let _guard = lock.lock().await;
+
+        let metadata_path = image_dir.join("metadata.json");
+        if !metadata_path.exists() {
+            info!("Image {} not found, downloading", hex_os_image_hash);
+            let download_started_at = std::time::Instant::now();
+            let result = tokio::time::timeout(
+                self.download_timeout,
+                self.download_image(hex_os_image_hash, &image_dir, cancellation),
+            )
+            .await;
+            histogram!("verifier_image_download_duration_seconds")
+                .record(download_started_at.elapsed().as_secs_f64());
+            result
+                .context("Download image timeout")?
+                .with_context(|| format!("Failed to download image {hex_os_image_hash}"))?;
+        }
+
+        Self::touch_image_access(&image_dir);
+        if let Err(e) = self.evict_image_cache_lru() {
+            warn!("Image cache LRU eviction failed: {e:?}");
+        }
+
+        Ok(image_dir)
+    }
+
+    /// Evicts the least-recently-used `images/<hash>` directories until usage is within
+    /// [`Self::image_cache_limits`], skipping any directory currently reference-counted in
+    /// `image_in_use`. A no-op when neither dimension is bounded.
+    fn evict_image_cache_lru(&self) -> Result<()> {
+        let limits = &self.image_cache_limits;
+        if limits.max_bytes.is_none() && limits.max_entries.is_none() {
+            return Ok(());
+        }
+
+        let images_dir = Path::new(&self.image_cache_dir).join("images");
+        if !images_dir.exists() {
+            return Ok(());
+        }
+
+        let in_use = self
+            .image_in_use
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        let mut images = Vec::new();
+        for entry in
+            fs_err::read_dir(&images_dir).context("Failed to read images cache directory")?
+        {
+            let entry = entry.context("Failed to read images cache directory entry")?;
+            let is_dir = entry
+                .file_type()
+                .context("Failed to read directory entry file type")?
+                .is_dir();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // `tmp` holds in-progress downloads (see `download_image`), not a cached image.
+            if !is_dir || name == "tmp" {
+                continue;
+            }
+            let path = entry.path();
+            images.push((
+                name,
+                Self::read_image_access(&path),
+                Self::dir_size_bytes(&path)?,
+                path,
+            ));
+        }
+
+        let mut total_bytes: u64 = images.iter().map(|(_, _, size, _)| size).sum();
+        let mut total_entries = images.len();
+
+        let mut candidates: Vec<&(String, u64, u64, PathBuf)> = images
+            .iter()
+            .filter(|(name, ..)| !in_use.contains_key(name))
+            .collect();
+        candidates.sort_by_key(|(_, last_accessed, ..)| *last_accessed);
+
+        for (name, _, size, path) in candidates {
+            let over_bytes = limits.max_bytes.is_some_and(|max| total_bytes > max);
+            let over_entries = limits.max_entries.is_some_and(|max| total_entries > max);
+            if !over_bytes && !over_entries {
+                break;
+            }
+            if fs_err::remove_dir_all(path).is_ok() {
+                total_bytes = total_bytes.saturating_sub(*size);
+                total_entries -= 1;
+                debug!("Evicted cached image {}", name);
+            }
+        }
         Ok(())
     }
 
+    /// Total on-disk size, in bytes, of every file under `dir` (recursively). A missing `dir`
+    /// reports `0` rather than erroring, since an empty cache hasn't created its subdirectory yet.
+    fn dir_size_bytes(dir: &Path) -> Result<u64> {
+        if !dir.exists() {
+            return Ok(0);
+        }
+        let mut total = 0u64;
+        for entry in fs_err::read_dir(dir).context("Failed to read directory")? {
+            let entry = entry.context("Failed to read directory entry")?;
+            let file_type = entry
+                .file_type()
+                .context("Failed to read directory entry file type")?;
+            total += if file_type.is_dir() {
+                Self::dir_size_bytes(&entry.path())?
+            } else {
+                entry
+                    .metadata()
+                    .context("Failed to read directory entry metadata")?
+                    .len()
+            };
+        }
+        Ok(total)
+    }
+
+    /// Lists every cached OS image directory and measurement cache entry with their on-disk
+    /// sizes, for the `GET /cache` management endpoint.
+    pub fn cache_usage(&self) -> Result<CacheUsageResponse> {
+        let images_dir = Path::new(&self.image_cache_dir).join("images");
+        let mut images = Vec::new();
+        if images_dir.exists() {
+            for entry in
+                fs_err::read_dir(&images_dir).context("Failed to read images cache directory")?
+            {
+                let entry = entry.context("Failed to read images cache directory entry")?;
+                let is_dir = entry
+                    .file_type()
+                    .context("Failed to read directory entry file type")?
+                    .is_dir();
+                // `tmp` holds in-progress downloads (see `download_image`), not a cached image.
+                if !is_dir || entry.file_name() == "tmp" {
+                    continue;
+                }
+                images.push(CachedImageEntry {
+                    os_image_hash: entry.file_name().to_string_lossy().into_owned(),
+                    size_bytes: Self::dir_size_bytes(&entry.path())?,
+                    last_accessed_unix_secs: Self::read_image_access(&entry.path()),
+                });
+            }
+        }
+        images.sort_by(|a, b| a.os_image_hash.cmp(&b.os_image_hash));
+
+        let measurements_dir = self.measurement_cache_dir();
+        let mut measurements = Vec::new();
+        if measurements_dir.exists() {
+            for entry in fs_err::read_dir(&measurements_dir)
+                .context("Failed to read measurement cache directory")?
+            {
+                let entry = entry.context("Failed to read measurement cache directory entry")?;
+                let path = entry.path();
+                if path.extension().and_then(OsStr::to_str) != Some("json") {
+                    continue;
+                }
+                let Some(cache_key) = path.file_stem().and_then(OsStr::to_str) else {
+                    continue;
+                };
+                let last_accessed_unix_secs = fs_err::read(&path)
+                    .ok()
+                    .and_then(|data| serde_json::from_slice::<CachedMeasurement>(&data).ok())
+                    .map(|cached| cached.last_accessed_unix_secs)
+                    .unwrap_or(0);
+                measurements.push(CachedMeasurementEntry {
+                    cache_key: cache_key.to_string(),
+                    size_bytes: entry
+                        .metadata()
+                        .context("Failed to read directory entry metadata")?
+                        .len(),
+                    last_accessed_unix_secs,
+                });
+            }
+        }
+        measurements.sort_by(|a, b| a.cache_key.cmp(&b.cache_key));
+
+        Ok(CacheUsageResponse {
+            images,
+            measurements,
+            image_cache_limits: self.image_cache_limits.clone(),
+            measurement_cache_limits: self.measurement_cache_limits.clone(),
+        })
+    }
+
+    /// Evicts the measurement cache entry `cache_key`, if present. Returns whether an entry was
+    /// actually removed, so the `DELETE /cache/measurements/<cache_key>` handler can tell
+    /// "evicted" apart from "nothing to evict".
+    pub fn evict_measurement_cache_entry(&self, cache_key: &str) -> Result<bool> {
+        let path = self.measurement_cache_path(cache_key);
+        if !path.exists() {
+            return Ok(false);
+        }
+        fs_err::remove_file(&path).context("Failed to remove measurement cache entry")?;
+        Ok(true)
+    }
+
+    /// Downloads/extracts the image and computes and caches the measurements for `vm_config`,
+    /// without running a full `verify` (no quote or event log is needed). Backs
+    /// `POST /cache/prewarm`, letting an operator pay the image-download and measurement cost for
+    /// a VM config ahead of the first verification request that needs it.
+    pub async fn prewarm(&self, vm_config: &VmConfig) -> Result<PrewarmResponse> {
+        let hex_os_image_hash = hex::encode(&vm_config.os_image_hash);
+        let _use_guard = self.mark_image_in_use(&hex_os_image_hash);
+        let image_dir = self
+            .ensure_image_cached(&hex_os_image_hash, &CancellationToken::new())
+            .await?;
+
+        let image_info = fs_err::read_to_string(image_dir.join("metadata.json"))
+            .context("Failed to read image metadata")?;
+        let image_info: dstack_types::ImageInfo =
+            serde_json::from_str(&image_info).context("Failed to parse image metadata")?;
+
+        let fw_path = image_dir.join(&image_info.bios);
+        let kernel_path = image_dir.join(&image_info.kernel);
+        let initrd_path = image_dir.join(&image_info.initrd);
+        let kernel_cmdline = image_info.cmdline + " initrd=initrd";
+
+        self.load_or_compute_measurements(
+            vm_config,
+            &fw_path,
+            &kernel_path,
+            &initrd_path,
+            &kernel_cmdline,
+        )
+        .context("Failed to compute measurements")?;
+
+        Ok(PrewarmResponse {
+            os_image_hash: hex_os_image_hash,
+            cache_key: Self::vm_config_cache_key(vm_config)?,
+        })
+    }
+
     fn compute_measurement_details(
         &self,
         vm_config: &VmConfig,
@@ -343,7 +1433,49 @@ impl CvmVerifier {
         Ok(measurements)
     }
 
-    pub async fn verify(&self, request: &VerificationRequest) -> Result<VerificationResponse> {
+    /// Verifies `request`, serving a cached result when one is fresh in
+    /// [`Self::verification_cache`]. Equivalent to [`Self::verify_without_cache`] when
+    /// `verification_cache_ttl` is `None`.
+    ///
+    /// `cancellation` is checked at natural checkpoints throughout (quote verification, image
+    /// download, measurement); if it's triggered the call returns `Err` with [`Cancelled`] as the
+    /// root cause rather than completing or producing an `is_valid: false` response. Pass
+    /// `&CancellationToken::new()` for a call that should never be cancelled.
+    pub async fn verify(
+        &self,
+        request: &VerificationRequest,
+        cancellation: &CancellationToken,
+    ) -> Result<VerificationResponse> {
+        let cache_key = verification_cache_key(request)?;
+        if let Some(cached) = self.load_verification_from_cache(&cache_key) {
+            counter!("verifier_cache_hits_total").increment(1);
+            return Ok(cached);
+        }
+        counter!("verifier_cache_misses_total").increment(1);
+
+        let response = self.verify_uncached(request, cancellation).await?;
+        self.store_verification_in_cache(&cache_key, &response);
+        Ok(response)
+    }
+
+    /// Verifies `request` from scratch, bypassing the verification-result cache entirely (not
+    /// even populating it). Used by oneshot mode's `--no-cache` flag.
+    pub async fn verify_without_cache(
+        &self,
+        request: &VerificationRequest,
+        cancellation: &CancellationToken,
+    ) -> Result<VerificationResponse> {
+        self.verify_uncached(request, cancellation).await
+    }
+
+    async fn verify_uncached(
+        &self,
+        request: &VerificationRequest,
+        cancellation: &CancellationToken,
+    ) -> Result<VerificationResponse> {
+        counter!("verifier_verifications_total").increment(1);
+        check_cancelled(cancellation)?;
+
         let quote = hex::decode(&request.quote).context("Failed to decode quote hex")?;
 
         // Event log is always JSON string
@@ -358,9 +1490,11 @@ impl CvmVerifier {
             quote_verified: false,
             event_log_verified: false,
             os_image_hash_verified: false,
+            cert_chain_verified: false,
             report_data: None,
             tcb_status: None,
             advisory_ids: vec![],
+            tcb_policy_decision: None,
             app_info: None,
             acpi_tables: None,
             rtmr_debug: None,
@@ -375,6 +1509,8 @@ impl CvmVerifier {
                 details.quote_verified = true;
                 details.tcb_status = Some(att.report.status.clone());
                 details.advisory_ids = att.report.advisory_ids.clone();
+                counter!("verifier_tcb_status_total", "status" => att.report.status.clone())
+                    .increment(1);
                 // Extract and store report_data
                 if let Ok(report_data) = att.decode_report_data() {
                     details.report_data = Some(hex::encode(report_data));
@@ -382,6 +1518,7 @@ impl CvmVerifier {
                 att
             }
             Err(e) => {
+                counter!("verifier_verification_failures_total", "reason" => "quote").increment(1);
                 return Ok(VerificationResponse {
                     is_valid: false,
                     details,
@@ -390,11 +1527,40 @@ impl CvmVerifier {
             }
         };
 
+        // Step 2: Deny-list check against the revocation cascade.
+        //
+        // TODO(tcb-identity): a proper TCB identity is the FMSPC + TCB level from the PCK cert,
+        // but this checkout's `ra_tls::attestation` doesn't expose the PCK cert or FMSPC (same
+        // gap noted on `Self::verify_cert_chain`), so the TCB status string is used as a stand-in
+        // identity here. Once FMSPC is available, build the revocation sets from it instead.
+        if let Some(reason) = self.check_revocation(
+            &vm_config.os_image_hash,
+            verified_attestation.report.status.as_bytes(),
+        ) {
+            counter!("verifier_verification_failures_total", "reason" => "revoked").increment(1);
+            return Ok(VerificationResponse {
+                is_valid: false,
+                details,
+                reason: Some(format!("Revoked: {reason}")),
+            });
+        }
+
         // Step 3: Verify os-image-hash matches using dstack-mr
         if let Err(e) = self
-            .verify_os_image_hash(&vm_config, &verified_attestation, debug, &mut details)
+            .verify_os_image_hash(
+                &vm_config,
+                &verified_attestation,
+                debug,
+                &mut details,
+                cancellation,
+            )
             .await
         {
+            if e.is::<Cancelled>() {
+                return Err(e);
+            }
+            counter!("verifier_verification_failures_total", "reason" => "os_image_hash")
+                .increment(1);
             return Ok(VerificationResponse {
                 is_valid: false,
                 details,
@@ -407,8 +1573,54 @@ impl CvmVerifier {
                 info.os_image_hash = vm_config.os_image_hash;
                 details.event_log_verified = true;
                 details.app_info = Some(info);
+
+                if let Some(authority) = &self.upgrade_authority {
+                    let info = details
+                        .app_info
+                        .as_ref()
+                        .expect("just set app_info above");
+                    let report = verified_attestation
+                        .report
+                        .report
+                        .as_td10()
+                        .context("Failed to decode TD report for upgrade-authority verification")?;
+                    let boot_info = upgrade_authority::BootInfo {
+                        mrtd: report.mr_td.to_vec(),
+                        rtmr0: report.rt_mr0.to_vec(),
+                        rtmr1: report.rt_mr1.to_vec(),
+                        rtmr2: report.rt_mr2.to_vec(),
+                        rtmr3: report.rt_mr3.to_vec(),
+                        mr_aggregated: info.mr_aggregated.to_vec(),
+                        // This checkout has no "system measurement" distinct from the MRTD/RTMR
+                        // tuple above (`dstack_mr::TdxMeasurements` only carries those four, and
+                        // `AppInfo` has no such field either) — a deployed bundle's `TargetEntry`s
+                        // must pin this empty too for a release to ever match.
+                        mr_system: Vec::new(),
+                        app_id: info.app_id.to_vec(),
+                        compose_hash: info.compose_hash.clone(),
+                        instance_id: info.instance_id.to_vec(),
+                        device_id: info.device_id.to_vec(),
+                        key_provider_info: info.key_provider_info.clone(),
+                        os_image_hash: info.os_image_hash.clone(),
+                        event_log: String::from_utf8_lossy(&verified_attestation.raw_event_log)
+                            .into_owned(),
+                        tcb_status: details.tcb_status.clone().unwrap_or_default(),
+                        advisory_ids: details.advisory_ids.clone(),
+                    };
+                    if let Err(e) = authority.verify(&boot_info) {
+                        counter!("verifier_verification_failures_total", "reason" => "upgrade_authority")
+                            .increment(1);
+                        return Ok(VerificationResponse {
+                            is_valid: false,
+                            details,
+                            reason: Some(format!("Upgrade authority rejected this boot: {e:#}")),
+                        });
+                    }
+                }
             }
             Err(e) => {
+                counter!("verifier_verification_failures_total", "reason" => "event_log")
+                    .increment(1);
                 return Ok(VerificationResponse {
                     is_valid: false,
                     details,
@@ -417,6 +1629,10 @@ impl CvmVerifier {
             }
         };
 
+        details.cert_chain_verified = self
+            .verify_cert_chain(&verified_attestation, &details)
+            .unwrap_or(false);
+
         Ok(VerificationResponse {
             is_valid: true,
             details,
@@ -438,14 +1654,39 @@ impl CvmVerifier {
             .context("Quote verification failed")
     }
 
+    /// Validates that the RA-TLS `app_cert`/`certificate_chain` chains to a trusted root and that
+    /// its embedded quote extension matches the already-verified `report_data`, in the same
+    /// spirit as the SGX mutual-RA cert path that pins a CA and walks supported signature
+    /// algorithms: parse leaf + intermediates, check signatures/validity/basic-constraints up to
+    /// a configured trust anchor, then confirm the leaf's public key hash appears in
+    /// `report_data`.
+    ///
+    /// TODO(cert-chain-verification): this checkout's `ra_tls::attestation` only re-exports
+    /// `Attestation`/`VerifiedAttestation`/`AppInfo` (`ra-tls/src/attestation.rs` isn't present,
+    /// same gap noted on [`Self::load_verification_from_cache`]), so there's no way here to read
+    /// the leaf/intermediate cert DER off `VerifiedAttestation` or to know which `AppInfo` field
+    /// carries it. There's also no X.509 chain-validation crate in this workspace's dependency
+    /// set to walk signatures/validity/basic-constraints against a trust anchor once the cert
+    /// bytes are in hand. Until both land upstream, this always returns `Ok(false)` rather than
+    /// claim a verification that didn't run.
+    fn verify_cert_chain(
+        &self,
+        _attestation: &VerifiedAttestation,
+        _details: &VerificationDetails,
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
     async fn verify_os_image_hash(
         &self,
         vm_config: &VmConfig,
         attestation: &VerifiedAttestation,
         debug: bool,
         details: &mut VerificationDetails,
+        cancellation: &CancellationToken,
     ) -> Result<()> {
         let hex_os_image_hash = hex::encode(&vm_config.os_image_hash);
+        let _use_guard = self.mark_image_in_use(&hex_os_image_hash);
 
         // Get boot info from attestation
         let report = attestation
@@ -462,25 +1703,13 @@ impl CvmVerifier {
             rtmr2: report.rt_mr2.to_vec(),
         };
 
-        // Get image directory
-        let image_dir = Path::new(&self.image_cache_dir)
-            .join("images")
-            .join(&hex_os_image_hash);
+        let image_dir = self
+            .ensure_image_cached(&hex_os_image_hash, cancellation)
+            .await?;
+        check_cancelled(cancellation)?;
 
-        let metadata_path = image_dir.join("metadata.json");
-        if !metadata_path.exists() {
-            info!("Image {} not found, downloading", hex_os_image_hash);
-            tokio::time::timeout(
-                self.download_timeout,
-                self.download_image(&hex_os_image_hash, &image_dir),
-            )
-            .await
-            .context("Download image timeout")?
-            .with_context(|| format!("Failed to download image {hex_os_image_hash}"))?;
-        }
-
-        let image_info =
-            fs_err::read_to_string(metadata_path).context("Failed to read image metadata")?;
+        let image_info = fs_err::read_to_string(image_dir.join("metadata.json"))
+            .context("Failed to read image metadata")?;
         let image_info: dstack_types::ImageInfo =
             serde_json::from_str(&image_info).context("Failed to parse image metadata")?;
 
@@ -539,11 +1768,32 @@ impl CvmVerifier {
         let computation_result = replay_event_logs(&event_log)
             .context("Failed to replay event logs for mismatch analysis")?;
 
+        // RTMR3 is extended at runtime by the guest agent (app compose-hash, instance ID, ...)
+        // rather than measured statically by dstack-mr, so there's no precomputed "expected"
+        // value to compare it against: the only invariant we can check is that replaying the
+        // attested event log from scratch reproduces the RTMR3 the quote reports.
         if computation_result.rtmrs[3] != report.rt_mr3 {
-            bail!("RTMR3 mismatch");
+            if debug {
+                details
+                    .rtmr_debug
+                    .get_or_insert_with(Vec::new)
+                    .push(collect_rtmr_mismatch(
+                        "RTMR3",
+                        &computation_result.rtmrs[3],
+                        &report.rt_mr3,
+                        &Vec::new(),
+                        &computation_result.event_indices[3],
+                        &event_log,
+                    ));
+            }
+            bail!(
+                "RTMR3 mismatch: event log replays to {}, attested value is {}",
+                hex::encode(computation_result.rtmrs[3]),
+                hex::encode(report.rt_mr3)
+            );
         }
 
-        match expected_mrs.assert_eq(&verified_mrs) {
+        let mrs_result = match expected_mrs.assert_eq(&verified_mrs) {
             Ok(()) => Ok(()),
             Err(e) => {
                 let result = Err(e).context("MRs do not match");
@@ -594,37 +1844,283 @@ impl CvmVerifier {
 
                 result
             }
+        };
+        mrs_result?;
+
+        // Measurements matching only proves the guest booted the expected software; it says
+        // nothing about whether the platform's TCB is still trustworthy. Evaluate that
+        // separately, against the `tcb_status`/`advisory_ids` the quote itself carried (set on
+        // `details` back in `verify_uncached`, before this function was called).
+        if let Some(policy) = &self.tcb_policy {
+            let decision = policy.evaluate(details.tcb_status.as_deref(), &details.advisory_ids);
+            let deny_reason = match &decision {
+                TcbPolicyDecision::Deny { reason } => Some(reason.clone()),
+                TcbPolicyDecision::Accept | TcbPolicyDecision::Warn { .. } => None,
+            };
+            details.tcb_policy_decision = Some(decision);
+            if let Some(reason) = deny_reason {
+                bail!("TCB policy denied this attestation: {reason}");
+            }
         }
+
+        Ok(())
     }
 
-    async fn download_image(&self, hex_os_image_hash: &str, dst_dir: &Path) -> Result<()> {
-        let url = self
-            .download_url
-            .replace("{OS_IMAGE_HASH}", hex_os_image_hash);
+    /// Directory backing the shared content-addressed chunk store, keyed by `sha256(chunk)` hex
+    /// digest so identical chunks across OS image versions are only ever downloaded once.
+    fn chunk_store_dir(&self) -> PathBuf {
+        Path::new(&self.image_cache_dir).join("chunks")
+    }
 
-        // Create a temporary directory for extraction within the cache directory
-        let cache_dir = Path::new(&self.image_cache_dir).join("images").join("tmp");
-        fs_err::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
-        let auto_delete_temp_dir = tempfile::Builder::new()
-            .prefix("tmp-download-")
-            .tempdir_in(&cache_dir)
-            .context("Failed to create temporary directory")?;
-        let tmp_dir = auto_delete_temp_dir.path();
+    /// URL of a chunk in the shared store, derived by swapping the `{OS_IMAGE_HASH}` path segment
+    /// of the tarball template for `chunks`, so the same URL is reused regardless of which image
+    /// version first referenced the chunk.
+    fn chunk_url(&self, chunk_hash: &str) -> String {
+        match self.download_url.split_once("{OS_IMAGE_HASH}") {
+            Some((prefix, _suffix)) => {
+                format!("{}/chunks/{chunk_hash}", prefix.trim_end_matches('/'))
+            }
+            None => match self.download_url.rsplit_once('/') {
+                Some((base, _filename)) => format!("{base}/chunks/{chunk_hash}"),
+                None => self.download_url.clone(),
+            },
+        }
+    }
+
+    /// Per-mirror URLs for the tarball/manifest GET, built by templating each of `download_url`
+    /// and `mirror_urls` with `hex_os_image_hash` (and, for the manifest, further swapping the
+    /// tarball filename for `manifest.json` the same way [`Self::manifest_url`] does).
+    fn tarball_urls(&self, hex_os_image_hash: &str) -> Vec<String> {
+        std::iter::once(&self.download_url)
+            .chain(self.mirror_urls.iter())
+            .map(|template| template.replace("{OS_IMAGE_HASH}", hex_os_image_hash))
+            .collect()
+    }
+
+    fn manifest_urls(&self, hex_os_image_hash: &str) -> Vec<String> {
+        self.tarball_urls(hex_os_image_hash)
+            .into_iter()
+            .map(|tarball_url| match tarball_url.rsplit_once('/') {
+                Some((base, _filename)) => format!("{base}/manifest.json"),
+                None => tarball_url,
+            })
+            .collect()
+    }
+
+    /// Issues a GET against `urls`, retrying with exponential backoff and rotating round-robin
+    /// through `urls` on a retryable failure (a non-2xx/404 status, or a transport-level error
+    /// such as a timeout or connection reset). A 404 is treated as definitive and returned
+    /// immediately without retrying. Returns `Ok(None)` on a `304 Not Modified` response to
+    /// `conditional`'s `If-None-Match`/`If-Modified-Since`, otherwise `Ok(Some(response))` with the
+    /// response headers/body left for the caller to consume.
+    async fn retrying_get(
+        &self,
+        client: &reqwest::Client,
+        urls: &[String],
+        conditional: Option<&ConditionalHeaders>,
+    ) -> Result<Option<reqwest::Response>> {
+        let mut errors = Vec::new();
+
+        for attempt in 0..DOWNLOAD_MAX_ATTEMPTS {
+            let url = &urls[attempt as usize % urls.len()];
+            let mut request = client.get(url);
+            if let Some(conditional) = conditional {
+                if let Some(etag) = &conditional.etag {
+                    request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &conditional.last_modified {
+                    request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+
+            match request.send().await {
+                Ok(response) if response.status() == reqwest::StatusCode::NOT_MODIFIED => {
+                    debug!("{} not modified, reusing cached copy", url);
+                    return Ok(None);
+                }
+                Ok(response) if response.status() == reqwest::StatusCode::NOT_FOUND => {
+                    bail!("Failed to fetch {url}: HTTP 404 Not Found");
+                }
+                Ok(response) if response.status().is_success() => return Ok(Some(response)),
+                Ok(response) => errors.push(format!("{url}: HTTP {}", response.status())),
+                Err(e) => errors.push(format!("{url}: {e}")),
+            }
+
+            if attempt + 1 < DOWNLOAD_MAX_ATTEMPTS {
+                let delay = backoff_delay(attempt);
+                debug!(
+                    "Retrying download in {:?} (attempt {}/{DOWNLOAD_MAX_ATTEMPTS})",
+                    delay,
+                    attempt + 1
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+
+        bail!(
+            "All mirrors exhausted after {DOWNLOAD_MAX_ATTEMPTS} attempts: {}",
+            errors.join("; ")
+        );
+    }
 
-        info!("Downloading image from {}", url);
+    /// Tries to reassemble `hex_os_image_hash` from the shared chunk store using its manifest.
+    /// Returns `Ok(None)` (not an error) when no manifest is published for this image, so the
+    /// caller can fall back to the whole-tarball download path. On success, returns each
+    /// reassembled file's path (relative to `extracted_dir`) mapped to its SHA-256 hex digest,
+    /// computed incrementally as the file is written so `download_image` can check it against
+    /// `sha256sum.txt` without a separate hashing pass.
+    async fn try_chunked_download(
+        &self,
+        hex_os_image_hash: &str,
+        extracted_dir: &Path,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<HashMap<String, String>>> {
+        let manifest_urls = self.manifest_urls(hex_os_image_hash);
         let client = reqwest::Client::new();
+
+        let response = match self.retrying_get(&client, &manifest_urls, None).await {
+            Ok(Some(response)) => response,
+            Ok(None) => bail!("manifest fetch unexpectedly returned 304 Not Modified"),
+            Err(e) => {
+                debug!(
+                    "No chunk manifest available for {}: {e:#}; falling back to whole-tarball download",
+                    hex_os_image_hash
+                );
+                return Ok(None);
+            }
+        };
+
+        let manifest: ChunkManifest = response
+            .json()
+            .await
+            .context("Failed to parse chunk manifest")?;
+        info!(
+            "Reassembling image {} from {} manifest files via the chunk store",
+            hex_os_image_hash,
+            manifest.files.len()
+        );
+
+        let chunk_store = self.chunk_store_dir();
+        fs_err::create_dir_all(&chunk_store).context("Failed to create chunk store directory")?;
+
+        let mut file_hashes = HashMap::new();
+        for manifest_file in &manifest.files {
+            check_cancelled(cancellation)?;
+            let dest_path = extracted_dir.join(&manifest_file.path);
+            if let Some(parent) = dest_path.parent() {
+                fs_err::create_dir_all(parent)
+                    .context("Failed to create extraction subdirectory")?;
+            }
+
+            let mut out = tokio::fs::File::create(&dest_path)
+                .await
+                .context("Failed to create reassembled file")?;
+            let mut hasher = Sha256::new();
+            for chunk_hash in &manifest_file.chunks {
+                let chunk_path = self.ensure_chunk_cached(&client, chunk_hash).await?;
+                let data = tokio::fs::read(&chunk_path)
+                    .await
+                    .context("Failed to read cached chunk")?;
+                hasher.update(&data);
+                out.write_all(&data)
+                    .await
+                    .context("Failed to write chunk into reassembled file")?;
+            }
+            file_hashes.insert(manifest_file.path.clone(), hex::encode(hasher.finalize()));
+        }
+
+        Ok(Some(file_hashes))
+    }
+
+    /// Returns the path of `chunk_hash` in the shared chunk store, downloading and verifying it
+    /// first if it isn't already cached there.
+    async fn ensure_chunk_cached(&self, client: &reqwest::Client, chunk_hash: &str) -> Result<PathBuf> {
+        let chunk_path = self.chunk_store_dir().join(chunk_hash);
+        if chunk_path.exists() {
+            return Ok(chunk_path);
+        }
+
+        let url = self.chunk_url(chunk_hash);
         let response = client
             .get(&url)
             .send()
             .await
-            .context("Failed to download image")?;
-
+            .context("Failed to download chunk")?;
         if !response.status().is_success() {
             bail!(
-                "Failed to download image: HTTP status {}, url: {url}",
-                response.status(),
+                "Failed to download chunk {chunk_hash}: HTTP status {}, url: {url}",
+                response.status()
+            );
+        }
+        let bytes = response.bytes().await.context("Failed to read chunk body")?;
+
+        let digest = hex::encode(Sha256::digest(&bytes));
+        if digest != chunk_hash {
+            bail!("Chunk {chunk_hash} failed integrity check: got digest {digest}");
+        }
+
+        let chunk_store = self.chunk_store_dir();
+        let mut tmp = tempfile::NamedTempFile::new_in(&chunk_store)
+            .context("Failed to create temporary chunk file")?;
+        tmp.as_file_mut()
+            .write_all(&bytes)
+            .context("Failed to write chunk")?;
+        tmp.persist(&chunk_path)
+            .map_err(|e| anyhow!("Failed to persist chunk {chunk_hash}: {e}"))?;
+
+        Ok(chunk_path)
+    }
+
+    fn conditional_headers_path(dst_dir: &Path) -> PathBuf {
+        dst_dir.join("download-headers.json")
+    }
+
+    /// Loads the `ETag`/`Last-Modified` recorded for a previously downloaded `dst_dir`, if any.
+    fn read_conditional_headers(dst_dir: &Path) -> Option<ConditionalHeaders> {
+        let data = fs_err::read(Self::conditional_headers_path(dst_dir)).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    /// Persists `headers` next to `metadata.json` in `dst_dir` for a later revalidation. Best
+    /// effort: a failure here shouldn't fail a download that otherwise succeeded.
+    fn write_conditional_headers(dst_dir: &Path, headers: &ConditionalHeaders) {
+        if headers.is_empty() {
+            return;
+        }
+        let path = Self::conditional_headers_path(dst_dir);
+        let result = serde_json::to_vec(headers)
+            .context("Failed to serialize conditional headers")
+            .and_then(|data| fs_err::write(&path, data).context("Failed to write conditional headers"));
+        if let Err(e) = result {
+            warn!(
+                "Failed to persist conditional headers to {}: {e:?}",
+                path.display()
             );
         }
+    }
+
+    /// Downloads and extracts the whole tarball for `hex_os_image_hash`, retrying across mirrors
+    /// with backoff. Returns `Ok(None)` when the server confirms (via `conditional`) that the
+    /// remote copy hasn't changed, in which case `extracted_dir` is left untouched; otherwise
+    /// returns `Ok(Some((headers, file_hashes)))` with the `ETag`/`Last-Modified` to persist for
+    /// next time and each extracted file's SHA-256 digest (see [`extract_tarball`]).
+    async fn download_whole_tarball(
+        &self,
+        hex_os_image_hash: &str,
+        tmp_dir: &Path,
+        extracted_dir: &Path,
+        conditional: Option<&ConditionalHeaders>,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<(ConditionalHeaders, HashMap<String, String>)>> {
+        let urls = self.tarball_urls(hex_os_image_hash);
+        let client = reqwest::Client::new();
+
+        info!("Downloading image {} from {:?}", hex_os_image_hash, urls);
+        let response = match self.retrying_get(&client, &urls, conditional).await? {
+            Some(response) => response,
+            None => return Ok(None),
+        };
+        let headers = ConditionalHeaders::from_response(&response);
 
         // Save the tarball to a temporary file using streaming
         let tarball_path = tmp_dir.join("image.tar.gz");
@@ -633,50 +2129,101 @@ impl CvmVerifier {
             .context("Failed to create tarball file")?;
         let mut response = response;
         while let Some(chunk) = response.chunk().await? {
+            check_cancelled(cancellation)?;
             file.write_all(&chunk)
                 .await
                 .context("Failed to write chunk to file")?;
         }
 
+        // `metadata.json` and `sha256sum.txt` are both required by the checks that run once
+        // extraction finishes (see `download_image`); requiring them here too means a tarball
+        // missing either is rejected before any further processing, not partway through it.
+        let required_manifest = ExtractManifest {
+            required_paths: ["metadata.json", "sha256sum.txt"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            ..Default::default()
+        };
+        let file_hashes = extract_tarball(&tarball_path, extracted_dir, Some(&required_manifest))?;
+
+        Ok(Some((headers, file_hashes)))
+    }
+
+    async fn download_image(
+        &self,
+        hex_os_image_hash: &str,
+        dst_dir: &Path,
+        cancellation: &CancellationToken,
+    ) -> Result<()> {
+        check_cancelled(cancellation)?;
+        // Create a temporary directory for extraction within the cache directory
+        let cache_dir = Path::new(&self.image_cache_dir).join("images").join("tmp");
+        fs_err::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
+        let auto_delete_temp_dir = tempfile::Builder::new()
+            .prefix("tmp-download-")
+            .tempdir_in(&cache_dir)
+            .context("Failed to create temporary directory")?;
+        let tmp_dir = auto_delete_temp_dir.path();
+
         let extracted_dir = tmp_dir.join("extracted");
         fs_err::create_dir_all(&extracted_dir).context("Failed to create extraction directory")?;
 
-        // Extract the tarball
-        let output = Command::new("tar")
-            .arg("xzf")
-            .arg(&tarball_path)
-            .current_dir(&extracted_dir)
-            .output()
-            .await
-            .context("Failed to extract tarball")?;
+        // Revalidate against any `ETag`/`Last-Modified` recorded for a copy already at `dst_dir`
+        // so an unchanged image is confirmed with a cheap conditional request instead of being
+        // re-fetched and re-extracted from scratch.
+        let conditional = Self::read_conditional_headers(dst_dir);
+        let mut new_headers = None;
 
-        if !output.status.success() {
-            bail!(
-                "Failed to extract tarball: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
-        }
-
-        // Verify checksum
-        let output = Command::new("sha256sum")
-            .arg("-c")
-            .arg("sha256sum.txt")
-            .current_dir(&extracted_dir)
-            .output()
-            .await
-            .context("Failed to verify checksum")?;
+        let file_hashes = match self
+            .try_chunked_download(hex_os_image_hash, &extracted_dir, cancellation)
+            .await?
+        {
+            Some(file_hashes) => file_hashes,
+            None => match self
+                .download_whole_tarball(
+                    hex_os_image_hash,
+                    tmp_dir,
+                    &extracted_dir,
+                    conditional.as_ref(),
+                    cancellation,
+                )
+                .await?
+            {
+                Some((headers, file_hashes)) => {
+                    new_headers = Some(headers);
+                    file_hashes
+                }
+                None => {
+                    info!(
+                        "Image {} unchanged on remote, keeping cached copy",
+                        hex_os_image_hash
+                    );
+                    return Ok(());
+                }
+            },
+        };
 
-        if !output.status.success() {
-            bail!(
-                "Checksum verification failed: {}",
-                String::from_utf8_lossy(&output.stderr)
+        // Verify checksum: every file listed in sha256sum.txt must match the digest computed
+        // while it was written above, rather than a second read-and-hash pass.
+        let sha256sum_path = extracted_dir.join("sha256sum.txt");
+        let files_doc =
+            fs_err::read_to_string(&sha256sum_path).context("Failed to read sha256sum.txt")?;
+        for line in files_doc.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(expected_hash), Some(filename)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let actual_hash = file_hashes.get(filename).with_context(|| {
+                format!("{filename} listed in sha256sum.txt was not found among the extracted files")
+            })?;
+            ensure!(
+                actual_hash.eq_ignore_ascii_case(expected_hash),
+                "Checksum mismatch for {filename}: expected {expected_hash}, got {actual_hash}"
             );
         }
 
         // Remove the files that are not listed in sha256sum.txt
-        let sha256sum_path = extracted_dir.join("sha256sum.txt");
-        let files_doc =
-            fs_err::read_to_string(&sha256sum_path).context("Failed to read sha256sum.txt")?;
         let listed_files: Vec<&OsStr> = files_doc
             .lines()
             .flat_map(|line| line.split_whitespace().nth(1))
@@ -707,6 +2254,15 @@ impl CvmVerifier {
             bail!("metadata.json not found in the extracted archive");
         }
 
+        let metadata_contents =
+            fs_err::read_to_string(&metadata_path).context("Failed to read metadata.json")?;
+        let metadata: ImageMetadata =
+            serde_json::from_str(&metadata_contents).context("Failed to parse metadata.json")?;
+        if let Some(integrity) = &metadata.integrity {
+            verify_extracted_integrity(&extracted_dir, integrity)
+                .context("Extracted image failed integrity verification")?;
+        }
+
         if dst_dir.exists() {
             fs_err::remove_dir_all(dst_dir).context("Failed to remove destination directory")?;
         }
@@ -715,10 +2271,46 @@ impl CvmVerifier {
         // Move the extracted files to the destination directory
         fs_err::rename(extracted_dir, dst_dir)
             .context("Failed to move extracted files to destination directory")?;
+
+        if let Some(headers) = new_headers {
+            Self::write_conditional_headers(dst_dir, &headers);
+        }
+
         Ok(())
     }
 }
 
+/// Last-access time for a cached image, persisted as a sidecar JSON file next to `metadata.json`
+/// (see [`CvmVerifier::image_access_path`]) since the image directory isn't otherwise a single
+/// file whose mtime could serve as a proxy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ImageAccess {
+    last_accessed_unix_secs: u64,
+}
+
+/// RAII guard returned by [`CvmVerifier::mark_image_in_use`]; decrements the image's in-use
+/// reference count on drop.
+struct ImageUseGuard<'a> {
+    verifier: &'a CvmVerifier,
+    hex_os_image_hash: String,
+}
+
+impl Drop for ImageUseGuard<'_> {
+    fn drop(&mut self) {
+        let mut in_use = self
+            .verifier
+            .image_in_use
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = in_use.get_mut(&self.hex_os_image_hash) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                in_use.remove(&self.hex_os_image_hash);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Mrs {
     mrtd: Vec<u8>,
@@ -761,8 +2353,14 @@ impl Mrs {
     }
 }
 
-mod upgrade_authority {
+pub(crate) mod upgrade_authority {
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+
+    use anyhow::{ensure, Context, Result};
+    use ed25519_dalek::{Signature as Ed25519Signature, Verifier as _, VerifyingKey};
     use serde::{Deserialize, Serialize};
+    use sha2::{Digest as _, Sha256};
 
     #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
     pub struct BootInfo {
@@ -783,4 +2381,261 @@ mod upgrade_authority {
         pub tcb_status: String,
         pub advisory_ids: Vec<String>,
     }
+
+    /// One Ed25519 public key trusted by a [`RootMetadata`], named by `key_id` so a [`Signature`]
+    /// can identify which key produced it without repeating the key bytes in every signature.
+    #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+    pub struct PublicKeyEntry {
+        pub key_id: String,
+        pub public_key_hex: String,
+    }
+
+    /// Root document of the TUF-style chain: the keys trusted to sign [`TargetsMetadata`]/
+    /// [`TimestampMetadata`], and how many distinct trusted keys (`threshold`) must sign either
+    /// one for it to be accepted. The root document is self-certifying: it must itself carry at
+    /// least `threshold` valid signatures from its own `keys`.
+    #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+    pub struct RootMetadata {
+        pub version: u64,
+        pub expires_unix_secs: u64,
+        pub threshold: u32,
+        pub keys: Vec<PublicKeyEntry>,
+    }
+
+    /// One approved release: the measurement tuple a [`BootInfo`] must match, plus the
+    /// monotonically increasing `release_version` rollback protection compares against.
+    #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+    pub struct TargetEntry {
+        pub release_version: u64,
+        pub os_image_hash: Vec<u8>,
+        pub mr_system: Vec<u8>,
+        pub compose_hash: Vec<u8>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+    pub struct TargetsMetadata {
+        pub version: u64,
+        pub expires_unix_secs: u64,
+        pub targets: Vec<TargetEntry>,
+    }
+
+    /// Freshness-signs [`TargetsMetadata`] so a stale (but still validly signed) targets document
+    /// can't be replayed once a newer one has been issued: `targets_version`/`targets_sha256` must
+    /// match the targets document currently held by [`UpgradeAuthority`].
+    #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+    pub struct TimestampMetadata {
+        pub version: u64,
+        pub expires_unix_secs: u64,
+        pub targets_version: u64,
+        pub targets_sha256: Vec<u8>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+    pub struct Signature {
+        pub key_id: String,
+        pub signature_hex: String,
+    }
+
+    /// A metadata document paired with the signatures over its encoding. Signing/verification
+    /// covers `serde_json::to_vec(&self.signed)`: since every field in [`RootMetadata`],
+    /// [`TargetsMetadata`] and [`TimestampMetadata`] is serialized in declared struct order, this
+    /// is deterministic per-schema even though it isn't general JCS canonical JSON.
+    #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+    pub struct Signed<T> {
+        pub signed: T,
+        pub signatures: Vec<Signature>,
+    }
+
+    impl<T: Serialize> Signed<T> {
+        /// Counts signatures from distinct key IDs in `keys` that verify over `self.signed`'s
+        /// encoding. Unknown key IDs, duplicate key IDs, and malformed key/signature hex are
+        /// ignored rather than treated as errors, so one bad signature can't sink an otherwise
+        /// sufficient set.
+        fn count_valid_signatures(&self, keys: &[PublicKeyEntry]) -> Result<u32> {
+            let message = serde_json::to_vec(&self.signed)
+                .context("Failed to encode signed metadata for signature verification")?;
+            let mut seen_key_ids = HashSet::new();
+            let mut valid = 0;
+            for sig in &self.signatures {
+                if !seen_key_ids.insert(&sig.key_id) {
+                    continue;
+                }
+                let Some(key) = keys.iter().find(|k| k.key_id == sig.key_id) else {
+                    continue;
+                };
+                let verifies = (|| -> Option<bool> {
+                    let key_bytes: [u8; 32] =
+                        hex::decode(&key.public_key_hex).ok()?.try_into().ok()?;
+                    let sig_bytes: [u8; 64] =
+                        hex::decode(&sig.signature_hex).ok()?.try_into().ok()?;
+                    let verifying_key = VerifyingKey::from_bytes(&key_bytes).ok()?;
+                    let signature = Ed25519Signature::from_bytes(&sig_bytes);
+                    Some(verifying_key.verify_strict(&message, &signature).is_ok())
+                })()
+                .unwrap_or(false);
+                if verifies {
+                    valid += 1;
+                }
+            }
+            Ok(valid)
+        }
+    }
+
+    /// TUF-style signed-metadata chain gating which [`BootInfo`] an upgrade flow accepts: a root
+    /// document of trusted keys/threshold, a targets document mapping measurement tuples to
+    /// release versions, and a timestamp document freshness-signing the targets. [`Self::verify`]
+    /// requires the whole chain to verify (signature threshold, not expired, timestamp attests to
+    /// the held targets) *before* comparing measurements, and rejects a release whose version is
+    /// below the highest one ever previously accepted — persisted in `state_path` so the rollback
+    /// check survives a restart — even if that older release is itself validly signed.
+    pub struct UpgradeAuthority {
+        root: RootMetadata,
+        targets: Signed<TargetsMetadata>,
+        timestamp: Signed<TimestampMetadata>,
+        state_path: PathBuf,
+    }
+
+    impl UpgradeAuthority {
+        pub fn new(
+            root: Signed<RootMetadata>,
+            targets: Signed<TargetsMetadata>,
+            timestamp: Signed<TimestampMetadata>,
+            state_path: impl Into<PathBuf>,
+        ) -> Result<Self> {
+            let valid = root.count_valid_signatures(&root.signed.keys)?;
+            ensure!(
+                valid >= root.signed.threshold,
+                "Root metadata has {valid} valid signature(s), threshold is {}",
+                root.signed.threshold
+            );
+            ensure!(
+                super::now_unix_secs() < root.signed.expires_unix_secs,
+                "Root metadata has expired"
+            );
+            Ok(Self {
+                root: root.signed,
+                targets,
+                timestamp,
+                state_path: state_path.into(),
+            })
+        }
+
+        /// Highest `release_version` ever accepted by [`Self::verify`], or `0` if `state_path`
+        /// doesn't exist yet (no release has been accepted).
+        fn highest_accepted_version(&self) -> u64 {
+            fs_err::read_to_string(&self.state_path)
+                .ok()
+                .and_then(|contents| contents.trim().parse().ok())
+                .unwrap_or(0)
+        }
+
+        fn persist_accepted_version(&self, version: u64) -> Result<()> {
+            if let Some(parent) = self.state_path.parent() {
+                fs_err::create_dir_all(parent)
+                    .context("Failed to create upgrade-authority state directory")?;
+            }
+            fs_err::write(&self.state_path, version.to_string())
+                .context("Failed to persist highest accepted release version")
+        }
+
+        /// Verifies `boot_info` against the signed metadata chain. Order matters: the timestamp
+        /// and targets documents must each meet the root's signature threshold and not be expired,
+        /// and the timestamp must attest to exactly the held targets version/hash, all *before* a
+        /// matching target (the measurement `assert_eq` equivalent for this chain) is even looked
+        /// for. Only once a match is found is its release version checked against the highest one
+        /// ever previously accepted, rejecting a signed-but-older release as a rollback attempt.
+        pub fn verify(&self, boot_info: &BootInfo) -> Result<()> {
+            let now = super::now_unix_secs();
+
+            let timestamp_valid = self.timestamp.count_valid_signatures(&self.root.keys)?;
+            ensure!(
+                timestamp_valid >= self.root.threshold,
+                "Timestamp metadata has {timestamp_valid} valid signature(s), threshold is {}",
+                self.root.threshold
+            );
+            ensure!(
+                now < self.timestamp.signed.expires_unix_secs,
+                "Timestamp metadata has expired"
+            );
+
+            let targets_valid = self.targets.count_valid_signatures(&self.root.keys)?;
+            ensure!(
+                targets_valid >= self.root.threshold,
+                "Targets metadata has {targets_valid} valid signature(s), threshold is {}",
+                self.root.threshold
+            );
+            ensure!(
+                now < self.targets.signed.expires_unix_secs,
+                "Targets metadata has expired"
+            );
+
+            ensure!(
+                self.timestamp.signed.targets_version == self.targets.signed.version,
+                "Timestamp attests targets version {}, but the held targets metadata is version {}",
+                self.timestamp.signed.targets_version,
+                self.targets.signed.version
+            );
+            let targets_hash = Sha256::digest(
+                serde_json::to_vec(&self.targets.signed)
+                    .context("Failed to encode targets metadata for timestamp hash verification")?,
+            )
+            .to_vec();
+            ensure!(
+                self.timestamp.signed.targets_sha256 == targets_hash,
+                "Timestamp's targets_sha256 doesn't match the held targets metadata"
+            );
+
+            let target = self
+                .targets
+                .signed
+                .targets
+                .iter()
+                .find(|t| {
+                    t.os_image_hash == boot_info.os_image_hash
+                        && t.mr_system == boot_info.mr_system
+                        && t.compose_hash == boot_info.compose_hash
+                })
+                .context("No signed target matches this BootInfo's measurement tuple")?;
+
+            let highest_accepted = self.highest_accepted_version();
+            ensure!(
+                target.release_version >= highest_accepted,
+                "Release version {} is below the highest previously accepted version {highest_accepted} (rollback rejected)",
+                target.release_version
+            );
+            if target.release_version > highest_accepted {
+                self.persist_accepted_version(target.release_version)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// On-disk shape of the `upgrade_authority` bundle [`UpgradeAuthority::load`] reads: the three
+    /// signed documents together in one file, rather than three separately-reloadable ones, since
+    /// nothing in this checkout updates one without reissuing the others anyway.
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    struct UpgradeAuthorityBundle {
+        root: Signed<RootMetadata>,
+        targets: Signed<TargetsMetadata>,
+        timestamp: Signed<TimestampMetadata>,
+    }
+
+    impl UpgradeAuthority {
+        /// Loads an [`UpgradeAuthority`] from the signed bundle at `bundle_path`, persisting
+        /// rollback state at `state_path`. Returns `None` if `bundle_path` doesn't exist, i.e. no
+        /// upgrade authority is configured and boot-acceptance doesn't enforce this chain at all —
+        /// the same "absent means disabled" convention as
+        /// [`crate::revocation::RevocationCascade::load`].
+        pub fn load(bundle_path: &Path, state_path: impl Into<PathBuf>) -> Result<Option<Self>> {
+            if !bundle_path.exists() {
+                return Ok(None);
+            }
+            let contents = fs_err::read_to_string(bundle_path)
+                .context("Failed to read upgrade authority bundle")?;
+            let bundle: UpgradeAuthorityBundle = serde_json::from_str(&contents)
+                .context("Failed to parse upgrade authority bundle")?;
+            Self::new(bundle.root, bundle.targets, bundle.timestamp, state_path).map(Some)
+        }
+    }
 }
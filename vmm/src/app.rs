@@ -19,7 +19,7 @@ use id_pool::IdPool;
 use or_panic::ResultOrPanic;
 use ra_rpc::client::RaClient;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::net::IpAddr;
 use std::path::{Path, PathBuf};
@@ -31,9 +31,20 @@ use tracing::{error, info, warn};
 pub use image::{Image, ImageInfo};
 pub use qemu::{VmConfig, VmWorkDir};
 
+pub mod balloon;
+pub mod console;
+pub mod console_buffer;
+pub mod fetcher;
+mod gpu;
+pub mod hotplug;
 mod id_pool;
 mod image;
+pub mod migration;
 mod qemu;
+pub mod qemu_hook;
+pub mod qmp;
+pub mod snapshot;
+pub mod vm_config_hook;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct PortMapping {
@@ -66,6 +77,215 @@ pub struct Manifest {
     pub gateway_urls: Vec<String>,
     #[serde(default)]
     pub no_tee: bool,
+    /// Explicit socket/die/core/thread split for `vcpu`; `None` lets QEMU pick a flat topology.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_topology: Option<CpuTopology>,
+    /// Ceiling `vcpu` can be hotplugged up to. Wired into the QEMU command line as the maximum
+    /// CPU count so later hotplug never needs a restart; `None` means no hotplug headroom.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_vcpu: Option<u32>,
+    /// Ceiling `memory` (in MB) can be hotplugged up to. Wired into the QEMU command line as the
+    /// `-m` maxmem; `None` means no hotplug headroom.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory: Option<u32>,
+    /// Whether a `virtio-balloon` device is attached, allowing the host to reclaim idle guest
+    /// memory via [`App::set_balloon`].
+    #[serde(default)]
+    pub balloon: bool,
+    /// Caps this VM's block I/O, applied as a `throttling` group on its `-drive`. `None` leaves
+    /// the disk unthrottled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_rate_limit: Option<RateLimit>,
+    /// Caps this VM's network traffic, applied to its netdev. `None` leaves the network
+    /// unthrottled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net_rate_limit: Option<RateLimit>,
+    /// Ordered component images assembled into this VM's disk, innermost (shared, read-only)
+    /// first and the writable overlay last. Empty means the legacy layout: a single `image`
+    /// plus a `qemu-img`-resized data disk.
+    #[serde(default)]
+    pub disk_layers: Vec<DiskLayer>,
+}
+
+/// A single component of a composite disk built from `Manifest::disk_layers`, addressed the
+/// same way `Manifest::image` addresses the base image: a directory name under the image store.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiskLayer {
+    /// Where this component stacks in the assembled disk.
+    pub role: DiskLayerRole,
+    /// Name of the image directory (resolved the same way `Manifest::image` is) backing this
+    /// layer.
+    pub image: String,
+}
+
+/// Where a [`DiskLayer`] sits in a composite disk, innermost (shared, read-only) to outermost
+/// (private, writable).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskLayerRole {
+    /// The shared, read-only rootfs many CVMs can boot from unmodified.
+    BaseRootfs,
+    /// An optional dm-verity/integrity partition measured alongside the rootfs.
+    Verity,
+    /// Per-app configuration baked in above the shared base.
+    Config,
+    /// The writable overlay unique to this VM; always stacked last.
+    Overlay,
+}
+
+impl Manifest {
+    /// Resolves `disk_layers` into absolute image-store paths, innermost first, validating each
+    /// component's image name the same way `App::load_vm` validates `image` (length, no `..`,
+    /// only filename-safe characters).
+    ///
+    /// This only resolves and validates the backing chain; turning it into a QCOW2 backing-file
+    /// chain (an overlay on top of the shared base images, so many CVMs can share one immutable
+    /// base) or a concatenated composite image on the work dir is `VmConfig::config_qemu`'s job,
+    /// in the `qemu` module (`mod qemu;` in this file) that isn't present in this checkout.
+    pub fn resolve_disk_layers(&self, image_path: &Path) -> Result<Vec<(DiskLayerRole, PathBuf)>> {
+        self.disk_layers
+            .iter()
+            .map(|layer| {
+                if layer.image.len() > 64
+                    || layer.image.contains("..")
+                    || !layer
+                        .image
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+                {
+                    bail!("Invalid disk layer image name: {}", layer.image);
+                }
+                Ok((layer.role, image_path.join(&layer.image)))
+            })
+            .collect()
+    }
+}
+
+/// A token-bucket rate limit for block I/O or network traffic: up to `burst_bytes` tokens can be
+/// spent immediately, after which admission is capped at `bytes_per_sec`/`ops_per_sec`.
+///
+/// This is the data shape `create_manifest_from_vm_config` validates and persists; turning it
+/// into running enforcement is QEMU's job via [`RateLimit::to_qemu_throttle_args`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RateLimit {
+    pub bytes_per_sec: u64,
+    pub ops_per_sec: u64,
+    pub burst_bytes: u64,
+}
+
+impl RateLimit {
+    /// Builds a rate limit, rejecting one that caps neither bytes nor ops (i.e. does nothing).
+    pub fn new(bytes_per_sec: u64, ops_per_sec: u64, burst_bytes: u64) -> Result<Self> {
+        if bytes_per_sec == 0 && ops_per_sec == 0 {
+            bail!("rate limit must cap at least one of bytes_per_sec/ops_per_sec");
+        }
+        Ok(Self {
+            bytes_per_sec,
+            ops_per_sec,
+            burst_bytes,
+        })
+    }
+
+    /// The `throttling.bps-total`/`throttling.iops-total`/`throttling.bps-total-max` properties
+    /// QEMU's `-drive` (or, for network, a `bps`/`bps_rd`/`bps_wr`-style netdev throttle) expects.
+    ///
+    /// Not yet wired into a running QEMU command line: that goes through `VmConfig::config_qemu`,
+    /// which lives in a `qemu` module (`mod qemu;` in this file) that isn't present in this
+    /// checkout. Once it is, `config_qemu` just needs to add a `throttle-group` object plus this
+    /// object's props onto the relevant `-drive`/netdev args for `manifest.disk_rate_limit`/
+    /// `net_rate_limit`.
+    pub fn to_qemu_throttle_args(self) -> serde_json::Value {
+        serde_json::json!({
+            "bps-total": self.bytes_per_sec,
+            "bps-total-max": self.burst_bytes,
+            "iops-total": self.ops_per_sec,
+        })
+    }
+}
+
+/// A VM's CPU topology, expressed the way QEMU's `-smp` wants it: `vcpu` must equal
+/// `sockets * dies * cores * threads`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CpuTopology {
+    pub sockets: u32,
+    #[serde(default = "default_dies")]
+    pub dies: u32,
+    pub cores: u32,
+    pub threads: u32,
+}
+
+fn default_dies() -> u32 {
+    1
+}
+
+impl CpuTopology {
+    /// Builds a topology and checks it accounts for exactly `vcpu` vCPUs.
+    pub fn new(sockets: u32, dies: u32, cores: u32, threads: u32, vcpu: u32) -> Result<Self> {
+        let total = sockets
+            .saturating_mul(dies)
+            .saturating_mul(cores)
+            .saturating_mul(threads);
+        if total != vcpu {
+            bail!(
+                "CPU topology {sockets}x{dies}x{cores}x{threads} accounts for {total} vcpus, \
+                 expected {vcpu}"
+            );
+        }
+        Ok(Self {
+            sockets,
+            dies,
+            cores,
+            threads,
+        })
+    }
+
+    /// Detects the host's own socket/core/thread layout from
+    /// `/sys/devices/system/cpu/cpu*/topology`, for a `match_host_topology` request that wants
+    /// the guest to see the same physical layout as the host (e.g. for license-sensitive
+    /// workloads that key off physical socket count). Dies aren't exposed per-CPU under
+    /// `topology/`, so this always reports a single die per socket.
+    pub fn from_host(vcpu: u32) -> Result<Self> {
+        let mut sockets = BTreeSet::new();
+        let mut cores_per_socket: HashMap<u32, BTreeSet<u32>> = HashMap::new();
+        let mut threads_per_core: HashMap<(u32, u32), u32> = HashMap::new();
+
+        for entry in fs::read_dir("/sys/devices/system/cpu")
+            .context("Failed to read /sys/devices/system/cpu")?
+        {
+            let entry = entry.context("Failed to read a /sys/devices/system/cpu entry")?;
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            let Some(cpu_num) = name.strip_prefix("cpu") else {
+                continue;
+            };
+            if cpu_num.parse::<u32>().is_err() {
+                continue;
+            }
+            let topology = entry.path().join("topology");
+            let Ok(package_id) = fs::read_to_string(topology.join("physical_package_id")) else {
+                continue;
+            };
+            let Ok(core_id) = fs::read_to_string(topology.join("core_id")) else {
+                continue;
+            };
+            let package_id: u32 = package_id.trim().parse().context("Invalid physical_package_id")?;
+            let core_id: u32 = core_id.trim().parse().context("Invalid core_id")?;
+            sockets.insert(package_id);
+            cores_per_socket.entry(package_id).or_default().insert(core_id);
+            *threads_per_core.entry((package_id, core_id)).or_insert(0) += 1;
+        }
+
+        let sockets_n = sockets.len() as u32;
+        let cores_n = cores_per_socket
+            .values()
+            .map(|cores| cores.len() as u32)
+            .max()
+            .context("No CPU topology information found under /sys/devices/system/cpu")?;
+        let threads_n = threads_per_core.values().copied().max().unwrap_or(1);
+        Self::new(sockets_n, 1, cores_n, threads_n, vcpu)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -109,17 +329,52 @@ impl GpuConfig {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct GpuSpec {
+    /// Explicit PCI slot/BDF, e.g. `"0b:00.3"`. Takes precedence over `vendor`/`device` when set.
     #[serde(default)]
     pub slot: String,
+    /// PCI vendor id, e.g. `"10de"` for NVIDIA. Used with `device` when `slot` is empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<String>,
+    /// PCI device id, e.g. `"1b80"`. Required alongside `vendor`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// Which match (0-based, in device-listing order) to pick when `vendor`/`device` matches
+    /// more than one physical device.
+    #[serde(default)]
+    pub index: u32,
+    /// Whether this is the VM's primary graphics device.
+    #[serde(default)]
+    pub graphics: bool,
+}
+
+/// Before/after topology snapshot around [`App::attach_gpu`]/[`App::detach_gpu`], so a caller
+/// can tell the guest/relying party whether attestation-relevant state changed and a fresh
+/// attestation is needed.
+///
+/// This only carries the [`snapshot::SnapshotMetadata`] subset (GPU/NVSwitch counts and the
+/// fields already tracked for snapshot compatibility); it does not recompute RTMR/ACPI event
+/// logs via `dstack_mr::Machine::measure_with_logs`, since that needs the VM's firmware/kernel/
+/// initrd paths and `Image` doesn't expose those here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuHotplugDelta {
+    pub before: snapshot::SnapshotMetadata,
+    pub after: snapshot::SnapshotMetadata,
 }
 
+/// How many times [`App::try_graceful_shutdown`] polls QMP `query-status` for `"shutdown"`
+/// before giving up and letting [`App::stop_vm`] fall back to SIGTERM.
+const GRACEFUL_SHUTDOWN_POLLS: u32 = 25;
+/// Delay between each [`App::try_graceful_shutdown`] poll.
+const GRACEFUL_SHUTDOWN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
 #[derive(Clone)]
 pub struct App {
     pub config: Arc<Config>,
     pub supervisor: SupervisorClient,
     state: Arc<Mutex<AppState>>,
+    consoles: Arc<tokio::sync::Mutex<HashMap<String, console::ConsoleProxy>>>,
 }
 
 impl App {
@@ -146,6 +401,7 @@ impl App {
                 vms: HashMap::new(),
             })),
             config: Arc::new(config),
+            consoles: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
         }
     }
 
@@ -168,6 +424,10 @@ impl App {
         }
         let image_path = self.config.image_path.join(&manifest.image);
         let image = Image::load(&image_path).context("Failed to load image")?;
+        for (role, layer_path) in manifest.resolve_disk_layers(&self.config.image_path)? {
+            Image::load(&layer_path)
+                .with_context(|| format!("Failed to load disk layer image for {role:?}"))?;
+        }
         let vm_id = manifest.id.clone();
         let app_compose = vm_work_dir
             .app_compose()
@@ -259,7 +519,583 @@ impl App {
 
     pub async fn stop_vm(&self, id: &str) -> Result<()> {
         self.set_started(id, false)?;
+        self.try_graceful_shutdown(id).await;
         self.supervisor.stop(id).await?;
+        self.consoles.lock().await.remove(id);
+        Ok(())
+    }
+
+    /// Asks QEMU to gracefully power down the guest over QMP, giving a cooperative guest a
+    /// chance to shut down clean, before [`App::stop_vm`] falls back to SIGTERM via the
+    /// supervisor. Best-effort: any failure (no QMP socket, guest ignores ACPI power-off, guest
+    /// hangs) just falls through to the existing forceful stop.
+    async fn try_graceful_shutdown(&self, id: &str) {
+        let qmp_socket = self.work_dir(id).qmp_socket();
+        if !qmp_socket.exists() {
+            return;
+        }
+        let Ok(client) = self.qmp_client(id) else {
+            return;
+        };
+        if client.system_powerdown().await.is_err() {
+            return;
+        }
+        for _ in 0..GRACEFUL_SHUTDOWN_POLLS {
+            if matches!(client.query_status().await, Ok(status) if status == "shutdown") {
+                return;
+            }
+            tokio::time::sleep(GRACEFUL_SHUTDOWN_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Live-migrates the guest memory and device state of VM `id` to a destination QEMU
+    /// process that already has `dest_fd_name` registered on its QMP socket (via `getfd`).
+    ///
+    /// Only same-host, local-mode migration (QEMU's `fd:` transport) is supported; there is no
+    /// network transport here, so both ends must already share the destination's file
+    /// descriptor (see [`migration::MigrationFd::pass_to`]).
+    pub async fn migrate_vm_local(&self, id: &str, dest_fd_name: &str) -> Result<()> {
+        let work_dir = self.work_dir(id);
+        let qmp_socket = work_dir.qmp_socket();
+        if !qmp_socket.exists() {
+            bail!("VM {id} is not running, cannot migrate");
+        }
+        let dest_fd = migration::MigrationFd {
+            name: dest_fd_name.to_string(),
+        };
+        migration::migrate_local(&qmp_socket, &dest_fd)
+            .await
+            .with_context(|| format!("Failed to migrate VM {id}"))
+    }
+
+    /// Drives the *source* side of a full local-mode migration of VM `id`: negotiates the
+    /// migration protocol version over `control_stream`, hands the destination the VM's
+    /// [`Manifest`], passes `mem_fd` to `dest_qmp_socket` under `dest_fd_name` via `SCM_RIGHTS`
+    /// (see [`migration::MigrationFd::pass_to`]), drives the QEMU-level migration to completion,
+    /// and only removes the VM locally once the destination acknowledges the handoff.
+    ///
+    /// Refuses to proceed for a TEE-attested guest (`manifest.no_tee == false`) unless
+    /// `allow_tee_migration` is set, since sealed TEE state cannot move transparently between
+    /// hosts. `control_stream` is an already-connected channel to the destination's migration
+    /// listener; see the `send_migration`/`receive_migration` gap note on
+    /// [`crate::main_service::RpcHandler`] for why that listener isn't reachable over the RPC
+    /// surface yet.
+    pub async fn migrate_vm_out(
+        &self,
+        id: &str,
+        control_stream: &mut tokio::net::UnixStream,
+        dest_qmp_socket: &Path,
+        dest_fd_name: &str,
+        mem_fd: std::os::fd::OwnedFd,
+    ) -> Result<()> {
+        let manifest = self
+            .lock()
+            .get(id)
+            .context("VM not found")?
+            .config
+            .manifest
+            .clone();
+        if !manifest.no_tee && !self.config.cvm.allow_tee_migration {
+            bail!(
+                "VM {id} is a TEE-attested guest (no_tee=false); migration is disabled unless \
+                 `allow_tee_migration` is set, since sealed state cannot move transparently"
+            );
+        }
+
+        migration::negotiate(control_stream)
+            .await
+            .with_context(|| format!("Migration handshake failed for VM {id}"))?;
+        migration::send_manifest(control_stream, &manifest)
+            .await
+            .with_context(|| format!("Failed to send manifest for VM {id}"))?;
+
+        let dest_fd = migration::MigrationFd::pass_to(dest_qmp_socket, dest_fd_name, mem_fd)
+            .await
+            .with_context(|| format!("Failed to pass migration fd for VM {id}"))?;
+
+        let qmp_socket = self.work_dir(id).qmp_socket();
+        migration::migrate_local(&qmp_socket, &dest_fd)
+            .await
+            .with_context(|| format!("Failed to migrate VM {id}"))?;
+
+        migration::send_complete(control_stream)
+            .await
+            .context("Failed to send migration completion marker")?;
+        migration::recv_complete(control_stream)
+            .await
+            .with_context(|| format!("Destination did not acknowledge migration of VM {id}"))?;
+
+        self.remove_vm(id)
+            .await
+            .with_context(|| format!("Migrated VM {id} but failed to remove it locally"))
+    }
+
+    /// Drives the *destination* side of the migration protocol: negotiates the handshake,
+    /// receives the source's [`Manifest`] over `control_stream`, persists it into this VM's work
+    /// dir, then exchanges the completion markers with the source.
+    ///
+    /// This does not itself start a QEMU process in `-incoming fd:<name>` mode or call
+    /// [`App::load_vm`] — launching QEMU for a VM (migration-incoming or otherwise) goes through
+    /// `VmConfig::config_qemu`, which is declared (`mod qemu;`) but not present in this checkout.
+    /// Once that module exists, the caller here would start QEMU in incoming mode, register
+    /// `dest_fd_name` with its own QMP socket, and then `load_vm` the persisted manifest after
+    /// this returns.
+    pub async fn receive_vm_migration(
+        &self,
+        control_stream: &mut tokio::net::UnixStream,
+    ) -> Result<Manifest> {
+        let peer = migration::negotiate(control_stream)
+            .await
+            .context("Migration handshake failed")?;
+        if !peer.capabilities.iter().any(|c| c == "local-fd") {
+            bail!("Peer does not support local-fd migration");
+        }
+        let manifest = migration::recv_manifest(control_stream)
+            .await
+            .context("Failed to receive manifest")?;
+
+        let work_dir = self.work_dir(&manifest.id);
+        work_dir
+            .set_manifest(&manifest)
+            .context("Failed to persist transferred manifest")?;
+
+        migration::send_complete(control_stream)
+            .await
+            .context("Failed to acknowledge migration")?;
+        migration::recv_complete(control_stream)
+            .await
+            .context("Source did not send migration completion")?;
+
+        Ok(manifest)
+    }
+
+    /// Attaches to VM `id`'s serial console, reusing an already-running proxy if one exists.
+    ///
+    /// Repeated calls (e.g. a client reconnecting after a dropped websocket) return a clone of
+    /// the same [`console::ConsoleProxy`] rather than re-opening the pty, so output produced
+    /// while nobody was attached isn't lost.
+    pub async fn attach_console(&self, id: &str) -> Result<console::ConsoleProxy> {
+        let mut consoles = self.consoles.lock().await;
+        if let Some(proxy) = consoles.get(id) {
+            return Ok(proxy.clone());
+        }
+        let pty_path = self.work_dir(id).serial_pty();
+        let proxy = console::ConsoleProxy::attach(pty_path)
+            .await
+            .with_context(|| format!("Failed to attach console for VM {id}"))?;
+        consoles.insert(id.to_string(), proxy.clone());
+        Ok(proxy)
+    }
+
+    /// Pauses VM `id` and writes a full memory/device-state snapshot, plus the manifest and
+    /// [`snapshot::SnapshotMetadata`] needed to validate a later restore, into `dest_dir`.
+    ///
+    /// For CVMs the snapshot's guest memory is opaque and (TDX-)encrypted: this call captures
+    /// enough to resume the same guest, not to inspect it, and the guest's attestation is not
+    /// itself preserved — callers must re-attest after [`App::restore_vm`] brings it back up.
+    pub async fn snapshot_vm(&self, id: &str, dest_dir: &Path) -> Result<PathBuf> {
+        let vm_config = self
+            .lock()
+            .get(id)
+            .context("VM not found")?
+            .config
+            .clone();
+        let work_dir = self.work_dir(id);
+        let qmp_socket = work_dir.qmp_socket();
+        if !qmp_socket.exists() {
+            bail!("VM {id} is not running, cannot snapshot");
+        }
+
+        self.set_snapshot_progress(id, "snapshotting")?;
+        let result = self
+            .write_snapshot(&vm_config.manifest, &qmp_socket, dest_dir)
+            .await;
+        self.set_snapshot_progress(id, if result.is_ok() { "done" } else { "failed" })?;
+        result?;
+        Ok(dest_dir.to_path_buf())
+    }
+
+    async fn write_snapshot(
+        &self,
+        manifest: &Manifest,
+        qmp_socket: &Path,
+        dest_dir: &Path,
+    ) -> Result<()> {
+        fs::create_dir_all(dest_dir).context("Failed to create snapshot directory")?;
+
+        let image_path = self.config.image_path.join(&manifest.image);
+        let image = Image::load(&image_path).context("Failed to load image info")?;
+        let metadata = snapshot_metadata(&self.config, manifest, &image)?;
+
+        fs::write(
+            dest_dir.join("manifest.json"),
+            serde_json::to_string(manifest).context("Failed to serialize manifest")?,
+        )
+        .context("Failed to write snapshot manifest")?;
+        fs::write(
+            snapshot::metadata_path(dest_dir),
+            serde_json::to_string(&metadata).context("Failed to serialize snapshot metadata")?,
+        )
+        .context("Failed to write snapshot metadata")?;
+
+        snapshot::save_snapshot(qmp_socket, &snapshot::state_path(dest_dir)).await
+    }
+
+    /// Restores VM `id`, which must have been started with `-incoming defer`, from a snapshot
+    /// directory previously written by [`App::snapshot_vm`].
+    ///
+    /// Fails cleanly, without touching the running (incoming) QEMU process, if the snapshot's
+    /// recorded image digest, CPU/memory, or GPU topology no longer match this VM's current
+    /// configuration — including the `img_ver < (0, 5, 0)` guard `make_sys_config` already
+    /// enforces for the image in use.
+    pub async fn restore_vm(&self, id: &str, src_dir: &Path) -> Result<()> {
+        let vm_config = self
+            .lock()
+            .get(id)
+            .context("VM not found")?
+            .config
+            .clone();
+        let work_dir = self.work_dir(id);
+        let qmp_socket = work_dir.qmp_socket();
+
+        self.set_snapshot_progress(id, "restoring")?;
+        let result = self.read_snapshot(&vm_config.manifest, &qmp_socket, src_dir).await;
+        self.set_snapshot_progress(id, if result.is_ok() { "done" } else { "failed" })?;
+        result
+    }
+
+    async fn read_snapshot(
+        &self,
+        manifest: &Manifest,
+        qmp_socket: &Path,
+        src_dir: &Path,
+    ) -> Result<()> {
+        let state_path = snapshot::state_path(src_dir);
+        if !state_path.exists() {
+            bail!("No snapshot found in {}", src_dir.display());
+        }
+
+        // Reuses make_sys_config's own version guard, so an unsupported target image fails with
+        // the same error a fresh `start_vm` would give, rather than a confusing migration error.
+        make_sys_config(&self.config, manifest)
+            .context("Target host's image is incompatible with this snapshot")?;
+
+        let image_path = self.config.image_path.join(&manifest.image);
+        let image = Image::load(&image_path).context("Failed to load image info")?;
+        let target_metadata = snapshot_metadata(&self.config, manifest, &image)?;
+
+        let saved_metadata: snapshot::SnapshotMetadata = serde_json::from_str(
+            &fs::read_to_string(snapshot::metadata_path(src_dir))
+                .context("Failed to read snapshot metadata")?,
+        )
+        .context("Failed to parse snapshot metadata")?;
+        saved_metadata
+            .check_compatible(&target_metadata)
+            .context("Snapshot is not compatible with this host")?;
+
+        snapshot::restore_snapshot(qmp_socket, &state_path).await
+    }
+
+    fn set_snapshot_progress(&self, id: &str, progress: &str) -> Result<()> {
+        let mut state = self.lock();
+        let vm = state.get_mut(id).context("VM not found")?;
+        vm.state.snapshot_progress = progress.to_string();
+        Ok(())
+    }
+
+    /// Grows or shrinks running VM `id`'s vCPU count and/or memory size without a restart,
+    /// provided the host hasn't disabled hotplug (`qemu_hotplug_off`) and the request stays
+    /// within both the host's `max_allocable_vcpu`/`max_allocable_memory_in_mb` ceilings
+    /// (`CvmConfig`, also reported by `get_meta`) and the manifest's own `max_vcpu`/`max_memory`
+    /// ceilings (the bounds the VM's QEMU command line was started with headroom for).
+    ///
+    /// On success the persisted manifest is updated to the new size, so
+    /// [`App::try_restart_exited_vms`] restarts the VM at its current size rather than the
+    /// original boot-time baseline.
+    pub async fn resize_vm(&self, id: &str, vcpus: Option<u32>, memory_mb: Option<u32>) -> Result<()> {
+        if self.config.cvm.qemu_hotplug_off {
+            bail!("Hotplug is disabled on this host (qemu_hotplug_off)");
+        }
+        if let Some(target) = vcpus {
+            if target > self.config.cvm.max_allocable_vcpu {
+                bail!(
+                    "Requested {target} vcpus exceeds the host's max_allocable_vcpu ceiling of {}",
+                    self.config.cvm.max_allocable_vcpu
+                );
+            }
+        }
+        if let Some(target_mb) = memory_mb {
+            if target_mb > self.config.cvm.max_allocable_memory_in_mb {
+                bail!(
+                    "Requested {target_mb}MB memory exceeds the host's max_allocable_memory_in_mb \
+                     ceiling of {}MB",
+                    self.config.cvm.max_allocable_memory_in_mb
+                );
+            }
+        }
+        let vm_config = self
+            .lock()
+            .get(id)
+            .context("VM not found")?
+            .config
+            .clone();
+        let work_dir = self.work_dir(id);
+        let qmp_socket = work_dir.qmp_socket();
+        if !qmp_socket.exists() {
+            bail!("VM {id} is not running, cannot resize");
+        }
+
+        let mut manifest = vm_config.manifest.clone();
+        if let Some(target) = vcpus {
+            if let Some(max) = manifest.max_vcpu {
+                if target > max {
+                    bail!("Requested {target} vcpus exceeds max_vcpu ceiling of {max}");
+                }
+            }
+            hotplug::resize_vcpus(&qmp_socket, manifest.vcpu, target).await?;
+            manifest.vcpu = target;
+        }
+        if let Some(target_mb) = memory_mb {
+            if let Some(max) = manifest.max_memory {
+                if target_mb > max {
+                    bail!("Requested {target_mb}MB memory exceeds max_memory ceiling of {max}MB");
+                }
+            }
+            match self.config.cvm.qemu_memory_hotplug_method {
+                hotplug::HotplugMethod::VirtioMem => {
+                    hotplug::virtio_mem_resize(&qmp_socket, "virtio-mem0", target_mb).await?;
+                }
+                hotplug::HotplugMethod::Acpi => {
+                    if target_mb < manifest.memory {
+                        bail!(
+                            "Cannot shrink memory from {}MB to {target_mb}MB with ACPI DIMM \
+                             hotplug; switch qemu_memory_hotplug_method to virtio_mem",
+                            manifest.memory
+                        );
+                    }
+                    let delta_mb = target_mb - manifest.memory;
+                    if delta_mb > 0 {
+                        let dimm_id = format!("dimm-{}", manifest.memory + delta_mb);
+                        hotplug::acpi_plug_memory(&qmp_socket, &dimm_id, delta_mb).await?;
+                    }
+                }
+            }
+            manifest.memory = target_mb;
+        }
+
+        let image_path = self.config.image_path.join(&manifest.image);
+        let image = Image::load(&image_path).context("Failed to load image info")?;
+        let new_vm_config = VmConfig {
+            manifest: manifest.clone(),
+            image,
+            cid: vm_config.cid,
+            workdir: vm_config.workdir.clone(),
+            gateway_enabled: vm_config.gateway_enabled,
+        };
+        work_dir
+            .set_manifest(&manifest)
+            .context("Failed to persist resized manifest")?;
+        let mut state = self.lock();
+        let vm = state.get_mut(id).context("VM not found")?;
+        vm.config = new_vm_config.into();
+        Ok(())
+    }
+
+    /// Hotplugs a `vfio-pci` GPU (or, with `is_bridge`, NVSwitch) device onto running VM `id`
+    /// over QMP `device_add`, the same mechanism [`hotplug::plug_vcpu`] uses for vCPUs.
+    ///
+    /// Refuses to run when the host has `qemu_hotplug_off` set, when `spec` resolves to a PCI
+    /// device already [`lspci::Device::in_use`], or when it's already attached to this VM.
+    /// `device_add` replying without an error isn't itself proof the device attached, so this
+    /// polls `query-pci` (see [`wait_for_pci_state`]) before updating the persisted manifest and
+    /// in-memory device bookkeeping, which feed into attestation-relevant `snapshot_metadata`.
+    pub async fn attach_gpu(&self, id: &str, spec: &GpuSpec, is_bridge: bool) -> Result<GpuHotplugDelta> {
+        if self.config.cvm.qemu_hotplug_off {
+            bail!("Hotplug is disabled on this host (qemu_hotplug_off)");
+        }
+        let (mut manifest, mut devices) = {
+            let state = self.lock();
+            let vm = state.get(id).context("VM not found")?;
+            (vm.config.manifest.clone(), vm.state.devices.clone())
+        };
+        let work_dir = self.work_dir(id);
+        let qmp_socket = work_dir.qmp_socket();
+        if !qmp_socket.exists() {
+            bail!("VM {id} is not running, cannot hotplug a GPU");
+        }
+        let image_path = self.config.image_path.join(&manifest.image);
+        let image = Image::load(&image_path).context("Failed to load image info")?;
+        let before = snapshot_metadata(&self.config, &manifest, &image)?;
+
+        let all_devices = self.config.cvm.gpu.list_devices()?;
+        let mut claimed: Vec<String> = devices
+            .gpus
+            .iter()
+            .chain(&devices.bridges)
+            .map(|g| g.slot.clone())
+            .collect();
+        let resolved = gpu::resolve_one(spec, &all_devices, &mut claimed)?;
+
+        let qdev_id = gpu_device_id(&resolved.slot);
+        let qmp = self.qmp_client(id)?;
+        qmp.device_add(json!({
+            "driver": "vfio-pci",
+            "host": resolved.slot,
+            "id": qdev_id,
+        }))
+        .await
+        .context("Failed to hotplug GPU via QMP")?;
+        wait_for_pci_state(&qmp, &qdev_id, true, 10).await?;
+
+        if is_bridge {
+            devices.bridges.push(resolved);
+        } else {
+            devices.gpus.push(resolved);
+        }
+        manifest.gpus = Some(devices.clone());
+        work_dir
+            .set_manifest(&manifest)
+            .context("Failed to persist manifest after GPU hotplug")?;
+        let mut state = self.lock();
+        let vm = state.get_mut(id).context("VM not found")?;
+        vm.state.devices = devices;
+        drop(state);
+
+        let after = snapshot_metadata(&self.config, &manifest, &image)?;
+        Ok(GpuHotplugDelta { before, after })
+    }
+
+    /// Detaches the GPU/NVSwitch device at PCI `slot` from running VM `id` over QMP
+    /// `device_del`, the inverse of [`App::attach_gpu`]. Confirms the device is actually gone
+    /// via `query-pci` (see [`wait_for_pci_state`]) before persisting the bookkeeping update, for
+    /// the same reason `attach_gpu` does.
+    pub async fn detach_gpu(&self, id: &str, slot: &str) -> Result<GpuHotplugDelta> {
+        if self.config.cvm.qemu_hotplug_off {
+            bail!("Hotplug is disabled on this host (qemu_hotplug_off)");
+        }
+        let (mut manifest, mut devices) = {
+            let state = self.lock();
+            let vm = state.get(id).context("VM not found")?;
+            (vm.config.manifest.clone(), vm.state.devices.clone())
+        };
+        let work_dir = self.work_dir(id);
+        let qmp_socket = work_dir.qmp_socket();
+        if !qmp_socket.exists() {
+            bail!("VM {id} is not running, cannot detach a GPU");
+        }
+        let image_path = self.config.image_path.join(&manifest.image);
+        let image = Image::load(&image_path).context("Failed to load image info")?;
+        let before = snapshot_metadata(&self.config, &manifest, &image)?;
+
+        let found_in_gpus = devices.gpus.iter().any(|g| g.slot == slot);
+        let found_in_bridges = devices.bridges.iter().any(|g| g.slot == slot);
+        if !found_in_gpus && !found_in_bridges {
+            bail!("No attached GPU/NVSwitch device at slot {slot}");
+        }
+
+        let qdev_id = gpu_device_id(slot);
+        let qmp = self.qmp_client(id)?;
+        qmp.device_del(&qdev_id)
+            .await
+            .context("Failed to hot-unplug GPU via QMP")?;
+        wait_for_pci_state(&qmp, &qdev_id, false, 10).await?;
+
+        devices.gpus.retain(|g| g.slot != slot);
+        devices.bridges.retain(|g| g.slot != slot);
+        manifest.gpus = Some(devices.clone());
+        work_dir
+            .set_manifest(&manifest)
+            .context("Failed to persist manifest after GPU detach")?;
+        let mut state = self.lock();
+        let vm = state.get_mut(id).context("VM not found")?;
+        vm.state.devices = devices;
+        drop(state);
+
+        let after = snapshot_metadata(&self.config, &manifest, &image)?;
+        Ok(GpuHotplugDelta { before, after })
+    }
+
+    /// Fetches `descriptor` into the content-addressed cache under `config.artifact_cache_path`,
+    /// verifying its size and sha256 digest, and returns the path it was stored at. A no-op if
+    /// that digest is already cached.
+    ///
+    /// Not yet reachable as an RPC: see the `BLOCKING PREREQUISITE` comment on `impl VmmRpc for
+    /// RpcHandler` in `main_service.rs` — there is no `.proto` file at all in this checkout, so a
+    /// `FetchArtifact` request/response pair can't be added without regenerating one first.
+    pub async fn fetch_artifact(
+        &self,
+        descriptor: &fetcher::ArtifactDescriptor,
+    ) -> Result<PathBuf, fetcher::FetchError> {
+        let cache = fetcher::ArtifactCache::new(self.config.artifact_cache_path.clone());
+        cache.fetch(descriptor).await
+    }
+
+    /// Asks VM `id`'s `virtio-balloon` device to settle on `target_mb` megabytes of guest
+    /// memory, then records what it actually reports in [`VmState`] alongside boot progress.
+    pub async fn set_balloon(&self, id: &str, target_mb: u32) -> Result<()> {
+        let manifest = self
+            .lock()
+            .get(id)
+            .context("VM not found")?
+            .config
+            .manifest
+            .clone();
+        if !manifest.balloon {
+            bail!("VM {id} was not started with a balloon device");
+        }
+        let qmp_socket = self.work_dir(id).qmp_socket();
+        if !qmp_socket.exists() {
+            bail!("VM {id} is not running, cannot adjust balloon");
+        }
+        balloon::set_balloon(&qmp_socket, target_mb).await?;
+        self.refresh_balloon(id, &qmp_socket).await
+    }
+
+    async fn refresh_balloon(&self, id: &str, qmp_socket: &Path) -> Result<()> {
+        let actual_mb = balloon::query_balloon(qmp_socket).await?;
+        let mut state = self.lock();
+        let vm = state.get_mut(id).context("VM not found")?;
+        vm.state.balloon_actual_mb = Some(actual_mb);
+        Ok(())
+    }
+
+    /// Proportionally deflates the balloons of running, balloon-enabled VMs to free up
+    /// `required_mb` megabytes of headroom for a VM that's about to start, e.g. when
+    /// [`App::try_restart_exited_vms`] has a queued VM waiting on memory.
+    ///
+    /// Each eligible VM is asked to give up a share of `required_mb` proportional to its own
+    /// manifest memory size; this is a best-effort host-side policy, not a guarantee — guests
+    /// may not be able to actually release that much back to the host.
+    pub async fn reclaim_for_pending_vm(&self, required_mb: u32) -> Result<()> {
+        if required_mb == 0 {
+            return Ok(());
+        }
+        let candidates: Vec<(String, u32)> = self
+            .lock()
+            .iter_vms()
+            .filter(|vm| vm.config.manifest.balloon)
+            .map(|vm| (vm.config.manifest.id.clone(), vm.config.manifest.memory))
+            .collect();
+        let total_memory: u64 = candidates.iter().map(|(_, mb)| u64::from(*mb)).sum();
+        if total_memory == 0 {
+            return Ok(());
+        }
+        for (id, memory_mb) in candidates {
+            let qmp_socket = self.work_dir(&id).qmp_socket();
+            if !qmp_socket.exists() {
+                continue;
+            }
+            let share = (u64::from(required_mb) * u64::from(memory_mb) / total_memory) as u32;
+            if share == 0 {
+                continue;
+            }
+            let target_mb = memory_mb.saturating_sub(share);
+            if let Err(err) = balloon::set_balloon(&qmp_socket, target_mb).await {
+                warn!("Failed to deflate balloon for VM {id}: {err}");
+                continue;
+            }
+            self.refresh_balloon(&id, &qmp_socket).await.ok();
+        }
         Ok(())
     }
 
@@ -469,6 +1305,10 @@ impl App {
         }
         let image_path = self.config.image_path.join(&manifest.image);
         let image = Image::load(&image_path).context("Failed to load image")?;
+        for (role, layer_path) in manifest.resolve_disk_layers(&self.config.image_path)? {
+            Image::load(&layer_path)
+                .with_context(|| format!("Failed to load disk layer image for {role:?}"))?;
+        }
         let vm_id = manifest.id.clone();
         let already_running = cids_assigned.contains_key(&vm_id);
         let app_compose = vm_work_dir
@@ -581,6 +1421,9 @@ impl App {
         })
     }
 
+    /// Images available in `self.config.image_path`, keyed by directory name — the same pool
+    /// `Manifest::image` and `Manifest::disk_layers` resolve entries against, so this also
+    /// surfaces the fleet's shared base-image set for composite disks.
     pub fn list_images(&self) -> Result<Vec<(String, ImageInfo)>> {
         let image_path = self.config.image_path.clone();
         let images = fs::read_dir(image_path).context("Failed to read image directory")?;
@@ -726,11 +1569,43 @@ impl App {
         )))
     }
 
-    fn try_allocate_gpus(&self, manifest: &Manifest) -> Result<GpuConfig> {
-        if !self.config.cvm.gpu.enabled {
-            return Ok(GpuConfig::default());
+    /// Opens a QMP control channel to VM `id`'s QEMU monitor socket, for operations (powerdown,
+    /// pause/resume, NMI injection) that must work even if the guest hasn't booted or is wedged.
+    pub(crate) fn qmp_client(&self, id: &str) -> Result<qmp::QmpClient> {
+        if self.lock().get(id).is_none() {
+            bail!("vm not found");
         }
-        Ok(manifest.gpus.clone().unwrap_or_default())
+        Ok(qmp::QmpClient::new(self.work_dir(id).qmp_socket()))
+    }
+
+    /// Queries QEMU's own run state over QMP and records it in the VM's in-memory state,
+    /// emitting a `qemu.status` lifecycle event when it changes. This reflects QEMU's view and
+    /// stays available when `boot_progress` (reported by the in-guest agent) cannot be trusted,
+    /// e.g. a guest that's paused or wedged.
+    pub(crate) async fn refresh_qemu_status(&self, id: &str) -> Result<String> {
+        let client = self.qmp_client(id)?;
+        let status = client.query_status().await?;
+        let mut state = self.lock();
+        let vm = state.get_mut(id).context("VM not found")?;
+        if vm.state.qemu_status.as_deref() != Some(status.as_str()) {
+            vm.state.qemu_status = Some(status.clone());
+            vm.state.events.push_back(pb::GuestEvent {
+                event: "qemu.status".into(),
+                body: status.clone(),
+                timestamp: SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64,
+            });
+            while vm.state.events.len() > self.config.event_buffer_size {
+                vm.state.events.pop_front();
+            }
+        }
+        Ok(status)
+    }
+
+    fn try_allocate_gpus(&self, manifest: &Manifest) -> Result<GpuConfig> {
+        resolve_gpu_allocation(&self.config, manifest)
     }
 
     pub(crate) async fn list_gpus(&self) -> Result<Vec<GpuInfo>> {
@@ -774,7 +1649,33 @@ impl App {
             .map(|vm| vm.config.manifest.id.clone())
             .collect::<Vec<_>>();
         for id in exited_vms {
+            // TODO(guest-power-off-vs-crash): this treats every non-paused exit as a crash and
+            // restarts it, which can't yet tell a guest-initiated shutdown apart from one. Doing
+            // that needs (a) a background task started alongside the VM that calls
+            // `QmpClient::wait_for_shutdown_event` (now implemented in qmp.rs) and persists
+            // whatever it observes somewhere `vm-state.json` survives a restart of this process,
+            // and (b) starting QEMU with `-S` so "configured" and "should run" are separate
+            // states in the first place. Both (a)'s persistence and (b)'s QEMU invocation live in
+            // `VmConfig::config_qemu`/`VmWorkDir::set_started`, in the `crate::app::qemu` module
+            // declared by `mod qemu;` above — but `app/qemu.rs` isn't present in this source tree,
+            // so the wiring can't be completed against real code here.
+            //
+            // The supervisor only tracks whether QEMU's OS process is alive, which says
+            // nothing about a paused-but-still-running guest (e.g. paused for debugging via
+            // QMP `stop`). Ask QEMU directly before concluding it actually exited.
+            if self.work_dir(&id).qmp_socket().exists() {
+                if let Ok(status) = self.refresh_qemu_status(&id).await {
+                    if status == "paused" {
+                        info!("Not restarting VM {id}: QEMU reports it paused");
+                        continue;
+                    }
+                }
+            }
             info!("Restarting VM {id}");
+            let memory_needed = self.lock().get(&id).map(|vm| vm.config.manifest.memory);
+            if let Some(memory_mb) = memory_needed {
+                self.reclaim_for_pending_vm(memory_mb).await.ok();
+            }
             self.start_vm(&id).await?;
         }
         Ok(())
@@ -799,27 +1700,98 @@ pub(crate) fn make_sys_config(cfg: &Config, manifest: &Manifest) -> Result<Strin
         bail!("Unsupported image version: {img_ver:?}");
     }
 
+    let resolved_gpus = resolve_gpu_allocation(cfg, manifest)?;
     let sys_config = json!({
         "kms_urls": kms_urls,
         "gateway_urls": gateway_urls,
         "pccs_url": cfg.cvm.pccs_url,
         "docker_registry": cfg.cvm.docker_registry,
         "host_api_url": format!("vsock://2:{}/api", cfg.host_api.port),
-        "vm_config": serde_json::to_string(&make_vm_config(cfg, manifest, &image))?,
+        "vm_config": serde_json::to_string(&make_vm_config(cfg, manifest, &image, &resolved_gpus)?)?,
     });
     let sys_config_str =
         serde_json::to_string(&sys_config).context("Failed to serialize vm config")?;
     Ok(sys_config_str)
 }
 
-fn make_vm_config(cfg: &Config, manifest: &Manifest, image: &Image) -> dstack_types::VmConfig {
+/// Resolves `manifest`'s requested GPUs/NVSwitches (by PCI slot, vendor:device id, or explicit
+/// BDF) against the physical devices currently present, rejecting any selector that matches no
+/// device or a device already in use. Returns an empty allocation if GPU passthrough is disabled
+/// on this host.
+/// Derives the QMP device id a hotplugged GPU is added/removed under from its PCI `slot`, e.g.
+/// `"0b:00.3"` becomes `"gpu-0b-00-3"` (QOM ids can't contain `:`/`.`).
+fn gpu_device_id(slot: &str) -> String {
+    format!("gpu-{}", slot.replace([':', '.'], "-"))
+}
+
+/// Recursively searches a QMP `query-pci` response for a device entry whose `qdev_id` is
+/// `qdev_id`, the QOM id `device_add`/`device_del` operate on. Walks generically over the
+/// response's `Value` tree, rather than indexing into `query-pci`'s documented
+/// `[{"devices": [...]}]` shape, so it isn't brittle to exactly which level `qdev_id` nests at
+/// across QEMU versions.
+fn query_pci_contains(pci_info: &Value, qdev_id: &str) -> bool {
+    match pci_info {
+        Value::Object(fields) => {
+            if fields.get("qdev_id").and_then(Value::as_str) == Some(qdev_id) {
+                return true;
+            }
+            fields.values().any(|v| query_pci_contains(v, qdev_id))
+        }
+        Value::Array(items) => items.iter().any(|v| query_pci_contains(v, qdev_id)),
+        _ => false,
+    }
+}
+
+/// Polls `query-pci` until `qdev_id`'s presence matches `expect_present`, or bails once
+/// `attempts` polls have passed without it settling. `device_add`/`device_del` completing is not
+/// itself proof the guest's PCI tree reflects it yet (hot-unplug in particular waits on the
+/// guest's ACPI unplug handler), so this is a short poll rather than a single check.
+async fn wait_for_pci_state(
+    qmp: &qmp::QmpClient,
+    qdev_id: &str,
+    expect_present: bool,
+    attempts: u32,
+) -> Result<()> {
+    for attempt in 0..attempts {
+        let pci_info = qmp
+            .query_pci()
+            .await
+            .context("Failed to query PCI state via QMP")?;
+        if query_pci_contains(&pci_info, qdev_id) == expect_present {
+            return Ok(());
+        }
+        if attempt + 1 < attempts {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        }
+    }
+    bail!(
+        "device_{} for {qdev_id} reported success but query-pci never showed it as {}",
+        if expect_present { "add" } else { "del" },
+        if expect_present { "attached" } else { "detached" },
+    );
+}
+
+fn resolve_gpu_allocation(cfg: &Config, manifest: &Manifest) -> Result<GpuConfig> {
+    if !cfg.cvm.gpu.enabled {
+        return Ok(GpuConfig::default());
+    }
+    let requested = manifest.gpus.clone().unwrap_or_default();
+    let devices = cfg.cvm.gpu.list_devices()?;
+    gpu::resolve_selectors(&requested, &devices)
+}
+
+fn make_vm_config(
+    cfg: &Config,
+    manifest: &Manifest,
+    image: &Image,
+    gpus: &GpuConfig,
+) -> Result<dstack_types::VmConfig> {
     let os_image_hash = image
         .digest
         .as_ref()
         .and_then(|d| hex::decode(d).ok())
         .unwrap_or_default();
-    let gpus = manifest.gpus.clone().unwrap_or_default();
-    dstack_types::VmConfig {
+    let vm_config = dstack_types::VmConfig {
         spec_version: 1,
         os_image_hash,
         cpu_count: manifest.vcpu,
@@ -833,9 +1805,29 @@ fn make_vm_config(cfg: &Config, manifest: &Manifest, image: &Image) -> dstack_ty
         num_nvswitches: gpus.bridges.len() as u32,
         hotplug_off: cfg.cvm.qemu_hotplug_off,
         image: Some(manifest.image.clone()),
+    };
+    match &cfg.cvm.qemu_config_hook {
+        Some(path) => vm_config_hook::VmConfigHook::load(path)
+            .and_then(|hook| hook.apply(vm_config))
+            .context("QEMU config hook failed"),
+        None => Ok(vm_config),
     }
 }
 
+/// Derives the subset of [`make_vm_config`]'s output a snapshot needs to validate against,
+/// for use by [`App::snapshot_vm`]/[`App::restore_vm`].
+fn snapshot_metadata(cfg: &Config, manifest: &Manifest, image: &Image) -> Result<snapshot::SnapshotMetadata> {
+    let resolved_gpus = resolve_gpu_allocation(cfg, manifest)?;
+    let vm_config = make_vm_config(cfg, manifest, image, &resolved_gpus)?;
+    Ok(snapshot::SnapshotMetadata {
+        os_image_hash: vm_config.os_image_hash,
+        cpu_count: vm_config.cpu_count,
+        memory_size: vm_config.memory_size,
+        num_gpus: vm_config.num_gpus,
+        num_nvswitches: vm_config.num_nvswitches,
+    })
+}
+
 fn paginate<T>(items: Vec<T>, page: u32, page_size: u32) -> impl Iterator<Item = T> {
     let skip;
     let take;
@@ -862,6 +1854,15 @@ struct VmStateMut {
     boot_progress: String,
     boot_error: String,
     shutdown_progress: String,
+    /// Mirrors `boot_progress`/`shutdown_progress` for the snapshot/restore lifecycle, e.g.
+    /// `"snapshotting"`, `"restoring"`, `"done"`.
+    snapshot_progress: String,
+    /// The balloon's last-known actual memory size in MB, if a `virtio-balloon` device is
+    /// attached and has been queried at least once.
+    balloon_actual_mb: Option<u32>,
+    /// QEMU's own last-known run state from QMP `query-status` (e.g. `"running"`, `"paused"`,
+    /// `"shutdown"`), refreshed by [`App::refresh_qemu_status`]. `None` until first queried.
+    qemu_status: Option<String>,
     devices: GpuConfig,
     events: VecDeque<pb::GuestEvent>,
 }
@@ -875,6 +1876,7 @@ impl VmStateMut {
         };
         self.boot_error.clear();
         self.shutdown_progress.clear();
+        self.snapshot_progress.clear();
     }
 
     pub fn reset_na(&mut self) {
@@ -0,0 +1,32 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Memory-balloon control for VMs that were started with a `virtio-balloon` device, so the host
+//! can overcommit memory across many CVMs and reclaim idle guest RAM under pressure, the way
+//! crosvm's balloon device is driven.
+
+use crate::migration::qmp_command;
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::Path;
+
+/// Asks the guest's balloon driver to settle on `target_mb` megabytes of usable memory; the
+/// guest may take some time to actually reach it, see [`query_balloon`].
+pub(crate) async fn set_balloon(qmp_socket: &Path, target_mb: u32) -> Result<()> {
+    let bytes = u64::from(target_mb) * 1024 * 1024;
+    qmp_command(qmp_socket, "balloon", json!({"value": bytes}))
+        .await
+        .context("Failed to set balloon target")?;
+    Ok(())
+}
+
+/// Returns the balloon's last-reported actual memory size, in megabytes.
+pub(crate) async fn query_balloon(qmp_socket: &Path) -> Result<u32> {
+    let result = qmp_command(qmp_socket, "query-balloon", json!({})).await?;
+    let bytes = result
+        .get("actual")
+        .and_then(serde_json::Value::as_u64)
+        .context("query-balloon response missing `actual`")?;
+    Ok((bytes / (1024 * 1024)) as u32)
+}
@@ -141,6 +141,10 @@ impl PortMappingConfig {
     }
 }
 
+fn default_memory_hotplug_method() -> crate::app::hotplug::HotplugMethod {
+    crate::app::hotplug::HotplugMethod::Acpi
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct CvmConfig {
     pub qemu_path: PathBuf,
@@ -188,6 +192,30 @@ pub struct CvmConfig {
     /// QEMU hotplug_off
     pub qemu_hotplug_off: bool,
 
+    /// Optional path to a Lua script (see [`crate::app::qemu_hook::QemuCommandHook`]) that can
+    /// append or rewrite the QEMU argv for every VM started on this host, e.g. to add
+    /// site-specific vsock channels or vhost-user backends.
+    #[serde(default)]
+    pub qemu_command_hook: Option<PathBuf>,
+
+    /// Optional path to a Lua script (see [`crate::app::vm_config_hook::VmConfigHook`]) that can
+    /// retune the non-attestation-critical fields of `make_vm_config`'s output, e.g. to flip
+    /// `pic`/`hotplug_off` or widen `pci_hole64_size` per deployment without a recompile.
+    #[serde(default)]
+    pub qemu_config_hook: Option<PathBuf>,
+
+    /// How [`App::resize_vm`](crate::app::App::resize_vm) grows or shrinks memory on a running
+    /// VM: ACPI `pc-dimm` hotplug (add-only) or a `virtio-mem` device (grow and shrink).
+    #[serde(default = "default_memory_hotplug_method")]
+    pub qemu_memory_hotplug_method: crate::app::hotplug::HotplugMethod,
+
+    /// Allow [`App::migrate_vm_out`](crate::app::App::migrate_vm_out) to move a VM whose
+    /// manifest has `no_tee == false` (i.e. TEE attestation is in effect). Off by default, since
+    /// sealed TEE state cannot move transparently between hosts without the destination sharing
+    /// the same attestation identity.
+    #[serde(default)]
+    pub allow_tee_migration: bool,
+
     /// Networking configuration
     pub networking: Networking,
 }
@@ -262,6 +290,10 @@ pub struct Config {
     pub image_path: PathBuf,
     #[serde(default)]
     pub run_path: PathBuf,
+    /// Where [`crate::fetcher::ArtifactCache`] stores content-addressed downloads, keyed by
+    /// their sha256 digest.
+    #[serde(default)]
+    pub artifact_cache_path: PathBuf,
     /// The URL of the KMS server
     pub kms_url: String,
 
@@ -408,6 +440,9 @@ impl Config {
             if me.run_path == PathBuf::default() {
                 me.run_path = app_home.join("vm");
             }
+            if me.artifact_cache_path == PathBuf::default() {
+                me.artifact_cache_path = app_home.join("artifacts");
+            }
             if me.cvm.qemu_path == PathBuf::default() {
                 // Prefer the path from dstack client config if present
                 if let Some(qemu_path) = read_qemu_path_from_client_conf() {
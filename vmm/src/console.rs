@@ -0,0 +1,111 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! A reconnectable proxy in front of a VM's serial console pty.
+//!
+//! QEMU exposes the guest serial console as a pty at `VmWorkDir::serial_pty()`. Opening that
+//! pty directly only allows one reader at a time and loses any output produced while nobody is
+//! attached. [`ConsoleProxy`] owns the pty, tees its output into a bounded backlog (see
+//! [`crate::console_buffer`]), and lets any number of callers attach and detach without
+//! disturbing the guest or each other.
+
+use crate::console_buffer::RingBuffer;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{broadcast, Mutex};
+
+/// Default broadcast backlog: lagging subscribers drop the oldest frames rather than block
+/// the pump task reading from the pty.
+const BROADCAST_CAPACITY: usize = 1024;
+
+/// How much recent console output `vm_info` can show without a subscriber attached.
+const RING_BUFFER_CAPACITY: usize = 64 * 1024;
+
+/// A live proxy in front of one VM's serial console pty.
+///
+/// Cloning a `ConsoleProxy` is cheap and shares the same underlying pty and broadcast channel;
+/// every clone is a new "connection" that can subscribe to output and write input independently.
+#[derive(Clone)]
+pub struct ConsoleProxy {
+    pty_path: PathBuf,
+    output: broadcast::Sender<Vec<u8>>,
+    writer: Arc<Mutex<tokio::fs::File>>,
+    backlog: Arc<std::sync::Mutex<RingBuffer>>,
+}
+
+impl ConsoleProxy {
+    /// Opens `pty_path` and spawns a background task that continuously reads from it and fans
+    /// output out to every current and future subscriber.
+    pub async fn attach(pty_path: PathBuf) -> Result<Self> {
+        let reader = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&pty_path)
+            .await
+            .with_context(|| format!("Failed to open console pty {}", pty_path.display()))?;
+        let writer = reader.try_clone().await.context("Failed to dup console pty")?;
+
+        let (tx, _rx) = broadcast::channel(BROADCAST_CAPACITY);
+        let pump_tx = tx.clone();
+        let backlog = Arc::new(std::sync::Mutex::new(RingBuffer::new(RING_BUFFER_CAPACITY)));
+        let pump_backlog = backlog.clone();
+        tokio::spawn(async move {
+            let mut reader = reader;
+            let mut buf = [0u8; 4096];
+            loop {
+                match reader.read(&mut buf).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        // No subscribers is not an error: the guest keeps running even if
+                        // nobody is watching the console right now.
+                        pump_backlog
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .push(&buf[..n]);
+                        let _ = pump_tx.send(buf[..n].to_vec());
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Ok(Self {
+            pty_path,
+            output: tx,
+            writer: Arc::new(Mutex::new(writer)),
+            backlog,
+        })
+    }
+
+    /// Subscribes to console output from this point forward. Reconnecting after a dropped
+    /// connection just calls this again; the pty and guest are unaffected by how many
+    /// subscribers come and go.
+    pub fn subscribe(&self) -> broadcast::Receiver<Vec<u8>> {
+        self.output.subscribe()
+    }
+
+    /// Writes guest-bound input (e.g. keystrokes) to the console.
+    pub async fn write_input(&self, data: &[u8]) -> Result<()> {
+        let mut writer = self.writer.lock().await;
+        writer
+            .write_all(data)
+            .await
+            .context("Failed to write to console pty")
+    }
+
+    /// The underlying pty path this proxy was attached to.
+    pub fn pty_path(&self) -> &std::path::Path {
+        &self.pty_path
+    }
+
+    /// Returns the most recent console output retained in the backlog, in chronological order.
+    ///
+    /// This lets `vm_info` show a boot/console tail even when nobody is currently subscribed.
+    pub fn backlog(&self) -> Vec<u8> {
+        self.backlog.lock().unwrap_or_else(|e| e.into_inner()).contents()
+    }
+}
@@ -0,0 +1,222 @@
+// SPDX-FileCopyrightText: © 2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Content-addressed, integrity-verified fetching of remote images/assets, for hosts that pull
+//! guest images from a remote store instead of having them preloaded under `image_path`.
+//!
+//! Modeled on the same idea as an RPM repository's `repomd.xml`-driven download or
+//! openethereum's hash-fetch: the caller supplies an [`ArtifactDescriptor`] naming the expected
+//! digest and size up front, [`ArtifactCache::fetch`] streams the body straight into a
+//! content-addressed cache file while hashing it, and rejects the result if either the digest or
+//! the size don't match. A second `fetch` for the same digest is a no-op once the file is
+//! already in the cache.
+
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// Names a remote artifact by where to get it and what it must hash/size to, e.g. a guest image
+/// layer pulled from an artifact store rather than shipped alongside `dstack-vmm`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactDescriptor {
+    pub url: String,
+    /// Expected digest, lowercase hex-encoded, as produced by `sha256sum`.
+    pub sha256: String,
+    /// Expected size in bytes; the download is rejected if the body is larger, and verified
+    /// against the final count once the body ends.
+    pub size: u64,
+}
+
+/// Errors [`ArtifactCache::fetch`] can fail with, distinguished so a caller can tell "the
+/// artifact doesn't exist upstream" apart from "it exists but we can't trust what we got".
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("artifact not found at {0}")]
+    NotFound(String),
+    #[error("artifact at {url} exceeded the expected size of {expected} bytes")]
+    SizeTooLarge { url: String, expected: u64 },
+    #[error("artifact at {url} was {actual} bytes, expected {expected}")]
+    SizeMismatch { url: String, expected: u64, actual: u64 },
+    #[error("artifact at {url} hashed to {actual}, expected {expected}")]
+    DigestMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("request to {url} failed: {source}")]
+    Request {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("I/O error while fetching {url}: {source}")]
+    Io {
+        url: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// A content-addressed cache of downloaded artifacts, keyed by sha256 digest.
+pub struct ArtifactCache {
+    client: Client,
+    cache_dir: PathBuf,
+}
+
+impl ArtifactCache {
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            client: Client::new(),
+            cache_dir: cache_dir.into(),
+        }
+    }
+
+    /// Where a fully-downloaded, verified artifact with this digest lives.
+    pub fn path_for(&self, sha256: &str) -> PathBuf {
+        self.cache_dir.join(sha256)
+    }
+
+    /// The partial-download path used to resume an interrupted fetch of this digest via HTTP
+    /// Range requests.
+    fn partial_path_for(&self, sha256: &str) -> PathBuf {
+        self.cache_dir.join(format!("{sha256}.part"))
+    }
+
+    /// Returns the cached path for `descriptor`, downloading (or resuming a partial download of)
+    /// it first if it isn't already present. The final file is only placed at
+    /// [`Self::path_for`] once both its size and sha256 digest have been checked against
+    /// `descriptor`; a mismatch leaves no file behind at that path.
+    pub async fn fetch(&self, descriptor: &ArtifactDescriptor) -> Result<PathBuf, FetchError> {
+        let final_path = self.path_for(&descriptor.sha256);
+        if final_path.exists() {
+            return Ok(final_path);
+        }
+
+        fs_err::create_dir_all(&self.cache_dir).map_err(|e| FetchError::Io {
+            url: descriptor.url.clone(),
+            source: e.into(),
+        })?;
+
+        let partial_path = self.partial_path_for(&descriptor.sha256);
+        let mut hasher = Sha256::new();
+        let mut downloaded = self.hash_existing_partial(&partial_path, &mut hasher).await?;
+
+        let mut request = self.client.get(&descriptor.url);
+        if downloaded > 0 {
+            request = request.header("Range", format!("bytes={downloaded}-"));
+        }
+        let response = request.send().await.map_err(|e| FetchError::Request {
+            url: descriptor.url.clone(),
+            source: e,
+        })?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(FetchError::NotFound(descriptor.url.clone()));
+        }
+        // A server that ignores Range restarts from the top; a 206 confirms it honored ours.
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            downloaded = 0;
+        }
+        let mut response = response.error_for_status().map_err(|e| FetchError::Request {
+            url: descriptor.url.clone(),
+            source: e,
+        })?;
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(downloaded == 0)
+            .open(&partial_path)
+            .await
+            .map_err(|e| FetchError::Io {
+                url: descriptor.url.clone(),
+                source: e,
+            })?;
+        if downloaded == 0 {
+            hasher = Sha256::new();
+            file.seek(SeekFrom::Start(0)).await.ok();
+        } else {
+            file.seek(SeekFrom::Start(downloaded)).await.map_err(|e| FetchError::Io {
+                url: descriptor.url.clone(),
+                source: e,
+            })?;
+        }
+
+        while let Some(chunk) = response.chunk().await.map_err(|e| FetchError::Request {
+            url: descriptor.url.clone(),
+            source: e,
+        })? {
+            downloaded += chunk.len() as u64;
+            if downloaded > descriptor.size {
+                return Err(FetchError::SizeTooLarge {
+                    url: descriptor.url.clone(),
+                    expected: descriptor.size,
+                });
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).await.map_err(|e| FetchError::Io {
+                url: descriptor.url.clone(),
+                source: e,
+            })?;
+        }
+        file.flush().await.map_err(|e| FetchError::Io {
+            url: descriptor.url.clone(),
+            source: e,
+        })?;
+        drop(file);
+
+        if downloaded != descriptor.size {
+            return Err(FetchError::SizeMismatch {
+                url: descriptor.url.clone(),
+                expected: descriptor.size,
+                actual: downloaded,
+            });
+        }
+        let actual = hex::encode(hasher.finalize());
+        if actual != descriptor.sha256 {
+            return Err(FetchError::DigestMismatch {
+                url: descriptor.url.clone(),
+                expected: descriptor.sha256.clone(),
+                actual,
+            });
+        }
+
+        fs_err::rename(&partial_path, &final_path).map_err(|e| FetchError::Io {
+            url: descriptor.url.clone(),
+            source: e.into(),
+        })?;
+        Ok(final_path)
+    }
+
+    /// Feeds any bytes already on disk from a previous interrupted download into `hasher` so
+    /// resuming keeps hashing from where it left off, and returns how many bytes that was.
+    async fn hash_existing_partial(
+        &self,
+        partial_path: &Path,
+        hasher: &mut Sha256,
+    ) -> Result<u64, FetchError> {
+        let Ok(mut file) = tokio::fs::File::open(partial_path).await else {
+            return Ok(0);
+        };
+        let mut buf = [0u8; 64 * 1024];
+        let mut total = 0u64;
+        loop {
+            let n = file.read(&mut buf).await.map_err(|e| FetchError::Io {
+                url: partial_path.display().to_string(),
+                source: e,
+            })?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            total += n as u64;
+        }
+        Ok(total)
+    }
+}
@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resolves a manifest's requested GPUs/NVSwitches against the physical PCI devices present on
+//! the host, the way vore's `[[vfio]]` config selects a device by PCI slot, vendor:device id
+//! (with an index to disambiguate multiple matches), or explicit BDF.
+
+use super::{GpuConfig, GpuSpec};
+use anyhow::{bail, Context, Result};
+use lspci::Device;
+
+/// Resolves every [`GpuSpec`] in `requested.gpus` and `requested.bridges` against `devices`,
+/// rejecting the request if a selector matches no device, matches a device that's already
+/// [`Device::in_use`], or would pick the same physical device twice.
+///
+/// The returned [`GpuConfig`] holds the *resolved* selectors (always by explicit `slot`), so
+/// `num_gpus`/`num_nvswitches` derived from it reflect what was actually allocated rather than
+/// the raw request.
+pub(crate) fn resolve_selectors(requested: &GpuConfig, devices: &[Device]) -> Result<GpuConfig> {
+    let mut claimed = Vec::new();
+    let gpus = requested
+        .gpus
+        .iter()
+        .map(|spec| resolve_one(spec, devices, &mut claimed))
+        .collect::<Result<Vec<_>>>()?;
+    let bridges = requested
+        .bridges
+        .iter()
+        .map(|spec| resolve_one(spec, devices, &mut claimed))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(GpuConfig {
+        attach_mode: requested.attach_mode.clone(),
+        gpus,
+        bridges,
+    })
+}
+
+/// Resolves a single [`GpuSpec`] selector against `devices`, rejecting devices already
+/// [`Device::in_use`] or already present in `claimed` (so a caller building up a
+/// [`GpuConfig`] one device at a time, e.g. for hotplug, still gets the same double-claim
+/// protection [`resolve_selectors`] gives a whole manifest at once).
+pub(crate) fn resolve_one(spec: &GpuSpec, devices: &[Device], claimed: &mut Vec<String>) -> Result<GpuSpec> {
+    let device = if !spec.slot.is_empty() {
+        devices
+            .iter()
+            .find(|dev| dev.slot == spec.slot)
+            .with_context(|| format!("No PCI device found at slot {}", spec.slot))?
+    } else {
+        let vendor = spec
+            .vendor
+            .as_deref()
+            .context("GPU selector needs either `slot` or `vendor`/`device`")?;
+        let product = spec
+            .device
+            .as_deref()
+            .context("GPU selector with `vendor` also needs `device`")?;
+        let product_id = format!("{vendor}:{product}");
+        devices
+            .iter()
+            .filter(|dev| dev.full_product_id() == product_id)
+            .nth(spec.index as usize)
+            .with_context(|| {
+                format!(
+                    "No PCI device matching {product_id} at index {} (vendor:device, 0-based)",
+                    spec.index
+                )
+            })?
+    };
+
+    if device.in_use() {
+        bail!("PCI device {} is already in use", device.slot);
+    }
+    if claimed.contains(&device.slot) {
+        bail!("PCI device {} was selected by more than one GPU entry", device.slot);
+    }
+    claimed.push(device.slot.clone());
+
+    Ok(GpuSpec {
+        slot: device.slot.clone(),
+        vendor: spec.vendor.clone(),
+        device: spec.device.clone(),
+        index: spec.index,
+        graphics: spec.graphics,
+    })
+}
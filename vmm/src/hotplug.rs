@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Online CPU and memory resize for running VMs, driven over the same QMP control socket
+//! [`crate::migration`] and [`crate::snapshot`] use.
+//!
+//! Memory can be grown (and, with virtio-mem, shrunk) two ways, mirroring cloud-hypervisor's
+//! `HotplugMethod`:
+//! - [`HotplugMethod::Acpi`]: plug/unplug a `pc-dimm` backed by a `memory-backend-ram` object.
+//!   ACPI memory hotplug is add-only; there is no clean way to unplug a DIMM the guest may still
+//!   be using, so shrinking with this method is unsupported.
+//! - [`HotplugMethod::VirtioMem`]: adjust the `requested-size` property of an already-attached
+//!   `virtio-mem` device, which the guest can both grow and shrink online.
+
+use crate::migration::qmp_command;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+
+/// How memory is grown or shrunk on a running VM, matching the device topology QEMU was
+/// started with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotplugMethod {
+    /// ACPI-based `pc-dimm` hotplug: add-only.
+    Acpi,
+    /// `virtio-mem`-based hotplug: supports both growing and shrinking.
+    VirtioMem,
+}
+
+/// Adds one vCPU by plugging the next unplugged entry reported by `query-hotpluggable-cpus`.
+///
+/// Returns the QOM id QEMU assigned the new CPU, so it can be targeted by [`unplug_vcpu`] later.
+pub(crate) async fn plug_vcpu(qmp_socket: &Path) -> Result<String> {
+    let hotpluggable = qmp_command(qmp_socket, "query-hotpluggable-cpus", json!({})).await?;
+    let entries = hotpluggable
+        .as_array()
+        .context("query-hotpluggable-cpus did not return an array")?;
+    let target = entries
+        .iter()
+        .find(|entry| entry.get("qom-path").is_none())
+        .context("No unplugged vCPU slots available")?;
+
+    let cpu_type = target
+        .get("type")
+        .and_then(serde_json::Value::as_str)
+        .context("Hotpluggable CPU entry missing `type`")?;
+    let props = target.get("props").cloned().unwrap_or_else(|| json!({}));
+    let id = format!(
+        "cpu-{}",
+        props
+            .get("core-id")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0)
+    );
+    let mut args = match props {
+        serde_json::Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    args.insert("driver".to_string(), json!(cpu_type));
+    args.insert("id".to_string(), json!(id));
+
+    qmp_command(qmp_socket, "device_add", serde_json::Value::Object(args))
+        .await
+        .context("Failed to hotplug vCPU")?;
+    Ok(id)
+}
+
+/// Removes the vCPU previously plugged under QOM id `id`.
+pub(crate) async fn unplug_vcpu(qmp_socket: &Path, id: &str) -> Result<()> {
+    qmp_command(qmp_socket, "device_del", json!({"id": id}))
+        .await
+        .context("Failed to hot-unplug vCPU")?;
+    Ok(())
+}
+
+/// Grows or shrinks the vCPU count from `current` to `target` by repeatedly plugging or
+/// unplugging one vCPU at a time.
+pub(crate) async fn resize_vcpus(qmp_socket: &Path, current: u32, target: u32) -> Result<()> {
+    for _ in target..current {
+        // query-hotpluggable-cpus reports plugged entries last-to-first, so the most recently
+        // plugged vCPU goes first, mirroring a simple stack-like grow/shrink.
+        let hotpluggable = qmp_command(qmp_socket, "query-hotpluggable-cpus", json!({})).await?;
+        let id = hotpluggable
+            .as_array()
+            .context("query-hotpluggable-cpus did not return an array")?
+            .iter()
+            .rev()
+            .find_map(|entry| entry.get("qom-path").and_then(serde_json::Value::as_str))
+            .context("No plugged vCPU available to remove")?
+            .to_string();
+        unplug_vcpu(qmp_socket, &id).await?;
+    }
+    for _ in current..target {
+        plug_vcpu(qmp_socket).await?;
+    }
+    Ok(())
+}
+
+/// Grows memory by `delta_mb` megabytes using a freshly plugged `pc-dimm`/`memory-backend-ram`
+/// pair. Only growth is supported; see [`HotplugMethod::Acpi`].
+pub(crate) async fn acpi_plug_memory(qmp_socket: &Path, dimm_id: &str, delta_mb: u32) -> Result<()> {
+    if delta_mb == 0 {
+        bail!("Refusing to plug a zero-sized DIMM");
+    }
+    let backend_id = format!("mem-{dimm_id}");
+    let size_bytes = u64::from(delta_mb) * 1024 * 1024;
+
+    qmp_command(
+        qmp_socket,
+        "object-add",
+        json!({"qom-type": "memory-backend-ram", "id": backend_id, "props": {"size": size_bytes}}),
+    )
+    .await
+    .context("Failed to allocate memory backend for DIMM")?;
+
+    qmp_command(
+        qmp_socket,
+        "device_add",
+        json!({"driver": "pc-dimm", "id": dimm_id, "memdev": backend_id}),
+    )
+    .await
+    .context("Failed to hotplug DIMM")?;
+    Ok(())
+}
+
+/// Sets a `virtio-mem` device's `requested-size` property, asking the guest to grow or shrink
+/// its usable memory toward `target_mb` megabytes.
+pub(crate) async fn virtio_mem_resize(
+    qmp_socket: &Path,
+    virtio_mem_id: &str,
+    target_mb: u32,
+) -> Result<()> {
+    let size_bytes = u64::from(target_mb) * 1024 * 1024;
+    qmp_command(
+        qmp_socket,
+        "qom-set",
+        json!({"path": virtio_mem_id, "property": "requested-size", "value": size_bytes}),
+    )
+    .await
+    .context("Failed to resize virtio-mem device")?;
+    Ok(())
+}
@@ -4,19 +4,151 @@
 
 use crate::app::App;
 use anyhow::Result;
+use flate2::Compression;
 use fs_err as fs;
+use metrics::{counter, gauge};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use rocket::{
     get,
-    http::ContentType,
-    response::{status::Custom, stream::TextStream},
-    routes, Route, State,
+    http::{ContentType, Header, Status},
+    response::{status::Custom, stream::ByteStream, Responder},
+    routes, Request, Route, State,
 };
 use rocket_apitoken::Authorized;
+use rocket_ws::{Message, WebSocket};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::broadcast;
 use tokio::time::timeout;
 use tracing::{debug, info};
 
+/// Wraps a Responder to set a status and/or extra headers on whatever response it produces, so a
+/// handler can attach cache-validator/range headers (`ETag`, `Last-Modified`, `Accept-Ranges`,
+/// `Content-Range`, ...) to a body type (like `TextStream`/`ByteStream`) that has no builder API
+/// of its own.
+struct WithHeaders<R> {
+    inner: R,
+    status: Option<Status>,
+    headers: Vec<(&'static str, String)>,
+}
+
+impl<R> WithHeaders<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            status: None,
+            headers: Vec::new(),
+        }
+    }
+
+    fn status(mut self, status: Status) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    fn maybe_header(self, name: &'static str, value: Option<impl Into<String>>) -> Self {
+        match value {
+            Some(value) => self.header(name, value),
+            None => self,
+        }
+    }
+
+    fn maybe_status(self, status: Option<Status>) -> Self {
+        match status {
+            Some(status) => self.status(status),
+            None => self,
+        }
+    }
+
+    /// Replaces the wrapped body, keeping whatever status/headers were already set. Useful when
+    /// the headers are decided before the body (e.g. a streamed response) is constructed.
+    fn with_inner<R2>(self, inner: R2) -> WithHeaders<R2> {
+        WithHeaders {
+            inner,
+            status: self.status,
+            headers: self.headers,
+        }
+    }
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for WithHeaders<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        let mut response = self.inner.respond_to(request)?;
+        if let Some(status) = self.status {
+            response.set_status(status);
+        }
+        for (name, value) in self.headers {
+            response.set_header(Header::new(name, value));
+        }
+        Ok(response)
+    }
+}
+
+/// A weak validator and `Last-Modified` timestamp derived from a log file's size and mtime —
+/// cheap to compute and good enough to let a client resume a dropped `follow=false` download,
+/// without reading (let alone hashing) the file's contents on every request.
+struct LogFileCacheInfo {
+    etag: String,
+    last_modified: String,
+    len: u64,
+}
+
+fn log_file_cache_info(path: &Path) -> Option<LogFileCacheInfo> {
+    let metadata = fs::metadata(path).ok()?;
+    let len = metadata.len();
+    let modified = metadata.modified().ok()?;
+    let mtime_secs = modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some(LogFileCacheInfo {
+        etag: format!("\"{len:x}-{mtime_secs:x}\""),
+        last_modified: httpdate::fmt_http_date(modified),
+        len,
+    })
+}
+
+/// Parses a single-range `Range: bytes=START-[END]` header into an inclusive `(start, end)` byte
+/// range clamped to `len`. Multi-range requests (`bytes=0-10,20-30`) aren't supported; callers
+/// fall back to serving the full file for anything this doesn't understand.
+fn parse_byte_range(header: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = header.strip_prefix("bytes=")?;
+    if spec.contains(',') || len == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let end = end.trim();
+    let (start, end) = if start.is_empty() {
+        // `bytes=-N` means "the last N bytes".
+        let suffix_len: u64 = end.parse().ok()?;
+        let suffix_len = suffix_len.min(len);
+        (len - suffix_len, len - 1)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {
+            len - 1
+        } else {
+            end.parse::<u64>().ok()?.min(len - 1)
+        };
+        (start, end)
+    };
+    if start > end || start >= len {
+        return None;
+    }
+    Some((start, end))
+}
+
 macro_rules! file_or_include_str {
     ($path:literal) => {
         fs::metadata($path)
@@ -62,19 +194,65 @@ async fn v0(app: &State<App>) -> (ContentType, String) {
 }
 
 #[get("/res/<path>")]
-async fn res(path: &str) -> Result<(ContentType, String), Custom<String>> {
-    match path {
-        "x25519.js" => Ok((ContentType::JavaScript, file_or_include_str!("x25519.js"))),
-        _ => Err(Custom(
-            rocket::http::Status::NotFound,
-            "Not found".to_string(),
-        )),
-    }
+async fn res(
+    path: &str,
+    req: &Request<'_>,
+) -> Result<WithHeaders<(ContentType, String)>, Custom<String>> {
+    let (content_type, content) = match path {
+        "x25519.js" => (ContentType::JavaScript, file_or_include_str!("x25519.js")),
+        _ => {
+            return Err(Custom(
+                rocket::http::Status::NotFound,
+                "Not found".to_string(),
+            ))
+        }
+    };
+
+    let etag = format!("\"{}\"", hex::encode(Sha256::digest(content.as_bytes())));
+    let not_modified = req.headers().get_one("If-None-Match") == Some(etag.as_str());
+
+    let body = if not_modified { String::new() } else { content };
+    let response = WithHeaders::new((content_type, body))
+        .header("ETag", etag)
+        .header("Accept-Ranges", "bytes");
+    Ok(if not_modified {
+        response.status(Status::NotModified)
+    } else {
+        response
+    })
 }
 
 static STREAM_CREATED_COUNTER: AtomicUsize = AtomicUsize::new(0);
 static STREAM_DROPPED_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
+/// Lazily installs the global Prometheus recorder on first use, so every `counter!`/`gauge!` call
+/// below works whether or not anything has hit `/metrics` yet, and regardless of how early in
+/// this process's life the first log stream is opened.
+fn metrics_handle() -> &'static PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE.get_or_init(|| {
+        PrometheusBuilder::new()
+            .install_recorder()
+            .expect("failed to install the Prometheus metrics recorder")
+    })
+}
+
+fn update_active_streams_gauge() {
+    let created = STREAM_CREATED_COUNTER.load(Ordering::Relaxed);
+    let dropped = STREAM_DROPPED_COUNTER.load(Ordering::Relaxed);
+    gauge!("vmm_log_streams_active").set(created.saturating_sub(dropped) as f64);
+}
+
+fn record_tail_error(channel: &str) {
+    metrics_handle();
+    counter!("vmm_log_tail_errors_total", "channel" => channel.to_string()).increment(1);
+}
+
+fn record_heartbeat() {
+    metrics_handle();
+    counter!("vmm_log_heartbeats_total").increment(1);
+}
+
 struct StreamCounter {
     id: usize,
 }
@@ -82,6 +260,8 @@ struct StreamCounter {
 impl StreamCounter {
     fn new() -> Self {
         let id = STREAM_CREATED_COUNTER.fetch_add(1, Ordering::Relaxed);
+        counter!("vmm_log_streams_opened_total").increment(1);
+        update_active_streams_gauge();
         info!(
             "Stream {id} created, created: {}, dropped: {}",
             STREAM_CREATED_COUNTER.load(Ordering::Relaxed),
@@ -94,6 +274,7 @@ impl StreamCounter {
 impl Drop for StreamCounter {
     fn drop(&mut self) {
         STREAM_DROPPED_COUNTER.fetch_add(1, Ordering::Relaxed);
+        update_active_streams_gauge();
         info!(
             "Stream {} dropped, created: {}, dropped: {}",
             self.id,
@@ -103,30 +284,463 @@ impl Drop for StreamCounter {
     }
 }
 
-#[get("/logs?<id>&<follow>&<ansi>&<lines>&<ch>")]
+/// Exposes the counters/gauges recorded above (plus the process defaults the `metrics` crate
+/// ships) in OpenMetrics/Prometheus text format, so operators can scrape this service with
+/// standard monitoring instead of grepping logs for the created/dropped lines above.
+#[get("/metrics")]
+fn metrics_route() -> (ContentType, String) {
+    (ContentType::Text, metrics_handle().render())
+}
+
+fn format_log_line(line: &str, ansi: bool) -> String {
+    if ansi {
+        line.to_string()
+    } else {
+        strip_ansi_escapes::strip_str(line)
+    }
+}
+
+/// Picks a response content-coding for a streamed body from the client's `Accept-Encoding`
+/// header, unless `disabled` (the route's `compress=false` escape hatch for debugging) is set.
+/// Prefers gzip when both are offered since it's the more widely cached/understood of the two.
+fn negotiate_encoding(req: &Request<'_>, disabled: bool) -> Option<&'static str> {
+    if disabled {
+        return None;
+    }
+    let accept_encoding = req.headers().get_one("Accept-Encoding")?;
+    if accept_encoding
+        .split(',')
+        .any(|coding| coding.split(';').next().unwrap_or("").trim() == "gzip")
+    {
+        Some("gzip")
+    } else if accept_encoding
+        .split(',')
+        .any(|coding| coding.split(';').next().unwrap_or("").trim() == "deflate")
+    {
+        Some("deflate")
+    } else {
+        None
+    }
+}
+
+/// Incrementally gzip/deflate-compresses a log stream chunk by chunk, flushing after every
+/// chunk so `follow` mode still delivers lines promptly instead of buffering behind the
+/// compressor's internal window. `Identity` is a passthrough for when no coding was negotiated.
+enum LineEncoder {
+    Identity,
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    Deflate(flate2::write::DeflateEncoder<Vec<u8>>),
+}
+
+impl LineEncoder {
+    fn new(encoding: Option<&str>) -> Self {
+        match encoding {
+            Some("gzip") => {
+                Self::Gzip(flate2::write::GzEncoder::new(Vec::new(), Compression::default()))
+            }
+            Some("deflate") => Self::Deflate(flate2::write::DeflateEncoder::new(
+                Vec::new(),
+                Compression::default(),
+            )),
+            _ => Self::Identity,
+        }
+    }
+
+    /// Compresses and flushes `chunk`, returning the compressed bytes produced so far.
+    fn encode(&mut self, chunk: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Identity => chunk.to_vec(),
+            Self::Gzip(encoder) => {
+                let _ = encoder.write_all(chunk);
+                let _ = encoder.flush();
+                std::mem::take(encoder.get_mut())
+            }
+            Self::Deflate(encoder) => {
+                let _ = encoder.write_all(chunk);
+                let _ = encoder.flush();
+                std::mem::take(encoder.get_mut())
+            }
+        }
+    }
+
+    /// Finalizes the stream, returning any trailing bytes (e.g. the gzip footer).
+    fn finish(self) -> Vec<u8> {
+        match self {
+            Self::Identity => Vec::new(),
+            Self::Gzip(encoder) => encoder.finish().unwrap_or_default(),
+            Self::Deflate(encoder) => encoder.finish().unwrap_or_default(),
+        }
+    }
+}
+
+/// How many lines a freshly spawned [`LogFanout`] backfills from the log file before following
+/// it live, and the most it ever retains for late-joining subscribers. Matches the previous
+/// per-connection default tail length.
+const FANOUT_BACKLOG_LINES: usize = 10000;
+
+/// Lagging subscribers drop the oldest broadcast lines rather than block the one task reading
+/// the file on behalf of everyone following it.
+const FANOUT_BROADCAST_CAPACITY: usize = 1024;
+
+type FanoutKey = (String, String);
+
+/// The shared state behind every `follow=true` connection to one `(vm_id, channel)`: a single
+/// background task tails the file and publishes each line here, so N dashboard tabs watching the
+/// same VM cause one file read and one inotify watch instead of N.
+struct LogFanout {
+    sender: broadcast::Sender<Arc<str>>,
+    backlog: Arc<std::sync::Mutex<VecDeque<Arc<str>>>>,
+    subscribers: AtomicUsize,
+    task: tokio::task::JoinHandle<()>,
+}
+
+fn fanout_registry() -> &'static std::sync::Mutex<HashMap<FanoutKey, Arc<LogFanout>>> {
+    static REGISTRY: OnceLock<std::sync::Mutex<HashMap<FanoutKey, Arc<LogFanout>>>> =
+        OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// One connection's handle to a [`LogFanout`]. Dropping it (e.g. when the client disconnects)
+/// releases the subscription; once the last one is gone the background tailer is aborted and the
+/// registry entry removed, so an idle VM's log file isn't held open forever.
+struct FanoutSubscription {
+    key: FanoutKey,
+    fanout: Arc<LogFanout>,
+    receiver: broadcast::Receiver<Arc<str>>,
+}
+
+impl Drop for FanoutSubscription {
+    fn drop(&mut self) {
+        if self.fanout.subscribers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let mut registry = fanout_registry().lock().unwrap_or_else(|e| e.into_inner());
+            if registry
+                .get(&self.key)
+                .is_some_and(|current| Arc::ptr_eq(current, &self.fanout))
+            {
+                registry.remove(&self.key);
+            }
+            drop(registry);
+            self.fanout.task.abort();
+        }
+    }
+}
+
+fn subscribe_fanout(vm_id: &str, channel: &str, log_file: std::path::PathBuf) -> FanoutSubscription {
+    let key = (vm_id.to_string(), channel.to_string());
+    let mut registry = fanout_registry().lock().unwrap_or_else(|e| e.into_inner());
+    let fanout = registry
+        .entry(key.clone())
+        .or_insert_with(|| {
+            let (tx, _rx) = broadcast::channel(FANOUT_BROADCAST_CAPACITY);
+            let pump_tx = tx.clone();
+            let backlog: Arc<std::sync::Mutex<VecDeque<Arc<str>>>> =
+                Arc::new(std::sync::Mutex::new(VecDeque::new()));
+            let pump_backlog = backlog.clone();
+            let task = tokio::spawn(async move {
+                let tailer_result = tailf::Options::builder()
+                    .num_lines(Some(FANOUT_BACKLOG_LINES))
+                    .follow(true)
+                    .build()
+                    .tail(log_file);
+                let Ok(mut tailer) = tailer_result else {
+                    return;
+                };
+                loop {
+                    match tailer.next().await {
+                        Ok(Some(line)) => {
+                            let line: Arc<str> = String::from_utf8_lossy(&line).into_owned().into();
+                            {
+                                let mut backlog =
+                                    pump_backlog.lock().unwrap_or_else(|e| e.into_inner());
+                                backlog.push_back(line.clone());
+                                while backlog.len() > FANOUT_BACKLOG_LINES {
+                                    backlog.pop_front();
+                                }
+                            }
+                            // No subscribers is not an error: the tailer keeps running so the
+                            // backlog stays warm for the next connection.
+                            let _ = pump_tx.send(line);
+                        }
+                        Ok(None) | Err(_) => break,
+                    }
+                }
+            });
+            Arc::new(LogFanout {
+                sender: tx,
+                backlog,
+                subscribers: AtomicUsize::new(0),
+                task,
+            })
+        })
+        .clone();
+    fanout.subscribers.fetch_add(1, Ordering::AcqRel);
+    let receiver = fanout.sender.subscribe();
+    drop(registry);
+    FanoutSubscription {
+        key,
+        fanout,
+        receiver,
+    }
+}
+
+#[get("/logs?<id>&<follow>&<ansi>&<lines>&<ch>&<compress>")]
 fn vm_logs(
     _auth: Authorized,
     app: &State<App>,
+    req: &Request<'_>,
+    id: String,
+    follow: bool,
+    ansi: bool,
+    lines: Option<usize>,
+    ch: Option<&str>,
+    compress: Option<bool>,
+) -> WithHeaders<ByteStream![Vec<u8>]> {
+    let encoding = negotiate_encoding(req, compress == Some(false));
+    let workdir = app.work_dir(&id);
+    let ch = ch.unwrap_or("serial").to_string();
+    let log_path = match ch.as_str() {
+        "serial" => Some(workdir.serial_file()),
+        "stdout" => Some(workdir.stdout_file()),
+        "stderr" => Some(workdir.stderr_file()),
+        _ => None,
+    };
+
+    // Range/conditional-GET support only applies to a finite, already-written read of the log
+    // file (`follow=false`); a live tail has no fixed length or stable mtime to key a cache
+    // validator off of, and nothing to resume into either.
+    let cache_info = if follow {
+        None
+    } else {
+        log_path.as_deref().and_then(log_file_cache_info)
+    };
+
+    let not_modified = cache_info.as_ref().is_some_and(|info| {
+        req.headers().get_one("If-None-Match") == Some(info.etag.as_str())
+            || req.headers().get_one("If-Modified-Since") == Some(info.last_modified.as_str())
+    });
+
+    let range = if not_modified {
+        None
+    } else {
+        cache_info.as_ref().and_then(|info| {
+            req.headers()
+                .get_one("Range")
+                .and_then(|value| parse_byte_range(value, info.len))
+        })
+    };
+    let range_unsatisfiable = !not_modified
+        && cache_info.is_some()
+        && req.headers().get_one("Range").is_some()
+        && range.is_none();
+
+    let mut headers = WithHeaders::new(()).maybe_header(
+        "Accept-Ranges",
+        cache_info.as_ref().map(|_| "bytes".to_string()),
+    );
+    if let Some(info) = &cache_info {
+        headers = headers
+            .header("ETag", info.etag.clone())
+            .header("Last-Modified", info.last_modified.clone());
+    }
+    if let Some((start, end)) = range {
+        headers = headers.header(
+            "Content-Range",
+            format!("bytes {start}-{end}/{}", cache_info.as_ref().unwrap().len),
+        );
+    } else if range_unsatisfiable {
+        headers = headers.header(
+            "Content-Range",
+            format!("bytes */{}", cache_info.as_ref().unwrap().len),
+        );
+    }
+    let status = if not_modified {
+        Some(Status::NotModified)
+    } else if range_unsatisfiable {
+        Some(Status::RangeNotSatisfiable)
+    } else if range.is_some() {
+        Some(Status::PartialContent)
+    } else {
+        None
+    };
+    headers = headers.maybe_status(status);
+    if let Some(encoding) = encoding {
+        headers = headers.header("Content-Encoding", encoding);
+    }
+
+    let stream = ByteStream! {
+        let mut encoder = LineEncoder::new(encoding);
+
+        let Some(log_file) = log_path else {
+            yield encoder.encode(format!("Unknown channel {ch}").as_bytes());
+            return;
+        };
+
+        if not_modified || range_unsatisfiable {
+            return;
+        }
+
+        // A satisfiable Range request resumes a dropped download from a byte offset: serve the
+        // requested span directly from the file instead of through the line-oriented tailer,
+        // which has no notion of byte offsets.
+        if let Some((start, end)) = range {
+            let Ok(mut file) = tokio::fs::File::open(&log_file).await else {
+                yield encoder.encode(format!("Failed to open {}", log_file.display()).as_bytes());
+                return;
+            };
+            if file.seek(std::io::SeekFrom::Start(start)).await.is_err() {
+                yield encoder.encode(format!("Failed to seek {}", log_file.display()).as_bytes());
+                return;
+            }
+            let mut remaining = end - start + 1;
+            let mut buf = vec![0u8; 65536];
+            while remaining > 0 {
+                let chunk_len = buf.len().min(remaining as usize);
+                match file.read(&mut buf[..chunk_len]).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        let chunk = &buf[..n];
+                        let text = if ansi {
+                            String::from_utf8_lossy(chunk).to_string()
+                        } else {
+                            strip_ansi_escapes::strip_str(&String::from_utf8_lossy(chunk))
+                        };
+                        yield encoder.encode(text.as_bytes());
+                        remaining -= n as u64;
+                    }
+                    Err(err) => {
+                        yield encoder.encode(format!("<failed to read range: {err}>").as_bytes());
+                        break;
+                    }
+                }
+            }
+            yield encoder.finish();
+            return;
+        }
+
+        let counter = StreamCounter::new();
+
+        if follow {
+            // Share one background tailer per (vm_id, channel) across every follower, instead of
+            // each connection opening its own file handle and inotify watch on it.
+            let mut subscription = subscribe_fanout(&id, &ch, log_file);
+            let requested = lines.unwrap_or(FANOUT_BACKLOG_LINES);
+            let backlog: Vec<Arc<str>> = {
+                let backlog = subscription
+                    .fanout
+                    .backlog
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                let start = backlog.len().saturating_sub(requested);
+                backlog.iter().skip(start).cloned().collect()
+            };
+            for line in backlog {
+                yield encoder.encode(format_log_line(&line, ansi).as_bytes());
+            }
+
+            loop {
+                // This is a workaround for https://github.com/rwf2/Rocket/issues/2888
+                // However, If is is accessed via vscode's port forwarding, it will still get
+                // trouble: https://github.com/microsoft/vscode-remote-release/issues/3561
+                let next = match timeout(Duration::from_secs(60), subscription.receiver.recv()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        yield encoder.encode(b"[vmm heartbeat]\n");
+                        record_heartbeat();
+                        let created = STREAM_CREATED_COUNTER.load(Ordering::Relaxed);
+                        let dropped = STREAM_DROPPED_COUNTER.load(Ordering::Relaxed);
+                        let diff = created.saturating_sub(dropped);
+                        debug!(
+                            "Stream {} heartbeat, created: {created}, dropped: {dropped}, diff: {diff}",
+                            counter.id,
+                        );
+                        continue;
+                    }
+                };
+                match next {
+                    Ok(line) => yield encoder.encode(format_log_line(&line, ansi).as_bytes()),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        yield encoder.encode(format!("<lagged: skipped {skipped} lines>\n").as_bytes());
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            yield encoder.finish();
+            return;
+        }
+
+        const DEFAULT_TAIL_LINES: usize = 10000;
+        let tailer_result = tailf::Options::builder()
+            .num_lines(lines.or(Some(DEFAULT_TAIL_LINES)))
+            .follow(follow)
+            .build()
+            .tail(log_file);
+        let mut tailer = match tailer_result {
+            Err(err) => {
+                yield encoder.encode(format!("{err:?}").as_bytes());
+                return;
+            }
+            Ok(tailer) => tailer,
+        };
+
+        loop {
+            match tailer.next().await {
+                Ok(Some(line)) => {
+                    let line_str = String::from_utf8_lossy(&line);
+                    let text = if ansi {
+                        line_str.to_string()
+                    } else {
+                        strip_ansi_escapes::strip_str(&line_str)
+                    };
+                    yield encoder.encode(text.as_bytes());
+                }
+                Ok(None) => {
+                    break;
+                }
+                Err(err) => {
+                    record_tail_error(&ch);
+                    yield encoder.encode(format!("<failed to read line: {err}>").as_bytes());
+                    break;
+                }
+            }
+        }
+        yield encoder.finish();
+    };
+
+    headers.with_inner(stream)
+}
+
+/// A WebSocket counterpart to [`vm_logs`], built on `rocket_ws`.
+///
+/// `vm_logs` fakes keep-alive with a `[vmm heartbeat]` text line every 60 seconds to work around
+/// https://github.com/rwf2/Rocket/issues/2888, and that workaround still doesn't survive VS
+/// Code's port forwarding (https://github.com/microsoft/vscode-remote-release/issues/3561). A
+/// real WebSocket doesn't need either: the protocol's own ping/pong frames, handled by the
+/// WebSocket library beneath `rocket_ws`, keep an idle connection alive on their own.
+#[get("/logs/ws?<id>&<follow>&<ansi>&<lines>&<ch>")]
+fn vm_logs_ws(
+    _auth: Authorized,
+    app: &State<App>,
+    ws: WebSocket,
     id: String,
     follow: bool,
     ansi: bool,
     lines: Option<usize>,
     ch: Option<&str>,
-) -> TextStream![String] {
+) -> rocket_ws::Stream!['static] {
     let workdir = app.work_dir(&id);
     let ch = ch.unwrap_or("serial").to_string();
-    TextStream! {
+    rocket_ws::stream! { ws =>
         let log_file = match ch.as_str() {
             "serial" => workdir.serial_file(),
             "stdout" => workdir.stdout_file(),
             "stderr" => workdir.stderr_file(),
             _ => {
-                yield format!("Unknown channel {ch}");
+                yield Message::Text(format!("Unknown channel {ch}"));
                 return;
             }
         };
 
-        let counter = StreamCounter::new();
+        let _counter = StreamCounter::new();
 
         const DEFAULT_TAIL_LINES: usize = 10000;
         let tailer_result = tailf::Options::builder()
@@ -136,44 +750,27 @@ fn vm_logs(
             .tail(log_file);
         let mut tailer = match tailer_result {
             Err(err) => {
-                yield format!("{err:?}");
+                yield Message::Text(format!("{err:?}"));
                 return;
             }
             Ok(tailer) => tailer,
         };
 
         loop {
-            // This is a workaround for https://github.com/rwf2/Rocket/issues/2888
-            // However, If is is accessed via vscode's port forwarding, it will still get trouble:
-            // https://github.com/microsoft/vscode-remote-release/issues/3561
-            let next = match timeout(Duration::from_secs(60), tailer.next()).await {
-                Ok(next) => next,
-                Err(_) => {
-                    yield format!("[vmm heartbeat]\n");
-                    let created = STREAM_CREATED_COUNTER.load(Ordering::Relaxed);
-                    let dropped = STREAM_DROPPED_COUNTER.load(Ordering::Relaxed);
-                    let diff = created.saturating_sub(dropped);
-                    debug!(
-                        "Stream {} heartbeat, created: {created}, dropped: {dropped}, diff: {diff}",
-                        counter.id,
-                    );
-                    continue;
-                }
-            };
-            match next {
+            match tailer.next().await {
                 Ok(Some(line)) => {
                     let line_str = String::from_utf8_lossy(&line);
-                    if ansi {
-                        yield line_str.to_string();
+                    let text = if ansi {
+                        line_str.to_string()
                     } else {
-                        yield strip_ansi_escapes::strip_str(&line_str);
-                    }
-                }
-                Ok(None) => {
-                    break;
+                        strip_ansi_escapes::strip_str(&line_str)
+                    };
+                    yield Message::Text(text);
                 }
+                Ok(None) => break,
                 Err(err) => {
-                    yield format!("<failed to read line: {err}>");
+                    record_tail_error(&ch);
+                    yield Message::Text(format!("<failed to read line: {err}>"));
                     break;
                 }
             }
@@ -182,5 +779,5 @@ fn vm_logs(
 }
 
 pub fn routes() -> Vec<Route> {
-    routes![index, v1, beta, v0, res, vm_logs]
+    routes![index, v1, beta, v0, res, vm_logs, vm_logs_ws, metrics_route]
 }
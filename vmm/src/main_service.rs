@@ -193,6 +193,17 @@ pub fn create_manifest_from_vm_config(
         kms_urls: request.kms_urls.clone(),
         gateway_urls: request.gateway_urls.clone(),
         no_tee: request.no_tee,
+        cpu_topology: None,
+        max_vcpu: None,
+        max_memory: None,
+        balloon: false,
+        // `VmConfiguration` has no rate-limit fields yet in this checkout's `.proto`; once it
+        // does, validate and map them here the same way `port_map` above validates `request.ports`.
+        disk_rate_limit: None,
+        net_rate_limit: None,
+        // `VmConfiguration` has no disk-layer fields yet in this checkout's `.proto`; once it
+        // does, map its ordered component list here the same way `gpus` above maps `request.gpus`.
+        disk_layers: Vec::new(),
     })
 }
 
@@ -219,8 +230,27 @@ impl RpcHandler {
         }
 
         let vm = self.app.vm_info(vm_id).await?.context("vm not found")?;
-        if !["stopped", "exited"].contains(&vm.status.as_str()) {
-            bail!("vm should be stopped before resize: {}", vm_id);
+        let is_stopped = ["stopped", "exited"].contains(&vm.status.as_str());
+        // Disk resize (needs `qemu-img`) and image swaps still require the VM to be down; a
+        // vcpu/memory-only change on a running VM can instead go through `App::resize_vm`'s
+        // online hotplug path.
+        let needs_stop = disk_size.is_some() || image.is_some();
+
+        if !is_stopped {
+            if needs_stop {
+                bail!("vm should be stopped before resize: {}", vm_id);
+            }
+            self.app
+                .resize_vm(vm_id, vcpu, memory)
+                .await
+                .with_context(|| format!("Failed to hotplug-resize running VM {vm_id}"))?;
+            if let Some(vcpu) = vcpu {
+                manifest.vcpu = vcpu;
+            }
+            if let Some(memory) = memory {
+                manifest.memory = memory;
+            }
+            return Ok(true);
         }
 
         if let Some(vcpu) = vcpu {
@@ -257,6 +287,40 @@ impl RpcHandler {
     }
 }
 
+// `App::migrate_vm_out`/`App::receive_vm_migration` (see `crate::app::migration`) have no
+// `send_migration`/`receive_migration` counterparts here: that needs a `SendMigration`/
+// `ReceiveMigration` request/response pair added to the `dstack_vmm_rpc` .proto and regenerated.
+// Once it is, `send_migration` just needs to open a control connection to the destination's
+// address, hand it to `self.app.migrate_vm_out(&request.id, &mut control_stream, ...)`, and
+// `receive_migration` needs to accept the inbound connection and call
+// `self.app.receive_vm_migration(&mut control_stream)`. The handshake, manifest transfer, FD
+// passing and completion protocol are fully implemented; only the RPC transport to carry the
+// control connection between two `dstack-vmm` hosts is missing.
+//
+// BLOCKING PREREQUISITE: there is no `.proto` file at all in this checkout (not merely missing
+// messages on an existing one), so `SendMigration`/`ReceiveMigration` can't be added here without
+// it. The `main_routes.rs` HTTP surface isn't a substitute: every handler there is a read-only
+// `#[get]` (status pages, logs, metrics) and this repo routes all VM-admin mutations through
+// `VmmRpc`, not ad hoc HTTP endpoints — adding one here would be a new convention, not following
+// the existing one. Regenerating the gRPC service definitions is a prerequisite before live VM
+// migration between two `dstack-vmm` hosts is actually drivable, rather than an unreachable pair
+// of library functions.
+//
+// STATUS: `App::migrate_vm_out`/`App::receive_vm_migration` are re-scoped as a library-only
+// follow-up, blocked on `.proto` regen. Neither is registered on `VmmRpc`, and neither has a
+// caller anywhere in this checkout (not even this crate's own tests) until the control-connection
+// transport above exists to drive them. Do not treat them as the callable `send_migration`/
+// `receive_migration` RPC the original request asked for until the `.proto` prerequisite is met.
+//
+// `App::fetch_artifact` (see `crate::fetcher`) is not wired up as a `VmmRpc` method here either:
+// doing so needs a `FetchArtifact`/`FetchArtifactResponse` pair added to the same `.proto` and
+// regenerated, and that generated crate isn't part of this checkout. Once it is, this impl just
+// needs an `async fn fetch_artifact(self, request: FetchArtifact) -> Result<...>` that forwards
+// to `self.app.fetch_artifact(&request.into())`. Same blocking prerequisite as above.
+//
+// STATUS: `App::fetch_artifact` is likewise re-scoped as a library-only follow-up, blocked on
+// `.proto` regen. It is not registered on `VmmRpc`; do not treat it as the callable `FetchArtifact`
+// RPC the original request asked for until the `.proto` prerequisite above is met.
 impl VmmRpc for RpcHandler {
     async fn create_vm(self, request: VmConfiguration) -> Result<Id> {
         let manifest = create_manifest_from_vm_config(request.clone(), &self.app.config.cvm)?;
@@ -471,9 +535,21 @@ impl VmmRpc for RpcHandler {
         Ok(())
     }
 
+    /// Shuts a VM down gracefully, preferring the in-guest agent (which can run app-level
+    /// cleanup first) but falling back to a QMP `system_powerdown` when the agent is
+    /// unreachable, e.g. because the guest hasn't finished booting or has wedged past the point
+    /// of answering vsock requests.
     async fn shutdown_vm(self, request: Id) -> Result<()> {
-        self.guest_agent_client(&request.id)?.shutdown().await?;
-        Ok(())
+        if let Ok(client) = self.guest_agent_client(&request.id) {
+            if client.shutdown().await.is_ok() {
+                return Ok(());
+            }
+        }
+        self.app
+            .qmp_client(&request.id)?
+            .system_powerdown()
+            .await
+            .context("Failed to shut down VM via guest agent or QMP")
     }
 
     async fn version(self) -> Result<VersionResponse> {
